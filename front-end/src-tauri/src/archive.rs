@@ -0,0 +1,844 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, State};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::error::ExplorerError;
+use crate::file_ops::{measure, unique_path, validate_file_name};
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// Event name used to report extraction and compression progress.
+pub const ARCHIVE_PROGRESS_EVENT: &str = "archive://progress";
+
+/// Size of the buffer used while writing entries.
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Minimum delay between two progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// zstd level used for `tar.zst` archives when none is given.
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// Archive formats that can be browsed.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+    SevenZ,
+}
+
+impl ArchiveFormat {
+    /// Guess the format from the file name.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(ArchiveFormat::TarZst)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".7z") {
+            Some(ArchiveFormat::SevenZ)
+        } else {
+            None
+        }
+    }
+}
+
+/// An entry as recorded in the archive, with a `/`-separated relative path.
+pub struct RawEntry {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub compressed_size: Option<u64>,
+    pub modified: Option<u64>,
+}
+
+/// A file or folder directly inside the listed archive folder.
+#[derive(Serialize, Clone)]
+pub struct ArchiveEntry {
+    pub name: String,
+    /// Path inside the archive, usable as `inner_path` for folders.
+    pub path: String,
+    pub is_dir: bool,
+    /// Uncompressed size; for folders, the total of everything below them.
+    pub size: u64,
+    /// Stored size, when the format records it per entry. For solid 7z
+    /// archives the packed size of a block is attributed to its first file.
+    pub compressed_size: Option<u64>,
+    /// Seconds since the Unix epoch.
+    pub modified: Option<u64>,
+}
+
+/// Contents of one folder inside an archive.
+#[derive(Serialize)]
+pub struct ArchiveListing {
+    pub archive: String,
+    pub format: ArchiveFormat,
+    pub inner_path: String,
+    pub entries: Vec<ArchiveEntry>,
+}
+
+/// List the contents of a folder inside a zip, tar, tar.gz, tar.zst or 7z
+/// archive.
+///
+/// `inner_path` is a `/`-separated folder inside the archive; the archive
+/// root is listed when it is omitted. Folders that are only implied by the
+/// paths of their files are listed as well.
+#[command]
+pub async fn list_archive(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    inner_path: Option<String>,
+) -> Result<ArchiveListing, ExplorerError> {
+    tasks
+        .run_blocking("list_archive", move || {
+            let archive = Path::new(&path);
+            let format = ArchiveFormat::detect(archive)
+                .ok_or_else(|| format!("Unsupported archive format: {}", path))?;
+            let inner_path = normalize_entry_path(inner_path.as_deref().unwrap_or(""));
+            let entries = list_folder(read_entries(archive, format)?, &inner_path)?;
+            Ok(ArchiveListing {
+                archive: path,
+                format,
+                inner_path,
+                entries,
+            })
+        })
+        .await
+}
+
+/// Read every entry of an archive without extracting anything.
+pub fn read_entries(path: &Path, format: ArchiveFormat) -> Result<Vec<RawEntry>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    match format {
+        ArchiveFormat::Zip => read_zip_entries(file),
+        ArchiveFormat::Tar => read_tar_entries(BufReader::new(file), true),
+        ArchiveFormat::TarGz => read_tar_entries(GzDecoder::new(BufReader::new(file)), false),
+        ArchiveFormat::TarZst => zstd::Decoder::new(file)
+            .map_err(|e| e.to_string())
+            .and_then(|decoder| read_tar_entries(decoder, false)),
+        ArchiveFormat::SevenZ => read_7z_entries(path),
+    }
+    .map_err(|e| format!("Failed to read archive: {}", e))
+}
+
+fn read_zip_entries(file: File) -> Result<Vec<RawEntry>, String> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i).map_err(|e| e.to_string())?;
+        entries.push(RawEntry {
+            path: normalize_entry_path(entry.name()),
+            is_dir: entry.is_dir(),
+            size: entry.size(),
+            compressed_size: Some(entry.compressed_size()),
+            modified: entry
+                .last_modified()
+                .and_then(|t| time::OffsetDateTime::try_from(t).ok())
+                .and_then(|t| u64::try_from(t.unix_timestamp()).ok()),
+        });
+    }
+    Ok(entries)
+}
+
+/// Tar entries carry no compressed size; for plain tars it equals the size.
+fn read_tar_entries<R: Read>(reader: R, uncompressed: bool) -> Result<Vec<RawEntry>, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let header = entry.header();
+        let size = header.size().unwrap_or(0);
+        entries.push(RawEntry {
+            path: normalize_entry_path(&entry.path().map_err(|e| e.to_string())?.to_string_lossy()),
+            is_dir: header.entry_type().is_dir(),
+            size,
+            compressed_size: uncompressed.then_some(size),
+            modified: header.mtime().ok(),
+        });
+    }
+    Ok(entries)
+}
+
+fn read_7z_entries(path: &Path) -> Result<Vec<RawEntry>, String> {
+    let archive = sevenz_rust::Archive::open(path).map_err(|e| e.to_string())?;
+    Ok(archive
+        .files
+        .iter()
+        .map(|entry| RawEntry {
+            path: normalize_entry_path(entry.name()),
+            is_dir: entry.is_directory(),
+            size: entry.size(),
+            compressed_size: Some(entry.compressed_size),
+            modified: entry
+                .has_last_modified_date
+                .then(|| u64::try_from(entry.last_modified_date().to_unix_time()).ok())
+                .flatten(),
+        })
+        .collect())
+}
+
+/// Use `/` separators and drop leading `./` or `/` and trailing `/`.
+pub fn normalize_entry_path(path: &str) -> String {
+    path.replace('\\', "/")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Collapse the flat entry list into the direct children of `folder`.
+fn list_folder(raw: Vec<RawEntry>, folder: &str) -> Result<Vec<ArchiveEntry>, String> {
+    let prefix = if folder.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", folder)
+    };
+    let mut found = folder.is_empty();
+    let mut dirs: BTreeMap<String, ArchiveEntry> = BTreeMap::new();
+    let mut files = Vec::new();
+
+    for entry in raw {
+        if entry.path == folder {
+            found = true;
+            continue;
+        }
+        let Some(rest) = entry.path.strip_prefix(&prefix) else {
+            continue;
+        };
+        found = true;
+
+        let (name, nested) = match rest.split_once('/') {
+            Some((name, _)) => (name, true),
+            None => (rest, false),
+        };
+        if !nested && !entry.is_dir {
+            files.push(ArchiveEntry {
+                name: name.to_string(),
+                path: entry.path.clone(),
+                is_dir: false,
+                size: entry.size,
+                compressed_size: entry.compressed_size,
+                modified: entry.modified,
+            });
+            continue;
+        }
+
+        let dir = dirs
+            .entry(name.to_string())
+            .or_insert_with(|| ArchiveEntry {
+                name: name.to_string(),
+                path: format!("{}{}", prefix, name),
+                is_dir: true,
+                size: 0,
+                compressed_size: Some(0),
+                modified: None,
+            });
+        if nested {
+            dir.size += entry.size;
+            dir.compressed_size = dir
+                .compressed_size
+                .zip(entry.compressed_size)
+                .map(|(a, b)| a + b);
+        } else {
+            dir.modified = entry.modified;
+        }
+    }
+
+    if !found {
+        return Err(format!("Folder not found in archive: {}", folder));
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(dirs.into_values().chain(files).collect())
+}
+
+/// What to do when an extracted file already exists at the destination.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+    #[default]
+    Skip,
+    /// Extract next to the existing file as `name (n).ext`.
+    Rename,
+    Overwrite,
+}
+
+/// Payload of the `archive://progress` event.
+#[derive(Serialize, Clone)]
+pub struct ArchiveProgress {
+    pub operation_id: String,
+    pub current_entry: String,
+    pub bytes_processed: u64,
+    pub total_bytes: u64,
+    pub entries_processed: u64,
+    pub total_entries: u64,
+}
+
+/// Summary returned once an extraction completes.
+#[derive(Serialize)]
+pub struct ExtractReport {
+    pub operation_id: String,
+    pub destination: String,
+    pub extracted: u64,
+    /// Files left untouched because they already existed.
+    pub skipped: u64,
+    pub bytes_written: u64,
+}
+
+/// Extract an archive, or only the given `entries`, into `destination`.
+///
+/// `entries` are paths inside the archive as returned by `list_archive`;
+/// selecting a folder extracts everything below it. Progress is emitted on
+/// `archive://progress` and the extraction can be paused or cancelled
+/// through `operation_id`. Entries that would land outside `destination`
+/// abort the extraction.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn extract_archive(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    archive: String,
+    destination: String,
+    entries: Option<Vec<String>>,
+    overwrite: Option<OverwritePolicy>,
+    operation_id: Option<String>,
+) -> Result<ExtractReport, ExplorerError> {
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("extract_archive", move || {
+            let path = Path::new(&archive);
+            let format = ArchiveFormat::detect(path)
+                .ok_or_else(|| format!("Unsupported archive format: {}", archive))?;
+            fs::create_dir_all(&destination)
+                .map_err(|e| format!("Failed to create destination: {}", e))?;
+
+            let mut extractor = Extractor {
+                app: &app,
+                operation: &handle,
+                destination: PathBuf::from(&destination),
+                selection: entries
+                    .map(|entries| entries.iter().map(|e| normalize_entry_path(e)).collect()),
+                policy: overwrite.unwrap_or_default(),
+                progress: ArchiveProgress {
+                    operation_id: id.clone(),
+                    current_entry: String::new(),
+                    bytes_processed: 0,
+                    total_bytes: 0,
+                    entries_processed: 0,
+                    total_entries: 0,
+                },
+                last_emit: Instant::now(),
+                report: ExtractReport {
+                    operation_id: id,
+                    destination: destination.clone(),
+                    extracted: 0,
+                    skipped: 0,
+                    bytes_written: 0,
+                },
+            };
+            for entry in read_entries(path, format)? {
+                if !entry.is_dir && extractor.selected(&entry.path) {
+                    extractor.progress.total_entries += 1;
+                    extractor.progress.total_bytes += entry.size;
+                }
+            }
+            extractor.extract(path, format)?;
+            extractor.emit();
+            Ok(extractor.report)
+        })
+        .await
+}
+
+/// State of a running extraction.
+struct Extractor<'a> {
+    app: &'a AppHandle,
+    operation: &'a OperationHandle,
+    destination: PathBuf,
+    selection: Option<Vec<String>>,
+    policy: OverwritePolicy,
+    progress: ArchiveProgress,
+    last_emit: Instant,
+    report: ExtractReport,
+}
+
+impl Extractor<'_> {
+    fn extract(&mut self, path: &Path, format: ArchiveFormat) -> Result<(), ExplorerError> {
+        let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+        let read_error = |e: &dyn std::fmt::Display| format!("Failed to read archive: {}", e);
+        match format {
+            ArchiveFormat::Zip => {
+                let mut archive =
+                    zip::ZipArchive::new(BufReader::new(file)).map_err(|e| read_error(&e))?;
+                for i in 0..archive.len() {
+                    let mut entry = archive.by_index(i).map_err(|e| read_error(&e))?;
+                    let (name, is_dir, size) =
+                        (entry.name().to_string(), entry.is_dir(), entry.size());
+                    self.entry(&name, is_dir, size, &mut entry)?;
+                }
+                Ok(())
+            }
+            ArchiveFormat::Tar => self.extract_tar(BufReader::new(file)),
+            ArchiveFormat::TarGz => self.extract_tar(GzDecoder::new(BufReader::new(file))),
+            ArchiveFormat::TarZst => {
+                self.extract_tar(zstd::Decoder::new(file).map_err(|e| read_error(&e))?)
+            }
+            ArchiveFormat::SevenZ => {
+                let mut archive = sevenz_rust::SevenZReader::new(
+                    BufReader::new(&file),
+                    file.metadata().map_err(|e| read_error(&e))?.len(),
+                    sevenz_rust::Password::empty(),
+                )
+                .map_err(|e| read_error(&e))?;
+                let mut failure = None;
+                archive
+                    .for_each_entries(|entry, data| {
+                        if failure.is_some() {
+                            return Ok(false);
+                        }
+                        if let Err(e) =
+                            self.entry(entry.name(), entry.is_directory(), entry.size(), data)
+                        {
+                            failure = Some(e);
+                            return Ok(false);
+                        }
+                        // Solid blocks must be read through to reach the next entry.
+                        io::copy(data, &mut io::sink())?;
+                        Ok(true)
+                    })
+                    .map_err(|e| read_error(&e))?;
+                failure.map_or(Ok(()), Err)
+            }
+        }
+    }
+
+    fn extract_tar<R: Read>(&mut self, reader: R) -> Result<(), ExplorerError> {
+        let mut archive = tar::Archive::new(reader);
+        let entries = archive
+            .entries()
+            .map_err(|e| format!("Failed to read archive: {}", e))?;
+        for entry in entries {
+            let mut entry = entry.map_err(|e| format!("Failed to read archive: {}", e))?;
+            let kind = entry.header().entry_type();
+            // Links and special files are not recreated.
+            if !kind.is_file() && !kind.is_dir() {
+                continue;
+            }
+            let name = entry
+                .path()
+                .map_err(|e| format!("Failed to read archive: {}", e))?
+                .to_string_lossy()
+                .to_string();
+            let size = entry.header().size().unwrap_or(0);
+            self.entry(&name, kind.is_dir(), size, &mut entry)?;
+        }
+        Ok(())
+    }
+
+    fn selected(&self, path: &str) -> bool {
+        match &self.selection {
+            None => true,
+            Some(selection) => selection.iter().any(|s| {
+                s.is_empty()
+                    || path == s
+                    || (path.starts_with(s.as_str()) && path[s.len()..].starts_with('/'))
+            }),
+        }
+    }
+
+    /// Extract one entry whose uncompressed bytes are read from `data`.
+    fn entry(
+        &mut self,
+        raw_path: &str,
+        is_dir: bool,
+        size: u64,
+        data: &mut dyn Read,
+    ) -> Result<(), ExplorerError> {
+        self.operation.checkpoint()?;
+        let path = normalize_entry_path(raw_path);
+        if path.is_empty() || !self.selected(&path) {
+            return Ok(());
+        }
+        let target = self.target(&path)?;
+        if is_dir {
+            return fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to extract {}: {}", path, e).into());
+        }
+
+        self.progress.current_entry = path.clone();
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to extract {}: {}", path, e))?;
+        }
+        let target = match self.policy {
+            _ if fs::symlink_metadata(&target).is_err() => target,
+            OverwritePolicy::Overwrite => target,
+            OverwritePolicy::Rename => unique_path(&target),
+            OverwritePolicy::Skip => {
+                self.report.skipped += 1;
+                self.progress.entries_processed += 1;
+                self.progress.bytes_processed += size;
+                self.maybe_emit();
+                return Ok(());
+            }
+        };
+
+        if let Err(e) = self.write_file(data, &target) {
+            self.operation.checkpoint()?;
+            return Err(format!("Failed to extract {}: {}", path, e).into());
+        }
+        self.report.extracted += 1;
+        self.progress.entries_processed += 1;
+        self.maybe_emit();
+        Ok(())
+    }
+
+    /// Destination path of an entry, rejecting names that would escape it.
+    fn target(&self, path: &str) -> Result<PathBuf, String> {
+        let mut target = self.destination.clone();
+        for part in path.split('/') {
+            validate_file_name(part)
+                .map_err(|e| format!("Invalid entry name in archive: {}: {}", path, e))?;
+            target.push(part);
+        }
+        Ok(target)
+    }
+
+    fn write_file(&mut self, data: &mut dyn Read, target: &Path) -> io::Result<()> {
+        let mut writer = File::create(target)?;
+        let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+        loop {
+            let chunk = self
+                .operation
+                .checkpoint()
+                .map_err(|e| io::Error::new(io::ErrorKind::Interrupted, e))
+                .and_then(|_| data.read(&mut buffer))
+                .and_then(|read| writer.write_all(&buffer[..read]).map(|_| read));
+            match chunk {
+                Ok(0) => return Ok(()),
+                Ok(read) => {
+                    self.report.bytes_written += read as u64;
+                    self.progress.bytes_processed += read as u64;
+                    self.maybe_emit();
+                }
+                Err(e) => {
+                    drop(writer);
+                    let _ = fs::remove_file(target);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    fn maybe_emit(&mut self) {
+        if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.emit();
+        }
+    }
+
+    fn emit(&mut self) {
+        let _ = self.app.emit(ARCHIVE_PROGRESS_EVENT, &self.progress);
+        self.last_emit = Instant::now();
+    }
+}
+
+/// Summary returned once an archive has been written.
+#[derive(Serialize)]
+pub struct CreateArchiveReport {
+    pub operation_id: String,
+    pub path: String,
+    pub format: ArchiveFormat,
+    pub files: u64,
+    /// Uncompressed bytes added to the archive.
+    pub bytes_processed: u64,
+    /// Size of the finished archive on disk.
+    pub archive_size: u64,
+}
+
+/// Compress files and folders into a new `zip` or `tar_zst` archive.
+///
+/// Entries are named relative to the folder containing each selected path.
+/// `compression_level` is 0-9 for zip (deflate) and 1-22 for tar.zst.
+/// Progress is emitted on `archive://progress`; a cancelled or failed run
+/// removes the partial archive.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_archive(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    paths: Vec<String>,
+    destination: String,
+    format: ArchiveFormat,
+    compression_level: Option<i32>,
+    operation_id: Option<String>,
+) -> Result<CreateArchiveReport, ExplorerError> {
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("create_archive", move || {
+            let dest = Path::new(&destination);
+            if fs::symlink_metadata(dest).is_ok() {
+                return Err(ExplorerError::AlreadyExists(format!(
+                    "Destination already exists: {}",
+                    destination
+                )));
+            }
+            let (total_bytes, total_entries, _) = measure(&paths);
+            let mut tracker = Tracker {
+                app: &app,
+                operation: &handle,
+                progress: ArchiveProgress {
+                    operation_id: id.clone(),
+                    current_entry: String::new(),
+                    bytes_processed: 0,
+                    total_bytes,
+                    entries_processed: 0,
+                    total_entries,
+                },
+                last_emit: Instant::now(),
+            };
+
+            let result = match format {
+                ArchiveFormat::Zip => write_zip(&mut tracker, &paths, dest, compression_level),
+                ArchiveFormat::TarZst => {
+                    write_tar_zst(&mut tracker, &paths, dest, compression_level)
+                }
+                _ => {
+                    return Err(ExplorerError::Unsupported(
+                        "Only zip and tar.zst archives can be created".to_string(),
+                    ))
+                }
+            };
+            if let Err(e) = result {
+                let _ = fs::remove_file(dest);
+                handle.checkpoint()?;
+                return Err(format!("Failed to create archive: {}", e).into());
+            }
+            tracker.emit();
+
+            Ok(CreateArchiveReport {
+                operation_id: id,
+                format,
+                files: tracker.progress.entries_processed,
+                bytes_processed: tracker.progress.bytes_processed,
+                archive_size: fs::metadata(dest).map(|m| m.len()).unwrap_or(0),
+                path: destination,
+            })
+        })
+        .await
+}
+
+/// Progress of an archive being written.
+struct Tracker<'a> {
+    app: &'a AppHandle,
+    operation: &'a OperationHandle,
+    progress: ArchiveProgress,
+    last_emit: Instant,
+}
+
+impl Tracker<'_> {
+    fn start_entry(&mut self, name: &str) {
+        self.progress.current_entry = name.to_string();
+    }
+
+    fn finish_entry(&mut self) {
+        self.progress.entries_processed += 1;
+        if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.emit();
+        }
+    }
+
+    fn emit(&mut self) {
+        let _ = self.app.emit(ARCHIVE_PROGRESS_EVENT, &self.progress);
+        self.last_emit = Instant::now();
+    }
+}
+
+/// Reader that counts bytes and honours pause/cancel while a file is added.
+struct TrackedReader<'t, 'a, R> {
+    inner: R,
+    tracker: &'t mut Tracker<'a>,
+}
+
+impl<R: Read> Read for TrackedReader<'_, '_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.tracker
+            .operation
+            .checkpoint()
+            // Not `Interrupted`: `io::copy` would retry that forever.
+            .map_err(io::Error::other)?;
+        let read = self.inner.read(buf)?;
+        self.tracker.progress.bytes_processed += read as u64;
+        if self.tracker.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.tracker.emit();
+        }
+        Ok(read)
+    }
+}
+
+/// Visit everything below `paths`, naming entries relative to each path's
+/// parent with `/` separators.
+fn for_each_source<F>(paths: &[String], mut visit: F) -> Result<(), String>
+where
+    F: FnMut(&DirEntry, &str) -> Result<(), String>,
+{
+    for source in paths {
+        let source = Path::new(source);
+        let base = source.parent().unwrap_or(source);
+        for entry in WalkDir::new(source).into_iter().flatten() {
+            let name = entry
+                .path()
+                .strip_prefix(base)
+                .map(|rel| {
+                    rel.components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/")
+                })
+                .unwrap_or_default();
+            if !name.is_empty() {
+                visit(&entry, &name)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_zip(
+    tracker: &mut Tracker,
+    paths: &[String],
+    dest: &Path,
+    level: Option<i32>,
+) -> Result<(), String> {
+    let file = File::create(dest).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(io::BufWriter::new(file));
+    let base = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .compression_level(level.map(i64::from));
+
+    for_each_source(paths, |entry, name| {
+        let meta = entry.metadata().map_err(|e| e.to_string())?;
+        let mut options = zip_permissions(base, &meta).large_file(meta.len() >= u32::MAX as u64);
+        if let Some(modified) = meta
+            .modified()
+            .ok()
+            .and_then(|t| zip::DateTime::try_from(time::OffsetDateTime::from(t)).ok())
+        {
+            options = options.last_modified_time(modified);
+        }
+
+        tracker.start_entry(name);
+        if entry.file_type().is_dir() {
+            zip.add_directory(name, options)
+                .map_err(|e| e.to_string())?;
+            return Ok(());
+        }
+        if entry.file_type().is_symlink() {
+            let target = fs::read_link(entry.path()).map_err(|e| e.to_string())?;
+            zip.add_symlink(name, target.to_string_lossy(), options)
+                .map_err(|e| e.to_string())?;
+        } else {
+            zip.start_file(name, options).map_err(|e| e.to_string())?;
+            let mut reader = TrackedReader {
+                inner: File::open(entry.path()).map_err(|e| e.to_string())?,
+                tracker: &mut *tracker,
+            };
+            io::copy(&mut reader, &mut zip).map_err(|e| e.to_string())?;
+        }
+        tracker.finish_entry();
+        Ok(())
+    })?;
+
+    zip.finish()
+        .map_err(|e| e.to_string())?
+        .flush()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+fn zip_permissions(
+    options: zip::write::SimpleFileOptions,
+    meta: &fs::Metadata,
+) -> zip::write::SimpleFileOptions {
+    use std::os::unix::fs::PermissionsExt;
+    options.unix_permissions(meta.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn zip_permissions(
+    options: zip::write::SimpleFileOptions,
+    _meta: &fs::Metadata,
+) -> zip::write::SimpleFileOptions {
+    options
+}
+
+fn write_tar_zst(
+    tracker: &mut Tracker,
+    paths: &[String],
+    dest: &Path,
+    level: Option<i32>,
+) -> Result<(), String> {
+    let file = File::create(dest).map_err(|e| e.to_string())?;
+    let encoder = zstd::Encoder::new(
+        io::BufWriter::new(file),
+        level.unwrap_or(DEFAULT_ZSTD_LEVEL),
+    )
+    .map_err(|e| e.to_string())?;
+    let mut builder = tar::Builder::new(encoder);
+
+    for_each_source(paths, |entry, name| {
+        let meta = entry.metadata().map_err(|e| e.to_string())?;
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&meta);
+
+        tracker.start_entry(name);
+        if entry.file_type().is_dir() {
+            header.set_size(0);
+            builder
+                .append_data(&mut header, name, io::empty())
+                .map_err(|e| e.to_string())?;
+            return Ok(());
+        }
+        if entry.file_type().is_symlink() {
+            let target = fs::read_link(entry.path()).map_err(|e| e.to_string())?;
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder
+                .append_link(&mut header, name, target)
+                .map_err(|e| e.to_string())?;
+        } else {
+            let size = meta.len();
+            header.set_size(size);
+            let file = File::open(entry.path()).map_err(|e| e.to_string())?;
+            let reader = TrackedReader {
+                inner: file.take(size),
+                tracker: &mut *tracker,
+            };
+            builder
+                .append_data(&mut header, name, reader)
+                .map_err(|e| e.to_string())?;
+        }
+        tracker.finish_entry();
+        Ok(())
+    })?;
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .and_then(|mut writer| writer.flush())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,123 @@
+//! Folder completions for the address bar.
+
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::commands::{natural_cmp, ListOptions};
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Default number of completions returned by `autocomplete_path`.
+const DEFAULT_COMPLETION_LIMIT: usize = 50;
+
+/// A folder whose name starts with the typed prefix.
+#[derive(Serialize)]
+pub struct PathCompletion {
+    pub name: String,
+    pub path: String,
+}
+
+/// Complete the last component of `partial` to the folders it could name.
+///
+/// `partial` is split at its last separator into a folder to list and a
+/// prefix to match, so `/home/al` lists `/home` for names starting with
+/// `al`, and `/home/` lists all of `/home`. A leading `~` stands for the home
+/// folder. Prefixes match case-insensitively, with exact-case matches
+/// first. Hidden folders are left out unless `show_hidden` is set or the
+/// prefix starts with a dot. Relative paths have no completions.
+#[command]
+pub async fn autocomplete_path(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    partial: String,
+    show_hidden: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<PathCompletion>, ExplorerError> {
+    let partial = match partial.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', MAIN_SEPARATOR]) => {
+            let home = app
+                .path()
+                .home_dir()
+                .map_err(|e| format!("Failed to resolve home folder: {}", e))?;
+            format!("{}{}", home.display(), rest)
+        }
+        _ => partial,
+    };
+    let show_hidden = show_hidden.unwrap_or(false);
+    let limit = limit.unwrap_or(DEFAULT_COMPLETION_LIMIT);
+    tasks
+        .run_blocking("autocomplete_path", move || {
+            Ok(complete(&partial, show_hidden, limit))
+        })
+        .await
+}
+
+fn complete(partial: &str, show_hidden: bool, limit: usize) -> Vec<PathCompletion> {
+    let Some((dir, prefix)) = split_partial(partial) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let visibility = ListOptions {
+        show_hidden: show_hidden || prefix.starts_with('.'),
+        show_system: show_hidden,
+    };
+    let lower_prefix = prefix.to_lowercase();
+
+    let mut matches: Vec<(bool, PathCompletion)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.to_lowercase().starts_with(&lower_prefix) {
+                return None;
+            }
+            let path = entry.path();
+            // Follow links so symlinked folders complete too.
+            let meta = fs::metadata(&path).ok().filter(|meta| meta.is_dir())?;
+            if !visibility.shows(&path, &meta) {
+                return None;
+            }
+            let exact_case = name.starts_with(prefix.as_str());
+            Some((
+                exact_case,
+                PathCompletion {
+                    name,
+                    path: path.to_string_lossy().to_string(),
+                },
+            ))
+        })
+        .collect();
+    matches.sort_by(|(a_exact, a), (b_exact, b)| {
+        b_exact
+            .cmp(a_exact)
+            .then_with(|| natural_cmp(&a.name, &b.name))
+    });
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, completion)| completion)
+        .collect()
+}
+
+/// The folder to list and the name prefix to match, or `None` for relative
+/// paths.
+fn split_partial(partial: &str) -> Option<(PathBuf, String)> {
+    let path = Path::new(partial);
+    if !path.is_absolute() {
+        return None;
+    }
+    if partial.ends_with(['/', MAIN_SEPARATOR]) {
+        return Some((path.to_path_buf(), String::new()));
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            Some((parent.to_path_buf(), name.to_string_lossy().to_string()))
+        }
+        // A bare root such as `/` or `C:\`.
+        _ => Some((path.to_path_buf(), String::new())),
+    }
+}
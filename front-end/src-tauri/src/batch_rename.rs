@@ -0,0 +1,528 @@
+//! Renaming many items at once from a pattern.
+//!
+//! A pattern builds each new name from a template, then optionally replaces
+//! a regular expression in it and changes its case. `preview_batch_rename`
+//! shows the names without touching anything; `batch_rename` applies them.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State};
+
+use crate::error::ExplorerError;
+use crate::file_ops::{differs_only_in_case, validate_file_name, PathResult};
+use crate::journal::{self, JournalEntry, JournalKind};
+use crate::tasks::TaskRegistry;
+
+/// Format of `{date}` without one of its own.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Case change applied to new names.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseTransform {
+    Lower,
+    Upper,
+    /// First letter of each word upper case, the rest lower case.
+    Title,
+}
+
+/// How `batch_rename` builds new names.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct RenamePattern {
+    /// Template of the new name. `{name}` is the old name without its
+    /// extension and `{ext}` the extension with its dot, or nothing.
+    /// `{counter}` counts the items in order, zero-padded with e.g.
+    /// `{counter:03}`. `{date}` is the modification date as `2024-01-31`, or
+    /// in a strftime format with e.g. `{date:%Y%m%d}`. `{{` and `}}` are
+    /// literal braces.
+    pub template: String,
+    /// Counter value of the first item.
+    pub counter_start: u64,
+    pub counter_step: u64,
+    /// Regular expression replaced in the name built from the template.
+    pub find: Option<String>,
+    /// Replacement for `find`; `$1` or `${group}` insert its groups.
+    pub replace: String,
+    /// Applied last, to the name without its extension.
+    pub case: Option<CaseTransform>,
+}
+
+impl Default for RenamePattern {
+    fn default() -> Self {
+        RenamePattern {
+            template: "{name}{ext}".to_string(),
+            counter_start: 1,
+            counter_step: 1,
+            find: None,
+            replace: String::new(),
+            case: None,
+        }
+    }
+}
+
+/// The new name of one item.
+#[derive(Serialize)]
+pub struct RenamePreview {
+    pub path: String,
+    /// Empty if the item could not be read.
+    pub new_name: String,
+    /// Why the item would not be renamed, e.g. because another item would
+    /// get the same name; `None` if it would.
+    pub error: Option<String>,
+}
+
+enum Token {
+    Text(String),
+    Name,
+    Ext,
+    Counter { width: usize },
+    Date(String),
+}
+
+/// A `RenamePattern` parsed and checked.
+struct Renamer {
+    tokens: Vec<Token>,
+    counter_start: u64,
+    counter_step: u64,
+    find: Option<Regex>,
+    replace: String,
+    case: Option<CaseTransform>,
+}
+
+impl Renamer {
+    fn new(pattern: RenamePattern) -> Result<Self, ExplorerError> {
+        let find = pattern
+            .find
+            .filter(|find| !find.is_empty())
+            .map(|find| Regex::new(&find))
+            .transpose()
+            .map_err(|e| {
+                ExplorerError::InvalidInput(format!("Invalid regular expression: {}", e))
+            })?;
+        Ok(Renamer {
+            tokens: parse_template(&pattern.template).map_err(ExplorerError::InvalidInput)?,
+            counter_start: pattern.counter_start,
+            counter_step: pattern.counter_step,
+            find,
+            replace: pattern.replace,
+            case: pattern.case,
+        })
+    }
+
+    /// New name of `path`, the item at `position` in the batch.
+    fn new_name(&self, path: &Path, meta: &fs::Metadata, position: u64) -> String {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let ext = path
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        let counter = self
+            .counter_start
+            .saturating_add(position.saturating_mul(self.counter_step));
+        let modified = meta.modified().ok().map(DateTime::<Local>::from);
+
+        let mut name = String::new();
+        for token in &self.tokens {
+            match token {
+                Token::Text(text) => name.push_str(text),
+                Token::Name => name.push_str(&stem),
+                Token::Ext => name.push_str(&ext),
+                Token::Counter { width } => name.push_str(&format!("{:0width$}", counter)),
+                Token::Date(format) => {
+                    if let Some(modified) = modified {
+                        name.push_str(&modified.format(format).to_string());
+                    }
+                }
+            }
+        }
+        if let Some(find) = &self.find {
+            name = find.replace_all(&name, self.replace.as_str()).into_owned();
+        }
+        match self.case {
+            Some(case) => change_case(&name, case),
+            None => name,
+        }
+    }
+}
+
+/// Split a template into text and fields.
+fn parse_template(template: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek() == Some(&c) => {
+                chars.next();
+                text.push(c);
+            }
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => field.push(c),
+                        None => return Err("Unclosed { in template".to_string()),
+                    }
+                }
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(parse_field(&field)?);
+            }
+            '}' => return Err("Unmatched } in template; write }} for a brace".to_string()),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+fn parse_field(field: &str) -> Result<Token, String> {
+    match field.split_once(':') {
+        None if field == "name" => Ok(Token::Name),
+        None if field == "ext" => Ok(Token::Ext),
+        None if field == "counter" => Ok(Token::Counter { width: 0 }),
+        None if field == "date" => Ok(Token::Date(DEFAULT_DATE_FORMAT.to_string())),
+        Some(("counter", width)) => width
+            .parse()
+            .map(|width| Token::Counter { width })
+            .map_err(|_| format!("Invalid counter width: {}", width)),
+        // Checked here, since formatting with an invalid format panics.
+        Some(("date", format)) if StrftimeItems::new(format).any(|item| item == Item::Error) => {
+            Err(format!("Invalid date format: {}", format))
+        }
+        Some(("date", format)) => Ok(Token::Date(format.to_string())),
+        _ => Err(format!("Unknown field in template: {{{}}}", field)),
+    }
+}
+
+/// Change the case of `name`, leaving its extension as it is.
+fn change_case(name: &str, case: CaseTransform) -> String {
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let stem = match case {
+        CaseTransform::Lower => stem.to_lowercase(),
+        CaseTransform::Upper => stem.to_uppercase(),
+        CaseTransform::Title => {
+            let mut title = String::with_capacity(stem.len());
+            let mut word_start = true;
+            for c in stem.chars() {
+                if word_start {
+                    title.extend(c.to_uppercase());
+                } else {
+                    title.extend(c.to_lowercase());
+                }
+                word_start = c.is_whitespace() || matches!(c, '_' | '-' | '.');
+            }
+            title
+        }
+    };
+    format!("{}{}", stem, ext)
+}
+
+/// New names for `paths`, each with the reason it cannot be renamed, if
+/// any.
+fn plan(renamer: &Renamer, paths: &[String]) -> Vec<RenamePreview> {
+    let mut previews: Vec<RenamePreview> = paths
+        .iter()
+        .enumerate()
+        .map(|(position, path)| {
+            let (new_name, error) = match fs::symlink_metadata(path) {
+                Ok(_) if Path::new(path).parent().is_none() => (
+                    String::new(),
+                    Some("Cannot rename a filesystem root".to_string()),
+                ),
+                Ok(meta) => {
+                    let new_name = renamer.new_name(Path::new(path), &meta, position as u64);
+                    let error = validate_file_name(&new_name).err();
+                    (new_name, error)
+                }
+                Err(e) => (String::new(), Some(e.to_string())),
+            };
+            RenamePreview {
+                path: path.clone(),
+                new_name,
+                error,
+            }
+        })
+        .collect();
+
+    let mut taken: HashMap<PathBuf, usize> = HashMap::new();
+    for preview in previews.iter().filter(|preview| preview.error.is_none()) {
+        *taken.entry(target(preview)).or_default() += 1;
+    }
+    for preview in previews
+        .iter_mut()
+        .filter(|preview| preview.error.is_none())
+    {
+        if taken[&target(preview)] > 1 {
+            preview.error = Some("Another item would get the same name".to_string());
+        }
+    }
+
+    // Names of items that stay where they are, in the batch or not, are
+    // taken. An item that cannot be renamed stays too, so this is repeated
+    // until no more items are ruled out.
+    loop {
+        let vacated: HashSet<PathBuf> = previews
+            .iter()
+            .filter(|preview| preview.error.is_none())
+            .map(|preview| PathBuf::from(&preview.path))
+            .collect();
+        let mut ruled_out = false;
+        for preview in previews
+            .iter_mut()
+            .filter(|preview| preview.error.is_none())
+        {
+            let target = target(preview);
+            // A case-only change resolves to the same item on
+            // case-insensitive filesystems.
+            let same_item = Path::new(&preview.path)
+                .file_name()
+                .is_some_and(|old| differs_only_in_case(&old.to_string_lossy(), &preview.new_name));
+            if !same_item && !vacated.contains(&target) && fs::symlink_metadata(&target).is_ok() {
+                preview.error = Some(format!("{} already exists", target.display()));
+                ruled_out = true;
+            }
+        }
+        if !ruled_out {
+            return previews;
+        }
+    }
+}
+
+fn target(preview: &RenamePreview) -> PathBuf {
+    Path::new(&preview.path).with_file_name(&preview.new_name)
+}
+
+/// The names `batch_rename` would give `paths`, in the same order. Nothing
+/// is renamed.
+#[command]
+pub async fn preview_batch_rename(
+    tasks: State<'_, TaskRegistry>,
+    paths: Vec<String>,
+    pattern: RenamePattern,
+) -> Result<Vec<RenamePreview>, ExplorerError> {
+    let renamer = Renamer::new(pattern)?;
+    tasks
+        .run_blocking("preview_batch_rename", move || Ok(plan(&renamer, &paths)))
+        .await
+}
+
+/// Rename `paths` in place following `pattern`, numbering them in the order
+/// given. Returns one result per path, in the same order.
+///
+/// Items that `preview_batch_rename` reports an error for are left alone.
+/// The others are renamed even when they swap or shift names among
+/// themselves. The whole batch is one operation for `undo_last_operation`.
+#[command]
+pub async fn batch_rename(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    paths: Vec<String>,
+    pattern: RenamePattern,
+) -> Result<Vec<PathResult>, ExplorerError> {
+    let renamer = Renamer::new(pattern)?;
+    tasks
+        .run_blocking("batch_rename", move || {
+            let mut results = Vec::new();
+
+            // Everything is first moved aside under a temporary name, so no
+            // item is in the way of another.
+            let mut aside = Vec::new();
+            for (position, preview) in plan(&renamer, &paths).into_iter().enumerate() {
+                let source = PathBuf::from(&preview.path);
+                let unchanged = source
+                    .file_name()
+                    .is_some_and(|old| old.to_string_lossy() == preview.new_name);
+                if let Some(error) = preview.error {
+                    results.push((position, PathResult::from_result(preview.path, Err(error))));
+                } else if unchanged {
+                    results.push((
+                        position,
+                        PathResult::from_result::<String>(preview.path, Ok(())),
+                    ));
+                } else {
+                    let temporary = source
+                        .with_file_name(format!(".{}.renaming", uuid::Uuid::new_v4().simple()));
+                    match fs::rename(&source, &temporary) {
+                        Ok(()) => aside.push((position, preview.path, preview.new_name, temporary)),
+                        Err(e) => {
+                            results.push((position, PathResult::from_result(preview.path, Err(e))))
+                        }
+                    }
+                }
+            }
+
+            let mut entries = Vec::new();
+            for (position, path, new_name, temporary) in aside {
+                let source = Path::new(&path);
+                let target = source.with_file_name(&new_name);
+                let result = match rename_to_free(&temporary, &target) {
+                    Ok(()) => {
+                        entries.push(JournalEntry {
+                            source: path.clone(),
+                            target: Some(target.to_string_lossy().into_owned()),
+                        });
+                        Ok(())
+                    }
+                    Err(e) => match rename_to_free(&temporary, source) {
+                        Ok(()) => Err(e.to_string()),
+                        Err(_) => Err(format!(
+                            "{}; the item was left at {}",
+                            e,
+                            temporary.display()
+                        )),
+                    },
+                };
+                results.push((position, PathResult::from_result(path, result)));
+            }
+            journal::record(&app, JournalKind::Rename, &entries);
+
+            results.sort_by_key(|(position, _)| *position);
+            Ok(results.into_iter().map(|(_, result)| result).collect())
+        })
+        .await
+}
+
+/// Rename `from` to `to` unless something is already there; a plain rename
+/// would replace it on some systems.
+fn rename_to_free(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    fs::rename(from, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scratch directory, removed with its contents when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "ude-batch-rename-{}",
+                uuid::Uuid::new_v4().simple()
+            ));
+            fs::create_dir(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, name: &str) -> String {
+            let path = self.0.join(name);
+            fs::write(&path, name).unwrap();
+            path.to_string_lossy().into_owned()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn renamer(template: &str) -> Renamer {
+        Renamer::new(RenamePattern {
+            template: template.to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn new_names(renamer: &Renamer, paths: &[String]) -> Vec<String> {
+        plan(renamer, paths)
+            .into_iter()
+            .map(|preview| {
+                assert_eq!(preview.error, None, "{}", preview.path);
+                preview.new_name
+            })
+            .collect()
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        let tokens = parse_template("{{{name}}}").unwrap();
+        assert!(matches!(
+            tokens.as_slice(),
+            [Token::Text(open), Token::Name, Token::Text(close)] if open == "{" && close == "}"
+        ));
+
+        let dir = TempDir::new();
+        let paths = [dir.file("report.txt")];
+        assert_eq!(
+            new_names(&renamer("{{{name}}}{ext}"), &paths),
+            ["{report}.txt"]
+        );
+    }
+
+    #[test]
+    fn counter_is_zero_padded() {
+        let dir = TempDir::new();
+        let paths = [dir.file("a.jpg"), dir.file("b.jpg")];
+        let renamer = Renamer::new(RenamePattern {
+            template: "photo-{counter:03}{ext}".to_string(),
+            counter_start: 9,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            new_names(&renamer, &paths),
+            ["photo-009.jpg", "photo-010.jpg"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_date_format() {
+        let error = parse_template("{date:%Q}").err().unwrap();
+        assert_eq!(error, "Invalid date format: %Q");
+    }
+
+    #[test]
+    fn rejects_unclosed_field() {
+        assert!(parse_template("{name").is_err());
+        assert!(parse_template("{name}{").is_err());
+        assert!(parse_template("name}").is_err());
+    }
+
+    #[test]
+    fn plans_a_swap_of_two_names() {
+        let dir = TempDir::new();
+        let paths = [dir.file("2.txt"), dir.file("1.txt")];
+        assert_eq!(
+            new_names(&renamer("{counter}{ext}"), &paths),
+            ["1.txt", "2.txt"]
+        );
+    }
+
+    #[test]
+    fn refuses_names_taken_outside_the_batch() {
+        let dir = TempDir::new();
+        dir.file("1.txt");
+        let paths = [dir.file("a.txt")];
+        let previews = plan(&renamer("{counter}{ext}"), &paths);
+        assert_eq!(previews[0].new_name, "1.txt");
+        assert!(previews[0].error.is_some());
+    }
+}
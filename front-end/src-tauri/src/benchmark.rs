@@ -0,0 +1,405 @@
+//! Drive read and write speed tests.
+//!
+//! A temporary file is written to the drive and then read back, first in
+//! large sequential blocks and then in small blocks at random offsets, which
+//! is roughly how copying big files and running programs use a drive.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, State};
+
+use crate::drives::{containing_drive, list_drives};
+use crate::error::ExplorerError;
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::shred::random_stream;
+use crate::tasks::TaskRegistry;
+
+/// Event name used to report benchmark progress.
+pub const BENCHMARK_PROGRESS_EVENT: &str = "benchmark://progress";
+
+/// Minimum delay between two progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Unbuffered I/O needs buffers, offsets and sizes aligned to the sector
+/// size, which is at most 4 KiB on current drives.
+const ALIGNMENT: usize = 4096;
+
+/// Upper bounds on the options, to keep a mistaken value from filling the
+/// drive or running for hours.
+const MAX_FILE_SIZE_MB: u64 = 64 * 1024;
+const MAX_BLOCK_KB: u64 = 64 * 1024;
+const MAX_RANDOM_SECONDS: u64 = 120;
+
+/// Options accepted by `benchmark_drive`.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct BenchmarkOptions {
+    /// Size of the test file in MiB.
+    pub file_size_mb: u64,
+    /// Block size of the sequential tests in KiB.
+    pub sequential_block_kb: u64,
+    /// Block size of the random tests in KiB.
+    pub random_block_kb: u64,
+    /// How long each random test runs, in seconds.
+    pub random_seconds: u64,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        BenchmarkOptions {
+            file_size_mb: 256,
+            sequential_block_kb: 1024,
+            random_block_kb: 4,
+            random_seconds: 5,
+        }
+    }
+}
+
+impl BenchmarkOptions {
+    fn validate(&self) -> Result<(), ExplorerError> {
+        if !(1..=MAX_FILE_SIZE_MB).contains(&self.file_size_mb) {
+            return Err(ExplorerError::InvalidInput(format!(
+                "File size must be between 1 and {} MiB",
+                MAX_FILE_SIZE_MB
+            )));
+        }
+        for block_kb in [self.sequential_block_kb, self.random_block_kb] {
+            if block_kb == 0 || block_kb > MAX_BLOCK_KB || block_kb * 1024 % ALIGNMENT as u64 != 0 {
+                return Err(ExplorerError::InvalidInput(format!(
+                    "Block sizes must be multiples of {} KiB up to {} KiB",
+                    ALIGNMENT / 1024,
+                    MAX_BLOCK_KB
+                )));
+            }
+        }
+        if self.sequential_block_kb > self.file_size_mb * 1024 {
+            return Err(ExplorerError::InvalidInput(
+                "The sequential block size is larger than the test file".to_string(),
+            ));
+        }
+        if !(1..=MAX_RANDOM_SECONDS).contains(&self.random_seconds) {
+            return Err(ExplorerError::InvalidInput(format!(
+                "Random tests must run between 1 and {} seconds",
+                MAX_RANDOM_SECONDS
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The tests run by `benchmark_drive`, in order.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchmarkTest {
+    #[default]
+    SequentialWrite,
+    SequentialRead,
+    RandomWrite,
+    RandomRead,
+}
+
+impl BenchmarkTest {
+    const ALL: [BenchmarkTest; 4] = [
+        BenchmarkTest::SequentialWrite,
+        BenchmarkTest::SequentialRead,
+        BenchmarkTest::RandomWrite,
+        BenchmarkTest::RandomRead,
+    ];
+
+    fn is_write(self) -> bool {
+        matches!(
+            self,
+            BenchmarkTest::SequentialWrite | BenchmarkTest::RandomWrite
+        )
+    }
+
+    fn is_random(self) -> bool {
+        matches!(self, BenchmarkTest::RandomWrite | BenchmarkTest::RandomRead)
+    }
+}
+
+/// Payload of the `benchmark://progress` event.
+#[derive(Serialize, Clone, Default)]
+pub struct BenchmarkProgress {
+    pub operation_id: String,
+    pub test: BenchmarkTest,
+    /// Fraction of the current test done, from 0 to 1.
+    pub fraction: f64,
+    /// Speed of the current test so far.
+    pub bytes_per_second: f64,
+}
+
+/// Measured speed of one test.
+#[derive(Serialize)]
+pub struct BenchmarkResult {
+    pub test: BenchmarkTest,
+    pub block_size: u64,
+    pub bytes: u64,
+    pub seconds: f64,
+    pub bytes_per_second: f64,
+    /// Blocks read or written per second.
+    pub iops: f64,
+}
+
+/// Result of `benchmark_drive`.
+#[derive(Serialize)]
+pub struct BenchmarkReport {
+    pub operation_id: String,
+    pub mount_point: String,
+    pub file_size: u64,
+    /// Whether the tests bypassed the operating system's cache. When the
+    /// file system does not allow it, reads may be served from memory and
+    /// overstate the drive's speed.
+    pub cache_bypassed: bool,
+    pub results: Vec<BenchmarkResult>,
+}
+
+/// Measure the sequential and random read and write speed of the drive
+/// mounted at `mount_point`.
+///
+/// A temporary file of `options.file_size_mb` is written to the drive's root
+/// folder and deleted afterwards, even when the benchmark fails. Progress is
+/// emitted on `benchmark://progress` and the benchmark can be paused or
+/// cancelled through `operation_id`; pausing skews the test in progress.
+#[command]
+pub async fn benchmark_drive(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    mount_point: String,
+    options: Option<BenchmarkOptions>,
+    operation_id: Option<String>,
+) -> Result<BenchmarkReport, ExplorerError> {
+    let options = options.unwrap_or_default();
+    options.validate()?;
+    if !Path::new(&mount_point).is_dir() {
+        return Err(ExplorerError::NotFound(format!(
+            "Drive is not mounted: {}",
+            mount_point
+        )));
+    }
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("benchmark", move || {
+            let file_size = options.file_size_mb * 1024 * 1024;
+            let drives = list_drives();
+            if let Some(drive) = containing_drive(&drives, Path::new(&mount_point)) {
+                if drive.free_bytes < file_size + file_size / 10 {
+                    return Err(ExplorerError::InvalidInput(format!(
+                        "Not enough free space on {} for a {} MiB test file",
+                        mount_point, options.file_size_mb
+                    )));
+                }
+            }
+
+            let file = TestFile(
+                Path::new(&mount_point)
+                    .join(format!(".ude-benchmark-{}", uuid::Uuid::new_v4().simple())),
+            );
+            let mut bench = Bench {
+                app: &app,
+                operation: &handle,
+                progress: BenchmarkProgress {
+                    operation_id: id.clone(),
+                    ..Default::default()
+                },
+                last_emit: Instant::now(),
+                path: &file.0,
+                file_size: file_size - file_size % (options.sequential_block_kb * 1024),
+                random_duration: Duration::from_secs(options.random_seconds),
+                buffer: AlignedBuffer::new(
+                    options.sequential_block_kb.max(options.random_block_kb) as usize * 1024,
+                ),
+                offsets: random_stream(),
+                cache_bypassed: true,
+            };
+            let mut results = Vec::with_capacity(BenchmarkTest::ALL.len());
+            for test in BenchmarkTest::ALL {
+                let block_kb = if test.is_random() {
+                    options.random_block_kb
+                } else {
+                    options.sequential_block_kb
+                };
+                results.push(bench.run(test, block_kb * 1024).map_err(|e| {
+                    if handle.is_cancelled() {
+                        ExplorerError::Cancelled
+                    } else {
+                        ExplorerError::io(&format!("Failed to benchmark {}", mount_point), e)
+                    }
+                })?);
+            }
+
+            Ok(BenchmarkReport {
+                operation_id: id,
+                file_size: bench.file_size,
+                cache_bypassed: bench.cache_bypassed,
+                mount_point,
+                results,
+            })
+        })
+        .await
+}
+
+/// The benchmark's temporary file, deleted when dropped.
+struct TestFile(PathBuf);
+
+impl Drop for TestFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// A buffer whose start is aligned for unbuffered I/O.
+struct AlignedBuffer {
+    storage: Vec<u8>,
+    start: usize,
+}
+
+impl AlignedBuffer {
+    /// A buffer of `len` random bytes, so compressing drives and file
+    /// systems cannot shrink the writes.
+    fn new(len: usize) -> Self {
+        let mut storage = vec![0u8; len + ALIGNMENT];
+        let start = storage.as_ptr().align_offset(ALIGNMENT);
+        random_stream().fill(&mut storage[start..start + len]);
+        AlignedBuffer { storage, start }
+    }
+
+    fn get(&mut self, len: usize) -> &mut [u8] {
+        &mut self.storage[self.start..self.start + len]
+    }
+}
+
+/// State of a running `benchmark_drive`.
+struct Bench<'a> {
+    app: &'a AppHandle,
+    operation: &'a OperationHandle,
+    progress: BenchmarkProgress,
+    last_emit: Instant,
+    path: &'a Path,
+    file_size: u64,
+    random_duration: Duration,
+    buffer: AlignedBuffer,
+    /// Source of random block offsets.
+    offsets: blake3::OutputReader,
+    cache_bypassed: bool,
+}
+
+impl Bench<'_> {
+    /// Run `test` in blocks of `block_size` bytes. Sequential tests cover the
+    /// whole file once; random tests run for `random_duration`.
+    fn run(&mut self, test: BenchmarkTest, block_size: u64) -> io::Result<BenchmarkResult> {
+        let (mut file, cache_bypassed) = open_uncached(self.path)?;
+        self.cache_bypassed &= cache_bypassed;
+        self.progress.test = test;
+        let blocks = self.file_size / block_size;
+
+        let started = Instant::now();
+        let mut done: u64 = 0;
+        loop {
+            let fraction = if test.is_random() {
+                started.elapsed().as_secs_f64() / self.random_duration.as_secs_f64()
+            } else {
+                done as f64 / blocks as f64
+            };
+            if fraction >= 1.0 {
+                break;
+            }
+            self.operation.checkpoint().map_err(io::Error::other)?;
+
+            let block = if test.is_random() {
+                let mut bytes = [0u8; 8];
+                self.offsets.fill(&mut bytes);
+                u64::from_le_bytes(bytes) % blocks
+            } else {
+                done
+            };
+            file.seek(SeekFrom::Start(block * block_size))?;
+            let buffer = self.buffer.get(block_size as usize);
+            if test.is_write() {
+                file.write_all(buffer)?;
+            } else {
+                file.read_exact(buffer)?;
+            }
+            done += 1;
+
+            if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+                self.progress.fraction = fraction;
+                self.progress.bytes_per_second =
+                    (done * block_size) as f64 / started.elapsed().as_secs_f64();
+                self.emit();
+            }
+        }
+        if test.is_write() {
+            file.sync_all()?;
+        }
+
+        let seconds = started.elapsed().as_secs_f64();
+        self.progress.fraction = 1.0;
+        self.emit();
+        Ok(BenchmarkResult {
+            test,
+            block_size,
+            bytes: done * block_size,
+            seconds,
+            bytes_per_second: (done * block_size) as f64 / seconds,
+            iops: done as f64 / seconds,
+        })
+    }
+
+    fn emit(&mut self) {
+        let _ = self.app.emit(BENCHMARK_PROGRESS_EVENT, &self.progress);
+        self.last_emit = Instant::now();
+    }
+}
+
+fn open_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create(true).truncate(false);
+    options
+}
+
+/// Open the test file so reads and writes bypass the system cache, along
+/// with whether they do.
+#[cfg(windows)]
+fn open_uncached(path: &Path) -> io::Result<(File, bool)> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows::Win32::Storage::FileSystem::{FILE_FLAG_NO_BUFFERING, FILE_FLAG_WRITE_THROUGH};
+
+    match open_options()
+        .custom_flags(FILE_FLAG_NO_BUFFERING.0 | FILE_FLAG_WRITE_THROUGH.0)
+        .open(path)
+    {
+        Ok(file) => Ok((file, true)),
+        Err(_) => Ok((open_options().open(path)?, false)),
+    }
+}
+
+/// Open the test file so reads and writes bypass the system cache, along
+/// with whether they do.
+#[cfg(target_os = "macos")]
+fn open_uncached(path: &Path) -> io::Result<(File, bool)> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = open_options().open(path)?;
+    let bypassed = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) } != -1;
+    Ok((file, bypassed))
+}
+
+/// Open the test file so reads and writes bypass the system cache, along
+/// with whether they do.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn open_uncached(path: &Path) -> io::Result<(File, bool)> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    // Some file systems, such as tmpfs, reject O_DIRECT.
+    match open_options().custom_flags(libc::O_DIRECT).open(path) {
+        Ok(file) => Ok((file, true)),
+        Err(_) => Ok((open_options().open(path)?, false)),
+    }
+}
@@ -0,0 +1,352 @@
+//! `ude`, the explorer's scanner and duplicate finder without the GUI.
+//!
+//! ```text
+//! ude scan <path> [--json] [--max-depth N] [--threads N] [--follow-symlinks]
+//!                 [--no-hidden] [--no-system] [--exclude PATTERN]...
+//! ude duplicates <path> [--json] [--min-size BYTES] [--follow-symlinks]
+//!                 [--exclude PATTERN]...
+//! ```
+//!
+//! With `--json` results are written as NDJSON, one object per line with a
+//! `type` of `folder`, `error`, `group` or `summary`, so they can be piped
+//! into other tools. Duplicate groups are written as soon as they are
+//! confirmed. Scans from the command line do not update the app's index, and
+//! the app's exclusion rules do not apply; pass `--exclude` instead.
+
+use std::error::Error;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use serde::Serialize;
+use universal_disk_explorer_lib::duplicates::{self, DuplicateGroup, DuplicateOptions};
+use universal_disk_explorer_lib::exclusions::{ExclusionRules, Exclusions};
+use universal_disk_explorer_lib::operations::OperationHandle;
+use universal_disk_explorer_lib::scanner::{self, DirNode, ScanError, ScanOptions};
+
+const USAGE: &str = "\
+Usage:
+  ude scan <path> [--json] [--max-depth N] [--threads N] [--follow-symlinks]
+                  [--no-hidden] [--no-system] [--exclude PATTERN]...
+  ude duplicates <path> [--json] [--min-size BYTES] [--follow-symlinks]
+                  [--exclude PATTERN]...";
+
+/// Folder depth printed by `scan` without `--json` unless `--max-depth` is
+/// given.
+const DEFAULT_TEXT_DEPTH: usize = 1;
+
+/// A line of `--json` output.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Line<'a> {
+    /// A scanned folder, without its children. Parents come before their
+    /// children.
+    Folder {
+        depth: usize,
+        name: &'a str,
+        path: &'a str,
+        apparent_size: u64,
+        disk_usage: u64,
+        allocated_size: u64,
+        file_count: u64,
+        placeholder_count: u64,
+        placeholder_size: u64,
+        is_symlink: bool,
+        symlink_target: Option<&'a str>,
+    },
+    Error(&'a ScanError),
+    Group(&'a DuplicateGroup),
+    Summary {
+        files_scanned: u64,
+        groups: usize,
+        total_reclaimable: u64,
+    },
+}
+
+/// Parsed command line.
+struct Args {
+    command: String,
+    path: String,
+    json: bool,
+    max_depth: Option<usize>,
+    threads: Option<usize>,
+    min_size: u64,
+    follow_symlinks: bool,
+    no_hidden: bool,
+    no_system: bool,
+    exclude: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("ude: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let result = match args.command.as_str() {
+        "scan" => scan(&args),
+        _ => find_duplicates(&args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader went away, e.g. `ude scan / --json | head`.
+        Err(e)
+            if e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("ude: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let command = args.next().ok_or("No command given")?;
+    if command == "-h" || command == "--help" {
+        println!("{}", USAGE);
+        std::process::exit(0);
+    }
+    if command != "scan" && command != "duplicates" {
+        return Err(format!("Unknown command: {}", command));
+    }
+    let mut parsed = Args {
+        command,
+        path: String::new(),
+        json: false,
+        max_depth: None,
+        threads: None,
+        min_size: 0,
+        follow_symlinks: false,
+        no_hidden: false,
+        no_system: false,
+        exclude: Vec::new(),
+    };
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        let number = |name: &str, value: String| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("{} expects a number, got {}", name, value))
+        };
+        match arg.as_str() {
+            "--json" => parsed.json = true,
+            "--follow-symlinks" => parsed.follow_symlinks = true,
+            "--no-hidden" => parsed.no_hidden = true,
+            "--no-system" => parsed.no_system = true,
+            "--max-depth" => parsed.max_depth = Some(number(&arg, value(&arg)?)? as usize),
+            "--threads" => parsed.threads = Some(number(&arg, value(&arg)?)? as usize),
+            "--min-size" => parsed.min_size = number(&arg, value(&arg)?)?,
+            "--exclude" => parsed.exclude.push(value(&arg)?),
+            flag if flag.starts_with("--") => return Err(format!("Unknown option: {}", flag)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    // Drop trailing separators, as the app does, so reported paths line up.
+    parsed.path = Path::new(&path.ok_or("No path given")?)
+        .components()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .to_string();
+    Ok(parsed)
+}
+
+fn compile_exclusions(args: &Args) -> Result<Exclusions, String> {
+    let rules = ExclusionRules {
+        patterns: args.exclude.clone(),
+        ..Default::default()
+    };
+    Exclusions::new(&rules, Path::new(&args.path))
+}
+
+fn scan(args: &Args) -> Result<(), Box<dyn Error>> {
+    let mut options = ScanOptions {
+        follow_symlinks: args.follow_symlinks,
+        threads: args.threads,
+        ..Default::default()
+    };
+    options.visibility.show_hidden = !args.no_hidden;
+    options.visibility.show_system = !args.no_system;
+
+    let show_progress = io::stderr().is_terminal();
+    let report = scanner::scan_tree(
+        &OperationHandle::default(),
+        None,
+        String::new(),
+        args.path.clone(),
+        &options,
+        compile_exclusions(args)?,
+        &mut |progress| {
+            if show_progress {
+                eprint!(
+                    "\r\x1b[KScanned {} files, {}",
+                    progress.scanned_files,
+                    format_size(progress.total_size)
+                );
+            }
+        },
+    )?;
+    if show_progress {
+        eprint!("\r\x1b[K");
+    }
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    if args.json {
+        write_folders(&mut out, &report.tree, 0, args.max_depth)?;
+        for error in &report.errors {
+            write_line(&mut out, &Line::Error(error))?;
+        }
+    } else {
+        let max_depth = args.max_depth.unwrap_or(DEFAULT_TEXT_DEPTH);
+        print_tree(&mut out, &report.tree, 0, max_depth)?;
+        if !report.errors.is_empty() {
+            eprintln!(
+                "{} entries could not be read; the sizes above them are incomplete",
+                report.errors.len()
+            );
+        }
+    }
+    Ok(out.flush()?)
+}
+
+fn find_duplicates(args: &Args) -> Result<(), Box<dyn Error>> {
+    let options = DuplicateOptions {
+        min_size: args.min_size,
+        follow_symlinks: args.follow_symlinks,
+    };
+    let mut out = BufWriter::new(io::stdout().lock());
+    // Groups are streamed as they are confirmed; a broken pipe ends the
+    // output but is only reported once the search returns.
+    let mut write_error = None;
+    let report = duplicates::search(
+        &OperationHandle::default(),
+        &args.path,
+        &options,
+        &compile_exclusions(args)?,
+        &mut |group| {
+            if args.json && write_error.is_none() {
+                write_error = write_line(&mut out, &Line::Group(group))
+                    .and_then(|()| out.flush())
+                    .err();
+            }
+        },
+    )?;
+    if let Some(e) = write_error {
+        return Err(e.into());
+    }
+
+    if args.json {
+        write_line(
+            &mut out,
+            &Line::Summary {
+                files_scanned: report.files_scanned,
+                groups: report.groups.len(),
+                total_reclaimable: report.total_reclaimable,
+            },
+        )?;
+    } else {
+        for group in &report.groups {
+            writeln!(
+                out,
+                "{} x {} ({} reclaimable)",
+                group.paths.len(),
+                format_size(group.size),
+                format_size(group.reclaimable)
+            )?;
+            for path in &group.paths {
+                writeln!(out, "  {}", path).map_err(|e| e.to_string())?;
+            }
+        }
+        writeln!(
+            out,
+            "{} groups in {} files, {} reclaimable",
+            report.groups.len(),
+            report.files_scanned,
+            format_size(report.total_reclaimable)
+        )?;
+    }
+    Ok(out.flush()?)
+}
+
+fn write_line(out: &mut impl Write, line: &Line) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, line)?;
+    out.write_all(b"\n")
+}
+
+/// Write `node` and its subfolders down to `max_depth`, parents first.
+fn write_folders(
+    out: &mut impl Write,
+    node: &DirNode,
+    depth: usize,
+    max_depth: Option<usize>,
+) -> io::Result<()> {
+    write_line(
+        out,
+        &Line::Folder {
+            depth,
+            name: &node.name,
+            path: &node.path,
+            apparent_size: node.apparent_size,
+            disk_usage: node.disk_usage,
+            allocated_size: node.allocated_size,
+            file_count: node.file_count,
+            placeholder_count: node.placeholder_count,
+            placeholder_size: node.placeholder_size,
+            is_symlink: node.is_symlink,
+            symlink_target: node.symlink_target.as_deref(),
+        },
+    )?;
+    if max_depth.is_none_or(|max| depth < max) {
+        for child in &node.children {
+            write_folders(out, child, depth + 1, max_depth)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print `node` and its subfolders down to `max_depth`, largest first.
+fn print_tree(
+    out: &mut impl Write,
+    node: &DirNode,
+    depth: usize,
+    max_depth: usize,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>10}  {:>9} files  {}{}",
+        format_size(node.disk_usage),
+        node.file_count,
+        "  ".repeat(depth),
+        if depth == 0 { &node.path } else { &node.name }
+    )?;
+    if depth < max_depth {
+        let mut children: Vec<&DirNode> = node.children.iter().collect();
+        children.sort_by_key(|child| std::cmp::Reverse(child.disk_usage));
+        for child in children {
+            print_tree(out, child, depth + 1, max_depth)?;
+        }
+    }
+    Ok(())
+}
+
+/// `bytes` in binary units, e.g. `1.5 GiB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
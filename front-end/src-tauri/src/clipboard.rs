@@ -0,0 +1,428 @@
+//! Copying and pasting files through the system clipboard.
+//!
+//! Files are stored in the formats other file managers use, so files copied
+//! here can be pasted in Explorer, Finder or Nautilus and the other way
+//! round: `CF_HDROP` with a `Preferred DropEffect` on Windows, file URLs on
+//! the general pasteboard on macOS, and `x-special/gnome-copied-files` on
+//! Linux.
+
+use std::path::PathBuf;
+
+use tauri::{command, AppHandle, State, Window};
+
+use crate::error::ExplorerError;
+use crate::file_ops::{copy_items, move_items, ConflictPolicy, FileOpReport};
+use crate::operations::OperationRegistry;
+use crate::tasks::TaskRegistry;
+
+/// Files on the clipboard.
+struct ClipboardFiles {
+    paths: Vec<PathBuf>,
+    /// Whether the files were cut, so pasting moves them.
+    cut: bool,
+}
+
+/// Put `paths` on the system clipboard to be copied when pasted.
+#[command]
+pub async fn clipboard_copy_files(
+    window: Window,
+    tasks: State<'_, TaskRegistry>,
+    paths: Vec<String>,
+) -> Result<(), ExplorerError> {
+    put_files(window, &tasks, paths, false).await
+}
+
+/// Put `paths` on the system clipboard to be moved when pasted.
+#[command]
+pub async fn clipboard_cut_files(
+    window: Window,
+    tasks: State<'_, TaskRegistry>,
+    paths: Vec<String>,
+) -> Result<(), ExplorerError> {
+    put_files(window, &tasks, paths, true).await
+}
+
+/// Paste the files on the system clipboard into the `destination` folder.
+///
+/// Copied files are copied and cut files are moved, as with `copy_items` and
+/// `move_items`, with conflicts handled by `conflict` and copies checked with
+/// `verify`, and the paste can be paused or cancelled through `operation_id`
+/// in the same way. The clipboard is left as is, so pasting cut files a
+/// second time reports them as missing.
+#[command]
+pub async fn clipboard_paste(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    destination: String,
+    operation_id: Option<String>,
+    conflict: Option<ConflictPolicy>,
+    verify: Option<bool>,
+) -> Result<FileOpReport, ExplorerError> {
+    let files = tasks
+        .run_blocking("clipboard_read", || Ok(read_files()?))
+        .await?;
+    if files.paths.is_empty() {
+        return Err(ExplorerError::NotFound(
+            "The clipboard holds no files".to_string(),
+        ));
+    }
+    let sources = files
+        .paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    if files.cut {
+        move_items(
+            app,
+            tasks,
+            operations,
+            sources,
+            destination,
+            operation_id,
+            conflict,
+            verify,
+        )
+        .await
+    } else {
+        copy_items(
+            app,
+            tasks,
+            operations,
+            sources,
+            destination,
+            operation_id,
+            conflict,
+            verify,
+        )
+        .await
+    }
+}
+
+async fn put_files(
+    window: Window,
+    tasks: &TaskRegistry,
+    paths: Vec<String>,
+    cut: bool,
+) -> Result<(), ExplorerError> {
+    if paths.is_empty() {
+        return Err(ExplorerError::InvalidInput("No files given".to_string()));
+    }
+    let mut absolute = Vec::with_capacity(paths.len());
+    for path in paths {
+        let path = PathBuf::from(path);
+        if std::fs::symlink_metadata(&path).is_err() {
+            return Err(ExplorerError::NotFound(format!(
+                "Path does not exist: {}",
+                path.display()
+            )));
+        }
+        absolute.push(
+            std::path::absolute(&path)
+                .map_err(|e| ExplorerError::io("Failed to resolve path", e))?,
+        );
+    }
+    tasks
+        .run_blocking("clipboard_write", move || {
+            Ok(write_files(&window, &absolute, cut)?)
+        })
+        .await
+}
+
+/// Copy `bytes` into a movable global memory block, as the clipboard takes
+/// its data.
+#[cfg(windows)]
+fn global_copy(bytes: &[u8]) -> Result<windows::Win32::Foundation::HGLOBAL, String> {
+    use windows::Win32::Foundation::GlobalFree;
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+
+    unsafe {
+        let memory = GlobalAlloc(GMEM_MOVEABLE, bytes.len())
+            .map_err(|e| format!("Failed to allocate clipboard data: {}", e))?;
+        let target = GlobalLock(memory) as *mut u8;
+        if target.is_null() {
+            let _ = GlobalFree(memory);
+            return Err("Failed to lock clipboard data".to_string());
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), target, bytes.len());
+        // Fails with no error once the block is unlocked.
+        let _ = GlobalUnlock(memory);
+        Ok(memory)
+    }
+}
+
+/// The registered format Explorer uses to tell copied from cut files.
+#[cfg(windows)]
+fn drop_effect_format() -> u32 {
+    use windows::Win32::System::DataExchange::RegisterClipboardFormatW;
+
+    unsafe { RegisterClipboardFormatW(windows::core::w!("Preferred DropEffect")) }
+}
+
+#[cfg(windows)]
+fn write_files(window: &Window, paths: &[PathBuf], cut: bool) -> Result<(), String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::Foundation::{GlobalFree, BOOL, HANDLE, HWND, POINT};
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+    };
+    use windows::Win32::System::Ole::{CF_HDROP, DROPEFFECT_COPY, DROPEFFECT_MOVE};
+    use windows::Win32::UI::Shell::DROPFILES;
+
+    // A DROPFILES header followed by the paths as a double-null-terminated
+    // list of wide strings.
+    let header = DROPFILES {
+        pFiles: std::mem::size_of::<DROPFILES>() as u32,
+        pt: POINT::default(),
+        fNC: BOOL(0),
+        fWide: BOOL(1),
+    };
+    let mut drop_files = unsafe {
+        std::slice::from_raw_parts(
+            &header as *const DROPFILES as *const u8,
+            std::mem::size_of::<DROPFILES>(),
+        )
+    }
+    .to_vec();
+    for path in paths {
+        for unit in path.as_os_str().encode_wide().chain(std::iter::once(0)) {
+            drop_files.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+    drop_files.extend_from_slice(&0u16.to_le_bytes());
+    let effect = if cut {
+        DROPEFFECT_MOVE
+    } else {
+        DROPEFFECT_COPY
+    };
+
+    // Clipboard data needs an owner window; with none, SetClipboardData
+    // fails.
+    let owner = window.hwnd().map_err(|e| e.to_string())?;
+    unsafe {
+        OpenClipboard(HWND(owner.0)).map_err(|e| format!("Failed to open the clipboard: {}", e))?;
+        let result = EmptyClipboard()
+            .map_err(|e| format!("Failed to clear the clipboard: {}", e))
+            .and_then(|()| {
+                for (format, bytes) in [
+                    (CF_HDROP.0 as u32, drop_files),
+                    (drop_effect_format(), effect.0.to_le_bytes().to_vec()),
+                ] {
+                    let memory = global_copy(&bytes)?;
+                    // The clipboard owns the memory once the call succeeds.
+                    if let Err(e) = SetClipboardData(format, HANDLE(memory.0)) {
+                        let _ = GlobalFree(memory);
+                        return Err(format!("Failed to set clipboard data: {}", e));
+                    }
+                }
+                Ok(())
+            });
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+#[cfg(windows)]
+fn read_files() -> Result<ClipboardFiles, String> {
+    use std::os::windows::ffi::OsStringExt;
+    use windows::Win32::Foundation::{HGLOBAL, HWND};
+    use windows::Win32::System::DataExchange::{CloseClipboard, GetClipboardData, OpenClipboard};
+    use windows::Win32::System::Memory::{GlobalLock, GlobalUnlock};
+    use windows::Win32::System::Ole::{CF_HDROP, DROPEFFECT_MOVE};
+    use windows::Win32::UI::Shell::{DragQueryFileW, HDROP};
+
+    let mut files = ClipboardFiles {
+        paths: Vec::new(),
+        cut: false,
+    };
+    unsafe {
+        OpenClipboard(HWND::default())
+            .map_err(|e| format!("Failed to open the clipboard: {}", e))?;
+        if let Ok(data) = GetClipboardData(CF_HDROP.0 as u32) {
+            let drop = HDROP(data.0);
+            let count = DragQueryFileW(drop, u32::MAX, None);
+            for index in 0..count {
+                let len = DragQueryFileW(drop, index, None) as usize;
+                let mut buffer = vec![0u16; len + 1];
+                DragQueryFileW(drop, index, Some(&mut buffer));
+                files
+                    .paths
+                    .push(PathBuf::from(std::ffi::OsString::from_wide(&buffer[..len])));
+            }
+        }
+        if let Ok(data) = GetClipboardData(drop_effect_format()) {
+            let memory = HGLOBAL(data.0);
+            let effect = GlobalLock(memory) as *const u32;
+            if !effect.is_null() {
+                files.cut = effect.read_unaligned() & DROPEFFECT_MOVE.0 != 0;
+                let _ = GlobalUnlock(memory);
+            }
+        }
+        let _ = CloseClipboard();
+    }
+    Ok(files)
+}
+
+/// The pasteboard's change count right after files were cut, as long as
+/// they may still be on it.
+///
+/// The pasteboard has no notion of cut files, since Finder moves files by
+/// pasting with a modifier instead, so cuts are remembered here.
+#[cfg(target_os = "macos")]
+static CUT_CHANGE_COUNT: std::sync::Mutex<Option<isize>> = std::sync::Mutex::new(None);
+
+#[cfg(target_os = "macos")]
+fn write_files(_window: &Window, paths: &[PathBuf], cut: bool) -> Result<(), String> {
+    use objc2::runtime::ProtocolObject;
+    use objc2_app_kit::NSPasteboard;
+    use objc2_foundation::{NSArray, NSString, NSURL};
+
+    let urls = paths
+        .iter()
+        .map(|path| {
+            let url =
+                unsafe { NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy())) };
+            ProtocolObject::from_retained(url)
+        })
+        .collect();
+    unsafe {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        pasteboard.clearContents();
+        if !pasteboard.writeObjects(&NSArray::from_vec(urls)) {
+            return Err("Failed to write to the pasteboard".to_string());
+        }
+        *CUT_CHANGE_COUNT.lock().map_err(|e| e.to_string())? =
+            cut.then(|| pasteboard.changeCount());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn read_files() -> Result<ClipboardFiles, String> {
+    use objc2_app_kit::{NSPasteboard, NSPasteboardTypeFileURL};
+    use objc2_foundation::NSURL;
+
+    unsafe {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        let paths = pasteboard
+            .pasteboardItems()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.stringForType(NSPasteboardTypeFileURL))
+                    .filter_map(|url| NSURL::URLWithString(&url))
+                    // Resolves the file reference URLs Finder copies.
+                    .filter_map(|url| url.path())
+                    .map(|path| PathBuf::from(path.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let cut =
+            *CUT_CHANGE_COUNT.lock().map_err(|e| e.to_string())? == Some(pasteboard.changeCount());
+        Ok(ClipboardFiles { paths, cut })
+    }
+}
+
+/// The clipboard target GNOME, Cinnamon, MATE and Xfce file managers use.
+#[cfg(all(unix, not(target_os = "macos")))]
+const GNOME_COPIED_FILES: &str = "x-special/gnome-copied-files";
+
+/// The clipboard is reached through `wl-copy`/`wl-paste` on Wayland and
+/// `xclip` on X11.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn clipboard_command(read: bool, target: &str) -> std::process::Command {
+    use std::process::Command;
+
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = Command::new(if read { "wl-paste" } else { "wl-copy" });
+        if read {
+            command.arg("--no-newline");
+        }
+        command.args(["--type", target]);
+        command
+    } else {
+        let mut command = Command::new("xclip");
+        command.args(["-selection", "clipboard", "-t", target]);
+        command.arg(if read { "-o" } else { "-i" });
+        command
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn write_files(_window: &Window, paths: &[PathBuf], cut: bool) -> Result<(), String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut text = String::from(if cut { "cut" } else { "copy" });
+    for path in paths {
+        let url = tauri::Url::from_file_path(path)
+            .map_err(|()| format!("Not an absolute path: {}", path.display()))?;
+        text.push('\n');
+        text.push_str(url.as_str());
+    }
+
+    // Both tools keep running in the background to serve the clipboard, so
+    // their output is not captured; waiting for it would never end.
+    let mut command = clipboard_command(false, GNOME_COPIED_FILES);
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} failed to set the clipboard", program));
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn read_files() -> Result<ClipboardFiles, String> {
+    let read = |target: &str| -> Result<Option<String>, String> {
+        let mut command = clipboard_command(true, target);
+        let output = command.output().map_err(|e| {
+            format!(
+                "Failed to run {}: {}",
+                command.get_program().to_string_lossy(),
+                e
+            )
+        })?;
+        // Both tools fail when the clipboard does not offer the target.
+        Ok(output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).to_string()))
+    };
+    let to_paths = |lines: std::str::Lines| -> Vec<PathBuf> {
+        lines
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| tauri::Url::parse(line).ok()?.to_file_path().ok())
+            .collect()
+    };
+
+    if let Some(text) = read(GNOME_COPIED_FILES)? {
+        let mut lines = text.lines();
+        let cut = lines.next().map(str::trim) == Some("cut");
+        return Ok(ClipboardFiles {
+            paths: to_paths(lines),
+            cut,
+        });
+    }
+    // Other applications, such as KDE's, only offer a URI list.
+    Ok(ClipboardFiles {
+        paths: read("text/uri-list")?
+            .map(|text| to_paths(text.lines()))
+            .unwrap_or_default(),
+        cut: false,
+    })
+}
@@ -1,98 +1,484 @@
-use std::fs;
-use std::io::Cursor;
-use std::path::Path;
-use base64::encode;
-use image::{
-    imageops::FilterType,
-    io::Reader as ImageReader,
-    DynamicImage,
-    GenericImageView,
-    ImageOutputFormat,
-};
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
 
-/// Structure returned by the thumbnail command.
-#[derive(Serialize, Deserialize)]
-pub struct ThumbnailResponse {
-    pub data_url: String,
-    pub width: u32,
-    pub height: u32,
+/// A single entry returned by the directory listing command.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DirEntryInfo {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    /// Seconds since the Unix epoch, if the platform reports it.
+    pub modified: Option<u64>,
+    /// Seconds since the Unix epoch, if the platform reports it.
+    pub created: Option<u64>,
+    pub is_dir: bool,
+    /// True for symlinks and, on Windows, directory junctions.
+    pub is_symlink: bool,
+    /// Where the link points, as stored in the link.
+    pub symlink_target: Option<String>,
+    /// The link exists but its target does not.
+    pub is_broken_link: bool,
+    /// The file is an online-only placeholder of a cloud storage client, so
+    /// deleting it frees no space and reading it downloads it.
+    pub is_cloud_placeholder: bool,
+    pub extension: Option<String>,
 }
 
-/// Generate a thumbnail from an image file.
-#[command]
-pub fn get_thumbnail(path: String) -> Result<ThumbnailResponse, String> {
-    // Open and decode the image.
-    let img: DynamicImage = ImageReader::open(&path)
-        .map_err(|e| format!("Failed to open image: {}", e))?
-        .decode()
-        .map_err(|e| format!("Failed to decode image: {}", e))?;
+/// Field `list_directory` sorts entries by.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// Natural order, so `file2` comes before `file10`.
+    #[default]
+    Name,
+    Size,
+    Modified,
+    /// Extension, then name.
+    Type,
+}
+
+/// Order of a directory listing. Ties are broken by name.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ListSort {
+    pub key: SortKey,
+    pub descending: bool,
+    /// List folders before files whatever the sort key.
+    pub folders_first: bool,
+}
+
+impl Default for ListSort {
+    fn default() -> Self {
+        ListSort {
+            key: SortKey::Name,
+            descending: false,
+            folders_first: true,
+        }
+    }
+}
+
+impl ListSort {
+    fn compare(&self, a: &DirEntryInfo, b: &DirEntryInfo) -> Ordering {
+        if self.folders_first && a.is_dir != b.is_dir {
+            return b.is_dir.cmp(&a.is_dir);
+        }
+        let order = match self.key {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+            SortKey::Type => a.extension.cmp(&b.extension),
+        }
+        .then_with(|| natural_cmp(&a.name, &b.name));
+        if self.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    }
+}
+
+/// A page of a directory listing.
+#[derive(Serialize)]
+pub struct DirectoryPage {
+    pub entries: Vec<DirEntryInfo>,
+    /// Number of entries in the whole directory after filtering.
+    pub total: usize,
+}
+
+/// Compare names the way people read them: runs of digits by their value
+/// and everything else case-insensitively.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_chars, mut b_chars) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let (a_next, b_next) = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(&a_next), Some(&b_next)) => (a_next, b_next),
+        };
+        let order = if a_next.is_ascii_digit() && b_next.is_ascii_digit() {
+            let a_digits = take_digits(&mut a_chars);
+            let b_digits = take_digits(&mut b_chars);
+            let (a_value, b_value) = (
+                a_digits.trim_start_matches('0'),
+                b_digits.trim_start_matches('0'),
+            );
+            a_value
+                .len()
+                .cmp(&b_value.len())
+                .then_with(|| a_value.cmp(b_value))
+        } else {
+            a_chars.next();
+            b_chars.next();
+            a_next.to_lowercase().cmp(b_next.to_lowercase())
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(digit);
+    }
+    digits
+}
+
+/// Visibility filters shared by listing and scanning commands.
+///
+/// Hidden entries are dotfiles on Unix and entries with the hidden attribute
+/// on Windows; system entries only exist on Windows. Both are shown unless
+/// turned off.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ListOptions {
+    pub show_hidden: bool,
+    pub show_system: bool,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        ListOptions {
+            show_hidden: true,
+            show_system: true,
+        }
+    }
+}
+
+impl ListOptions {
+    /// Whether nothing is filtered out.
+    pub fn shows_all(&self) -> bool {
+        self.show_hidden && self.show_system
+    }
+
+    /// Whether an entry passes the hidden and system filters.
+    pub fn shows(&self, path: &Path, meta: &fs::Metadata) -> bool {
+        (self.show_hidden || !is_hidden(path, meta)) && (self.show_system || !is_system(meta))
+    }
+}
+
+#[cfg(windows)]
+fn is_hidden(_path: &Path, meta: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    meta.file_attributes() & windows::Win32::Storage::FileSystem::FILE_ATTRIBUTE_HIDDEN.0 != 0
+}
+
+#[cfg(not(windows))]
+fn is_hidden(path: &Path, _meta: &fs::Metadata) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
 
-    // Get original dimensions.
-    let (width, height) = img.dimensions();
+#[cfg(windows)]
+fn is_system(meta: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    meta.file_attributes() & windows::Win32::Storage::FileSystem::FILE_ATTRIBUTE_SYSTEM.0 != 0
+}
 
-    // Calculate aspect ratio preserving dimensions.
-    let aspect_ratio = width as f32 / height as f32;
-    let new_width = 100;
-    let new_height = (new_width as f32 / aspect_ratio) as u32;
+#[cfg(not(windows))]
+fn is_system(_meta: &fs::Metadata) -> bool {
+    false
+}
 
-    // Resize with high-quality Lanczos3 filter.
-    let thumbnail = img.resize_exact(new_width, new_height, FilterType::Lanczos3);
+/// Whether an entry is an online-only placeholder whose contents are fetched
+/// from cloud storage (OneDrive, iCloud Drive, Dropbox and others) on access.
+///
+/// Cloud files are reparse points whose recall attributes are part of the
+/// directory listing, so the reparse tag itself need not be read by opening
+/// the file, which could start a download.
+#[cfg(windows)]
+pub fn is_cloud_placeholder(meta: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    use windows::Win32::Storage::FileSystem::{
+        FILE_ATTRIBUTE_OFFLINE, FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS, FILE_ATTRIBUTE_RECALL_ON_OPEN,
+    };
+    let recall = FILE_ATTRIBUTE_OFFLINE.0
+        | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS.0
+        | FILE_ATTRIBUTE_RECALL_ON_OPEN.0;
+    meta.file_attributes() & recall != 0
+}
 
-    // Encode the thumbnail to JPEG bytes.
-    let mut bytes: Vec<u8> = Vec::new();
-    thumbnail
-        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Jpeg(90))
-        .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+/// Whether an entry is an online-only placeholder whose contents are fetched
+/// from cloud storage (iCloud Drive, OneDrive, Dropbox and others) on access.
+///
+/// File provider placeholders carry the `SF_DATALESS` flag.
+#[cfg(target_os = "macos")]
+pub fn is_cloud_placeholder(meta: &fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const SF_DATALESS: u32 = 0x4000_0000;
+    meta.st_flags() & SF_DATALESS != 0
+}
 
-    // Create a data URL with the base64-encoded image.
-    let data_url = format!("data:image/jpeg;base64,{}", encode(&bytes));
+/// Whether an entry is an online-only placeholder whose contents are fetched
+/// from cloud storage on access. Linux sync clients download files in full.
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn is_cloud_placeholder(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+/// Bytes a file occupies on disk, which is less than its length for sparse
+/// and compressed files and rounded up to whole blocks otherwise.
+#[cfg(windows)]
+pub fn allocated_size(path: &Path, meta: &fs::Metadata) -> u64 {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{GetLastError, NO_ERROR};
+    use windows::Win32::Storage::FileSystem::{GetCompressedFileSizeW, INVALID_FILE_SIZE};
+
+    if !meta.is_file() {
+        return 0;
+    }
+    let wide: Vec<u16> = crate::long_paths::to_extended(path)
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut high = 0u32;
+    let low = unsafe { GetCompressedFileSizeW(PCWSTR(wide.as_ptr()), Some(&mut high)) };
+    if low == INVALID_FILE_SIZE && unsafe { GetLastError() } != NO_ERROR {
+        return meta.len();
+    }
+    (high as u64) << 32 | low as u64
+}
 
-    Ok(ThumbnailResponse {
-        data_url,
-        width: new_width,
-        height: new_height,
-    })
+/// Bytes a file occupies on disk, which is less than its length for sparse
+/// and compressed files and rounded up to whole blocks otherwise.
+#[cfg(unix)]
+pub fn allocated_size(_path: &Path, meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    // `st_blocks` is in 512-byte units whatever the file system block size.
+    meta.blocks() * 512
 }
 
-/// List available drives.
+/// Convert a `SystemTime` into seconds since the Unix epoch.
+pub fn to_unix_secs(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+}
+
+/// The app data directory, created if it does not exist yet.
+pub fn app_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Serialize `value` as JSON next to `path`, then swap it in, so a crash
+/// never leaves a truncated file.
+pub fn write_json_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, json)
+        .and_then(|_| fs::rename(&temp, path))
+        .map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+/// List the contents of a directory along with basic metadata.
+///
+/// Entries are sorted by `sort`, by default by name in natural order with
+/// folders first, and only `limit` of them starting at `offset` are
+/// returned, so large folders can be shown page by page. Every page lists
+/// and sorts the whole directory again.
 #[command]
-pub fn get_drives() -> Vec<String> {
-    let mut drives = Vec::new();
-
-    if cfg!(target_os = "macos") {
-        // macOS: List drives in /Volumes.
-        if let Ok(entries) = fs::read_dir("/Volumes") {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    drives.push(path.to_string_lossy().to_string());
-                }
-            }
+pub async fn list_directory(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    options: Option<ListOptions>,
+    sort: Option<ListSort>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<DirectoryPage, ExplorerError> {
+    let options = options.unwrap_or_default();
+    let sort = sort.unwrap_or_default();
+    tasks
+        .run_blocking("list_directory", move || {
+            let mut entries = read_directory(&path, &options)?;
+            entries.sort_by(|a, b| sort.compare(a, b));
+            let total = entries.len();
+            let entries = entries
+                .into_iter()
+                .skip(offset.unwrap_or(0))
+                .take(limit.unwrap_or(usize::MAX))
+                .collect();
+            Ok(DirectoryPage { entries, total })
+        })
+        .await
+}
+
+fn read_directory(path: &str, options: &ListOptions) -> Result<Vec<DirEntryInfo>, ExplorerError> {
+    let entries =
+        fs::read_dir(path).map_err(|e| ExplorerError::io("Failed to read directory", e))?;
+
+    let mut items = Vec::new();
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+
+        // Use symlink_metadata so links are reported rather than followed.
+        let link_meta = match fs::symlink_metadata(&entry_path) {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        if !options.shows(&entry_path, &link_meta) {
+            continue;
         }
-    } else if cfg!(target_os = "windows") {
-        // Windows: Example for detecting drives.
-        drives.push("C:\\".to_string());
-        drives.push("D:\\".to_string());
-    } else {
-        // Linux or others: Check root or other mount points.
-        drives.push("/".to_string());
+        let is_symlink = link_meta.file_type().is_symlink();
+
+        // For symlinks, report the target's metadata when it resolves.
+        let (meta, is_broken_link) = if is_symlink {
+            match fs::metadata(&entry_path) {
+                Ok(meta) => (meta, false),
+                Err(_) => (link_meta, true),
+            }
+        } else {
+            (link_meta, false)
+        };
+        let symlink_target = if is_symlink {
+            fs::read_link(&entry_path)
+                .ok()
+                .map(|target| target.to_string_lossy().to_string())
+        } else {
+            None
+        };
+
+        items.push(DirEntryInfo {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: entry_path.to_string_lossy().to_string(),
+            size: if meta.is_dir() { 0 } else { meta.len() },
+            modified: to_unix_secs(meta.modified()),
+            created: to_unix_secs(meta.created()),
+            is_dir: meta.is_dir(),
+            is_symlink,
+            symlink_target,
+            is_broken_link,
+            is_cloud_placeholder: is_cloud_placeholder(&meta),
+            extension: entry_path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase()),
+        });
     }
-    drives
+
+    Ok(items)
 }
 
 /// Open a file using the system default application.
 #[command]
-pub fn open_file(path: String) {
-    opener::open(path).unwrap();
+pub async fn open_file(path: String) -> Result<(), ExplorerError> {
+    opener::open(&path).map_err(|e| open_error("Failed to open file", e))
+}
+
+/// Open the folder containing `path` in the system file manager.
+#[command]
+pub async fn open_file_folder(path: String) -> Result<(), ExplorerError> {
+    let parent = Path::new(&path)
+        .parent()
+        .ok_or_else(|| ExplorerError::InvalidInput(format!("No parent folder: {}", path)))?;
+    opener::open(parent).map_err(|e| open_error("Failed to open folder", e))
+}
+
+fn open_error(context: &str, error: opener::OpenError) -> ExplorerError {
+    match error {
+        opener::OpenError::Io(e) => ExplorerError::io(context, e),
+        e => ExplorerError::Failed(format!("{}: {}", context, e)),
+    }
 }
 
+/// Show `path` in the system file manager with the file itself selected,
+/// rather than just opening its folder like `open_file_folder`.
+///
+/// Uses `explorer /select,` on Windows, `open -R` on macOS and the
+/// `org.freedesktop.FileManager1` D-Bus interface on Linux, falling back to
+/// opening the parent folder where no file manager implements it.
 #[command]
-pub fn open_file_folder(path: String) {
-    if let Some(parent) = std::path::Path::new(&path).parent() {
-        let _ = opener::open(parent.to_str().unwrap());
+pub async fn reveal_in_file_manager(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<(), ExplorerError> {
+    let path = PathBuf::from(path);
+    tasks
+        .run_blocking("reveal_in_file_manager", move || {
+            if fs::symlink_metadata(&path).is_err() {
+                return Err(ExplorerError::NotFound(format!(
+                    "Path does not exist: {}",
+                    path.display()
+                )));
+            }
+            reveal(&path)
+        })
+        .await
+}
+
+#[cfg(windows)]
+fn reveal(path: &Path) -> Result<(), ExplorerError> {
+    use std::os::windows::process::CommandExt;
+
+    // Explorer parses its own command line, so the path is quoted by hand.
+    // It exits with 1 even when it succeeds.
+    std::process::Command::new("explorer")
+        .raw_arg(format!("/select,\"{}\"", path.display()))
+        .spawn()
+        .map_err(|e| ExplorerError::io("Failed to run explorer", e))?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> Result<(), ExplorerError> {
+    let status = std::process::Command::new("open")
+        .arg("-R")
+        .arg(path)
+        .status()
+        .map_err(|e| ExplorerError::io("Failed to run open", e))?;
+    if !status.success() {
+        return Err(ExplorerError::Failed(format!(
+            "Failed to reveal {}",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn reveal(path: &Path) -> Result<(), ExplorerError> {
+    let uri = std::path::absolute(path)
+        .ok()
+        .and_then(|path| tauri::Url::from_file_path(path).ok());
+    let shown = uri.is_some_and(|uri| {
+        std::process::Command::new("gdbus")
+            .args([
+                "call",
+                "--session",
+                "--dest",
+                "org.freedesktop.FileManager1",
+                "--object-path",
+                "/org/freedesktop/FileManager1",
+                "--method",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("['{}']", uri))
+            .arg("")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    });
+    if shown {
+        return Ok(());
     }
+    let parent = path.parent().ok_or_else(|| {
+        ExplorerError::InvalidInput(format!("No parent folder: {}", path.display()))
+    })?;
+    opener::open(parent).map_err(|e| open_error("Failed to open folder", e))
 }
 
 /// Macro to auto-register all commands.
@@ -101,10 +487,138 @@ pub fn open_file_folder(path: String) {
 macro_rules! register_commands {
     () => {
         tauri::generate_handler![
-            crate::commands::get_drives,
-            crate::commands::open_file,
-            crate::commands::get_thumbnail,
-            crate::commands::open_file_folder
+            $crate::drives::get_drives,
+            $crate::commands::list_directory,
+            $crate::commands::open_file,
+            $crate::thumbnails::get_thumbnail,
+            $crate::commands::open_file_folder,
+            $crate::scanner::scan_directory,
+            $crate::tasks::list_running_tasks,
+            $crate::duplicates::find_duplicates,
+            $crate::largest::find_largest_files,
+            $crate::search::search_files,
+            $crate::search::search_content,
+            $crate::thumbnails::get_video_thumbnail,
+            $crate::file_ops::delete_to_trash,
+            $crate::file_ops::prepare_delete,
+            $crate::file_ops::delete_permanently,
+            $crate::file_ops::copy_items,
+            $crate::file_ops::move_items,
+            $crate::file_ops::rename_item,
+            $crate::metadata::get_file_info,
+            $crate::metadata::get_image_metadata,
+            $crate::metadata::get_audio_metadata,
+            $crate::metadata::get_video_metadata,
+            $crate::metadata::detect_file_type,
+            $crate::metadata::detect_file_types,
+            $crate::watcher::watch_path,
+            $crate::watcher::unwatch_path,
+            $crate::operations::cancel_operation,
+            $crate::operations::pause_operation,
+            $crate::operations::resume_operation,
+            $crate::operations::list_operations,
+            $crate::index::index_status,
+            $crate::index::rebuild_index,
+            $crate::index::query_index,
+            $crate::hashing::hash_file,
+            $crate::hashing::hash_files,
+            $crate::hashing::verify_checksums,
+            $crate::archive::list_archive,
+            $crate::archive::extract_archive,
+            $crate::archive::create_archive,
+            $crate::file_ops::set_permissions,
+            $crate::drives::get_drive_health,
+            $crate::drives::eject_drive,
+            $crate::remote::sftp::connect_sftp,
+            $crate::remote::list_remote_connections,
+            $crate::remote::disconnect_remote,
+            $crate::remote::list_remote,
+            $crate::remote::scan_remote,
+            $crate::remote::download_remote,
+            $crate::remote::s3::connect_s3,
+            $crate::remote::webdav::connect_webdav,
+            $crate::shares::discover_network_shares,
+            $crate::shares::mount_share,
+            $crate::remote::mtp::list_mtp_devices,
+            $crate::remote::mtp::connect_mtp,
+            $crate::disk_images::mount_image,
+            $crate::disk_images::unmount_image,
+            $crate::shred::secure_delete,
+            $crate::compare::compare_folders,
+            $crate::diff::diff_files,
+            $crate::tags::add_tag,
+            $crate::tags::remove_tag,
+            $crate::tags::list_tags,
+            $crate::tags::find_by_tag,
+            $crate::favorites::add_favorite,
+            $crate::favorites::remove_favorite,
+            $crate::favorites::list_favorites,
+            $crate::history::record_visit,
+            $crate::history::get_recent,
+            $crate::history::clear_history,
+            $crate::export::export_scan,
+            $crate::scheduler::create_schedule,
+            $crate::scheduler::list_schedules,
+            $crate::scheduler::delete_schedule,
+            $crate::snapshots::create_snapshot,
+            $crate::snapshots::list_snapshots,
+            $crate::snapshots::delete_snapshot,
+            $crate::snapshots::diff_snapshots,
+            $crate::exclusions::get_exclusions,
+            $crate::exclusions::set_exclusions,
+            $crate::thumbnails::queue::request_thumbnails,
+            $crate::thumbnails::queue::cancel_thumbnails,
+            $crate::preview::read_text_preview,
+            $crate::preview::read_hex_preview,
+            $crate::preview::get_code_preview,
+            $crate::streams::open_read_stream,
+            $crate::streams::read_chunk,
+            $crate::streams::close_stream,
+            $crate::similar_images::find_similar_images,
+            $crate::saved_searches::save_search,
+            $crate::saved_searches::list_saved_searches,
+            $crate::saved_searches::delete_saved_search,
+            $crate::saved_searches::run_saved_search,
+            $crate::autocomplete::autocomplete_path,
+            $crate::fuzzy::fuzzy_find,
+            $crate::icons::get_file_icon,
+            $crate::open_with::get_open_with_apps,
+            $crate::open_with::open_with,
+            $crate::commands::reveal_in_file_manager,
+            $crate::terminal::get_terminal,
+            $crate::terminal::set_terminal,
+            $crate::terminal::open_terminal,
+            $crate::clipboard::clipboard_copy_files,
+            $crate::clipboard::clipboard_cut_files,
+            $crate::clipboard::clipboard_paste,
+            $crate::native_drag::start_native_drag,
+            $crate::benchmark::benchmark_drive,
+            $crate::partitions::get_partitions,
+            $crate::quota::get_quota,
+            $crate::plugins::list_plugins,
+            $crate::plugins::get_plugin_metadata,
+            $crate::plugins::get_cleanup_suggestions,
+            $crate::http_api::start_api_server,
+            $crate::http_api::stop_api_server,
+            $crate::http_api::get_api_server_status,
+            $crate::settings::get_settings,
+            $crate::settings::update_settings,
+            $crate::windows::new_window,
+            $crate::windows::get_window_state,
+            $crate::windows::set_window_path,
+            $crate::windows::set_window_selection,
+            $crate::windows::set_window_tabs,
+            $crate::windows::attach_operation,
+            $crate::session::restore_session,
+            $crate::journal::get_journal,
+            $crate::journal::undo_last_operation,
+            $crate::journal::redo,
+            $crate::file_ops::resolve_conflict,
+            $crate::duplicates::dedupe_hardlink,
+            $crate::elevation::run_elevated,
+            $crate::batch_rename::preview_batch_rename,
+            $crate::batch_rename::batch_rename,
+            $crate::organize::organize_photos
         ]
     };
 }
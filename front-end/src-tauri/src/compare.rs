@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use walkdir::WalkDir;
+
+use crate::commands::to_unix_secs;
+use crate::duplicates::blake3_file;
+use crate::error::ExplorerError;
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// Modification times closer than this are treated as equal, since FAT and
+/// some network filesystems only store them to two seconds.
+const DEFAULT_MTIME_TOLERANCE_SECS: u64 = 2;
+
+/// How files present on both sides are checked for differences.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareMode {
+    Size,
+    /// Size and modification time, like most sync tools.
+    #[default]
+    SizeAndModified,
+    /// Size, then a BLAKE3 hash of the contents. Slow but exact.
+    Content,
+}
+
+/// Options accepted by `compare_folders`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct CompareOptions {
+    pub mode: CompareMode,
+    pub mtime_tolerance_secs: u64,
+    pub follow_symlinks: bool,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        CompareOptions {
+            mode: CompareMode::default(),
+            mtime_tolerance_secs: DEFAULT_MTIME_TOLERANCE_SECS,
+            follow_symlinks: false,
+        }
+    }
+}
+
+/// A file or folder found on only one side.
+///
+/// Folders missing on the other side are listed once, without their contents.
+#[derive(Serialize)]
+pub struct ComparedEntry {
+    /// Path relative to the compared folder.
+    pub relative_path: String,
+    pub is_dir: bool,
+    /// Total size of the file, or of everything below the folder.
+    pub size: u64,
+}
+
+/// Why an entry present on both sides is reported as different.
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum DifferenceKind {
+    /// A file on one side and a folder on the other.
+    Type,
+    Size,
+    Modified,
+    Content,
+}
+
+/// An entry present on both sides that does not match.
+#[derive(Serialize)]
+pub struct DifferingEntry {
+    pub relative_path: String,
+    pub kind: DifferenceKind,
+    pub left_size: u64,
+    pub right_size: u64,
+    pub left_modified: Option<u64>,
+    pub right_modified: Option<u64>,
+}
+
+/// Result of `compare_folders`.
+#[derive(Serialize)]
+pub struct FolderComparison {
+    pub left: String,
+    pub right: String,
+    pub only_left: Vec<ComparedEntry>,
+    pub only_right: Vec<ComparedEntry>,
+    pub different: Vec<DifferingEntry>,
+    /// Files present on both sides that matched.
+    pub identical_files: u64,
+}
+
+/// What a side of the comparison holds at a relative path.
+struct Item {
+    is_dir: bool,
+    size: u64,
+    modified: Option<u64>,
+    path: PathBuf,
+}
+
+/// Compare two folder trees, e.g. a folder and its backup.
+///
+/// Results are sorted by relative path. Content comparison can be paused or
+/// cancelled through `operation_id`.
+#[command]
+pub async fn compare_folders(
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    left: String,
+    right: String,
+    options: Option<CompareOptions>,
+    operation_id: Option<String>,
+) -> Result<FolderComparison, ExplorerError> {
+    for side in [&left, &right] {
+        if !Path::new(side).is_dir() {
+            return Err(ExplorerError::InvalidInput(format!(
+                "Not a directory: {}",
+                side
+            )));
+        }
+    }
+    let options = options.unwrap_or_default();
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    tasks
+        .run_blocking("compare_folders", move || {
+            let left_items = collect(&handle, Path::new(&left), options.follow_symlinks)?;
+            let right_items = collect(&handle, Path::new(&right), options.follow_symlinks)?;
+            let mut comparison = FolderComparison {
+                only_left: only_in(&left_items, &right_items),
+                only_right: only_in(&right_items, &left_items),
+                different: Vec::new(),
+                identical_files: 0,
+                left,
+                right,
+            };
+
+            for (relative, a) in &left_items {
+                let Some(b) = right_items.get(relative) else {
+                    continue;
+                };
+                if a.is_dir && b.is_dir {
+                    continue;
+                }
+                handle.checkpoint()?;
+                match difference(a, b, &options) {
+                    Some(kind) => comparison.different.push(DifferingEntry {
+                        relative_path: relative.clone(),
+                        kind,
+                        left_size: a.size,
+                        right_size: b.size,
+                        left_modified: a.modified,
+                        right_modified: b.modified,
+                    }),
+                    None => comparison.identical_files += 1,
+                }
+            }
+            Ok(comparison)
+        })
+        .await
+}
+
+/// Everything below `root`, keyed by relative path.
+fn collect(
+    operation: &OperationHandle,
+    root: &Path,
+    follow_symlinks: bool,
+) -> Result<BTreeMap<String, Item>, ExplorerError> {
+    let mut items = BTreeMap::new();
+    for entry in WalkDir::new(root)
+        .min_depth(1)
+        .follow_links(follow_symlinks)
+        .into_iter()
+        .flatten()
+    {
+        operation.checkpoint()?;
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let meta = entry.metadata().ok();
+        items.insert(
+            relative.to_string_lossy().to_string(),
+            Item {
+                is_dir: entry.file_type().is_dir(),
+                size: meta.as_ref().filter(|m| !m.is_dir()).map_or(0, |m| m.len()),
+                modified: meta.and_then(|m| to_unix_secs(m.modified())),
+                path: entry.into_path(),
+            },
+        );
+    }
+    Ok(items)
+}
+
+/// Entries of `side` missing from `other`, collapsing missing folders.
+fn only_in(side: &BTreeMap<String, Item>, other: &BTreeMap<String, Item>) -> Vec<ComparedEntry> {
+    let mut entries: Vec<ComparedEntry> = Vec::new();
+    let mut missing_dir: Option<PathBuf> = None;
+    // BTreeMap order visits a folder right before its contents.
+    for (relative, item) in side {
+        if other.contains_key(relative) {
+            continue;
+        }
+        if let (Some(dir), Some(last)) = (&missing_dir, entries.last_mut()) {
+            if Path::new(relative).starts_with(dir) {
+                last.size += item.size;
+                continue;
+            }
+        }
+        missing_dir = item.is_dir.then(|| PathBuf::from(relative));
+        entries.push(ComparedEntry {
+            relative_path: relative.clone(),
+            is_dir: item.is_dir,
+            size: item.size,
+        });
+    }
+    entries
+}
+
+fn difference(a: &Item, b: &Item, options: &CompareOptions) -> Option<DifferenceKind> {
+    if a.is_dir != b.is_dir {
+        return Some(DifferenceKind::Type);
+    }
+    if a.size != b.size {
+        return Some(DifferenceKind::Size);
+    }
+    match options.mode {
+        CompareMode::Size => None,
+        CompareMode::SizeAndModified => match (a.modified, b.modified) {
+            (Some(x), Some(y)) if x.abs_diff(y) > options.mtime_tolerance_secs => {
+                Some(DifferenceKind::Modified)
+            }
+            _ => None,
+        },
+        CompareMode::Content => {
+            // An unreadable file cannot be shown to match.
+            match (blake3_file(&a.path, None), blake3_file(&b.path, None)) {
+                (Ok(x), Ok(y)) if x == y => None,
+                _ => Some(DifferenceKind::Content),
+            }
+        }
+    }
+}
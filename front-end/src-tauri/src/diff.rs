@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use tauri::{command, State};
+
+use crate::duplicates::blake3_file;
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Files larger than this are only compared by hash, not diffed line by line.
+const MAX_DIFF_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Bytes inspected when deciding whether a file is binary.
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Unchanged lines shown around each change when no count is given.
+const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Time after which the diff algorithm settles for a less minimal result.
+const DIFF_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Result of `diff_files`.
+#[derive(Serialize)]
+pub struct FileDiff {
+    pub path_a: String,
+    pub path_b: String,
+    pub identical: bool,
+    /// One of the files is not text, so only `identical` is reported.
+    pub binary: bool,
+    /// One of the files exceeds the size cap, so only `identical` is reported.
+    pub too_large: bool,
+    /// Unified diff of the two files; `None` for binary or oversized files.
+    pub unified: Option<String>,
+    pub added_lines: usize,
+    pub removed_lines: usize,
+}
+
+/// Compare two files and return a unified diff when both are text.
+///
+/// Binary files and files over 4 MiB are compared by hash only.
+#[command]
+pub async fn diff_files(
+    tasks: State<'_, TaskRegistry>,
+    path_a: String,
+    path_b: String,
+    context_lines: Option<usize>,
+) -> Result<FileDiff, ExplorerError> {
+    tasks
+        .run_blocking("diff_files", move || {
+            let mut diff = FileDiff {
+                path_a,
+                path_b,
+                identical: false,
+                binary: false,
+                too_large: false,
+                unified: None,
+                added_lines: 0,
+                removed_lines: 0,
+            };
+            for path in [&diff.path_a, &diff.path_b] {
+                let meta =
+                    fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                if !meta.is_file() {
+                    return Err(ExplorerError::InvalidInput(format!("Not a file: {}", path)));
+                }
+                diff.too_large |= meta.len() > MAX_DIFF_FILE_SIZE;
+            }
+
+            if diff.too_large {
+                diff.identical = same_contents(&diff.path_a, &diff.path_b)?;
+                return Ok(diff);
+            }
+            let a = read_file(&diff.path_a)?;
+            let b = read_file(&diff.path_b)?;
+            diff.identical = a == b;
+            let (Some(a), Some(b)) = (text(a), text(b)) else {
+                diff.binary = true;
+                return Ok(diff);
+            };
+            if diff.identical {
+                return Ok(diff);
+            }
+
+            let lines = TextDiff::configure()
+                .timeout(DIFF_TIMEOUT)
+                .diff_lines(&a, &b);
+            for change in lines.iter_all_changes() {
+                match change.tag() {
+                    ChangeTag::Insert => diff.added_lines += 1,
+                    ChangeTag::Delete => diff.removed_lines += 1,
+                    ChangeTag::Equal => {}
+                }
+            }
+            diff.unified = Some(
+                lines
+                    .unified_diff()
+                    .context_radius(context_lines.unwrap_or(DEFAULT_CONTEXT_LINES))
+                    .header(&diff.path_a, &diff.path_b)
+                    .to_string(),
+            );
+            Ok(diff)
+        })
+        .await
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))
+}
+
+/// The file as text, or `None` if it looks binary.
+fn text(bytes: Vec<u8>) -> Option<String> {
+    // NUL bytes almost never appear in text but are common in binary formats.
+    if bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn same_contents(a: &str, b: &str) -> Result<bool, String> {
+    let size = |path: &str| fs::metadata(path).map(|m| m.len()).ok();
+    if size(a) != size(b) {
+        return Ok(false);
+    }
+    let hash = |path: &str| {
+        blake3_file(Path::new(path), None).map_err(|e| format!("Failed to read {}: {}", path, e))
+    };
+    Ok(hash(a)? == hash(b)?)
+}
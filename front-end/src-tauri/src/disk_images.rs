@@ -0,0 +1,229 @@
+use std::path::Path;
+use std::process::Command;
+
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::drives::{list_drives, DriveInfo};
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// A disk image attached by `mount_image`.
+#[derive(Serialize)]
+pub struct MountedImage {
+    /// Pass to `unmount_image` to detach the image again.
+    pub handle: String,
+    pub image: String,
+    /// Volumes of the image that were mounted. They also appear in `get_drives`
+    /// until the image is unmounted.
+    pub drives: Vec<DriveInfo>,
+}
+
+/// Attach a disk image (ISO, DMG, IMG, VHD) read-only and mount its volumes.
+///
+/// Uses `hdiutil` on macOS, `udisksctl` loop devices on Linux and
+/// `Mount-DiskImage` on Windows, which supports ISO, VHD and VHDX images.
+#[command]
+pub async fn mount_image(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<MountedImage, ExplorerError> {
+    if !Path::new(&path).is_file() {
+        return Err(ExplorerError::InvalidInput(format!("Not a file: {}", path)));
+    }
+    tasks
+        .run_blocking("mount_image", move || {
+            let (handle, mount_points) = attach(&path)?;
+            let drives = list_drives()
+                .into_iter()
+                .filter(|drive| {
+                    mount_points
+                        .iter()
+                        .any(|m| same_mount_point(m, &drive.mount_point))
+                })
+                .collect();
+            Ok(MountedImage {
+                handle,
+                image: path,
+                drives,
+            })
+        })
+        .await
+}
+
+/// Unmount the volumes of an image attached with `mount_image` and detach it.
+#[command]
+pub async fn unmount_image(
+    tasks: State<'_, TaskRegistry>,
+    handle: String,
+) -> Result<(), ExplorerError> {
+    tasks
+        .run_blocking("unmount_image", move || Ok(detach(&handle)?))
+        .await
+}
+
+fn same_mount_point(a: &str, b: &str) -> bool {
+    let trim = |s: &str| s.trim_end_matches(['/', '\\']).to_lowercase();
+    trim(a) == trim(b)
+}
+
+/// Run a tool and return its standard output, or its error output on failure.
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("{} failed: {}", program, stderr.trim()))
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn attach(path: &str) -> Result<(String, Vec<String>), String> {
+    // Rows are `/dev/disk4s1 <TAB> Apple_HFS <TAB> /Volumes/Name`; the first
+    // row is the whole disk, which `hdiutil detach` accepts.
+    let output = run("hdiutil", &["attach", "-readonly", path])?;
+    let rows: Vec<Vec<&str>> = output
+        .lines()
+        .map(|line| line.split('\t').map(str::trim).collect())
+        .collect();
+    let handle = rows
+        .first()
+        .and_then(|row| row.first())
+        .filter(|dev| dev.starts_with("/dev/"))
+        .ok_or_else(|| format!("hdiutil did not report a device for {}", path))?
+        .to_string();
+    let mount_points = rows
+        .iter()
+        .filter_map(|row| row.get(2))
+        .filter(|mount| !mount.is_empty())
+        .map(|mount| mount.to_string())
+        .collect();
+    Ok((handle, mount_points))
+}
+
+#[cfg(target_os = "macos")]
+fn detach(handle: &str) -> Result<(), String> {
+    run("hdiutil", &["detach", handle]).map(|_| ())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn attach(path: &str) -> Result<(String, Vec<String>), String> {
+    // Prints `Mapped file image.iso as /dev/loop0.`
+    let output = run("udisksctl", &["loop-setup", "--read-only", "--file", path])?;
+    let device = output
+        .split_whitespace()
+        .last()
+        .map(|dev| dev.trim_end_matches('.'))
+        .filter(|dev| dev.starts_with("/dev/"))
+        .ok_or_else(|| format!("udisksctl did not report a loop device for {}", path))?
+        .to_string();
+
+    // Partitioned images expose `loop0p1`, `loop0p2`, ...; plain ones mount
+    // the loop device itself.
+    let mut devices = loop_partitions(&device);
+    if devices.is_empty() {
+        devices.push(device.clone());
+    }
+    let mut mount_points = Vec::new();
+    for dev in &devices {
+        // The desktop may already have auto-mounted it.
+        if let Some(mount) = mount_point_of(dev) {
+            mount_points.push(mount);
+        } else if let Ok(output) = run("udisksctl", &["mount", "--block-device", dev]) {
+            // Prints `Mounted /dev/loop0 at /media/user/LABEL`
+            if let Some((_, mount)) = output.trim().split_once(" at ") {
+                mount_points.push(mount.trim_end_matches('.').to_string());
+            }
+        }
+    }
+    if mount_points.is_empty() {
+        let _ = run("udisksctl", &["loop-delete", "--block-device", &device]);
+        return Err(format!("No mountable filesystem found in {}", path));
+    }
+    Ok((device, mount_points))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn detach(handle: &str) -> Result<(), String> {
+    let mut devices = loop_partitions(handle);
+    devices.push(handle.to_string());
+    for dev in devices.iter().filter(|dev| mount_point_of(dev).is_some()) {
+        run("udisksctl", &["unmount", "--block-device", dev])?;
+    }
+    run("udisksctl", &["loop-delete", "--block-device", handle]).map(|_| ())
+}
+
+/// Partition devices of a loop device, e.g. `/dev/loop0p1`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn loop_partitions(device: &str) -> Vec<String> {
+    let Some(name) = Path::new(device)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+    else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(Path::new("/sys/block").join(&name)) else {
+        return Vec::new();
+    };
+    let mut partitions: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|child| child.starts_with(&format!("{}p", name)))
+        .map(|child| format!("/dev/{}", child))
+        .collect();
+    partitions.sort();
+    partitions
+}
+
+/// Where `device` is mounted, from `/proc/self/mounts`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount_point_of(device: &str) -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split(' ');
+        let (source, target) = (fields.next()?, fields.next()?);
+        // Spaces in mount points are written as `\040`.
+        (source == device).then(|| target.replace("\\040", " "))
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn attach(path: &str) -> Result<(String, Vec<String>), String> {
+    let script = format!(
+        "Mount-DiskImage -ImagePath {} -Access ReadOnly -PassThru | Get-Volume | \
+         Where-Object DriveLetter | ForEach-Object {{ $_.DriveLetter }}",
+        powershell_quote(path)
+    );
+    let output = run(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", &script],
+    )?;
+    let mount_points = output
+        .lines()
+        .map(str::trim)
+        .filter(|letter| letter.len() == 1)
+        .map(|letter| format!("{}:\\", letter))
+        .collect();
+    // Windows identifies attached images by their path.
+    Ok((path.to_string(), mount_points))
+}
+
+#[cfg(target_os = "windows")]
+fn detach(handle: &str) -> Result<(), String> {
+    let script = format!("Dismount-DiskImage -ImagePath {}", powershell_quote(handle));
+    run(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", &script],
+    )
+    .map(|_| ())
+}
+
+/// Quote a string as a PowerShell single-quoted literal.
+#[cfg(target_os = "windows")]
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
@@ -0,0 +1,838 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, State};
+
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Event name used to report drives being mounted or unmounted.
+pub const DRIVES_CHANGED_EVENT: &str = "drives://changes";
+
+/// How often the drive monitor polls the mounted volumes.
+const DRIVE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Name of the smartmontools executable used to read SMART data.
+const SMARTCTL_BIN: &str = "smartctl";
+
+/// smartctl exit status bits meaning the device could not be queried at all.
+const SMARTCTL_FATAL_BITS: i32 = 0b11;
+
+/// ATA attribute id of the reallocated sector count.
+const REALLOCATED_SECTORS_ID: u32 = 5;
+
+/// Kind of storage backing a drive.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DriveType {
+    Fixed,
+    Removable,
+    Network,
+    CdRom,
+    RamDisk,
+    /// A phone or media player attached over MTP.
+    Phone,
+    Unknown,
+}
+
+/// Whether an encrypted volume can be read.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionState {
+    Unlocked,
+    /// The volume has to be unlocked with its password or key before its
+    /// files can be read.
+    Locked,
+}
+
+/// A mounted drive or volume.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DriveInfo {
+    /// Drive root, e.g. `C:\` on Windows or `/Volumes/Data` on macOS.
+    pub mount_point: String,
+    /// Volume label, when the platform reports one.
+    pub label: Option<String>,
+    pub drive_type: DriveType,
+    /// Removable media or a drive attached over USB, which can be ejected.
+    pub is_removable: bool,
+    /// Filesystem name such as `NTFS`, `apfs` or `ext4`.
+    pub file_system: Option<String>,
+    /// BitLocker, FileVault or LUKS encryption of the volume; `None` when it
+    /// is not encrypted or the platform cannot tell.
+    #[serde(default)]
+    pub encryption: Option<EncryptionState>,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    pub used_bytes: u64,
+}
+
+/// List available drives.
+#[command]
+pub async fn get_drives(tasks: State<'_, TaskRegistry>) -> Result<Vec<DriveInfo>, ExplorerError> {
+    // Querying network mounts can stall, so keep it off the main thread.
+    tasks.run_blocking("drives", || Ok(list_drives())).await
+}
+
+/// Payload of the `drives://changes` event.
+#[derive(Serialize, Clone)]
+pub struct DriveChanges {
+    pub added: Vec<DriveInfo>,
+    /// Mount points that are no longer present.
+    pub removed: Vec<String>,
+    /// The full, current drive list.
+    pub drives: Vec<DriveInfo>,
+}
+
+/// Start a background thread that emits `drives://changes` whenever a drive
+/// or network share is mounted or unmounted.
+///
+/// Mounted volumes are polled rather than hooked into each platform's device
+/// notifications, so changes show up within a couple of seconds.
+pub fn spawn_drive_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut known = list_drives();
+        loop {
+            std::thread::sleep(DRIVE_POLL_INTERVAL);
+            let drives = list_drives();
+            let before: HashSet<&str> = known.iter().map(|d| d.mount_point.as_str()).collect();
+            let after: HashSet<&str> = drives.iter().map(|d| d.mount_point.as_str()).collect();
+
+            let added: Vec<DriveInfo> = drives
+                .iter()
+                .filter(|d| !before.contains(d.mount_point.as_str()))
+                .cloned()
+                .collect();
+            let removed: Vec<String> = known
+                .iter()
+                .filter(|d| !after.contains(d.mount_point.as_str()))
+                .map(|d| d.mount_point.clone())
+                .collect();
+
+            if !added.is_empty() || !removed.is_empty() {
+                let _ = app.emit(
+                    DRIVES_CHANGED_EVENT,
+                    DriveChanges {
+                        added,
+                        removed,
+                        drives: drives.clone(),
+                    },
+                );
+            }
+            known = drives;
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+pub fn list_drives() -> Vec<DriveInfo> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::{
+        GetDiskFreeSpaceExW, GetDriveTypeW, GetLogicalDrives, GetVolumeInformationW,
+    };
+    use windows::Win32::System::WindowsProgramming::{
+        DRIVE_CDROM, DRIVE_FIXED, DRIVE_RAMDISK, DRIVE_REMOTE, DRIVE_REMOVABLE,
+    };
+
+    let mut drives = Vec::new();
+    // Bit 0 is A:, bit 1 is B:, and so on.
+    let mask = unsafe { GetLogicalDrives() };
+
+    for index in 0..26u8 {
+        if mask & (1 << index) == 0 {
+            continue;
+        }
+        let root = format!("{}:\\", (b'A' + index) as char);
+        let wide: Vec<u16> = root.encode_utf16().chain(std::iter::once(0)).collect();
+        let root_ptr = PCWSTR(wide.as_ptr());
+
+        let drive_type = match unsafe { GetDriveTypeW(root_ptr) } {
+            DRIVE_FIXED => DriveType::Fixed,
+            DRIVE_REMOVABLE => DriveType::Removable,
+            DRIVE_REMOTE => DriveType::Network,
+            DRIVE_CDROM => DriveType::CdRom,
+            DRIVE_RAMDISK => DriveType::RamDisk,
+            _ => DriveType::Unknown,
+        };
+
+        // Empty card readers and optical drives fail here; keep them without details.
+        let mut label_buf = [0u16; 261];
+        let mut fs_buf = [0u16; 261];
+        let volume_ok = unsafe {
+            GetVolumeInformationW(
+                root_ptr,
+                Some(&mut label_buf),
+                None,
+                None,
+                None,
+                Some(&mut fs_buf),
+            )
+        }
+        .is_ok();
+        let label = Some(wide_to_string(&label_buf)).filter(|label| volume_ok && !label.is_empty());
+        let file_system = Some(wide_to_string(&fs_buf)).filter(|fs| volume_ok && !fs.is_empty());
+
+        let mut free_bytes = 0u64;
+        let mut total_bytes = 0u64;
+        let _ = unsafe {
+            GetDiskFreeSpaceExW(
+                root_ptr,
+                None,
+                Some(&mut total_bytes),
+                Some(&mut free_bytes),
+            )
+        };
+
+        let encryption = match drive_type {
+            DriveType::Fixed | DriveType::Removable => bitlocker_state(&root),
+            _ => None,
+        };
+        drives.push(DriveInfo {
+            is_removable: drive_type == DriveType::Removable
+                || (drive_type == DriveType::Fixed && is_usb_volume(&root)),
+            mount_point: root,
+            label,
+            drive_type,
+            file_system,
+            encryption,
+            total_bytes,
+            free_bytes,
+            used_bytes: total_bytes.saturating_sub(free_bytes),
+        });
+    }
+    drives
+}
+
+/// Open the volume device (`\\.\X:`) behind a drive root such as `X:\`.
+#[cfg(target_os = "windows")]
+fn open_volume(
+    root: &str,
+    access: u32,
+) -> windows::core::Result<windows::Win32::Foundation::HANDLE> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+
+    let device = format!("\\\\.\\{}", root.trim_end_matches('\\'));
+    let wide: Vec<u16> = device.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        CreateFileW(
+            PCWSTR(wide.as_ptr()),
+            access,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            None,
+        )
+    }
+}
+
+/// Send an IOCTL without an output buffer.
+#[cfg(target_os = "windows")]
+fn device_control<T>(
+    handle: windows::Win32::Foundation::HANDLE,
+    code: u32,
+    input: Option<&T>,
+) -> windows::core::Result<()> {
+    use windows::Win32::System::IO::DeviceIoControl;
+
+    let mut returned = 0u32;
+    unsafe {
+        DeviceIoControl(
+            handle,
+            code,
+            input.map(|value| value as *const T as *const std::ffi::c_void),
+            input.map_or(0, |_| std::mem::size_of::<T>() as u32),
+            None,
+            0,
+            Some(&mut returned),
+            None,
+        )
+    }
+}
+
+/// Whether a fixed volume sits on a USB disk, which Windows reports as fixed.
+#[cfg(target_os = "windows")]
+fn is_usb_volume(root: &str) -> bool {
+    use windows::Win32::Storage::FileSystem::BusTypeUsb;
+    use windows::Win32::System::Ioctl::{StorageDeviceProperty, STORAGE_DEVICE_DESCRIPTOR};
+
+    query_storage_property::<STORAGE_DEVICE_DESCRIPTOR>(root, StorageDeviceProperty).is_some_and(
+        |descriptor| descriptor.BusType == BusTypeUsb || descriptor.RemovableMedia.as_bool(),
+    )
+}
+
+/// BitLocker state of the volume mounted at `root`, read from the shell
+/// property Explorer uses for its padlock icons, which needs no
+/// administrator rights.
+#[cfg(target_os = "windows")]
+pub fn bitlocker_state(root: &str) -> Option<EncryptionState> {
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::System::Com::{CoInitializeEx, COINIT_APARTMENTTHREADED};
+    use windows::Win32::UI::Shell::PropertiesSystem::{PSGetPropertyKeyFromName, PROPERTYKEY};
+    use windows::Win32::UI::Shell::{IShellItem2, SHCreateItemFromParsingName};
+
+    let wide: Vec<u16> = root.encode_utf16().chain(std::iter::once(0)).collect();
+    // A thread that is already initialised keeps its apartment.
+    let _ = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+    let protection = unsafe {
+        let mut key = PROPERTYKEY::default();
+        PSGetPropertyKeyFromName(w!("System.Volume.BitLockerProtection"), &mut key).ok()?;
+        let item: IShellItem2 = SHCreateItemFromParsingName(PCWSTR(wide.as_ptr()), None).ok()?;
+        item.GetInt32(&key).ok()?
+    };
+    // 1 is on, 3 encrypting, 4 decrypting, 5 suspended and 6 locked; 0 and 2
+    // mean the volume is not protected.
+    match protection {
+        6 => Some(EncryptionState::Locked),
+        1 | 3 | 4 | 5 => Some(EncryptionState::Unlocked),
+        _ => None,
+    }
+}
+
+/// The drive with the longest mount point that contains `path`.
+pub fn containing_drive<'d>(drives: &'d [DriveInfo], path: &Path) -> Option<&'d DriveInfo> {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    drives
+        .iter()
+        .filter(|drive| path.starts_with(&drive.mount_point))
+        .max_by_key(|drive| drive.mount_point.len())
+}
+
+/// Whether the drive mounted at `mount_point` is an SSD, when the platform
+/// can tell.
+#[cfg(target_os = "windows")]
+pub fn is_solid_state(mount_point: &str) -> Option<bool> {
+    use windows::Win32::System::Ioctl::{
+        StorageDeviceSeekPenaltyProperty, DEVICE_SEEK_PENALTY_DESCRIPTOR,
+    };
+
+    query_storage_property::<DEVICE_SEEK_PENALTY_DESCRIPTOR>(
+        mount_point,
+        StorageDeviceSeekPenaltyProperty,
+    )
+    .map(|descriptor| !descriptor.IncursSeekPenalty.as_bool())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn is_solid_state(mount_point: &str) -> Option<bool> {
+    use sysinfo::{DiskKind, Disks};
+
+    let disks = Disks::new_with_refreshed_list();
+    let disk = disks
+        .list()
+        .iter()
+        .find(|disk| disk.mount_point() == std::path::Path::new(mount_point))?;
+    match disk.kind() {
+        DiskKind::SSD => Some(true),
+        DiskKind::HDD => Some(false),
+        DiskKind::Unknown(_) => None,
+    }
+}
+
+/// Run `IOCTL_STORAGE_QUERY_PROPERTY` for the disk behind a drive root.
+#[cfg(target_os = "windows")]
+fn query_storage_property<T: Default>(
+    root: &str,
+    property: windows::Win32::System::Ioctl::STORAGE_PROPERTY_ID,
+) -> Option<T> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Ioctl::{
+        PropertyStandardQuery, IOCTL_STORAGE_QUERY_PROPERTY, STORAGE_PROPERTY_QUERY,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+
+    let handle = open_volume(root, 0).ok()?;
+    let query = STORAGE_PROPERTY_QUERY {
+        PropertyId: property,
+        QueryType: PropertyStandardQuery,
+        ..Default::default()
+    };
+    let mut output = T::default();
+    let mut returned = 0u32;
+    let ok = unsafe {
+        DeviceIoControl(
+            handle,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            Some(&query as *const _ as *const std::ffi::c_void),
+            std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as u32,
+            Some(&mut output as *mut T as *mut std::ffi::c_void),
+            std::mem::size_of::<T>() as u32,
+            Some(&mut returned),
+            None,
+        )
+    }
+    .is_ok();
+    let _ = unsafe { CloseHandle(handle) };
+    ok.then_some(output)
+}
+
+/// Convert a NUL-terminated UTF-16 buffer into a `String`.
+#[cfg(target_os = "windows")]
+fn wide_to_string(buf: &[u16]) -> String {
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    String::from_utf16_lossy(&buf[..len])
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn list_drives() -> Vec<DriveInfo> {
+    use sysinfo::Disks;
+
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .map(|disk| {
+            let file_system = disk.file_system().to_string_lossy().to_string();
+            let name = disk.name().to_string_lossy().to_string();
+            let is_network = is_network_fs(&file_system);
+            let is_removable = !is_network && (disk.is_removable() || is_usb_device(&name));
+            let drive_type = if is_network {
+                DriveType::Network
+            } else if is_removable {
+                DriveType::Removable
+            } else {
+                DriveType::Fixed
+            };
+            let total_bytes = disk.total_space();
+            let free_bytes = disk.available_space();
+            let mount_point = disk.mount_point().to_string_lossy().to_string();
+
+            DriveInfo {
+                encryption: if is_network {
+                    None
+                } else {
+                    volume_encryption(&name, &mount_point)
+                },
+                mount_point,
+                label: Some(name).filter(|name| !name.is_empty()),
+                drive_type,
+                is_removable,
+                file_system: Some(file_system).filter(|fs| !fs.is_empty()),
+                total_bytes,
+                free_bytes,
+                used_bytes: total_bytes.saturating_sub(free_bytes),
+            }
+        })
+        .chain(gvfs_shares())
+        .collect()
+}
+
+/// SMB shares mounted through GVfs, which all live below a single FUSE mount.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn gvfs_shares() -> Vec<DriveInfo> {
+    let Ok(entries) = std::fs::read_dir(crate::shares::gvfs_root()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            // Folders are named like `smb-share:server=nas,share=media`.
+            let name = entry.file_name().to_string_lossy().to_string();
+            let params = name.strip_prefix("smb-share:")?;
+            let param = |key: &str| {
+                params
+                    .split(',')
+                    .find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+                    .map(str::to_string)
+            };
+            let (share, server) = (param("share")?, param("server")?);
+            let (total_bytes, free_bytes) = filesystem_space(&entry.path()).unwrap_or((0, 0));
+            Some(DriveInfo {
+                mount_point: entry.path().to_string_lossy().to_string(),
+                label: Some(format!("{} on {}", share, server)),
+                drive_type: DriveType::Network,
+                is_removable: false,
+                file_system: Some("smb".to_string()),
+                encryption: None,
+                total_bytes,
+                free_bytes,
+                used_bytes: total_bytes.saturating_sub(free_bytes),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn gvfs_shares() -> Vec<DriveInfo> {
+    Vec::new()
+}
+
+/// Total and available bytes of the filesystem holding `path`.
+#[cfg(all(unix, not(target_os = "macos")))]
+// Field widths of `statvfs` vary between targets.
+#[allow(clippy::unnecessary_cast)]
+fn filesystem_space(path: &std::path::Path) -> Option<(u64, u64)> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+    let block_size = stat.f_frsize as u64;
+    Some((
+        stat.f_blocks as u64 * block_size,
+        stat.f_bavail as u64 * block_size,
+    ))
+}
+
+/// Whether a block device such as `/dev/sdb1` is attached over USB.
+///
+/// Many USB disks report themselves as non-removable, so the sysfs device
+/// path is checked as well.
+#[cfg(target_os = "linux")]
+fn is_usb_device(device: &str) -> bool {
+    let Some(name) = std::path::Path::new(device).file_name() else {
+        return false;
+    };
+    std::fs::canonicalize(std::path::Path::new("/sys/class/block").join(name))
+        .is_ok_and(|path| path.to_string_lossy().contains("/usb"))
+}
+
+#[cfg(all(not(target_os = "windows"), not(target_os = "linux")))]
+fn is_usb_device(_device: &str) -> bool {
+    false
+}
+
+/// Encryption of a mounted volume from its device, such as
+/// `/dev/mapper/luks-1234`.
+///
+/// Mounted volumes are unlocked, so this only tells whether the device is a
+/// dm-crypt mapping, or sits on one as LVM volumes inside LUKS do.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn volume_encryption(device: &str, _mount_point: &str) -> Option<EncryptionState> {
+    fn on_dm_crypt(block: &Path, depth: u32) -> bool {
+        let is_crypt = std::fs::read_to_string(block.join("dm/uuid"))
+            .is_ok_and(|uuid| uuid.starts_with("CRYPT-"));
+        is_crypt
+            || depth < 8
+                && std::fs::read_dir(block.join("slaves"))
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .any(|slave| on_dm_crypt(&slave.path(), depth + 1))
+    }
+
+    let device = std::fs::canonicalize(device).ok()?;
+    let block = Path::new("/sys/class/block").join(device.file_name()?);
+    on_dm_crypt(&block, 0).then_some(EncryptionState::Unlocked)
+}
+
+/// Encryption of a mounted volume, from `diskutil info`.
+///
+/// Mounted volumes are unlocked and stay encrypted or not while mounted, so
+/// the answer is cached; `list_drives` runs every few seconds and
+/// `diskutil` is slow.
+#[cfg(target_os = "macos")]
+fn volume_encryption(device: &str, mount_point: &str) -> Option<EncryptionState> {
+    static ENCRYPTED: std::sync::Mutex<std::collections::BTreeMap<String, bool>> =
+        std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+    let key = format!("{}\0{}", device, mount_point);
+    let cached = ENCRYPTED.lock().ok()?.get(&key).copied();
+    let encrypted = match cached {
+        Some(encrypted) => encrypted,
+        None => {
+            let output = Command::new("diskutil")
+                .args(["info", "-plist", mount_point])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            let info =
+                crate::partitions::read_plist(&String::from_utf8_lossy(&output.stdout)).ok()?;
+            let encrypted = crate::partitions::is_encrypted(&info);
+            ENCRYPTED.lock().ok()?.insert(key, encrypted);
+            encrypted
+        }
+    };
+    encrypted.then_some(EncryptionState::Unlocked)
+}
+
+/// Whether a filesystem name refers to a network mount.
+#[cfg(not(target_os = "windows"))]
+fn is_network_fs(file_system: &str) -> bool {
+    matches!(
+        file_system,
+        "nfs" | "nfs4" | "cifs" | "smbfs" | "smb3" | "afpfs" | "webdav" | "sshfs" | "fuse.sshfs"
+    )
+}
+
+/// Overall SMART self-assessment.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Passed,
+    Failed,
+    /// The drive does not report a SMART verdict.
+    Unknown,
+}
+
+/// One row of the ATA SMART attribute table.
+#[derive(Serialize)]
+pub struct SmartAttribute {
+    pub id: u32,
+    pub name: String,
+    /// Normalised value; lower is worse.
+    pub value: Option<u64>,
+    pub worst: Option<u64>,
+    pub threshold: Option<u64>,
+    pub raw_value: Option<u64>,
+    /// True when the value has reached its failure threshold.
+    pub failing: bool,
+}
+
+/// SMART health summary of a physical drive.
+#[derive(Serialize)]
+pub struct DriveHealth {
+    pub device: String,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub health: HealthStatus,
+    pub temperature_celsius: Option<i64>,
+    pub power_on_hours: Option<u64>,
+    /// ATA drives only.
+    pub reallocated_sectors: Option<u64>,
+    /// Percentage of rated endurance used, reported by NVMe drives.
+    pub percentage_used: Option<u64>,
+    /// NVMe drives only.
+    pub media_errors: Option<u64>,
+    pub attributes: Vec<SmartAttribute>,
+}
+
+/// Subset of `smartctl --json` output that we read.
+#[derive(Deserialize)]
+struct SmartctlOutput {
+    model_name: Option<String>,
+    serial_number: Option<String>,
+    smart_status: Option<SmartctlStatus>,
+    temperature: Option<SmartctlTemperature>,
+    power_on_time: Option<SmartctlPowerOn>,
+    ata_smart_attributes: Option<SmartctlAttributes>,
+    nvme_smart_health_information_log: Option<SmartctlNvmeLog>,
+    smartctl: Option<SmartctlInfo>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlStatus {
+    passed: bool,
+}
+
+#[derive(Deserialize)]
+struct SmartctlTemperature {
+    current: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlPowerOn {
+    hours: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlAttributes {
+    #[serde(default)]
+    table: Vec<SmartctlAttribute>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlAttribute {
+    id: u32,
+    name: String,
+    value: Option<u64>,
+    worst: Option<u64>,
+    thresh: Option<u64>,
+    when_failed: Option<String>,
+    raw: Option<SmartctlRaw>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlRaw {
+    value: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlNvmeLog {
+    percentage_used: Option<u64>,
+    media_errors: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlInfo {
+    #[serde(default)]
+    messages: Vec<SmartctlMessage>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlMessage {
+    string: String,
+}
+
+/// Read SMART health data for a device using smartctl.
+///
+/// `device` is anything smartctl accepts, e.g. `/dev/sda`, `/dev/disk0` or
+/// `C:`. Reading SMART data usually needs administrator rights.
+#[command]
+pub async fn get_drive_health(
+    tasks: State<'_, TaskRegistry>,
+    device: String,
+) -> Result<DriveHealth, ExplorerError> {
+    tasks
+        .run_blocking("drive_health", move || Ok(read_drive_health(&device)?))
+        .await
+}
+
+fn read_drive_health(device: &str) -> Result<DriveHealth, String> {
+    let output = Command::new(SMARTCTL_BIN)
+        .args(["--json", "--all"])
+        .arg(device)
+        .output()
+        .map_err(|e| format!("Failed to run smartctl: {}", e))?;
+    let report: SmartctlOutput = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse smartctl output: {}", e))?;
+
+    // Other exit bits flag disk problems, which is exactly what we report.
+    if output.status.code().unwrap_or(SMARTCTL_FATAL_BITS) & SMARTCTL_FATAL_BITS != 0 {
+        let message = report
+            .smartctl
+            .map(|info| {
+                info.messages
+                    .into_iter()
+                    .map(|m| m.string)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+            .unwrap_or_default();
+        return Err(format!("Failed to read SMART data: {}", message));
+    }
+
+    let attributes: Vec<SmartAttribute> = report
+        .ata_smart_attributes
+        .map(|attrs| attrs.table)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|attr| SmartAttribute {
+            failing: attr.when_failed.is_some_and(|when| !when.is_empty()),
+            id: attr.id,
+            name: attr.name,
+            value: attr.value,
+            worst: attr.worst,
+            threshold: attr.thresh,
+            raw_value: attr.raw.and_then(|raw| raw.value),
+        })
+        .collect();
+    let nvme = report.nvme_smart_health_information_log;
+
+    Ok(DriveHealth {
+        device: device.to_string(),
+        model: report.model_name,
+        serial_number: report.serial_number,
+        health: match report.smart_status {
+            Some(status) if status.passed => HealthStatus::Passed,
+            Some(_) => HealthStatus::Failed,
+            None => HealthStatus::Unknown,
+        },
+        temperature_celsius: report.temperature.and_then(|t| t.current),
+        power_on_hours: report.power_on_time.and_then(|p| p.hours),
+        reallocated_sectors: attributes
+            .iter()
+            .find(|attr| attr.id == REALLOCATED_SECTORS_ID)
+            .and_then(|attr| attr.raw_value),
+        percentage_used: nvme.as_ref().and_then(|log| log.percentage_used),
+        media_errors: nvme.and_then(|log| log.media_errors),
+        attributes,
+    })
+}
+
+/// Unmount a removable drive and prepare it for removal.
+///
+/// Uses `diskutil eject` on macOS, `udisksctl` (falling back to `umount`) on
+/// Linux, and volume dismount plus media eject IOCTLs on Windows.
+#[command]
+pub async fn eject_drive(
+    tasks: State<'_, TaskRegistry>,
+    mount_point: String,
+) -> Result<(), ExplorerError> {
+    tasks
+        .run_blocking("eject_drive", move || Ok(eject(&mount_point)?))
+        .await
+}
+
+#[cfg(target_os = "windows")]
+fn eject(mount_point: &str) -> Result<(), String> {
+    use windows::Win32::Foundation::{CloseHandle, GENERIC_READ, GENERIC_WRITE};
+    use windows::Win32::System::Ioctl::{
+        FSCTL_DISMOUNT_VOLUME, FSCTL_LOCK_VOLUME, IOCTL_STORAGE_EJECT_MEDIA,
+        IOCTL_STORAGE_MEDIA_REMOVAL, PREVENT_MEDIA_REMOVAL,
+    };
+
+    let handle = open_volume(mount_point, (GENERIC_READ | GENERIC_WRITE).0)
+        .map_err(|e| format!("Failed to open drive: {}", e))?;
+    let allow_removal = PREVENT_MEDIA_REMOVAL {
+        PreventMediaRemoval: false.into(),
+    };
+    let result = device_control::<()>(handle, FSCTL_LOCK_VOLUME, None)
+        .map_err(|e| format!("Drive is in use: {}", e))
+        .and_then(|_| {
+            device_control::<()>(handle, FSCTL_DISMOUNT_VOLUME, None)
+                .and_then(|_| {
+                    device_control(handle, IOCTL_STORAGE_MEDIA_REMOVAL, Some(&allow_removal))
+                })
+                .and_then(|_| device_control::<()>(handle, IOCTL_STORAGE_EJECT_MEDIA, None))
+                .map_err(|e| format!("Failed to eject drive: {}", e))
+        });
+    let _ = unsafe { CloseHandle(handle) };
+    result
+}
+
+#[cfg(target_os = "macos")]
+fn eject(mount_point: &str) -> Result<(), String> {
+    run_tool("diskutil", &["eject", mount_point])
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn eject(mount_point: &str) -> Result<(), String> {
+    // udisks lets desktop users unmount without root and can power the disk off.
+    if let Some(device) = mounted_device(mount_point) {
+        if run_tool("udisksctl", &["unmount", "-b", &device]).is_ok() {
+            let _ = run_tool("udisksctl", &["power-off", "-b", &device]);
+            return Ok(());
+        }
+    }
+    run_tool("umount", &[mount_point])
+}
+
+/// Block device mounted at `mount_point`, from `/proc/self/mounts`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mounted_device(mount_point: &str) -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    let mount_point = mount_point.trim_end_matches('/');
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split(' ');
+        let (device, target) = (fields.next()?, fields.next()?);
+        // Spaces in mount points are written as `\040`.
+        let target = target.replace("\\040", " ");
+        (device.starts_with("/dev/") && target.trim_end_matches('/') == mount_point)
+            .then(|| device.to_string())
+    })
+}
+
+#[cfg(unix)]
+fn run_tool(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to eject drive: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
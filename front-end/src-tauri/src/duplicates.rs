@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, State};
+use walkdir::WalkDir;
+
+use crate::error::ExplorerError;
+use crate::exclusions::Exclusions;
+use crate::file_ops::PathResult;
+use crate::hashing::read_chunks;
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::settings::SettingsStore;
+use crate::tasks::TaskRegistry;
+
+/// Event name used to stream duplicate groups as they are confirmed.
+pub const DUPLICATE_GROUP_EVENT: &str = "duplicates://group";
+
+/// Number of leading bytes hashed in the cheap pre-filter pass.
+const PARTIAL_HASH_BYTES: u64 = 64 * 1024;
+
+/// Options accepted by `find_duplicates`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct DuplicateOptions {
+    /// Ignore files smaller than this many bytes (empty files are always ignored).
+    pub min_size: u64,
+    pub follow_symlinks: bool,
+}
+
+/// A set of files with identical content.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DuplicateGroup {
+    /// Hex-encoded BLAKE3 hash of the shared content.
+    pub hash: String,
+    /// Size of each file in bytes.
+    pub size: u64,
+    pub paths: Vec<String>,
+    /// Bytes freed by keeping a single copy.
+    pub reclaimable: u64,
+}
+
+/// Summary returned once the search completes.
+#[derive(Serialize)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    pub files_scanned: u64,
+    pub total_reclaimable: u64,
+}
+
+/// Find files with identical content below `root`.
+///
+/// Candidates are narrowed by size, then by a hash of the first 64 KiB, and
+/// finally confirmed with a full BLAKE3 hash. Each confirmed group is emitted
+/// on `duplicates://group` as soon as it is known. The search can be paused
+/// or cancelled through `operation_id`. Files matching the exclusion rules
+/// are not considered.
+#[command]
+pub async fn find_duplicates(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    settings: State<'_, SettingsStore>,
+    root: String,
+    options: Option<DuplicateOptions>,
+    operation_id: Option<String>,
+) -> Result<DuplicateReport, ExplorerError> {
+    let options = options.unwrap_or_default();
+    let exclusions = settings.exclusions(Path::new(&root))?;
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    tasks
+        .run_blocking("duplicates", move || {
+            search(&handle, &root, &options, &exclusions, &mut |group| {
+                let _ = app.emit(DUPLICATE_GROUP_EVENT, group);
+            })
+        })
+        .await
+}
+
+/// Find files with identical content below `root`, calling `on_group` with
+/// each group as soon as it is confirmed.
+pub fn search(
+    operation: &OperationHandle,
+    root: &str,
+    options: &DuplicateOptions,
+    exclusions: &Exclusions,
+    on_group: &mut dyn FnMut(&DuplicateGroup),
+) -> Result<DuplicateReport, ExplorerError> {
+    if !Path::new(root).is_dir() {
+        return Err(ExplorerError::NotFound(format!(
+            "Not a directory: {}",
+            root
+        )));
+    }
+
+    // Pass 1: group by size.
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    let mut files_scanned = 0;
+    for entry in WalkDir::new(root)
+        .follow_links(options.follow_symlinks)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || entry
+                    .metadata()
+                    .map_or(true, |meta| !exclusions.excludes(entry.path(), &meta))
+        })
+        .flatten()
+    {
+        operation.checkpoint()?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let size = match entry.metadata() {
+            Ok(meta) => meta.len(),
+            Err(_) => continue,
+        };
+        files_scanned += 1;
+        if size == 0 || size < options.min_size {
+            continue;
+        }
+        by_size.entry(size).or_default().push(entry.into_path());
+    }
+
+    let mut groups = Vec::new();
+    for (size, paths) in by_size.into_iter().filter(|(_, paths)| paths.len() > 1) {
+        // Pass 2: group by partial hash. Files that fit in the partial window
+        // are already fully hashed, so the last pass can be skipped for them.
+        let by_partial = group_by_hash(operation, paths, Some(PARTIAL_HASH_BYTES))?;
+        for (partial, candidates) in by_partial {
+            let confirmed = if size <= PARTIAL_HASH_BYTES {
+                vec![(partial, candidates)]
+            } else {
+                // Pass 3: confirm with a full hash.
+                group_by_hash(operation, candidates, None)?
+            };
+
+            for (hash, paths) in confirmed {
+                let group = DuplicateGroup {
+                    hash,
+                    size,
+                    reclaimable: size * (paths.len() as u64 - 1),
+                    paths: paths
+                        .iter()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .collect(),
+                };
+                on_group(&group);
+                groups.push(group);
+            }
+        }
+    }
+
+    groups.sort_by_key(|group| std::cmp::Reverse(group.reclaimable));
+    let total_reclaimable = groups.iter().map(|g| g.reclaimable).sum();
+    Ok(DuplicateReport {
+        groups,
+        files_scanned,
+        total_reclaimable,
+    })
+}
+
+/// Result of `dedupe_hardlink`.
+#[derive(Serialize)]
+pub struct DedupeReport {
+    /// One entry per duplicate replaced, not including the kept file.
+    pub results: Vec<PathResult>,
+    pub bytes_reclaimed: u64,
+}
+
+/// Replace the files of `duplicate_group` other than `keep_path` with hard
+/// links to it, so they all share one copy of the data.
+///
+/// Every file is hashed again first and left alone if it no longer matches
+/// the group. Files on another volume than `keep_path` cannot be linked and
+/// are reported as failed. Linked files take on the permissions and
+/// timestamps of the kept file. Only files whose data had no other links
+/// count towards `bytes_reclaimed`. The replacement can be cancelled through
+/// `operation_id`.
+#[command]
+pub async fn dedupe_hardlink(
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    duplicate_group: DuplicateGroup,
+    keep_path: String,
+    operation_id: Option<String>,
+) -> Result<DedupeReport, ExplorerError> {
+    if !duplicate_group.paths.contains(&keep_path) {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Not part of the duplicate group: {}",
+            keep_path
+        )));
+    }
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    tasks
+        .run_blocking("dedupe_hardlink", move || {
+            let keep = Path::new(&keep_path);
+            let kept = matching_file(&handle, keep, &duplicate_group)?;
+            let mut bytes_reclaimed = 0;
+            let results = duplicate_group
+                .paths
+                .iter()
+                .filter(|path| **path != keep_path)
+                .map(|path| {
+                    let result =
+                        link_duplicate(&handle, keep, &kept, Path::new(path), &duplicate_group)
+                            .map(|reclaimed| bytes_reclaimed += reclaimed);
+                    PathResult::from_result(path.clone(), result)
+                })
+                .collect();
+            Ok(DedupeReport {
+                results,
+                bytes_reclaimed,
+            })
+        })
+        .await
+}
+
+/// Replace `duplicate` with a hard link to `keep`, returning the bytes freed.
+fn link_duplicate(
+    operation: &OperationHandle,
+    keep: &Path,
+    kept: &FileIdentity,
+    duplicate: &Path,
+    group: &DuplicateGroup,
+) -> Result<u64, ExplorerError> {
+    let identity = matching_file(operation, duplicate, group)?;
+    if identity.volume != kept.volume {
+        return Err(ExplorerError::Unsupported(
+            "Not on the same volume as the kept file".to_string(),
+        ));
+    }
+    if identity.index == kept.index {
+        // Already a link to the kept file.
+        return Ok(0);
+    }
+
+    // Linked under a temporary name first and then renamed over the
+    // duplicate, so a failure never leaves it missing.
+    let name = duplicate.file_name().unwrap_or_default().to_string_lossy();
+    let temp = duplicate.with_file_name(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()));
+    fs::hard_link(keep, &temp).map_err(|e| format!("Failed to create hard link: {}", e))?;
+    if let Err(e) = fs::rename(&temp, duplicate) {
+        let _ = fs::remove_file(&temp);
+        return Err(ExplorerError::io(
+            &format!("Failed to replace {}", duplicate.display()),
+            e,
+        ));
+    }
+    Ok(if identity.links == 1 { group.size } else { 0 })
+}
+
+/// Identity of the regular file at `path`, after checking that its content
+/// still matches `group`.
+fn matching_file(
+    operation: &OperationHandle,
+    path: &Path,
+    group: &DuplicateGroup,
+) -> Result<FileIdentity, ExplorerError> {
+    let meta = fs::symlink_metadata(path)
+        .map_err(|e| ExplorerError::io(&format!("Failed to read {}", path.display()), e))?;
+    if !meta.is_file() {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Not a regular file: {}",
+            path.display()
+        )));
+    }
+    // Reads fail with the cancellation itself once the operation is
+    // cancelled.
+    let hash = blake3_file_with(path, None, |_| {
+        operation.checkpoint().map_err(io::Error::other)
+    })
+    .map_err(|e| {
+        operation
+            .checkpoint()
+            .err()
+            .unwrap_or_else(|| ExplorerError::io(&format!("Failed to read {}", path.display()), e))
+    })?;
+    if meta.len() != group.size || hash != group.hash {
+        return Err(format!("{} changed since the search", path.display()).into());
+    }
+    file_identity(path)
+        .map_err(|e| ExplorerError::io(&format!("Failed to read {}", path.display()), e))
+}
+
+/// Where a file's data lives, and how many names it has.
+pub struct FileIdentity {
+    pub volume: u64,
+    /// The inode, or file index on Windows.
+    pub index: u64,
+    pub links: u64,
+}
+
+#[cfg(unix)]
+pub fn file_identity(path: &Path) -> io::Result<FileIdentity> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = fs::metadata(path)?;
+    Ok(FileIdentity {
+        volume: meta.dev(),
+        index: meta.ino(),
+        links: meta.nlink(),
+    })
+}
+
+#[cfg(windows)]
+pub fn file_identity(path: &Path) -> io::Result<FileIdentity> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_SHARE_DELETE, FILE_SHARE_READ,
+        FILE_SHARE_WRITE,
+    };
+
+    let file = fs::OpenOptions::new()
+        .access_mode(0)
+        .share_mode((FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE).0)
+        .open(path)?;
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle()), &mut info) }?;
+    Ok(FileIdentity {
+        volume: info.dwVolumeSerialNumber as u64,
+        index: (info.nFileIndexHigh as u64) << 32 | info.nFileIndexLow as u64,
+        links: info.nNumberOfLinks as u64,
+    })
+}
+
+/// Hash each path and keep only the hashes shared by two or more files.
+fn group_by_hash(
+    operation: &OperationHandle,
+    paths: Vec<PathBuf>,
+    limit: Option<u64>,
+) -> Result<Vec<(String, Vec<PathBuf>)>, ExplorerError> {
+    let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        operation.checkpoint()?;
+        // Large files can be cancelled part way through.
+        let hash = blake3_file_with(&path, limit, |_| {
+            operation.checkpoint().map_err(io::Error::other)
+        });
+        match hash {
+            Ok(hash) => by_hash.entry(hash).or_default().push(path),
+            // Unreadable files simply drop out of the candidate set.
+            Err(_) => operation.checkpoint()?,
+        }
+    }
+    Ok(by_hash
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect())
+}
+
+/// Hash a file with BLAKE3, optionally stopping after `limit` bytes.
+pub fn blake3_file(path: &Path, limit: Option<u64>) -> io::Result<String> {
+    blake3_file_with(path, limit, |_| Ok(()))
+}
+
+/// Like `blake3_file`, calling `on_chunk` with the bytes read so far.
+/// Returning an error from `on_chunk` aborts the read.
+fn blake3_file_with<F>(path: &Path, limit: Option<u64>, mut on_chunk: F) -> io::Result<String>
+where
+    F: FnMut(u64) -> io::Result<()>,
+{
+    let mut hasher = blake3::Hasher::new();
+    read_chunks(path, limit, |chunk, read_total| {
+        hasher.update(chunk);
+        on_chunk(read_total)
+    })?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
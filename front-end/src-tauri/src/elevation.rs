@@ -0,0 +1,313 @@
+//! Copies, moves and deletes run with administrator rights, for protected
+//! folders where the same operation fails with `permission_denied`.
+//!
+//! The system asks the user for credentials once per call: through UAC on
+//! Windows, `osascript`'s administrator prompt on macOS and `pkexec`
+//! elsewhere. The work is done by the system's own tools running elevated,
+//! never by this app.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tauri::{command, AppHandle, State};
+
+use crate::error::ExplorerError;
+use crate::file_ops::{PathResult, PendingDeletes};
+use crate::journal::{self, JournalEntry, JournalKind};
+use crate::tasks::TaskRegistry;
+
+/// What `run_elevated` does with its paths.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevatedAction {
+    Copy,
+    Move,
+    /// Delete permanently; the trash of an administrator is no use to the
+    /// user.
+    Delete,
+}
+
+/// Copy or move `paths` into `destination`, or delete them permanently,
+/// with administrator rights. Call it after the normal command reported
+/// `permission_denied` and the user chose to retry as administrator.
+///
+/// Deleting needs a `confirm_token` from `prepare_delete`, as for
+/// `delete_permanently`. Items whose name is already taken in `destination`
+/// are reported and left alone, since the system tools would replace them
+/// without asking. Fails with `permission_denied` if the user dismisses the
+/// prompt or is not an administrator.
+#[command]
+pub async fn run_elevated(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    pending: State<'_, PendingDeletes>,
+    action: ElevatedAction,
+    paths: Vec<String>,
+    destination: Option<String>,
+    confirm_token: Option<String>,
+) -> Result<Vec<PathResult>, ExplorerError> {
+    let destination = match action {
+        ElevatedAction::Delete => {
+            let token = confirm_token.ok_or_else(|| {
+                ExplorerError::InvalidInput(
+                    "Deleting needs a confirm_token from prepare_delete".to_string(),
+                )
+            })?;
+            pending.take(&token, &paths)?;
+            None
+        }
+        ElevatedAction::Copy | ElevatedAction::Move => {
+            let destination = destination.ok_or_else(|| {
+                ExplorerError::InvalidInput("Copying and moving need a destination".to_string())
+            })?;
+            if !Path::new(&destination).is_dir() {
+                return Err(ExplorerError::NotFound(format!(
+                    "Not a directory: {}",
+                    destination
+                )));
+            }
+            Some(destination)
+        }
+    };
+
+    tasks
+        .run_blocking("run_elevated", move || {
+            let mut results = Vec::new();
+            let mut items: Vec<(String, Option<PathBuf>)> = Vec::new();
+            for path in paths {
+                let target = match &destination {
+                    Some(destination) => match Path::new(&path).file_name() {
+                        Some(name) => Some(Path::new(destination).join(name)),
+                        None => {
+                            let error = format!("Not a file name: {}", path);
+                            results.push(PathResult::from_result(path, Err(error)));
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                match &target {
+                    Some(target) if exists(target) => {
+                        let error = format!("{} already exists", target.display());
+                        results.push(PathResult::from_result(path, Err(error)));
+                    }
+                    _ => items.push((path, target)),
+                }
+            }
+            if items.is_empty() {
+                return Ok(results);
+            }
+
+            let sources: Vec<String> = items.iter().map(|(path, _)| path.clone()).collect();
+            let outcome = match run_privileged(action, &sources, destination.as_deref()) {
+                Err(ExplorerError::PermissionDenied(message)) => {
+                    return Err(ExplorerError::PermissionDenied(message))
+                }
+                Err(e) => Err(e.to_string()),
+                Ok(()) => Ok(()),
+            };
+
+            // A tool that failed may still have done part of the work, so
+            // what happened to each item is read back from disk.
+            let mut entries = Vec::new();
+            for (path, target) in items {
+                let done = match (action, &target) {
+                    (ElevatedAction::Delete, _) => !exists(Path::new(&path)),
+                    (ElevatedAction::Copy, Some(target)) => exists(target),
+                    (ElevatedAction::Move, Some(target)) => {
+                        exists(target) && !exists(Path::new(&path))
+                    }
+                    _ => false,
+                };
+                let result = if done {
+                    entries.push(JournalEntry {
+                        source: path.clone(),
+                        target: target.map(|target| target.to_string_lossy().into_owned()),
+                    });
+                    Ok(())
+                } else {
+                    Err(outcome.clone().err().unwrap_or_else(|| {
+                        "The elevated operation left this item as it was".to_string()
+                    }))
+                };
+                results.push(PathResult::from_result(path, result));
+            }
+            let kind = match action {
+                ElevatedAction::Copy => JournalKind::Copy,
+                ElevatedAction::Move => JournalKind::Move,
+                ElevatedAction::Delete => JournalKind::Delete,
+            };
+            journal::record(&app, kind, &entries);
+            Ok(results)
+        })
+        .await
+}
+
+/// Whether something is at `path`. Paths that cannot be looked at count as
+/// there, so an unreadable item is never reported as deleted.
+fn exists(path: &Path) -> bool {
+    match fs::symlink_metadata(path) {
+        Ok(_) => true,
+        Err(e) => e.kind() != io::ErrorKind::NotFound,
+    }
+}
+
+fn refused() -> ExplorerError {
+    ExplorerError::PermissionDenied("Administrator rights were not granted".to_string())
+}
+
+/// Command line of the system tool doing `action`.
+#[cfg(unix)]
+fn tool_args(action: ElevatedAction, sources: &[String], destination: Option<&str>) -> Vec<String> {
+    let mut args: Vec<String> = match action {
+        ElevatedAction::Copy => vec!["/bin/cp".into(), "-R".into(), "--".into()],
+        ElevatedAction::Move => vec!["/bin/mv".into(), "--".into()],
+        ElevatedAction::Delete => vec!["/bin/rm".into(), "-rf".into(), "--".into()],
+    };
+    args.extend(sources.iter().cloned());
+    args.extend(destination.map(str::to_string));
+    args
+}
+
+/// Error output of a tool, or a generic message if it printed none.
+#[cfg(unix)]
+fn error_output(stderr: &[u8]) -> String {
+    let message = String::from_utf8_lossy(stderr).trim().to_string();
+    if message.is_empty() {
+        "The elevated operation failed".to_string()
+    } else {
+        message
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn run_privileged(
+    action: ElevatedAction,
+    sources: &[String],
+    destination: Option<&str>,
+) -> Result<(), ExplorerError> {
+    let output = std::process::Command::new("pkexec")
+        .args(tool_args(action, sources, destination))
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => {
+                ExplorerError::Unsupported("pkexec is not installed".to_string())
+            }
+            _ => ExplorerError::Failed(format!("Failed to run pkexec: {}", e)),
+        })?;
+    match output.status.code() {
+        Some(0) => Ok(()),
+        // pkexec's codes for a dismissed prompt and refused authorization.
+        Some(126) | Some(127) => Err(refused()),
+        _ => Err(ExplorerError::Failed(error_output(&output.stderr))),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn run_privileged(
+    action: ElevatedAction,
+    sources: &[String],
+    destination: Option<&str>,
+) -> Result<(), ExplorerError> {
+    let command = tool_args(action, sources, destination)
+        .iter()
+        .map(|arg| format!("'{}'", arg.replace('\'', r"'\''")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let script = format!(
+        "do shell script \"{}\" with administrator privileges",
+        command.replace('\\', r"\\").replace('"', "\\\"")
+    );
+    let output = std::process::Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .output()
+        .map_err(|e| ExplorerError::Failed(format!("Failed to run osascript: {}", e)))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let message = error_output(&output.stderr);
+    // AppleScript's "User canceled." error.
+    if message.contains("(-128)") {
+        Err(refused())
+    } else {
+        Err(ExplorerError::Failed(message))
+    }
+}
+
+#[cfg(windows)]
+fn run_privileged(
+    action: ElevatedAction,
+    sources: &[String],
+    destination: Option<&str>,
+) -> Result<(), ExplorerError> {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, ERROR_CANCELLED};
+    use windows::Win32::System::Threading::{GetExitCodeProcess, WaitForSingleObject, INFINITE};
+    use windows::Win32::UI::Shell::{
+        ShellExecuteExW, SEE_MASK_NOASYNC, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
+
+    let quote = |path: &str| format!("'{}'", path.replace('\'', "''"));
+    let items = sources
+        .iter()
+        .map(|source| quote(source))
+        .collect::<Vec<_>>()
+        .join(",");
+    let script = match (action, destination) {
+        (ElevatedAction::Copy, Some(destination)) => format!(
+            "Copy-Item -LiteralPath {} -Destination {} -Recurse",
+            items,
+            quote(destination)
+        ),
+        (ElevatedAction::Move, Some(destination)) => format!(
+            "Move-Item -LiteralPath {} -Destination {}",
+            items,
+            quote(destination)
+        ),
+        _ => format!("Remove-Item -LiteralPath {} -Recurse -Force", items),
+    };
+    // Passed encoded, so no path needs quoting for the command line.
+    let script = format!("$ErrorActionPreference = 'Stop'; {}", script);
+    let encoded: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let parameters = format!(
+        "-NoProfile -NonInteractive -EncodedCommand {}",
+        STANDARD.encode(encoded)
+    );
+
+    let wide = |text: &str| text.encode_utf16().chain([0]).collect::<Vec<u16>>();
+    let (verb, file, parameters) = (wide("runas"), wide("powershell.exe"), wide(&parameters));
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        fMask: SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC,
+        lpVerb: PCWSTR(verb.as_ptr()),
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: PCWSTR(parameters.as_ptr()),
+        nShow: SW_HIDE.0,
+        ..Default::default()
+    };
+    if let Err(e) = unsafe { ShellExecuteExW(&mut info) } {
+        return Err(if e.code() == ERROR_CANCELLED.to_hresult() {
+            refused()
+        } else {
+            ExplorerError::Failed(format!("Failed to start the elevated operation: {}", e))
+        });
+    }
+    let mut code = 1;
+    unsafe {
+        WaitForSingleObject(info.hProcess, INFINITE);
+        let _ = GetExitCodeProcess(info.hProcess, &mut code);
+        let _ = CloseHandle(info.hProcess);
+    }
+    if code == 0 {
+        Ok(())
+    } else {
+        Err(ExplorerError::Failed(
+            "The elevated operation failed".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,97 @@
+//! The error type returned by commands.
+//!
+//! Errors reach the frontend as `{ "code": "not_found", "message": "..." }`,
+//! so the UI can pick a translated, actionable message by code and fall back
+//! to the English message for anything it does not know. `cancelled` has no
+//! message.
+
+use std::fmt;
+use std::io;
+
+use serde::Serialize;
+
+use crate::operations::CANCELLED;
+
+/// Why a command failed. Each variant carries an English description.
+#[derive(Serialize, Debug)]
+#[serde(tag = "code", content = "message", rename_all = "snake_case")]
+pub enum ExplorerError {
+    /// A file or folder does not exist.
+    NotFound(String),
+    /// The user lacks permission to read or change something.
+    PermissionDenied(String),
+    /// The destination of a create, copy or rename is taken.
+    AlreadyExists(String),
+    /// A bad argument, such as an invalid file name or option.
+    InvalidInput(String),
+    /// The operation is not available for this platform, file system or
+    /// file type.
+    Unsupported(String),
+    /// Any other I/O failure.
+    Io(String),
+    /// The operation was cancelled through `cancel_operation`.
+    Cancelled,
+    /// Any other failure.
+    Failed(String),
+}
+
+impl ExplorerError {
+    /// Classify an I/O error by its kind, describing it as
+    /// `"<context>: <error>"`.
+    pub fn io(context: &str, error: io::Error) -> Self {
+        ExplorerError::with_kind(error.kind(), format!("{}: {}", context, error))
+    }
+
+    fn with_kind(kind: io::ErrorKind, message: String) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => ExplorerError::NotFound(message),
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+                ExplorerError::PermissionDenied(message)
+            }
+            io::ErrorKind::AlreadyExists => ExplorerError::AlreadyExists(message),
+            io::ErrorKind::InvalidInput | io::ErrorKind::InvalidFilename => {
+                ExplorerError::InvalidInput(message)
+            }
+            io::ErrorKind::Unsupported | io::ErrorKind::CrossesDevices => {
+                ExplorerError::Unsupported(message)
+            }
+            _ => ExplorerError::Io(message),
+        }
+    }
+}
+
+impl fmt::Display for ExplorerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExplorerError::NotFound(message)
+            | ExplorerError::PermissionDenied(message)
+            | ExplorerError::AlreadyExists(message)
+            | ExplorerError::InvalidInput(message)
+            | ExplorerError::Unsupported(message)
+            | ExplorerError::Io(message)
+            | ExplorerError::Failed(message) => f.write_str(message),
+            ExplorerError::Cancelled => f.write_str(CANCELLED),
+        }
+    }
+}
+
+impl std::error::Error for ExplorerError {}
+
+/// Internal helpers describe failures as strings; those surface as `failed`.
+impl From<String> for ExplorerError {
+    fn from(message: String) -> Self {
+        ExplorerError::Failed(message)
+    }
+}
+
+impl From<&str> for ExplorerError {
+    fn from(message: &str) -> Self {
+        ExplorerError::from(message.to_string())
+    }
+}
+
+impl From<io::Error> for ExplorerError {
+    fn from(error: io::Error) -> Self {
+        ExplorerError::with_kind(error.kind(), error.to_string())
+    }
+}
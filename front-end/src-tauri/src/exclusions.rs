@@ -0,0 +1,133 @@
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State};
+
+use crate::error::ExplorerError;
+use crate::settings::SettingsStore;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Entries left out of scans, name searches, content searches and the
+/// duplicate finder.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ExclusionRules {
+    /// Gitignore-style patterns, e.g. `node_modules/`, `*.tmp` or `!keep.tmp`.
+    /// Patterns starting with `/` are anchored to the folder being walked.
+    pub patterns: Vec<String>,
+    /// Skip files smaller than this many bytes.
+    pub min_file_size: Option<u64>,
+    /// Skip files larger than this many bytes.
+    pub max_file_size: Option<u64>,
+    /// Skip files not modified in this many days.
+    pub older_than_days: Option<u64>,
+    /// Skip files modified in the last this many days.
+    pub newer_than_days: Option<u64>,
+}
+
+impl ExclusionRules {
+    /// Reject rules that would fail every later walk.
+    pub fn validate(&self) -> Result<(), ExplorerError> {
+        if let (Some(min), Some(max)) = (self.min_file_size, self.max_file_size) {
+            if min > max {
+                return Err(ExplorerError::InvalidInput(
+                    "Minimum file size is larger than the maximum".to_string(),
+                ));
+            }
+        }
+        Exclusions::new(self, Path::new("")).map_err(ExplorerError::InvalidInput)?;
+        Ok(())
+    }
+}
+
+/// Exclusion rules compiled for one walk.
+pub struct Exclusions {
+    patterns: Gitignore,
+    min_file_size: Option<u64>,
+    max_file_size: Option<u64>,
+    /// Files modified before this are excluded.
+    modified_after: Option<SystemTime>,
+    /// Files modified after this are excluded.
+    modified_before: Option<SystemTime>,
+}
+
+impl Exclusions {
+    /// Compile `rules` for a walk of `root`.
+    pub fn new(rules: &ExclusionRules, root: &Path) -> Result<Self, String> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in rules.patterns.iter().map(|p| p.trim()) {
+            if pattern.is_empty() || pattern.starts_with('#') {
+                continue;
+            }
+            builder
+                .add_line(None, pattern)
+                .map_err(|e| format!("Invalid exclusion pattern {:?}: {}", pattern, e))?;
+        }
+        let patterns = builder
+            .build()
+            .map_err(|e| format!("Invalid exclusion patterns: {}", e))?;
+
+        let days_ago = |days: u64| {
+            SystemTime::now().checked_sub(Duration::from_secs(days.saturating_mul(SECONDS_PER_DAY)))
+        };
+        Ok(Exclusions {
+            patterns,
+            min_file_size: rules.min_file_size,
+            max_file_size: rules.max_file_size,
+            modified_after: rules.older_than_days.and_then(days_ago),
+            modified_before: rules.newer_than_days.and_then(days_ago),
+        })
+    }
+
+    /// Whether `path` should be skipped. Folders are only matched against the
+    /// patterns; the size and age rules apply to files.
+    ///
+    /// Walkers check entries top-down and skip the contents of excluded
+    /// folders, so parents are not matched again here.
+    pub fn excludes(&self, path: &Path, meta: &Metadata) -> bool {
+        if let Match::Ignore(_) = self.patterns.matched(path, meta.is_dir()) {
+            return true;
+        }
+        if meta.is_dir() {
+            return false;
+        }
+        let size = meta.len();
+        if self.min_file_size.is_some_and(|min| size < min)
+            || self.max_file_size.is_some_and(|max| size > max)
+        {
+            return true;
+        }
+        if self.modified_after.is_none() && self.modified_before.is_none() {
+            return false;
+        }
+        meta.modified().is_ok_and(|modified| {
+            self.modified_after.is_some_and(|after| modified < after)
+                || self.modified_before.is_some_and(|before| modified > before)
+        })
+    }
+}
+
+/// Current exclusion rules.
+#[command]
+pub fn get_exclusions(settings: State<'_, SettingsStore>) -> ExclusionRules {
+    settings.get().exclusions
+}
+
+/// Replace the exclusion rules. They apply to walks started afterwards.
+#[command]
+pub fn set_exclusions(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    rules: ExclusionRules,
+) -> Result<ExclusionRules, ExplorerError> {
+    let updated = settings.update(&app, |settings| {
+        settings.exclusions = rules;
+        Ok(())
+    })?;
+    Ok(updated.exclusions)
+}
@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+use crate::index::{subtree_bounds, FileIndex};
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// File format written by `export_scan`.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    /// A single JSON document; folder exports are nested like the scan tree.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+/// What each exported row describes.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportShape {
+    /// Every file below the scanned folder.
+    #[default]
+    Files,
+    /// Every folder, with the total size and file count below it.
+    Folders,
+}
+
+/// A field of an exported row.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportColumn {
+    Path,
+    Name,
+    Parent,
+    Size,
+    /// Seconds since the Unix epoch.
+    Modified,
+    IsDir,
+    Extension,
+    /// Files below a folder; 1 for files.
+    FileCount,
+}
+
+impl ExportColumn {
+    fn key(self) -> &'static str {
+        match self {
+            ExportColumn::Path => "path",
+            ExportColumn::Name => "name",
+            ExportColumn::Parent => "parent",
+            ExportColumn::Size => "size",
+            ExportColumn::Modified => "modified",
+            ExportColumn::IsDir => "is_dir",
+            ExportColumn::Extension => "extension",
+            ExportColumn::FileCount => "file_count",
+        }
+    }
+}
+
+/// Options accepted by `export_scan`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub shape: ExportShape,
+    /// Columns in output order. Defaults depend on `shape`.
+    pub columns: Option<Vec<ExportColumn>>,
+}
+
+/// Result of `export_scan`.
+#[derive(Serialize)]
+pub struct ExportSummary {
+    pub destination: String,
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+/// An indexed entry, with totals aggregated for folders.
+struct Row {
+    path: String,
+    parent: String,
+    name: String,
+    size: u64,
+    modified: Option<u64>,
+    is_dir: bool,
+    extension: Option<String>,
+    file_count: u64,
+}
+
+/// Export a completed scan to CSV, JSON or NDJSON.
+///
+/// `scan_id` is the scanned folder, or any folder below it; rows come from
+/// the file index that scans populate. Nothing is left at `destination` if
+/// the export fails or is cancelled through `operation_id`.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_scan(
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    index: State<'_, FileIndex>,
+    scan_id: String,
+    format: ExportFormat,
+    destination: String,
+    options: Option<ExportOptions>,
+    operation_id: Option<String>,
+) -> Result<ExportSummary, ExplorerError> {
+    let options = options.unwrap_or_default();
+    let columns = options
+        .columns
+        .clone()
+        .unwrap_or_else(|| match options.shape {
+            ExportShape::Files => vec![
+                ExportColumn::Path,
+                ExportColumn::Name,
+                ExportColumn::Size,
+                ExportColumn::Modified,
+                ExportColumn::Extension,
+            ],
+            ExportShape::Folders => vec![
+                ExportColumn::Path,
+                ExportColumn::Name,
+                ExportColumn::Size,
+                ExportColumn::FileCount,
+                ExportColumn::Modified,
+            ],
+        });
+    if columns.is_empty() {
+        return Err(ExplorerError::InvalidInput(
+            "At least one column must be exported".to_string(),
+        ));
+    }
+    // Stored paths have no trailing separator.
+    let root = Path::new(&scan_id)
+        .components()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .to_string();
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("export_scan", move || {
+            let rows = load_rows(&conn, &handle, &root, options.shape)?;
+            let file = File::create(&destination)
+                .map_err(|e| format!("Failed to create {}: {}", destination, e))?;
+            let mut out = BufWriter::new(file);
+            let result = match format {
+                ExportFormat::Json if options.shape == ExportShape::Folders => {
+                    write_tree(&mut out, &rows, &root, &columns).map_err(ExplorerError::from)
+                }
+                _ => write_rows(&mut out, &handle, &rows, format, &columns),
+            }
+            .and_then(|_| out.flush().map_err(|e| e.to_string().into()));
+            drop(out);
+            if let Err(e) = result.and_then(|_| handle.checkpoint()) {
+                let _ = fs::remove_file(&destination);
+                return Err(match e {
+                    ExplorerError::Cancelled => e,
+                    e => format!("Failed to export scan: {}", e).into(),
+                });
+            }
+
+            Ok(ExportSummary {
+                bytes: fs::metadata(&destination).map(|m| m.len()).unwrap_or(0),
+                rows: rows.len() as u64,
+                destination,
+            })
+        })
+        .await
+}
+
+/// Indexed rows under `root` in path order, aggregated into folders for
+/// `ExportShape::Folders`.
+fn load_rows(
+    conn: &Connection,
+    operation: &OperationHandle,
+    root: &str,
+    shape: ExportShape,
+) -> Result<Vec<Row>, ExplorerError> {
+    let indexed = conn
+        .query_row(
+            "SELECT 1 FROM files WHERE path = ?1 AND is_dir = 1",
+            params![root],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query index: {}", e))?;
+    if indexed.is_none() {
+        return Err(format!("No scan found for {}; scan the folder first", root).into());
+    }
+
+    let (lower, upper) = subtree_bounds(root);
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, parent, name, size, mtime, is_dir, extension FROM files
+             WHERE path = ?1 OR (path >= ?2 AND path < ?3) ORDER BY path",
+        )
+        .map_err(|e| format!("Failed to query index: {}", e))?;
+    let rows = stmt
+        .query_map(params![root, lower, upper], |row| {
+            let is_dir: bool = row.get(5)?;
+            Ok(Row {
+                path: row.get(0)?,
+                parent: row.get(1)?,
+                name: row.get(2)?,
+                size: row.get::<_, i64>(3)? as u64,
+                modified: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+                is_dir,
+                extension: row.get(6)?,
+                file_count: if is_dir { 0 } else { 1 },
+            })
+        })
+        .map_err(|e| format!("Failed to query index: {}", e))?;
+
+    let mut files = Vec::new();
+    let mut folders: BTreeMap<String, Row> = BTreeMap::new();
+    for row in rows {
+        operation.checkpoint()?;
+        let row = row.map_err(|e| format!("Failed to query index: {}", e))?;
+        match (shape, row.is_dir) {
+            (ExportShape::Files, false) => files.push(row),
+            (ExportShape::Files, true) => {}
+            (ExportShape::Folders, true) => {
+                folders.insert(row.path.clone(), row);
+            }
+            (ExportShape::Folders, false) => {
+                // Rows come in path order, so every ancestor is already known.
+                let mut parent = Some(Path::new(&row.path));
+                while let Some(dir) = parent.and_then(Path::parent) {
+                    let Some(folder) = folders.get_mut(dir.to_string_lossy().as_ref()) else {
+                        break;
+                    };
+                    folder.size += row.size;
+                    folder.file_count += 1;
+                    if folder.path == root {
+                        break;
+                    }
+                    parent = Some(dir);
+                }
+            }
+        }
+    }
+    Ok(match shape {
+        ExportShape::Files => files,
+        ExportShape::Folders => folders.into_values().collect(),
+    })
+}
+
+fn value(row: &Row, column: ExportColumn) -> Value {
+    match column {
+        ExportColumn::Path => Value::from(row.path.as_str()),
+        ExportColumn::Name => Value::from(row.name.as_str()),
+        ExportColumn::Parent => Value::from(row.parent.as_str()),
+        ExportColumn::Size => Value::from(row.size),
+        ExportColumn::Modified => row.modified.map_or(Value::Null, Value::from),
+        ExportColumn::IsDir => Value::from(row.is_dir),
+        ExportColumn::Extension => row.extension.as_deref().map_or(Value::Null, Value::from),
+        ExportColumn::FileCount => Value::from(row.file_count),
+    }
+}
+
+fn object(row: &Row, columns: &[ExportColumn]) -> Map<String, Value> {
+    columns
+        .iter()
+        .map(|&column| (column.key().to_string(), value(row, column)))
+        .collect()
+}
+
+/// Write one row per line (CSV, NDJSON) or a flat JSON array.
+fn write_rows(
+    out: &mut impl Write,
+    operation: &OperationHandle,
+    rows: &[Row],
+    format: ExportFormat,
+    columns: &[ExportColumn],
+) -> Result<(), ExplorerError> {
+    let io = |e: std::io::Error| e.to_string();
+    match format {
+        ExportFormat::Csv => {
+            let header: Vec<&str> = columns.iter().map(|column| column.key()).collect();
+            writeln!(out, "{}", header.join(",")).map_err(io)?;
+        }
+        ExportFormat::Json => write!(out, "[").map_err(io)?,
+        ExportFormat::Ndjson => {}
+    }
+    for (i, row) in rows.iter().enumerate() {
+        operation.checkpoint()?;
+        match format {
+            ExportFormat::Csv => {
+                let fields: Vec<String> = columns
+                    .iter()
+                    .map(|&column| csv_field(&value(row, column)))
+                    .collect();
+                writeln!(out, "{}", fields.join(",")).map_err(io)?;
+            }
+            ExportFormat::Json => {
+                if i > 0 {
+                    write!(out, ",").map_err(io)?;
+                }
+                serde_json::to_writer(&mut *out, &object(row, columns))
+                    .map_err(|e| e.to_string())?;
+            }
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut *out, &object(row, columns))
+                    .map_err(|e| e.to_string())?;
+                writeln!(out).map_err(io)?;
+            }
+        }
+    }
+    if format == ExportFormat::Json {
+        writeln!(out, "]").map_err(io)?;
+    }
+    Ok(())
+}
+
+/// Write folders as a nested JSON tree rooted at `root`.
+fn write_tree(
+    out: &mut impl Write,
+    rows: &[Row],
+    root: &str,
+    columns: &[ExportColumn],
+) -> Result<(), String> {
+    let mut children: BTreeMap<&str, Vec<&Row>> = BTreeMap::new();
+    for row in rows.iter().filter(|row| row.path != root) {
+        children.entry(row.parent.as_str()).or_default().push(row);
+    }
+    fn build(row: &Row, children: &BTreeMap<&str, Vec<&Row>>, columns: &[ExportColumn]) -> Value {
+        let mut node = object(row, columns);
+        let nested = children
+            .get(row.path.as_str())
+            .into_iter()
+            .flatten()
+            .map(|child| build(child, children, columns))
+            .collect();
+        node.insert("children".to_string(), Value::Array(nested));
+        Value::Object(node)
+    }
+    let root = rows
+        .iter()
+        .find(|row| row.path == root)
+        .ok_or_else(|| format!("No scan found for {}", root))?;
+    serde_json::to_writer(out, &build(root, &children, columns)).map_err(|e| e.to_string())
+}
+
+/// Format a value as a CSV field, quoting it when needed.
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
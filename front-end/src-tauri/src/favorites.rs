@@ -0,0 +1,169 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, State};
+
+use crate::commands::{app_data_dir, to_unix_secs, write_json_atomic};
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// File name of the favorites list inside the app data directory.
+const FAVORITES_FILE_NAME: &str = "favorites.json";
+
+/// A favorite as saved on disk.
+#[derive(Serialize, Deserialize, Clone)]
+struct StoredFavorite {
+    path: String,
+    name: String,
+    /// Seconds since the Unix epoch.
+    added_at: u64,
+}
+
+/// A pinned location shown in the sidebar.
+#[derive(Serialize)]
+pub struct Favorite {
+    pub path: String,
+    /// Display name; defaults to the last path component.
+    pub name: String,
+    /// Seconds since the Unix epoch.
+    pub added_at: u64,
+    pub is_dir: bool,
+    /// `false` if the path was removed or its drive is not connected.
+    pub exists: bool,
+}
+
+/// Managed state holding the favorites, kept in sync with the file on disk.
+pub struct Favorites {
+    file: PathBuf,
+    entries: Mutex<Vec<StoredFavorite>>,
+}
+
+impl Favorites {
+    /// Load the favorites from the app data directory.
+    ///
+    /// A missing or unreadable file starts an empty list rather than failing
+    /// startup.
+    pub fn init(app: &AppHandle) -> Result<Self, String> {
+        let file = app_data_dir(app)?.join(FAVORITES_FILE_NAME);
+        let entries = fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Ok(Favorites {
+            file,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// The favorites with whether each still exists. This looks at every
+    /// path, which can take as long as an unreachable share takes to time
+    /// out, so it runs off the main thread and outside the lock.
+    fn snapshot(entries: &[StoredFavorite]) -> Vec<Favorite> {
+        entries
+            .iter()
+            .map(|stored| {
+                let meta = fs::metadata(&stored.path).ok();
+                Favorite {
+                    path: stored.path.clone(),
+                    name: stored.name.clone(),
+                    added_at: stored.added_at,
+                    is_dir: meta.as_ref().is_some_and(|m| m.is_dir()),
+                    exists: meta.is_some(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Pin `path` to the sidebar and return the updated list.
+///
+/// The path must exist. Pinning it again only updates its `name`.
+#[command]
+pub async fn add_favorite(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    name: Option<String>,
+) -> Result<Vec<Favorite>, ExplorerError> {
+    tasks
+        .run_blocking("add_favorite", move || {
+            if !Path::new(&path).exists() {
+                return Err(ExplorerError::NotFound(format!(
+                    "Path does not exist: {}",
+                    path
+                )));
+            }
+            let name = name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .or_else(|| {
+                    Path::new(&path)
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                })
+                .unwrap_or_else(|| path.clone());
+
+            let favorites = app.state::<Favorites>();
+            let mut entries = favorites.entries.lock().unwrap();
+            let mut updated = entries.clone();
+            match updated.iter_mut().find(|stored| stored.path == path) {
+                Some(stored) => stored.name = name,
+                None => updated.push(StoredFavorite {
+                    path,
+                    name,
+                    added_at: to_unix_secs(Ok(SystemTime::now())).unwrap_or(0),
+                }),
+            }
+            write_json_atomic(&favorites.file, &updated)?;
+            *entries = updated.clone();
+            drop(entries);
+            Ok(Favorites::snapshot(&updated))
+        })
+        .await
+}
+
+/// Unpin `path` and return the updated list.
+#[command]
+pub async fn remove_favorite(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<Vec<Favorite>, ExplorerError> {
+    tasks
+        .run_blocking("remove_favorite", move || {
+            let favorites = app.state::<Favorites>();
+            let mut entries = favorites.entries.lock().unwrap();
+            let updated: Vec<StoredFavorite> = entries
+                .iter()
+                .filter(|stored| stored.path != path)
+                .cloned()
+                .collect();
+            if updated.len() != entries.len() {
+                write_json_atomic(&favorites.file, &updated)?;
+                *entries = updated.clone();
+            }
+            drop(entries);
+            Ok(Favorites::snapshot(&updated))
+        })
+        .await
+}
+
+/// List pinned locations in the order they were added.
+///
+/// Favorites whose path no longer exists are kept, with `exists` unset, so
+/// they reappear when e.g. their drive is reconnected.
+#[command]
+pub async fn list_favorites(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+) -> Result<Vec<Favorite>, ExplorerError> {
+    tasks
+        .run_blocking("list_favorites", move || {
+            let entries = app.state::<Favorites>().entries.lock().unwrap().clone();
+            Ok(Favorites::snapshot(&entries))
+        })
+        .await
+}
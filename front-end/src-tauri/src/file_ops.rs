@@ -0,0 +1,1225 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, State};
+use walkdir::WalkDir;
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+use crate::hashing::read_chunks;
+use crate::journal::{self, JournalEntry, JournalKind};
+use crate::operations::{resume_operation, OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// How long a permanent-delete confirmation token stays valid.
+const DELETE_TOKEN_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Event name used to report copy/move progress.
+pub const FILE_OP_PROGRESS_EVENT: &str = "file-ops://progress";
+
+/// Event emitted when an operation with the `ask` conflict policy finds a
+/// destination taken.
+pub const FILE_OP_CONFLICT_EVENT: &str = "file-ops://conflict";
+
+/// Size of the buffer used when copying file contents.
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Minimum delay between two progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Outcome of a file operation on a single path.
+#[derive(Serialize, Clone)]
+pub struct PathResult {
+    pub path: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl PathResult {
+    pub fn from_result<E: std::fmt::Display>(path: String, result: Result<(), E>) -> Self {
+        match result {
+            Ok(()) => PathResult {
+                path,
+                success: true,
+                error: None,
+            },
+            Err(e) => PathResult {
+                path,
+                success: false,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Move files and folders to the system trash / recycle bin.
+///
+/// Each path is handled independently so one failure does not stop the rest.
+#[command]
+pub async fn delete_to_trash(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    paths: Vec<String>,
+) -> Result<Vec<PathResult>, ExplorerError> {
+    tasks
+        .run_blocking("trash", move || {
+            let results: Vec<PathResult> = paths
+                .into_iter()
+                .map(|path| {
+                    let result = trash::delete(&path);
+                    PathResult::from_result(path, result)
+                })
+                .collect();
+            journal::record(&app, JournalKind::Trash, &succeeded(&results));
+            Ok(results)
+        })
+        .await
+}
+
+/// Journal entries for the paths that were handled successfully.
+fn succeeded(results: &[PathResult]) -> Vec<JournalEntry> {
+    results
+        .iter()
+        .filter(|result| result.success)
+        .map(|result| JournalEntry {
+            source: result.path.clone(),
+            target: None,
+        })
+        .collect()
+}
+
+/// Summary of what a permanent delete would remove.
+#[derive(Serialize)]
+pub struct DeletePlan {
+    /// Single-use token that must be passed to `delete_permanently`.
+    pub token: String,
+    pub paths: Vec<String>,
+    pub total_size: u64,
+    pub file_count: u64,
+    pub dir_count: u64,
+}
+
+struct PendingDelete {
+    paths: Vec<String>,
+    created: Instant,
+}
+
+/// Managed state holding confirmation tokens issued by `prepare_delete`.
+#[derive(Default)]
+pub struct PendingDeletes {
+    tokens: Mutex<HashMap<String, PendingDelete>>,
+}
+
+impl PendingDeletes {
+    /// Consume `token`, checking that it was issued for exactly `paths`.
+    pub fn take(&self, token: &str, paths: &[String]) -> Result<(), String> {
+        let plan = self
+            .tokens
+            .lock()
+            .unwrap()
+            .remove(token)
+            .ok_or_else(|| "Invalid or already used confirmation token".to_string())?;
+        if plan.created.elapsed() >= DELETE_TOKEN_TTL {
+            return Err("Confirmation token has expired".to_string());
+        }
+        if plan.paths != paths {
+            return Err("Confirmation token does not match the requested paths".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Measure what deleting `paths` would remove and issue a confirmation token
+/// for `delete_permanently` or `secure_delete`.
+#[command]
+pub async fn prepare_delete(
+    tasks: State<'_, TaskRegistry>,
+    pending: State<'_, PendingDeletes>,
+    paths: Vec<String>,
+) -> Result<DeletePlan, ExplorerError> {
+    let measured = paths.clone();
+    let (total_size, file_count, dir_count) = tasks
+        .run_blocking("prepare_delete", move || Ok(measure(&measured)))
+        .await?;
+
+    let token = uuid::Uuid::new_v4().to_string();
+    let mut tokens = pending.tokens.lock().unwrap();
+    tokens.retain(|_, p| p.created.elapsed() < DELETE_TOKEN_TTL);
+    tokens.insert(
+        token.clone(),
+        PendingDelete {
+            paths: paths.clone(),
+            created: Instant::now(),
+        },
+    );
+
+    Ok(DeletePlan {
+        token,
+        paths,
+        total_size,
+        file_count,
+        dir_count,
+    })
+}
+
+/// Irreversibly delete `paths`, bypassing the trash.
+///
+/// `confirm_token` must come from a `prepare_delete` call for exactly the same
+/// paths; tokens are single-use and expire after five minutes.
+#[command]
+pub async fn delete_permanently(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    pending: State<'_, PendingDeletes>,
+    paths: Vec<String>,
+    confirm_token: String,
+) -> Result<Vec<PathResult>, ExplorerError> {
+    pending.take(&confirm_token, &paths)?;
+
+    tasks
+        .run_blocking("delete_permanently", move || {
+            let results: Vec<PathResult> = paths
+                .into_iter()
+                .map(|path| {
+                    let result = remove_path(Path::new(&path));
+                    PathResult::from_result(path, result)
+                })
+                .collect();
+            journal::record(&app, JournalKind::Delete, &succeeded(&results));
+            Ok(results)
+        })
+        .await
+}
+
+/// Total size, file count and directory count below the given paths.
+pub fn measure<P: AsRef<Path>>(paths: &[P]) -> (u64, u64, u64) {
+    let (mut size, mut files, mut dirs) = (0, 0, 0);
+    for path in paths {
+        for entry in WalkDir::new(path.as_ref()).into_iter().flatten() {
+            if entry.file_type().is_dir() {
+                dirs += 1;
+            } else {
+                files += 1;
+                size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+    }
+    (size, files, dirs)
+}
+
+/// Remove a file, symlink or directory tree.
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Hidden, unused path next to `path` for an item on its way to replacing
+/// it, or on its way out.
+fn staging_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{}.{}.{}",
+        name,
+        uuid::Uuid::new_v4().simple(),
+        suffix
+    ))
+}
+
+/// Put the complete item at `staged` in place of `target`. The old `target`
+/// is set aside until `staged` has taken its place, and put back if it
+/// could not.
+fn replace_with(staged: &Path, target: &Path) -> io::Result<()> {
+    let old = staging_path(target, "ude-old");
+    fs::rename(target, &old)?;
+    if let Err(e) = fs::rename(staged, target) {
+        let _ = fs::rename(&old, target);
+        return Err(e);
+    }
+    // Only a hidden leftover remains if this fails; the item was replaced.
+    let _ = remove_path(&old);
+    Ok(())
+}
+
+/// Payload of the `file-ops://progress` event.
+#[derive(Serialize, Clone, Default)]
+pub struct FileOpProgress {
+    pub operation_id: String,
+    pub current_file: String,
+    pub file_bytes_copied: u64,
+    pub file_total_bytes: u64,
+    pub bytes_copied: u64,
+    pub total_bytes: u64,
+    pub files_copied: u64,
+    pub total_files: u64,
+}
+
+/// Result of a copy or move operation.
+#[derive(Serialize)]
+pub struct FileOpReport {
+    pub operation_id: String,
+    pub bytes_copied: u64,
+    pub files_copied: u64,
+    pub cancelled: bool,
+    /// One entry per source path.
+    pub results: Vec<PathResult>,
+    /// Sources left alone because their destination was taken and the
+    /// conflict was resolved by skipping them.
+    pub skipped: Vec<String>,
+    /// Copied files that did not match their original when read back; only
+    /// checked with `verify`.
+    pub mismatched: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferKind {
+    Copy,
+    Move,
+}
+
+/// What to do when an item's destination is taken.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Leave the source alone.
+    Skip,
+    /// Replace what is at the destination.
+    Overwrite,
+    /// Give the new item a free name, such as `name (1).ext`.
+    KeepBoth,
+    /// Pause the operation and emit `file-ops://conflict` until
+    /// `resolve_conflict` answers.
+    Ask,
+}
+
+/// Payload of the `file-ops://conflict` event.
+#[derive(Serialize, Clone)]
+pub struct FileConflict {
+    pub operation_id: String,
+    pub source: String,
+    /// The existing item.
+    pub target: String,
+    pub source_size: u64,
+    pub target_size: u64,
+    /// Seconds since the Unix epoch.
+    pub source_modified: Option<u64>,
+    pub target_modified: Option<u64>,
+    pub source_is_dir: bool,
+    pub target_is_dir: bool,
+}
+
+/// Managed state holding the answers to conflicts that operations wait on.
+#[derive(Default)]
+pub struct PendingConflicts {
+    /// Operations waiting for an answer, with the answer once given and
+    /// whether it applies to the rest of the operation too.
+    waiting: Mutex<HashMap<String, Option<(ConflictPolicy, bool)>>>,
+}
+
+/// Answer the conflict the operation `operation_id` is waiting on, and
+/// resume it. With `apply_to_all` the answer is also used for the
+/// operation's remaining conflicts.
+#[command]
+pub fn resolve_conflict(
+    operations: State<'_, OperationRegistry>,
+    conflicts: State<'_, PendingConflicts>,
+    operation_id: String,
+    decision: ConflictPolicy,
+    apply_to_all: Option<bool>,
+) -> Result<(), ExplorerError> {
+    if decision == ConflictPolicy::Ask {
+        return Err(ExplorerError::InvalidInput(
+            "A conflict must be answered with skip, overwrite or keep_both".to_string(),
+        ));
+    }
+    match conflicts.waiting.lock().unwrap().get_mut(&operation_id) {
+        Some(answer) => *answer = Some((decision, apply_to_all.unwrap_or(false))),
+        None => {
+            return Err(ExplorerError::NotFound(format!(
+                "No conflict waiting in operation: {}",
+                operation_id
+            )))
+        }
+    }
+    resume_operation(operations, operation_id)
+}
+
+/// Recursively copy `sources` into the `destination` folder.
+///
+/// Items whose destination is taken are handled by `conflict`; without it
+/// they are reported as failed. With `verify`, every copied file is read back
+/// and compared with its original by BLAKE3 hash; items with files that
+/// differ are reported as failed and the files listed in `mismatched`. The
+/// copy can be paused or cancelled through `operation_id`.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn copy_items(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    sources: Vec<String>,
+    destination: String,
+    operation_id: Option<String>,
+    conflict: Option<ConflictPolicy>,
+    verify: Option<bool>,
+) -> Result<FileOpReport, ExplorerError> {
+    run_transfer(
+        app,
+        &tasks,
+        &operations,
+        sources,
+        destination,
+        operation_id,
+        conflict,
+        verify.unwrap_or(false),
+        TransferKind::Copy,
+    )
+    .await
+}
+
+/// Move `sources` into the `destination` folder.
+///
+/// Moves within a filesystem are renames; moves across devices fall back to
+/// copying and then deleting the source. Conflicts are handled as by
+/// `copy_items`, and so is `verify` for moves that copy; a source whose copy
+/// does not match is kept. The move can be paused or cancelled through
+/// `operation_id`.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn move_items(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    sources: Vec<String>,
+    destination: String,
+    operation_id: Option<String>,
+    conflict: Option<ConflictPolicy>,
+    verify: Option<bool>,
+) -> Result<FileOpReport, ExplorerError> {
+    run_transfer(
+        app,
+        &tasks,
+        &operations,
+        sources,
+        destination,
+        operation_id,
+        conflict,
+        verify.unwrap_or(false),
+        TransferKind::Move,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_transfer(
+    app: AppHandle,
+    tasks: &TaskRegistry,
+    operations: &OperationRegistry,
+    sources: Vec<String>,
+    destination: String,
+    operation_id: Option<String>,
+    conflict: Option<ConflictPolicy>,
+    verify: bool,
+    kind: TransferKind,
+) -> Result<FileOpReport, ExplorerError> {
+    if !Path::new(&destination).is_dir() {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Destination is not a directory: {}",
+            destination
+        )));
+    }
+
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    let (label, journal_kind) = match kind {
+        TransferKind::Copy => ("copy", JournalKind::Copy),
+        TransferKind::Move => ("move", JournalKind::Move),
+    };
+    tasks
+        .run_blocking(label, move || {
+            let mut transfer = Transfer::new(&app, handle, id, conflict, verify);
+            let report = transfer.run(&sources, Path::new(&destination), kind);
+            journal::record(&app, journal_kind, &transfer.completed);
+            Ok(report)
+        })
+        .await
+}
+
+/// Copy or move `source` to `target` as part of the operation
+/// `operation_id`, reporting progress like `copy_items`. Fails if `target`
+/// is taken.
+pub fn transfer_item(
+    app: &AppHandle,
+    operation: Arc<OperationHandle>,
+    operation_id: String,
+    source: &Path,
+    target: &Path,
+    kind: TransferKind,
+) -> io::Result<()> {
+    let mut transfer = Transfer::new(app, operation, operation_id, None, false);
+    let (total_bytes, total_files, _) = measure(&[source]);
+    transfer.progress.total_bytes = total_bytes;
+    transfer.progress.total_files = total_files;
+    if fs::symlink_metadata(target).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Destination already exists: {}", target.display()),
+        ));
+    }
+    let result = transfer.transfer_to(source, target, None, kind);
+    transfer.emit();
+    result
+}
+
+/// Copy or move each source into the folder paired with it, as the
+/// operation `operation_id`, reporting progress like `copy_items`. Returns
+/// the report and where each item that went ended up, for the journal.
+pub fn transfer_into(
+    app: &AppHandle,
+    operation: Arc<OperationHandle>,
+    operation_id: String,
+    items: &[(String, PathBuf)],
+    kind: TransferKind,
+    conflict: Option<ConflictPolicy>,
+) -> (FileOpReport, Vec<JournalEntry>) {
+    let mut transfer = Transfer::new(app, operation, operation_id, conflict, false);
+    let report = transfer.run_into(items, kind);
+    (report, transfer.completed)
+}
+
+/// State of a single copy/move operation.
+struct Transfer<'a, R: Runtime> {
+    app: &'a AppHandle<R>,
+    operation: Arc<OperationHandle>,
+    progress: FileOpProgress,
+    last_emit: Instant,
+    /// How to handle taken destinations; `None` fails the item.
+    conflict: Option<ConflictPolicy>,
+    /// Whether to read copied files back and compare them.
+    verify: bool,
+    /// Copied files that did not match their original.
+    mismatched: Vec<String>,
+    /// Whether to try cloning files; cleared once a clone fails, as the rest
+    /// of the item is most likely on the same volumes.
+    clone: bool,
+    /// Sources copied or moved so far, with where they went.
+    completed: Vec<JournalEntry>,
+}
+
+impl<'a, R: Runtime> Transfer<'a, R> {
+    fn new(
+        app: &'a AppHandle<R>,
+        operation: Arc<OperationHandle>,
+        operation_id: String,
+        conflict: Option<ConflictPolicy>,
+        verify: bool,
+    ) -> Self {
+        Transfer {
+            app,
+            operation,
+            progress: FileOpProgress {
+                operation_id,
+                ..Default::default()
+            },
+            last_emit: Instant::now(),
+            conflict,
+            verify,
+            mismatched: Vec::new(),
+            clone: true,
+            completed: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, sources: &[String], destination: &Path, kind: TransferKind) -> FileOpReport {
+        let items: Vec<(String, PathBuf)> = sources
+            .iter()
+            .map(|source| (source.clone(), destination.to_path_buf()))
+            .collect();
+        self.run_into(&items, kind)
+    }
+
+    /// Copy or move each source into the folder paired with it.
+    fn run_into(&mut self, items: &[(String, PathBuf)], kind: TransferKind) -> FileOpReport {
+        let sources: Vec<&String> = items.iter().map(|(source, _)| source).collect();
+        let (total_bytes, total_files, _) = measure(&sources);
+        self.progress.total_bytes = total_bytes;
+        self.progress.total_files = total_files;
+
+        let mut results = Vec::with_capacity(items.len());
+        let mut skipped = Vec::new();
+        for (source, destination) in items {
+            let result = self
+                .checkpoint()
+                .and_then(|()| self.transfer_one(Path::new(source), destination, kind))
+                .map(|target| match target {
+                    Some(target) => self.completed.push(JournalEntry {
+                        source: source.clone(),
+                        target: Some(target.to_string_lossy().to_string()),
+                    }),
+                    None => skipped.push(source.clone()),
+                });
+            results.push(PathResult::from_result(source.clone(), result));
+        }
+        self.emit();
+
+        FileOpReport {
+            operation_id: self.progress.operation_id.clone(),
+            bytes_copied: self.progress.bytes_copied,
+            files_copied: self.progress.files_copied,
+            cancelled: self.operation.is_cancelled(),
+            results,
+            skipped,
+            mismatched: std::mem::take(&mut self.mismatched),
+        }
+    }
+
+    /// Copy or move `source` into `destination`, returning where it went, or
+    /// `None` if it was skipped.
+    fn transfer_one(
+        &mut self,
+        source: &Path,
+        destination: &Path,
+        kind: TransferKind,
+    ) -> io::Result<Option<PathBuf>> {
+        let name = source.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Source has no file name")
+        })?;
+        if destination.starts_with(source) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Cannot copy or move a folder into itself",
+            ));
+        }
+        let mut target = destination.join(name);
+        if fs::symlink_metadata(&target).is_ok() {
+            match self.resolve_conflict(source, &target)? {
+                ConflictPolicy::Skip => return Ok(None),
+                ConflictPolicy::Overwrite => {
+                    // Replacing the item with itself, or with something
+                    // inside it, would delete the source.
+                    if source.starts_with(&target) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Cannot overwrite {} with itself", target.display()),
+                        ));
+                    }
+                    // The item goes next to the old one first, so the old one
+                    // survives a transfer that fails or is cancelled.
+                    let staged = staging_path(&target, "ude-tmp");
+                    self.transfer_to(source, &staged, Some(&target), kind)?;
+                    return Ok(Some(target));
+                }
+                _ => target = unique_path(&target),
+            }
+        }
+        self.transfer_to(source, &target, None, kind)?;
+        Ok(Some(target))
+    }
+
+    /// How to handle `target` being taken, asking the user if the policy
+    /// says so.
+    fn resolve_conflict(&mut self, source: &Path, target: &Path) -> io::Result<ConflictPolicy> {
+        match self.conflict {
+            None => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Destination already exists: {}", target.display()),
+            )),
+            Some(ConflictPolicy::Ask) => {
+                let (decision, apply_to_all) = self.ask(source, target)?;
+                if apply_to_all {
+                    self.conflict = Some(decision);
+                }
+                Ok(decision)
+            }
+            Some(policy) => Ok(policy),
+        }
+    }
+
+    /// Pause the operation and announce the conflict, then wait for
+    /// `resolve_conflict`. Resuming the operation without an answer asks
+    /// again.
+    fn ask(&mut self, source: &Path, target: &Path) -> io::Result<(ConflictPolicy, bool)> {
+        let source_meta = fs::symlink_metadata(source)?;
+        let target_meta = fs::symlink_metadata(target)?;
+        let conflict = FileConflict {
+            operation_id: self.progress.operation_id.clone(),
+            source: source.to_string_lossy().to_string(),
+            target: target.to_string_lossy().to_string(),
+            source_size: source_meta.len(),
+            target_size: target_meta.len(),
+            source_modified: to_unix_secs(source_meta.modified()),
+            target_modified: to_unix_secs(target_meta.modified()),
+            source_is_dir: source_meta.is_dir(),
+            target_is_dir: target_meta.is_dir(),
+        };
+        let conflicts = self.app.state::<PendingConflicts>();
+        let id = &self.progress.operation_id;
+        conflicts.waiting.lock().unwrap().insert(id.clone(), None);
+        let answer = loop {
+            self.operation.set_paused(true);
+            let _ = self.app.emit(FILE_OP_CONFLICT_EVENT, &conflict);
+            if let Err(e) = self.checkpoint() {
+                break Err(e);
+            }
+            if let Some(Some(answer)) = conflicts.waiting.lock().unwrap().get(id) {
+                break Ok(*answer);
+            }
+        };
+        conflicts.waiting.lock().unwrap().remove(id);
+        answer
+    }
+
+    /// Copy or move `source` to the free path `target`.
+    ///
+    /// With `replacing`, `target` is a staging path whose result takes the
+    /// place of `replacing` once it is complete. A transfer that fails
+    /// leaves `replacing` as it was and removes what was staged.
+    fn transfer_to(
+        &mut self,
+        source: &Path,
+        target: &Path,
+        replacing: Option<&Path>,
+        kind: TransferKind,
+    ) -> io::Result<()> {
+        if kind == TransferKind::Move {
+            let (size, files, _) = measure(&[source]);
+            match fs::rename(source, target) {
+                Ok(()) => {
+                    if let Some(replaced) = replacing {
+                        if let Err(e) = replace_with(target, replaced) {
+                            let _ = fs::rename(target, source);
+                            return Err(e);
+                        }
+                    }
+                    self.progress.bytes_copied += size;
+                    self.progress.files_copied += files;
+                    self.maybe_emit();
+                    return Ok(());
+                }
+                Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let verified = self.mismatched.len();
+        self.clone = true;
+        let copied = self.copy_tree(source, target).and_then(|()| {
+            let differing = self.mismatched.len() - verified;
+            if differing > 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{} copied files do not match the original{}",
+                        differing,
+                        match kind {
+                            TransferKind::Copy => "",
+                            TransferKind::Move => "; the source was kept",
+                        }
+                    ),
+                ));
+            }
+            match replacing {
+                Some(replaced) => replace_with(target, replaced),
+                None => Ok(()),
+            }
+        });
+        if let Err(e) = copied {
+            if replacing.is_some() {
+                let _ = remove_path(target);
+            }
+            return Err(e);
+        }
+        if kind == TransferKind::Move {
+            remove_path(source)?;
+        }
+        Ok(())
+    }
+
+    fn copy_tree(&mut self, source: &Path, target: &Path) -> io::Result<()> {
+        for entry in WalkDir::new(source) {
+            // Clones skip the checkpoints of copying, so a folder of them
+            // is checked between entries.
+            self.checkpoint()?;
+            let entry = entry?;
+            let relative = entry.path().strip_prefix(source).unwrap_or(Path::new(""));
+            let dest: PathBuf = target.join(relative);
+
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&dest)?;
+            } else if entry.file_type().is_symlink() {
+                copy_symlink(entry.path(), &dest)?;
+            } else {
+                self.copy_file(entry.path(), &dest)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_file(&mut self, source: &Path, dest: &Path) -> io::Result<()> {
+        let mut reader = File::open(source)?;
+        let meta = reader.metadata()?;
+        self.progress.current_file = source.to_string_lossy().to_string();
+        self.progress.file_total_bytes = meta.len();
+        self.progress.file_bytes_copied = 0;
+
+        // On APFS, Btrfs, XFS and ReFS a clone within the volume is instant
+        // and takes no space until either file changes. Clones are not read
+        // back with `verify`, since both files read the same blocks.
+        if self.clone {
+            match clone_file(&reader, meta.len(), dest) {
+                Ok(()) => {
+                    fs::set_permissions(dest, meta.permissions())?;
+                    self.progress.file_bytes_copied = meta.len();
+                    self.progress.bytes_copied += meta.len();
+                    self.progress.files_copied += 1;
+                    self.maybe_emit();
+                    return Ok(());
+                }
+                Err(_) => self.clone = false,
+            }
+        }
+
+        let mut writer = File::create(dest)?;
+
+        // The source is hashed as it is copied, so verifying only reads the
+        // copy again.
+        let mut source_hash = self.verify.then(blake3::Hasher::new);
+        let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+        loop {
+            if let Err(e) = self.checkpoint() {
+                drop(writer);
+                let _ = fs::remove_file(dest);
+                return Err(e);
+            }
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..read])?;
+            if let Some(hasher) = &mut source_hash {
+                hasher.update(&buffer[..read]);
+            }
+            self.progress.file_bytes_copied += read as u64;
+            self.progress.bytes_copied += read as u64;
+            self.maybe_emit();
+        }
+
+        fs::set_permissions(dest, meta.permissions())?;
+        if let Some(source_hash) = source_hash {
+            writer.sync_all()?;
+            let mut dest_hash = blake3::Hasher::new();
+            read_chunks(dest, None, |chunk, _| {
+                dest_hash.update(chunk);
+                self.checkpoint()
+            })?;
+            if dest_hash.finalize() != source_hash.finalize() {
+                self.mismatched.push(dest.to_string_lossy().to_string());
+            }
+        }
+        self.progress.files_copied += 1;
+        Ok(())
+    }
+
+    /// Wait out a pause; fails once the operation is cancelled.
+    fn checkpoint(&self) -> io::Result<()> {
+        self.operation
+            .checkpoint()
+            .map_err(|e| io::Error::new(io::ErrorKind::Interrupted, e))
+    }
+
+    fn maybe_emit(&mut self) {
+        if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.emit();
+        }
+    }
+
+    fn emit(&mut self) {
+        let _ = self.app.emit(FILE_OP_PROGRESS_EVENT, &self.progress);
+        self.last_emit = Instant::now();
+    }
+}
+
+/// Recreate a symlink at `dest` pointing to the same target as `source`.
+fn copy_symlink(source: &Path, dest: &Path) -> io::Result<()> {
+    let target = fs::read_link(source)?;
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, dest)
+    }
+    #[cfg(windows)]
+    {
+        if source.is_dir() {
+            std::os::windows::fs::symlink_dir(target, dest)
+        } else {
+            std::os::windows::fs::symlink_file(target, dest)
+        }
+    }
+}
+
+/// Create `dest` as a clone of `source` that shares its blocks instead of
+/// copying them. Fails, leaving no `dest` behind, unless both are on the same
+/// Btrfs, XFS or other volume supporting `FICLONE`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn clone_file(source: &File, _len: u64, dest: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let clone = File::create_new(dest)?;
+    if unsafe { libc::ioctl(clone.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) } == -1 {
+        let e = io::Error::last_os_error();
+        drop(clone);
+        let _ = fs::remove_file(dest);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Create `dest` as a clone of `source` that shares its blocks instead of
+/// copying them. Fails unless both are on the same APFS volume.
+#[cfg(target_os = "macos")]
+fn clone_file(source: &File, _len: u64, dest: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::AsRawFd;
+
+    let dest = CString::new(dest.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if unsafe { libc::fclonefileat(source.as_raw_fd(), libc::AT_FDCWD, dest.as_ptr(), 0) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Create `dest` as a clone of the `len` bytes of `source` that shares its
+/// blocks instead of copying them. Fails, leaving no `dest` behind, unless
+/// both are on the same ReFS volume.
+#[cfg(windows)]
+fn clone_file(source: &File, len: u64, dest: &Path) -> io::Result<()> {
+    use std::ffi::c_void;
+    use std::os::windows::fs::MetadataExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::FILE_ATTRIBUTE_SPARSE_FILE;
+    use windows::Win32::System::Ioctl::{
+        DUPLICATE_EXTENTS_DATA, FSCTL_DUPLICATE_EXTENTS_TO_FILE, FSCTL_GET_INTEGRITY_INFORMATION,
+        FSCTL_GET_INTEGRITY_INFORMATION_BUFFER, FSCTL_SET_INTEGRITY_INFORMATION,
+        FSCTL_SET_INTEGRITY_INFORMATION_BUFFER, FSCTL_SET_SPARSE,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+
+    let source_handle = HANDLE(source.as_raw_handle());
+    // Only ReFS answers this, with the cluster size cloned ranges must be
+    // aligned to.
+    let mut integrity = FSCTL_GET_INTEGRITY_INFORMATION_BUFFER::default();
+    unsafe {
+        DeviceIoControl(
+            source_handle,
+            FSCTL_GET_INTEGRITY_INFORMATION,
+            None,
+            0,
+            Some(&mut integrity as *mut _ as *mut c_void),
+            std::mem::size_of::<FSCTL_GET_INTEGRITY_INFORMATION_BUFFER>() as u32,
+            None,
+            None,
+        )
+    }?;
+    let cluster = u64::from(integrity.ClusterSizeInBytes.max(1));
+
+    let clone = File::create_new(dest)?;
+    let handle = HANDLE(clone.as_raw_handle());
+    let ioctl = |code: u32, input: *const c_void, size: usize| {
+        unsafe { DeviceIoControl(handle, code, Some(input), size as u32, None, 0, None, None) }
+            .map_err(io::Error::from)
+    };
+    let result = (|| {
+        // The clone must match the source in both, or the call fails.
+        if source.metadata()?.file_attributes() & FILE_ATTRIBUTE_SPARSE_FILE.0 != 0 {
+            ioctl(FSCTL_SET_SPARSE, std::ptr::null(), 0)?;
+        }
+        let settings = FSCTL_SET_INTEGRITY_INFORMATION_BUFFER {
+            ChecksumAlgorithm: integrity.ChecksumAlgorithm,
+            Reserved: 0,
+            Flags: integrity.Flags,
+        };
+        ioctl(
+            FSCTL_SET_INTEGRITY_INFORMATION,
+            &settings as *const _ as *const c_void,
+            std::mem::size_of_val(&settings),
+        )?;
+        clone.set_len(len)?;
+
+        // Each call takes whole clusters, less than 4 GiB of them.
+        let end = len.div_ceil(cluster) * cluster;
+        let max_chunk = (u64::from(u32::MAX) / cluster) * cluster;
+        let mut offset = 0;
+        while offset < end {
+            let count = (end - offset).min(max_chunk);
+            let extents = DUPLICATE_EXTENTS_DATA {
+                FileHandle: source_handle,
+                SourceFileOffset: offset as i64,
+                TargetFileOffset: offset as i64,
+                ByteCount: count as i64,
+            };
+            ioctl(
+                FSCTL_DUPLICATE_EXTENTS_TO_FILE,
+                &extents as *const _ as *const c_void,
+                std::mem::size_of_val(&extents),
+            )?;
+            offset += count;
+        }
+        Ok(())
+    })();
+    if result.is_err() {
+        drop(clone);
+        let _ = fs::remove_file(dest);
+    }
+    result
+}
+
+/// Rename a file or folder in place, returning its new path.
+#[command]
+pub async fn rename_item(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    new_name: String,
+) -> Result<String, ExplorerError> {
+    tasks
+        .run_blocking("rename", move || {
+            let target = rename_in_place(Path::new(&path), &new_name)?;
+            let entry = JournalEntry {
+                source: path,
+                target: Some(target.clone()),
+            };
+            journal::record(&app, JournalKind::Rename, &[entry]);
+            Ok(target)
+        })
+        .await
+}
+
+pub fn rename_in_place(path: &Path, new_name: &str) -> Result<String, ExplorerError> {
+    validate_file_name(new_name).map_err(ExplorerError::InvalidInput)?;
+    fs::symlink_metadata(path)?;
+
+    let parent = path.parent().ok_or_else(|| {
+        ExplorerError::InvalidInput("Cannot rename a filesystem root".to_string())
+    })?;
+    let target = parent.join(new_name);
+
+    let old_name = path
+        .file_name()
+        .map(|old| old.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if old_name == new_name {
+        return Ok(target.to_string_lossy().to_string());
+    }
+    if !differs_only_in_case(&old_name, new_name) {
+        if fs::symlink_metadata(&target).is_ok() {
+            return Err(ExplorerError::AlreadyExists(
+                target.to_string_lossy().to_string(),
+            ));
+        }
+        fs::rename(path, &target)?;
+        return Ok(target.to_string_lossy().to_string());
+    }
+
+    // A case-only change goes through a temporary name. On case-insensitive
+    // filesystems the new name would otherwise resolve to the item itself,
+    // and on case-sensitive ones a separate item with that name must be
+    // found rather than replaced.
+    let temporary = parent.join(format!(".{}.renaming", uuid::Uuid::new_v4().simple()));
+    fs::rename(path, &temporary)?;
+    if fs::symlink_metadata(&target).is_ok() {
+        fs::rename(&temporary, path)?;
+        return Err(ExplorerError::AlreadyExists(
+            target.to_string_lossy().to_string(),
+        ));
+    }
+    if let Err(e) = fs::rename(&temporary, &target) {
+        let _ = fs::rename(&temporary, path);
+        return Err(e.into());
+    }
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Change the Unix permission bits of `path`, and of everything below it
+/// when `recursive` is set.
+///
+/// `mode` is an octal string such as `"755"` or `"0644"`. Symlinks inside a
+/// recursive change are left alone, since chmod would follow them.
+#[command]
+pub async fn set_permissions(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    mode: String,
+    recursive: Option<bool>,
+) -> Result<Vec<PathResult>, ExplorerError> {
+    let digits = mode.trim().trim_start_matches("0o");
+    let mode = u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| format!("Invalid permission mode: {}", mode))?;
+    tasks
+        .run_blocking("set_permissions", move || {
+            if recursive.unwrap_or(false) {
+                Ok(WalkDir::new(&path)
+                    .into_iter()
+                    .flatten()
+                    .filter(|entry| !entry.path_is_symlink())
+                    .map(|entry| {
+                        let result = chmod(entry.path(), mode);
+                        PathResult::from_result(entry.path().to_string_lossy().to_string(), result)
+                    })
+                    .collect())
+            } else {
+                let result = chmod(Path::new(&path), mode);
+                Ok(vec![PathResult::from_result(path, result)])
+            }
+        })
+        .await
+}
+
+#[cfg(unix)]
+fn chmod(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn chmod(_path: &Path, _mode: u32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix permissions are not supported on this platform",
+    ))
+}
+
+/// Whether two names are the same apart from letter case, which
+/// case-insensitive filesystems treat as the same name.
+pub fn differs_only_in_case(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// Check that `name` is a valid single path component on this platform.
+pub fn validate_file_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("\"{}\" is a reserved name", name));
+    }
+    if name.contains('/') || name.contains('\0') {
+        return Err("Name cannot contain '/' or NUL characters".to_string());
+    }
+
+    if cfg!(target_os = "windows") {
+        const INVALID: &[char] = &['<', '>', ':', '"', '\\', '|', '?', '*'];
+        if let Some(c) = name.chars().find(|c| INVALID.contains(c) || c.is_control()) {
+            return Err(format!("Name cannot contain '{}'", c.escape_default()));
+        }
+        if name.ends_with(' ') || name.ends_with('.') {
+            return Err("Name cannot end with a space or a period".to_string());
+        }
+        let stem = name.split('.').next().unwrap_or(name).to_ascii_uppercase();
+        let reserved = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+            || ((stem.starts_with("COM") || stem.starts_with("LPT"))
+                && stem.len() == 4
+                && stem.as_bytes()[3].is_ascii_digit()
+                && stem.as_bytes()[3] != b'0');
+        if reserved {
+            return Err(format!("\"{}\" is a reserved device name", stem));
+        }
+    } else if cfg!(target_os = "macos") && name.contains(':') {
+        return Err("Name cannot contain ':'".to_string());
+    }
+
+    Ok(())
+}
+
+/// First free path of the form `name (n).ext` next to `path`.
+pub fn unique_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let mut n = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{} ({}){}", stem, n, extension));
+        if fs::symlink_metadata(&candidate).is_err() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_only_rename_of_a_non_ascii_name() {
+        assert!(differs_only_in_case("Ärger.txt", "ärger.TXT"));
+        assert!(!differs_only_in_case("Ärger.txt", "Arger.txt"));
+
+        let dir =
+            std::env::temp_dir().join(format!("ude-file-ops-{}", uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Ärger"), b"x").unwrap();
+        let renamed = rename_in_place(&dir.join("Ärger"), "ärger");
+        let left: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        let _ = fs::remove_dir_all(&dir);
+        assert!(renamed.is_ok());
+        assert_eq!(left, ["ärger"]);
+    }
+
+    #[test]
+    fn cancelled_overwrite_keeps_the_destination() {
+        let dir =
+            std::env::temp_dir().join(format!("ude-file-ops-{}", uuid::Uuid::new_v4().simple()));
+        let (source, destination) = (dir.join("source"), dir.join("destination"));
+        fs::create_dir_all(source.join("photos")).unwrap();
+        fs::create_dir_all(destination.join("photos")).unwrap();
+        fs::write(source.join("photos").join("new.jpg"), vec![1u8; 1 << 20]).unwrap();
+        fs::write(destination.join("photos").join("old.jpg"), b"old").unwrap();
+
+        let app = tauri::test::mock_app();
+        let operation = Arc::new(OperationHandle::default());
+        let mut transfer = Transfer::new(
+            app.handle(),
+            operation.clone(),
+            "test".to_string(),
+            Some(ConflictPolicy::Overwrite),
+            false,
+        );
+        operation.cancel();
+        let result =
+            transfer.transfer_one(&source.join("photos"), &destination, TransferKind::Copy);
+
+        let left: Vec<_> = fs::read_dir(&destination)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        let old = fs::read(destination.join("photos").join("old.jpg"));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(result.is_err());
+        assert_eq!(old.unwrap(), b"old");
+        assert_eq!(left, ["photos"]);
+    }
+}
@@ -0,0 +1,164 @@
+//! Quick-open fuzzy search over the file index.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use nucleo_matcher::pattern::{AtomKind, CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32Str};
+use rusqlite::params_from_iter;
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+use crate::index::{escape_like, row_to_file, FileIndex, IndexedFile};
+use crate::tasks::TaskRegistry;
+
+/// Default number of matches returned by `fuzzy_find`.
+const DEFAULT_FUZZY_LIMIT: usize = 50;
+
+/// An indexed entry matching a `fuzzy_find` query.
+#[derive(Serialize)]
+pub struct FuzzyMatch {
+    #[serde(flatten)]
+    pub file: IndexedFile,
+    pub score: u32,
+    /// Character positions that matched, in `name`, or in `path` for queries
+    /// containing a separator, for highlighting.
+    pub indices: Vec<u32>,
+}
+
+/// A match ranked by score, then by shorter path.
+struct Candidate {
+    score: u32,
+    file: IndexedFile,
+}
+
+impl Candidate {
+    fn key(&self) -> (u32, Reverse<usize>) {
+        (self.score, Reverse(self.file.path.len()))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Find indexed files and folders whose name fuzzily matches `query`, best
+/// match first, for a quick-open palette.
+///
+/// Matching follows fzf: every space-separated word of the query must appear
+/// in order, not necessarily contiguously, and matches at word boundaries,
+/// in consecutive runs and near the start score higher. The query is
+/// case-insensitive unless it contains an uppercase letter. Queries that
+/// contain a path separator are matched against the full path instead of
+/// the name. Only indexed roots are searched.
+#[command]
+pub async fn fuzzy_find(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<FuzzyMatch>, ExplorerError> {
+    let limit = limit.unwrap_or(DEFAULT_FUZZY_LIMIT);
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("fuzzy_find", move || {
+            let pattern = Pattern::new(
+                &query,
+                CaseMatching::Smart,
+                Normalization::Never,
+                AtomKind::Fuzzy,
+            );
+            if pattern.atoms.is_empty() || limit == 0 {
+                return Ok(Vec::new());
+            }
+            let match_paths = query.contains(['/', '\\']);
+            let mut matcher = Matcher::new(if match_paths {
+                Config::DEFAULT.match_paths()
+            } else {
+                Config::DEFAULT
+            });
+
+            // SQLite narrows the rows down to names containing every word as
+            // a subsequence, which `LIKE '%a%b%c%'` expresses, before they are
+            // scored here.
+            let column = if match_paths { "path" } else { "name" };
+            let mut sql = String::from(
+                "SELECT path, name, size, mtime, is_dir, extension FROM files WHERE 1",
+            );
+            let mut likes = Vec::new();
+            for word in query.split_whitespace() {
+                let mut like = String::from("%");
+                for c in word.chars() {
+                    like.push_str(&escape_like(&c.to_string()));
+                    like.push('%');
+                }
+                sql.push_str(&format!(" AND {} LIKE ? ESCAPE '\\'", column));
+                likes.push(like);
+            }
+            let mut stmt = conn
+                .prepare(&sql)
+                .map_err(|e| format!("Failed to query index: {}", e))?;
+            let mut rows = stmt
+                .query(params_from_iter(&likes))
+                .map_err(|e| format!("Failed to query index: {}", e))?;
+
+            // Min-heap holding the best `limit` matches; the root is the worst.
+            let mut heap: BinaryHeap<Reverse<Candidate>> = BinaryHeap::with_capacity(limit + 1);
+            let mut buf = Vec::new();
+            while let Some(row) = rows
+                .next()
+                .map_err(|e| format!("Failed to query index: {}", e))?
+            {
+                let file = row_to_file(row).map_err(|e| format!("Failed to query index: {}", e))?;
+                let haystack = if match_paths { &file.path } else { &file.name };
+                let Some(score) = pattern.score(Utf32Str::new(haystack, &mut buf), &mut matcher)
+                else {
+                    continue;
+                };
+                heap.push(Reverse(Candidate { score, file }));
+                if heap.len() > limit {
+                    heap.pop();
+                }
+            }
+
+            let mut candidates: Vec<Candidate> = heap.into_iter().map(|entry| entry.0).collect();
+            candidates.sort_by(|a, b| b.cmp(a));
+            Ok(candidates
+                .into_iter()
+                .map(|Candidate { score, file }| {
+                    let haystack = if match_paths { &file.path } else { &file.name };
+                    let mut indices = Vec::new();
+                    pattern.indices(
+                        Utf32Str::new(haystack, &mut buf),
+                        &mut matcher,
+                        &mut indices,
+                    );
+                    indices.sort_unstable();
+                    indices.dedup();
+                    FuzzyMatch {
+                        file,
+                        score,
+                        indices,
+                    }
+                })
+                .collect())
+        })
+        .await
+}
@@ -0,0 +1,476 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use tauri::{command, AppHandle, Emitter, State};
+
+use crate::error::ExplorerError;
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// Event name used to report hashing progress to the frontend.
+pub const HASH_PROGRESS_EVENT: &str = "hash://progress";
+
+/// Largest read buffer used while hashing; smaller files get a buffer their
+/// own size.
+const HASH_BUFFER_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Event name used to stream per-file results of `verify_checksums`.
+pub const CHECKSUM_RESULT_EVENT: &str = "checksum://result";
+
+/// Minimum delay between two progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Supported checksum algorithms.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Blake3,
+    /// CRC-32 as used by `.sfv` files.
+    Crc32,
+}
+
+/// Payload of the `hash://progress` event.
+#[derive(Serialize, Clone)]
+pub struct HashProgress {
+    pub operation_id: String,
+    pub path: String,
+    pub bytes_hashed: u64,
+    pub total_bytes: u64,
+}
+
+/// Result of hashing a single file.
+#[derive(Serialize)]
+pub struct FileHash {
+    pub path: String,
+    pub algorithm: HashAlgorithm,
+    /// Lowercase hex digest, absent when the file could not be read.
+    pub hash: Option<String>,
+    pub size: u64,
+    pub error: Option<String>,
+}
+
+/// Running state of one of the supported algorithms.
+enum Hasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Crc32(crc32fast::Hasher),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5 => Hasher::Md5(Md5::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::default()),
+            HashAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Sha1(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Blake3(h) => {
+                h.update(data);
+            }
+            Hasher::Crc32(h) => h.update(data),
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            Hasher::Md5(h) => to_hex(&h.finalize()),
+            Hasher::Sha1(h) => to_hex(&h.finalize()),
+            Hasher::Sha256(h) => to_hex(&h.finalize()),
+            Hasher::Blake3(h) => h.finalize().to_hex().to_string(),
+            Hasher::Crc32(h) => format!("{:08x}", h.finalize()),
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Read up to `limit` bytes of a file in chunks, passing each to `consume`
+/// along with the bytes read so far.
+///
+/// Memory use stays at one buffer of at most 4 MiB whatever the file size.
+/// Files are read rather than memory-mapped: mapped pages of a huge file count
+/// towards resident memory, and a file truncated while mapped crashes the
+/// process. Returning an error from `consume` aborts the read.
+pub fn read_chunks<F>(path: &Path, limit: Option<u64>, mut consume: F) -> io::Result<u64>
+where
+    F: FnMut(&[u8], u64) -> io::Result<()>,
+{
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let limit = limit.unwrap_or(u64::MAX);
+    let buffer_size = len.min(limit).clamp(1, HASH_BUFFER_SIZE);
+    let mut buffer = vec![0u8; buffer_size as usize];
+    let mut reader = file.take(limit);
+    let mut read_total = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        read_total += read as u64;
+        consume(&buffer[..read], read_total)?;
+    }
+    Ok(read_total)
+}
+
+/// Hash a file in chunks, calling `on_chunk` with the bytes read so far.
+///
+/// Returning an error from `on_chunk` aborts the read.
+pub fn hash_path<F>(path: &Path, algorithm: HashAlgorithm, mut on_chunk: F) -> io::Result<String>
+where
+    F: FnMut(u64) -> io::Result<()>,
+{
+    let mut hasher = Hasher::new(algorithm);
+    read_chunks(path, None, |chunk, read_total| {
+        hasher.update(chunk);
+        on_chunk(read_total)
+    })?;
+    Ok(hasher.finalize())
+}
+
+/// Compute the checksum of a file.
+///
+/// Progress is emitted on `hash://progress`; the operation can be paused or
+/// cancelled through `operation_id`.
+#[command]
+pub async fn hash_file(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    path: String,
+    algorithm: HashAlgorithm,
+    operation_id: Option<String>,
+) -> Result<FileHash, ExplorerError> {
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("hash", move || {
+            let result = hash_one(&app, &handle, &id, &path, algorithm);
+            handle.checkpoint()?;
+            match result.error {
+                Some(e) => Err(format!("Failed to hash {}: {}", path, e).into()),
+                None => Ok(result),
+            }
+        })
+        .await
+}
+
+/// Compute the checksum of several files, one after another.
+///
+/// Each file is handled independently so one unreadable file does not stop
+/// the rest.
+#[command]
+pub async fn hash_files(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    paths: Vec<String>,
+    algorithm: HashAlgorithm,
+    operation_id: Option<String>,
+) -> Result<Vec<FileHash>, ExplorerError> {
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("hash", move || {
+            let mut results = Vec::with_capacity(paths.len());
+            for path in &paths {
+                handle.checkpoint()?;
+                results.push(hash_one(&app, &handle, &id, path, algorithm));
+            }
+            handle.checkpoint()?;
+            Ok(results)
+        })
+        .await
+}
+
+fn hash_one(
+    app: &AppHandle,
+    operation: &OperationHandle,
+    operation_id: &str,
+    path: &str,
+    algorithm: HashAlgorithm,
+) -> FileHash {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut progress = HashProgress {
+        operation_id: operation_id.to_string(),
+        path: path.to_string(),
+        bytes_hashed: 0,
+        total_bytes: size,
+    };
+    let mut last_emit = Instant::now();
+
+    let result = hash_path(Path::new(path), algorithm, |bytes_hashed| {
+        operation
+            .checkpoint()
+            .map_err(|e| io::Error::new(io::ErrorKind::Interrupted, e))?;
+        if last_emit.elapsed() >= PROGRESS_INTERVAL {
+            progress.bytes_hashed = bytes_hashed;
+            let _ = app.emit(HASH_PROGRESS_EVENT, &progress);
+            last_emit = Instant::now();
+        }
+        Ok(())
+    });
+
+    let (hash, error) = match result {
+        Ok(hash) => (Some(hash), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    FileHash {
+        path: path.to_string(),
+        algorithm,
+        hash,
+        size,
+        error,
+    }
+}
+
+/// Outcome of checking one entry of a checksum file.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumStatus {
+    Ok,
+    Mismatch,
+    Missing,
+    /// The file exists but could not be read.
+    Error,
+}
+
+/// Payload of the `checksum://result` event.
+#[derive(Serialize, Clone)]
+pub struct ChecksumResult {
+    pub operation_id: String,
+    pub path: String,
+    pub expected: String,
+    pub actual: Option<String>,
+    pub status: ChecksumStatus,
+    pub error: Option<String>,
+}
+
+/// Summary returned once every entry has been checked.
+#[derive(Serialize)]
+pub struct ChecksumReport {
+    pub algorithm: HashAlgorithm,
+    pub ok: u64,
+    pub mismatched: u64,
+    pub missing: u64,
+    pub errors: u64,
+    pub results: Vec<ChecksumResult>,
+}
+
+/// Verify the files listed in a `.sha256`, `.sha1`, `.md5` or `.sfv` file.
+///
+/// Relative paths are resolved against the checksum file's folder. Each
+/// result is emitted on `checksum://result` as soon as it is known.
+#[command]
+pub async fn verify_checksums(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<ChecksumReport, ExplorerError> {
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("verify_checksums", move || {
+            verify(&app, &handle, &id, Path::new(&path))
+        })
+        .await
+}
+
+fn verify(
+    app: &AppHandle,
+    operation: &OperationHandle,
+    operation_id: &str,
+    checksum_file: &Path,
+) -> Result<ChecksumReport, ExplorerError> {
+    let algorithm = checksum_algorithm(checksum_file).ok_or_else(|| {
+        format!(
+            "Unsupported checksum file: {}",
+            checksum_file.to_string_lossy()
+        )
+    })?;
+    let contents = std::fs::read_to_string(checksum_file)
+        .map_err(|e| format!("Failed to read checksum file: {}", e))?;
+    let base = checksum_file.parent().unwrap_or(Path::new(""));
+
+    let mut report = ChecksumReport {
+        algorithm,
+        ok: 0,
+        mismatched: 0,
+        missing: 0,
+        errors: 0,
+        results: Vec::new(),
+    };
+    for (name, expected) in contents
+        .lines()
+        .filter_map(|line| parse_checksum_line(line, algorithm))
+    {
+        operation.checkpoint()?;
+        let target = base.join(&name);
+        let mut result = ChecksumResult {
+            operation_id: operation_id.to_string(),
+            path: target.to_string_lossy().to_string(),
+            expected: expected.to_lowercase(),
+            actual: None,
+            status: ChecksumStatus::Missing,
+            error: None,
+        };
+
+        if target.is_file() {
+            let hashed = hash_one(app, operation, operation_id, &result.path, algorithm);
+            operation.checkpoint()?;
+            result.status = match &hashed.hash {
+                Some(actual) if *actual == result.expected => ChecksumStatus::Ok,
+                Some(_) => ChecksumStatus::Mismatch,
+                None => ChecksumStatus::Error,
+            };
+            result.actual = hashed.hash;
+            result.error = hashed.error;
+        }
+
+        match result.status {
+            ChecksumStatus::Ok => report.ok += 1,
+            ChecksumStatus::Mismatch => report.mismatched += 1,
+            ChecksumStatus::Missing => report.missing += 1,
+            ChecksumStatus::Error => report.errors += 1,
+        }
+        let _ = app.emit(CHECKSUM_RESULT_EVENT, &result);
+        report.results.push(result);
+    }
+    Ok(report)
+}
+
+/// Infer the algorithm from the checksum file's extension.
+fn checksum_algorithm(path: &Path) -> Option<HashAlgorithm> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "md5" => Some(HashAlgorithm::Md5),
+        "sha1" => Some(HashAlgorithm::Sha1),
+        "sha256" => Some(HashAlgorithm::Sha256),
+        "sfv" => Some(HashAlgorithm::Crc32),
+        _ => None,
+    }
+}
+
+/// Parse one line into `(file name, expected hash)`.
+///
+/// `.sfv` lines are `name CRC32`; the other formats accept both the GNU
+/// `hash  name` / `hash *name` layout and the BSD `ALGO (name) = hash` one.
+fn parse_checksum_line(line: &str, algorithm: HashAlgorithm) -> Option<(String, String)> {
+    let line = line.trim_end_matches('\r').trim_start_matches('\u{feff}');
+    if line.trim().is_empty() || line.starts_with(';') || line.starts_with('#') {
+        return None;
+    }
+
+    if algorithm == HashAlgorithm::Crc32 {
+        let (name, crc) = line.trim_end().rsplit_once(char::is_whitespace)?;
+        return Some((name.trim().to_string(), crc.to_string()));
+    }
+
+    if let Some((left, hash)) = line.rsplit_once(") = ") {
+        let (_, name) = left.split_once(" (")?;
+        return Some((name.to_string(), hash.trim().to_string()));
+    }
+    let (hash, name) = line.split_once(char::is_whitespace)?;
+    let name = name.trim_start_matches([' ', '*']);
+    if hash.is_empty() || name.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), hash.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, hash: &str) -> Option<(String, String)> {
+        Some((name.to_string(), hash.to_string()))
+    }
+
+    #[test]
+    fn parses_gnu_lines() {
+        assert_eq!(
+            parse_checksum_line(
+                "d41d8cd98f00b204e9800998ecf8427e  empty file.txt",
+                HashAlgorithm::Md5
+            ),
+            entry("empty file.txt", "d41d8cd98f00b204e9800998ecf8427e")
+        );
+    }
+
+    #[test]
+    fn parses_gnu_binary_mode_lines() {
+        assert_eq!(
+            parse_checksum_line(
+                "da39a3ee5e6b4b0d3255bfef95601890afd80709 *image.iso",
+                HashAlgorithm::Sha1
+            ),
+            entry("image.iso", "da39a3ee5e6b4b0d3255bfef95601890afd80709")
+        );
+    }
+
+    #[test]
+    fn parses_bsd_lines() {
+        assert_eq!(
+            parse_checksum_line("SHA256 (notes (draft).txt) = abc123", HashAlgorithm::Sha256),
+            entry("notes (draft).txt", "abc123")
+        );
+    }
+
+    #[test]
+    fn parses_sfv_lines() {
+        assert_eq!(
+            parse_checksum_line("track 01.flac 1A2B3C4D", HashAlgorithm::Crc32),
+            entry("track 01.flac", "1A2B3C4D")
+        );
+        assert_eq!(
+            parse_checksum_line("; generated by some tool", HashAlgorithm::Crc32),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_crlf_and_bom() {
+        assert_eq!(
+            parse_checksum_line("\u{feff}abc123  file.bin\r", HashAlgorithm::Md5),
+            entry("file.bin", "abc123")
+        );
+        assert_eq!(
+            parse_checksum_line("\u{feff}file.bin DEADBEEF\r", HashAlgorithm::Crc32),
+            entry("file.bin", "DEADBEEF")
+        );
+    }
+
+    #[test]
+    fn skips_blank_and_comment_lines() {
+        assert_eq!(parse_checksum_line("", HashAlgorithm::Md5), None);
+        assert_eq!(parse_checksum_line("   \r", HashAlgorithm::Md5), None);
+        assert_eq!(
+            parse_checksum_line("# comment", HashAlgorithm::Sha256),
+            None
+        );
+        assert_eq!(parse_checksum_line("abc123", HashAlgorithm::Md5), None);
+    }
+}
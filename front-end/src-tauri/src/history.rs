@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+use crate::index::FileIndex;
+use crate::tasks::TaskRegistry;
+
+/// Entries kept in the history; the least recently visited are dropped.
+const MAX_HISTORY_ENTRIES: i64 = 1000;
+
+/// Default number of entries returned by `get_recent`.
+const DEFAULT_RECENT_LIMIT: usize = 50;
+
+/// Which entries `get_recent` and `clear_history` apply to.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    Files,
+    Folders,
+}
+
+impl HistoryKind {
+    fn is_dir(kind: Option<HistoryKind>) -> Option<bool> {
+        kind.map(|kind| matches!(kind, HistoryKind::Folders))
+    }
+}
+
+/// A file or folder returned by `get_recent`.
+#[derive(Serialize)]
+pub struct RecentItem {
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    /// Seconds since the Unix epoch of the latest visit.
+    pub visited_at: u64,
+    pub visit_count: u64,
+    /// `false` if the path was removed or its drive is not connected.
+    pub exists: bool,
+}
+
+/// Record that a file was opened or a folder was visited.
+///
+/// Repeat visits move the entry to the top and bump its count.
+#[command]
+pub async fn record_visit(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    path: String,
+) -> Result<(), ExplorerError> {
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("record_visit", move || {
+            let meta =
+                fs::metadata(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            let now = to_unix_secs(Ok(SystemTime::now())).unwrap_or(0) as i64;
+            Ok(conn
+                .execute(
+                    "INSERT INTO history (path, is_dir, visited_at, visit_count)
+                 VALUES (?1, ?2, ?3, 1)
+                 ON CONFLICT(path) DO UPDATE SET
+                     is_dir = excluded.is_dir,
+                     visited_at = excluded.visited_at,
+                     visit_count = visit_count + 1",
+                    params![path, meta.is_dir(), now],
+                )
+                .and_then(|_| {
+                    conn.execute(
+                        "DELETE FROM history WHERE path NOT IN
+                     (SELECT path FROM history ORDER BY visited_at DESC LIMIT ?1)",
+                        params![MAX_HISTORY_ENTRIES],
+                    )
+                })
+                .map(|_| ())
+                .map_err(|e| format!("Failed to record visit: {}", e))?)
+        })
+        .await
+}
+
+/// Most recently visited files and folders, newest first.
+#[command]
+pub async fn get_recent(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    limit: Option<usize>,
+    kind: Option<HistoryKind>,
+) -> Result<Vec<RecentItem>, ExplorerError> {
+    let limit = limit.unwrap_or(DEFAULT_RECENT_LIMIT);
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("get_recent", move || {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT path, is_dir, visited_at, visit_count FROM history
+                     WHERE ?1 IS NULL OR is_dir = ?1
+                     ORDER BY visited_at DESC, path LIMIT ?2",
+                )
+                .map_err(|e| format!("Failed to query history: {}", e))?;
+            Ok(stmt
+                .query_map(params![HistoryKind::is_dir(kind), limit as i64], |row| {
+                    let path: String = row.get(0)?;
+                    Ok(RecentItem {
+                        name: Path::new(&path)
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_else(|| path.clone()),
+                        exists: Path::new(&path).exists(),
+                        is_dir: row.get(1)?,
+                        visited_at: row.get::<_, i64>(2)? as u64,
+                        visit_count: row.get::<_, i64>(3)? as u64,
+                        path,
+                    })
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query history: {}", e))?)
+        })
+        .await
+}
+
+/// Forget visited files, folders, or both when `kind` is omitted.
+#[command]
+pub async fn clear_history(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    kind: Option<HistoryKind>,
+) -> Result<(), ExplorerError> {
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("clear_history", move || {
+            Ok(conn
+                .execute(
+                    "DELETE FROM history WHERE ?1 IS NULL OR is_dir = ?1",
+                    params![HistoryKind::is_dir(kind)],
+                )
+                .map(|_| ())
+                .map_err(|e| format!("Failed to clear history: {}", e))?)
+        })
+        .await
+}
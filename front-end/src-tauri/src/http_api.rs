@@ -0,0 +1,480 @@
+//! Optional HTTP and WebSocket API for querying this machine remotely.
+//!
+//! The server is off until `start_api_server` is called. Every request must
+//! carry the server's token, either as `Authorization: Bearer <token>` or,
+//! for browsers opening a WebSocket, as a `token` query parameter. Responses
+//! are JSON; failures use the same `{ "code", "message" }` shape as
+//! commands.
+//!
+//! - `GET /api/drives`
+//! - `GET /api/list?path=...&show_hidden=...&show_system=...&offset=...&limit=...`
+//! - `GET /api/index/search?query=...&limit=...` searches names in the index
+//! - `POST /api/search/content` with `{ "root", "pattern", "options" }`
+//! - `POST /api/scan` with `{ "path", "options", "depth" }` returns the tree,
+//!   cut off below `depth` levels when given
+//! - `GET /api/scan/ws?path=...&depth=...&incremental=...&follow_symlinks=...`
+//!   streams `{"type": "progress"}` messages, then a `report` or `error`
+//!   message. Closing the socket cancels the scan.
+//! - `POST /api/operations/{id}/cancel`
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path as UrlPath, Query, Request, State as ApiState};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{command, AppHandle, Listener, Manager, State};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::commands::{list_directory, DirectoryPage, ListOptions};
+use crate::drives::{get_drives, DriveInfo};
+use crate::error::ExplorerError;
+use crate::index::{query_index, IndexedFile};
+use crate::operations::cancel_operation;
+use crate::scanner::{scan_directory, DirNode, ScanOptions, ScanReport, SCAN_PROGRESS_EVENT};
+use crate::search::{search_content, ContentMatch, ContentSearchOptions};
+
+/// Port used unless the caller picks one.
+const DEFAULT_PORT: u16 = 7428;
+
+/// Options accepted by `start_api_server`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ApiServerOptions {
+    /// 0 lets the system pick a free port.
+    pub port: u16,
+    /// Listen on every network interface instead of only this machine.
+    pub allow_remote: bool,
+    /// Token clients must send. A random one is generated when not given.
+    pub token: Option<String>,
+}
+
+impl Default for ApiServerOptions {
+    fn default() -> Self {
+        ApiServerOptions {
+            port: DEFAULT_PORT,
+            allow_remote: false,
+            token: None,
+        }
+    }
+}
+
+/// Result of `start_api_server` and `get_api_server_status`.
+#[derive(Serialize)]
+pub struct ApiServerStatus {
+    pub running: bool,
+    /// Address the server listens on, e.g. `127.0.0.1:7428`.
+    pub address: Option<String>,
+    pub token: Option<String>,
+}
+
+/// A server that is listening.
+struct RunningServer {
+    address: SocketAddr,
+    token: String,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// Managed state holding the running server, if any.
+#[derive(Default)]
+pub struct ApiServer {
+    running: Mutex<Option<RunningServer>>,
+}
+
+impl ApiServer {
+    fn status(&self) -> ApiServerStatus {
+        match &*self.running.lock().unwrap() {
+            Some(server) => ApiServerStatus {
+                running: true,
+                address: Some(server.address.to_string()),
+                token: Some(server.token.clone()),
+            },
+            None => ApiServerStatus {
+                running: false,
+                address: None,
+                token: None,
+            },
+        }
+    }
+}
+
+/// State shared by the request handlers.
+#[derive(Clone)]
+struct ServerState {
+    app: AppHandle,
+    token: Arc<str>,
+}
+
+/// Start the API server.
+///
+/// It listens on `127.0.0.1` unless `options.allow_remote` is set, and runs
+/// until `stop_api_server` is called or the app exits. Anyone holding the
+/// returned token can list and scan this machine's files, and traffic is not
+/// encrypted, so only allow remote access on trusted networks.
+#[command]
+pub async fn start_api_server(
+    app: AppHandle,
+    server: State<'_, ApiServer>,
+    options: Option<ApiServerOptions>,
+) -> Result<ApiServerStatus, ExplorerError> {
+    let options = options.unwrap_or_default();
+    if server.running.lock().unwrap().is_some() {
+        return Err(ExplorerError::AlreadyExists(
+            "The API server is already running".to_string(),
+        ));
+    }
+    let token = match options.token {
+        Some(token) if token.trim().is_empty() => {
+            return Err(ExplorerError::InvalidInput("Token is empty".to_string()))
+        }
+        Some(token) => token,
+        None => uuid::Uuid::new_v4().simple().to_string(),
+    };
+    let host = if options.allow_remote {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let listener = TcpListener::bind((host, options.port))
+        .await
+        .map_err(|e| ExplorerError::io("Failed to start the API server", e))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| ExplorerError::io("Failed to start the API server", e))?;
+
+    let state = ServerState {
+        app,
+        token: token.as_str().into(),
+    };
+    let router = Router::new()
+        .route("/api/drives", get(drives))
+        .route("/api/list", get(list))
+        .route("/api/index/search", get(index_search))
+        .route("/api/search/content", post(content_search))
+        .route("/api/scan", post(scan))
+        .route("/api/scan/ws", get(scan_socket))
+        .route("/api/operations/{id}/cancel", post(cancel))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state);
+
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    {
+        let mut running = server.running.lock().unwrap();
+        if running.is_some() {
+            return Err(ExplorerError::AlreadyExists(
+                "The API server is already running".to_string(),
+            ));
+        }
+        *running = Some(RunningServer {
+            address,
+            token,
+            shutdown,
+        });
+    }
+    tauri::async_runtime::spawn(async move {
+        let _ = axum::serve(listener, router)
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            })
+            .await;
+    });
+    Ok(server.status())
+}
+
+/// Stop the API server. Requests in flight are allowed to finish.
+#[command]
+pub fn stop_api_server(server: State<'_, ApiServer>) {
+    if let Some(running) = server.running.lock().unwrap().take() {
+        let _ = running.shutdown.send(());
+    }
+}
+
+/// Whether the API server is running, and where.
+#[command]
+pub fn get_api_server_status(server: State<'_, ApiServer>) -> ApiServerStatus {
+    server.status()
+}
+
+/// A command error sent as an HTTP response.
+struct ApiError(ExplorerError);
+
+impl From<ExplorerError> for ApiError {
+    fn from(error: ExplorerError) -> Self {
+        ApiError(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            ExplorerError::NotFound(_) => StatusCode::NOT_FOUND,
+            ExplorerError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+            ExplorerError::AlreadyExists(_) | ExplorerError::Cancelled => StatusCode::CONFLICT,
+            ExplorerError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            ExplorerError::Unsupported(_) => StatusCode::NOT_IMPLEMENTED,
+            ExplorerError::Io(_) | ExplorerError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self.0)).into_response()
+    }
+}
+
+/// Reject requests without the server's token.
+async fn authorize(
+    ApiState(state): ApiState<ServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let from_header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let from_query = || {
+        request.uri().query().and_then(|query| {
+            query.split('&').find_map(|pair| {
+                let value = pair.strip_prefix("token=")?;
+                Some(percent_decode_str(value).decode_utf8_lossy().to_string())
+            })
+        })
+    };
+    match from_header.or_else(from_query) {
+        Some(token) if same_token(&token, &state.token) => next.run(request).await,
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            Json(ExplorerError::PermissionDenied(
+                "Missing or invalid token".to_string(),
+            )),
+        )
+            .into_response(),
+    }
+}
+
+/// Compare tokens in time independent of where they first differ.
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn drives(ApiState(state): ApiState<ServerState>) -> Result<Json<Vec<DriveInfo>>, ApiError> {
+    Ok(Json(get_drives(state.app.state()).await?))
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    path: String,
+    show_hidden: Option<bool>,
+    show_system: Option<bool>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+async fn list(
+    ApiState(state): ApiState<ServerState>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<DirectoryPage>, ApiError> {
+    let defaults = ListOptions::default();
+    let options = ListOptions {
+        show_hidden: query.show_hidden.unwrap_or(defaults.show_hidden),
+        show_system: query.show_system.unwrap_or(defaults.show_system),
+    };
+    Ok(Json(
+        list_directory(
+            state.app.state(),
+            query.path,
+            Some(options),
+            None,
+            query.offset,
+            query.limit,
+        )
+        .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+struct IndexQuery {
+    query: String,
+    limit: Option<usize>,
+}
+
+async fn index_search(
+    ApiState(state): ApiState<ServerState>,
+    Query(query): Query<IndexQuery>,
+) -> Result<Json<Vec<IndexedFile>>, ApiError> {
+    Ok(Json(
+        query_index(
+            state.app.state(),
+            state.app.state(),
+            query.query,
+            query.limit,
+        )
+        .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+struct ContentSearchRequest {
+    root: String,
+    pattern: String,
+    options: Option<ContentSearchOptions>,
+}
+
+async fn content_search(
+    ApiState(state): ApiState<ServerState>,
+    Json(request): Json<ContentSearchRequest>,
+) -> Result<Json<Vec<ContentMatch>>, ApiError> {
+    Ok(Json(
+        search_content(
+            state.app.state(),
+            state.app.state(),
+            request.root,
+            request.pattern,
+            request.options,
+        )
+        .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+struct ScanRequest {
+    path: String,
+    options: Option<ScanOptions>,
+    /// Levels of folders below `path` to return.
+    depth: Option<usize>,
+}
+
+async fn scan(
+    ApiState(state): ApiState<ServerState>,
+    Json(request): Json<ScanRequest>,
+) -> Result<Json<ScanReport>, ApiError> {
+    let app = &state.app;
+    let mut report = scan_directory(
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+        request.path,
+        None,
+        request.options,
+    )
+    .await?;
+    if let Some(depth) = request.depth {
+        prune(&mut report.tree, depth);
+    }
+    Ok(Json(report))
+}
+
+#[derive(Deserialize)]
+struct ScanSocketQuery {
+    path: String,
+    depth: Option<usize>,
+    incremental: Option<bool>,
+    follow_symlinks: Option<bool>,
+}
+
+async fn scan_socket(
+    ApiState(state): ApiState<ServerState>,
+    Query(query): Query<ScanSocketQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade.on_upgrade(move |socket| stream_scan(state.app, socket, query))
+}
+
+/// Run a scan, forwarding its progress to `socket`.
+async fn stream_scan(app: AppHandle, mut socket: WebSocket, query: ScanSocketQuery) {
+    let operation_id = uuid::Uuid::new_v4().to_string();
+    let (sender, mut progress) = mpsc::unbounded_channel::<String>();
+    let id = operation_id.clone();
+    let listener = app.listen(SCAN_PROGRESS_EVENT, move |event| {
+        let Ok(payload) = serde_json::from_str::<Value>(event.payload()) else {
+            return;
+        };
+        if payload["operation_id"] == id.as_str() {
+            let _ = sender.send(json!({ "type": "progress", "progress": payload }).to_string());
+        }
+    });
+
+    let options = ScanOptions {
+        incremental: query.incremental.unwrap_or(false),
+        follow_symlinks: query.follow_symlinks.unwrap_or(false),
+        ..Default::default()
+    };
+    let scan = scan_directory(
+        app.clone(),
+        app.state(),
+        app.state(),
+        app.state(),
+        app.state(),
+        query.path,
+        Some(operation_id.clone()),
+        Some(options),
+    );
+    tokio::pin!(scan);
+    let result = loop {
+        tokio::select! {
+            result = &mut scan => break Some(result),
+            Some(message) = progress.recv() => {
+                if socket.send(Message::Text(message.into())).await.is_err() {
+                    break None;
+                }
+            }
+            incoming = socket.recv() => {
+                if !matches!(incoming, Some(Ok(message)) if !matches!(message, Message::Close(_))) {
+                    break None;
+                }
+            }
+        }
+    };
+    app.unlisten(listener);
+
+    let message = match result {
+        Some(Ok(mut report)) => {
+            if let Some(depth) = query.depth {
+                prune(&mut report.tree, depth);
+            }
+            json!({ "type": "report", "report": report })
+        }
+        Some(Err(error)) => json!({ "type": "error", "error": error }),
+        None => {
+            // The client went away. The scan is cancelled and awaited so it
+            // stops before its operation is unregistered.
+            let _ = cancel_operation(app.state(), operation_id);
+            let _ = scan.await;
+            return;
+        }
+    };
+    let _ = socket.send(Message::Text(message.to_string().into())).await;
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+async fn cancel(
+    ApiState(state): ApiState<ServerState>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<StatusCode, ApiError> {
+    cancel_operation(state.app.state(), id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Drop the folders more than `depth` levels below `node`.
+fn prune(node: &mut DirNode, depth: usize) {
+    if depth == 0 {
+        node.children.clear();
+    } else {
+        for child in &mut node.children {
+            prune(child, depth - 1);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! File type icons from the operating system.
+
+use std::path::Path;
+
+use image::DynamicImage;
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+use crate::thumbnails::{png_data_url, ThumbnailResponse};
+
+/// Default icon size in pixels.
+const DEFAULT_ICON_SIZE: u32 = 32;
+
+/// Largest icon size in pixels that can be requested.
+const MAX_ICON_SIZE: u32 = 256;
+
+/// The icon the system file manager shows for `path`, as a PNG data URL
+/// scaled to fit a square of `size` pixels (default 32, at most 256).
+///
+/// Icons come from `SHGetFileInfo` and the shell image lists on Windows,
+/// from `NSWorkspace` on macOS, and from the current icon theme on Linux,
+/// where `gio` names the icons for the file's type. Executables, shortcuts
+/// and folders with custom icons get their own icon on Windows and macOS.
+#[command]
+pub async fn get_file_icon(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    size: Option<u32>,
+) -> Result<ThumbnailResponse, ExplorerError> {
+    let size = size.unwrap_or(DEFAULT_ICON_SIZE);
+    if !(1..=MAX_ICON_SIZE).contains(&size) {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Icon size must be between 1 and {} pixels",
+            MAX_ICON_SIZE
+        )));
+    }
+    tasks
+        .run_blocking("file_icon", move || {
+            let icon = load_icon(Path::new(&path), size)?;
+            Ok(png_data_url(&icon, size)?)
+        })
+        .await
+}
+
+/// Load the system icon for `path` at `size` pixels or larger where
+/// available.
+#[cfg(windows)]
+pub fn load_icon(path: &Path, size: u32) -> Result<DynamicImage, String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES;
+    use windows::Win32::System::Com::{CoInitializeEx, COINIT_APARTMENTTHREADED};
+    use windows::Win32::UI::Controls::{IImageList, ILD_TRANSPARENT};
+    use windows::Win32::UI::Shell::{
+        SHGetFileInfoW, SHGetImageList, SHFILEINFOW, SHGFI_SYSICONINDEX, SHIL_EXTRALARGE,
+        SHIL_JUMBO, SHIL_LARGE, SHIL_SMALL,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::DestroyIcon;
+
+    // The shell image lists are COM objects. A thread that is already
+    // initialised keeps its apartment.
+    let _ = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut info = SHFILEINFOW::default();
+    let found = unsafe {
+        SHGetFileInfoW(
+            PCWSTR(wide.as_ptr()),
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut info),
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_SYSICONINDEX,
+        )
+    };
+    if found == 0 {
+        return Err(format!("No icon for {}", path.display()));
+    }
+
+    // The lists hold 16, 32, 48 and 256 pixel icons.
+    let list = match size {
+        0..=16 => SHIL_SMALL,
+        17..=32 => SHIL_LARGE,
+        33..=48 => SHIL_EXTRALARGE,
+        _ => SHIL_JUMBO,
+    };
+    let list: IImageList = unsafe { SHGetImageList(list as i32) }
+        .map_err(|e| format!("Failed to load system icons: {}", e))?;
+    let icon = unsafe { list.GetIcon(info.iIcon, ILD_TRANSPARENT.0) }
+        .map_err(|e| format!("Failed to load icon for {}: {}", path.display(), e))?;
+    let image = icon_to_image(icon);
+    let _ = unsafe { DestroyIcon(icon) };
+    image
+}
+
+/// Read the pixels of an icon handle.
+#[cfg(windows)]
+fn icon_to_image(
+    icon: windows::Win32::UI::WindowsAndMessaging::HICON,
+) -> Result<DynamicImage, String> {
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits, GetObjectW, BITMAP, BITMAPINFO,
+        BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HDC,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetIconInfo, ICONINFO};
+
+    let mut info = ICONINFO::default();
+    unsafe { GetIconInfo(icon, &mut info) }.map_err(|e| format!("Failed to read icon: {}", e))?;
+    let mut bitmap = BITMAP::default();
+    let read = unsafe {
+        GetObjectW(
+            info.hbmColor,
+            std::mem::size_of::<BITMAP>() as i32,
+            Some(&mut bitmap as *mut BITMAP as *mut _),
+        )
+    };
+    let (width, height) = (bitmap.bmWidth.max(0) as u32, bitmap.bmHeight.max(0) as u32);
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    let mut header = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            // Negative for rows from top to bottom.
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let lines = unsafe {
+        let dc = CreateCompatibleDC(HDC::default());
+        let lines = if read == 0 {
+            0
+        } else {
+            GetDIBits(
+                dc,
+                info.hbmColor,
+                0,
+                height,
+                Some(pixels.as_mut_ptr().cast()),
+                &mut header,
+                DIB_RGB_COLORS,
+            )
+        };
+        let _ = DeleteDC(dc);
+        let _ = DeleteObject(info.hbmColor);
+        let _ = DeleteObject(info.hbmMask);
+        lines
+    };
+    if lines == 0 {
+        return Err("Failed to read icon pixels".to_string());
+    }
+
+    // BGRA to RGBA. Old icons without an alpha channel are opaque.
+    let opaque = pixels.chunks_exact(4).all(|pixel| pixel[3] == 0);
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+        if opaque {
+            pixel[3] = u8::MAX;
+        }
+    }
+    image::RgbaImage::from_raw(width, height, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| "Failed to read icon pixels".to_string())
+}
+
+/// Load the system icon for `path` at `size` pixels or larger where
+/// available.
+#[cfg(target_os = "macos")]
+pub fn load_icon(path: &Path, size: u32) -> Result<DynamicImage, String> {
+    use objc2::rc::Retained;
+    use objc2::ClassType;
+    use objc2_app_kit::{NSBitmapImageRep, NSImage, NSImageRep, NSWorkspace};
+    use objc2_foundation::{NSSize, NSString};
+
+    let no_icon = || format!("No icon for {}", path.display());
+    let tiff = unsafe {
+        NSWorkspace::sharedWorkspace()
+            .iconForFile(&NSString::from_str(&path.to_string_lossy()))
+            .TIFFRepresentation()
+    }
+    .ok_or_else(no_icon)?;
+
+    // The icon holds one bitmap per resolution: take the smallest that is
+    // at least `size` wide, or else the largest.
+    let reps = unsafe { NSBitmapImageRep::imageRepsWithData(&tiff) };
+    let width = |rep: &Retained<NSImageRep>| unsafe { rep.pixelsWide() };
+    let reps: Vec<Retained<NSImageRep>> = reps.iter().map(|rep| rep.retain()).collect();
+    let rep = reps
+        .iter()
+        .filter(|rep| width(rep) >= size as isize)
+        .min_by_key(|rep| width(rep))
+        .or_else(|| reps.iter().max_by_key(|rep| width(rep)))
+        .ok_or_else(no_icon)?;
+
+    // A TIFF of that bitmap alone, which `image` decodes.
+    let single = unsafe {
+        let image = NSImage::initWithSize(NSImage::alloc(), NSSize::new(0.0, 0.0));
+        image.addRepresentation(rep);
+        image.TIFFRepresentation()
+    }
+    .ok_or_else(no_icon)?;
+    image::load_from_memory(single.bytes())
+        .map_err(|e| format!("Failed to decode icon for {}: {}", path.display(), e))
+}
+
+/// Load the icon theme's icon for `path` at `size` pixels or larger where
+/// available.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn load_icon(path: &Path, size: u32) -> Result<DynamicImage, String> {
+    let names = linux::icon_names(path);
+    let icon =
+        linux::find_icon(&names, size).ok_or_else(|| format!("No icon for {}", path.display()))?;
+    crate::thumbnails::decode_image(&icon.to_string_lossy(), size)
+}
+
+/// Load an icon theme icon by name, as given in the `Icon` key of desktop
+/// entries, or from the file an absolute `name` points to.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn load_named_icon(name: &str, size: u32) -> Result<DynamicImage, String> {
+    let icon = if Path::new(name).is_absolute() {
+        Some(Path::new(name).to_path_buf())
+    } else {
+        linux::find_icon(&[name.to_string()], size)
+    }
+    .ok_or_else(|| format!("No icon named {}", name))?;
+    crate::thumbnails::decode_image(&icon.to_string_lossy(), size)
+}
+
+/// Icon lookup following the freedesktop.org icon theme specification, much
+/// simplified: sizes are read from folder names rather than `index.theme`
+/// sections, and scalable icons are preferred over the wrong size.
+#[cfg(all(unix, not(target_os = "macos")))]
+mod linux {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    /// Themes searched after the current one.
+    const FALLBACK_THEMES: &[&str] = &["Adwaita", "breeze", "hicolor"];
+
+    /// Icon names for the file's type, most specific first.
+    pub fn icon_names(path: &Path) -> Vec<String> {
+        let mut names = gio_icon_names(path).unwrap_or_default();
+        if path.is_dir() {
+            names.push("folder".to_string());
+        } else {
+            if let Some(mime) = mime_guess::from_path(path).first() {
+                names.push(mime.essence_str().replace('/', "-"));
+                names.push(format!("{}-x-generic", mime.type_()));
+            }
+            names.push("text-x-generic".to_string());
+        }
+        names
+    }
+
+    /// Names listed by `gio info`, which also knows custom folder icons and
+    /// types detected from contents.
+    fn gio_icon_names(path: &Path) -> Option<Vec<String>> {
+        let output = Command::new("gio")
+            .args(["info", "--attributes=standard::icon"])
+            .arg(path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        // Prints `  standard::icon: text-plain, text-x-generic, ...`.
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("standard::icon:"))?;
+        Some(
+            line.split(',')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty() && !name.ends_with("-symbolic"))
+                .collect(),
+        )
+    }
+
+    /// The best file for the first of `names` found in any theme.
+    pub fn find_icon(names: &[String], size: u32) -> Option<PathBuf> {
+        let bases = base_dirs();
+        let mut themes: Vec<String> = current_theme().into_iter().collect();
+        themes.extend(FALLBACK_THEMES.iter().map(|theme| theme.to_string()));
+        for theme in &themes {
+            let dirs: Vec<PathBuf> = bases
+                .iter()
+                .map(|base| base.join(theme))
+                .filter(|dir| dir.is_dir())
+                .collect();
+            for name in names {
+                if let Some(icon) = best_in_theme(&dirs, name, size) {
+                    return Some(icon);
+                }
+            }
+        }
+        names.iter().find_map(|name| {
+            ["png", "svg"]
+                .iter()
+                .map(|ext| Path::new("/usr/share/pixmaps").join(format!("{}.{}", name, ext)))
+                .find(|icon| icon.is_file())
+        })
+    }
+
+    /// The icon named `name` in the theme folders closest in size to `size`,
+    /// scalable icons counting as a match for every size.
+    fn best_in_theme(dirs: &[PathBuf], name: &str, size: u32) -> Option<PathBuf> {
+        let mut best: Option<(u32, PathBuf)> = None;
+        for dir in dirs {
+            let Ok(size_dirs) = fs::read_dir(dir) else {
+                continue;
+            };
+            for size_dir in size_dirs.flatten() {
+                let dir_name = size_dir.file_name().to_string_lossy().to_string();
+                // `48x48`, `48` or `scalable`; `@2x` variants are skipped.
+                let distance = if dir_name == "scalable" {
+                    0
+                } else if let Some(dir_size) = dir_name
+                    .split('x')
+                    .next()
+                    .and_then(|width| width.parse::<u32>().ok())
+                    .filter(|_| !dir_name.contains('@'))
+                {
+                    // Prefer downscaling over upscaling.
+                    if dir_size >= size {
+                        dir_size - size
+                    } else {
+                        (size - dir_size) * 4
+                    }
+                } else {
+                    continue;
+                };
+                if best.as_ref().is_some_and(|(best, _)| *best <= distance) {
+                    continue;
+                }
+                let Ok(contexts) = fs::read_dir(size_dir.path()) else {
+                    continue;
+                };
+                let found = contexts.flatten().find_map(|context| {
+                    ["png", "svg"]
+                        .iter()
+                        .map(|ext| context.path().join(format!("{}.{}", name, ext)))
+                        .find(|icon| icon.is_file())
+                });
+                if let Some(icon) = found {
+                    best = Some((distance, icon));
+                }
+            }
+        }
+        best.map(|(_, icon)| icon)
+    }
+
+    /// Folders holding icon themes, most specific first.
+    fn base_dirs() -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        let home = env::var_os("HOME").map(PathBuf::from);
+        match env::var_os("XDG_DATA_HOME") {
+            Some(data_home) => dirs.push(PathBuf::from(data_home).join("icons")),
+            None => dirs.extend(home.as_ref().map(|home| home.join(".local/share/icons"))),
+        }
+        dirs.extend(home.map(|home| home.join(".icons")));
+        let data_dirs = env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        dirs.extend(data_dirs.split(':').map(|dir| Path::new(dir).join("icons")));
+        dirs
+    }
+
+    /// The icon theme chosen in the desktop settings.
+    fn current_theme() -> Option<String> {
+        let output = Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.interface", "icon-theme"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        // Prints the name in single quotes.
+        let theme = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .trim_matches('\'')
+            .to_string();
+        (!theme.is_empty()).then_some(theme)
+    }
+}
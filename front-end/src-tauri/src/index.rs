@@ -0,0 +1,463 @@
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+use std::time::SystemTime;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{command, AppHandle, State};
+use walkdir::WalkDir;
+
+use crate::commands::{app_data_dir, to_unix_secs};
+use crate::error::ExplorerError;
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// File name of the index database inside the app data directory.
+const INDEX_FILE_NAME: &str = "file-index.sqlite3";
+
+/// Writes an `IndexWriter` makes before committing them, letting other
+/// writers in.
+const INDEX_BATCH_SIZE: usize = 5_000;
+
+/// Default number of rows returned by `query_index`.
+const DEFAULT_QUERY_LIMIT: usize = 200;
+
+pub(crate) const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        path TEXT PRIMARY KEY,
+        parent TEXT NOT NULL,
+        name TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        mtime INTEGER,
+        is_dir INTEGER NOT NULL,
+        extension TEXT
+    );
+    CREATE INDEX IF NOT EXISTS files_name ON files(name);
+    CREATE INDEX IF NOT EXISTS files_parent ON files(parent);
+    CREATE TABLE IF NOT EXISTS roots (
+        path TEXT PRIMARY KEY,
+        indexed_at INTEGER NOT NULL,
+        file_count INTEGER NOT NULL,
+        total_size INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS tags (
+        path TEXT NOT NULL,
+        tag TEXT NOT NULL COLLATE NOCASE,
+        tagged_at INTEGER NOT NULL,
+        PRIMARY KEY (path, tag)
+    );
+    CREATE INDEX IF NOT EXISTS tags_tag ON tags(tag);
+    CREATE TABLE IF NOT EXISTS history (
+        path TEXT PRIMARY KEY,
+        is_dir INTEGER NOT NULL,
+        visited_at INTEGER NOT NULL,
+        visit_count INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS history_visited_at ON history(visited_at);
+    CREATE TABLE IF NOT EXISTS schedules (
+        id TEXT PRIMARY KEY,
+        path TEXT NOT NULL,
+        cron_expr TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        last_run INTEGER,
+        last_error TEXT
+    );
+    CREATE TABLE IF NOT EXISTS snapshots (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        root TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        total_size INTEGER NOT NULL,
+        file_count INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS snapshot_folders (
+        snapshot_id TEXT NOT NULL,
+        path TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        file_count INTEGER NOT NULL,
+        PRIMARY KEY (snapshot_id, path)
+    );
+    CREATE TABLE IF NOT EXISTS saved_searches (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        root TEXT NOT NULL,
+        query TEXT NOT NULL,
+        mode TEXT NOT NULL,
+        options TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    -- The undo journal has its own database now.
+    DROP TABLE IF EXISTS journal;
+";
+
+/// Managed state pointing at the on-disk file index.
+///
+/// Each command opens its own connection so long-running writers (scans)
+/// never block readers; the database runs in WAL mode.
+pub struct FileIndex {
+    db_path: PathBuf,
+}
+
+impl FileIndex {
+    /// Create the index database under the app data directory if needed.
+    pub fn init(app: &AppHandle) -> Result<Self, String> {
+        let dir = app_data_dir(app)?;
+        let index = FileIndex {
+            db_path: dir.join(INDEX_FILE_NAME),
+        };
+        index
+            .connect()?
+            .execute_batch(SCHEMA)
+            .map_err(|e| format!("Failed to initialise file index: {}", e))?;
+        Ok(index)
+    }
+
+    /// Open a new connection to the index.
+    pub fn connect(&self) -> Result<Connection, String> {
+        let conn = Connection::open(&self.db_path)
+            .map_err(|e| format!("Failed to open file index: {}", e))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .and_then(|_| conn.pragma_update(None, "synchronous", "NORMAL"))
+            .map_err(|e| format!("Failed to configure file index: {}", e))?;
+        Ok(conn)
+    }
+}
+
+/// Writes the entries of one indexed root.
+///
+/// Existing rows under the root are replaced (or, for incremental scans,
+/// updated in place). Writes are committed every `INDEX_BATCH_SIZE` rows, so
+/// a long scan does not hold off other writers of the index until it ends.
+/// A scan that stops early keeps the batches it committed, but its root is
+/// only recorded as indexed by `finish`.
+pub struct IndexWriter<'conn> {
+    conn: &'conn Connection,
+    root: String,
+    file_count: u64,
+    total_size: u64,
+    /// Writes since the last commit.
+    pending: usize,
+}
+
+impl<'conn> IndexWriter<'conn> {
+    pub fn begin(conn: &'conn mut Connection, root: &str) -> Result<Self, String> {
+        let mut writer = Self::begin_incremental(conn, root)?;
+        writer.remove(root)?;
+        Ok(writer)
+    }
+
+    /// Start a transaction that keeps the existing rows under `root`.
+    ///
+    /// The caller is responsible for updating changed entries and removing
+    /// stale ones.
+    pub fn begin_incremental(conn: &'conn mut Connection, root: &str) -> Result<Self, String> {
+        // Immediate, so reads made before a write cannot go stale.
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("Failed to start index transaction: {}", e))?;
+        Ok(IndexWriter {
+            conn,
+            root: root.to_string(),
+            file_count: 0,
+            total_size: 0,
+            pending: 0,
+        })
+    }
+
+    /// Count a write, committing the batch once it is full.
+    fn wrote(&mut self) -> Result<(), String> {
+        self.pending += 1;
+        if self.pending >= INDEX_BATCH_SIZE {
+            self.conn
+                .execute_batch("COMMIT; BEGIN IMMEDIATE")
+                .map_err(|e| format!("Failed to commit index: {}", e))?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    /// Stored modification time of a directory, if it is indexed.
+    pub fn dir_mtime(&self, path: &Path) -> Result<Option<u64>, String> {
+        self.conn
+            .query_row(
+                "SELECT mtime FROM files WHERE path = ?1 AND is_dir = 1",
+                params![path.to_string_lossy()],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .optional()
+            .map(|mtime| mtime.flatten().map(|t| t as u64))
+            .map_err(|e| format!("Failed to query index: {}", e))
+    }
+
+    /// Indexed entries directly inside `parent`.
+    pub fn children(&self, parent: &Path) -> Result<Vec<IndexedFile>, String> {
+        self.conn
+            .prepare_cached(
+                "SELECT path, name, size, mtime, is_dir, extension FROM files WHERE parent = ?1",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![parent.to_string_lossy()], row_to_file)?
+                    .collect()
+            })
+            .map_err(|e| format!("Failed to query index: {}", e))
+    }
+
+    /// Delete `path` and everything indexed below it.
+    pub fn remove(&mut self, path: &str) -> Result<(), String> {
+        let (lower, upper) = subtree_bounds(path);
+        self.conn
+            .execute(
+                "DELETE FROM files WHERE path = ?1 OR (path >= ?2 AND path < ?3)",
+                params![path, lower, upper],
+            )
+            .map_err(|e| format!("Failed to clear index: {}", e))?;
+        self.wrote()
+    }
+
+    /// Count a file that is kept from the previous index without rewriting it.
+    pub fn keep(&mut self, file: &IndexedFile) {
+        if !file.is_dir {
+            self.file_count += 1;
+            self.total_size += file.size;
+        }
+    }
+
+    /// Record a single file or directory.
+    pub fn insert(&mut self, path: &Path, meta: &fs::Metadata) -> Result<(), String> {
+        let is_dir = meta.is_dir();
+        let size = if is_dir { 0 } else { meta.len() };
+        self.conn
+            .prepare_cached(
+                "INSERT OR REPLACE INTO files (path, parent, name, size, mtime, is_dir, extension)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
+                    path.to_string_lossy(),
+                    path.parent()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    path.file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    size as i64,
+                    to_unix_secs(meta.modified()).map(|t| t as i64),
+                    is_dir,
+                    path.extension().map(|e| e.to_string_lossy().to_lowercase()),
+                ])
+            })
+            .map_err(|e| format!("Failed to write index entry: {}", e))?;
+        if !is_dir {
+            self.file_count += 1;
+            self.total_size += size;
+        }
+        self.wrote()
+    }
+
+    /// Commit the entries and record when the root was indexed.
+    pub fn finish(self) -> Result<(), String> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO roots (path, indexed_at, file_count, total_size)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    self.root,
+                    to_unix_secs(Ok(SystemTime::now())).unwrap_or(0) as i64,
+                    self.file_count as i64,
+                    self.total_size as i64,
+                ],
+            )
+            .map_err(|e| format!("Failed to record indexed root: {}", e))?;
+        self.conn
+            .execute_batch("COMMIT")
+            .map_err(|e| format!("Failed to commit index: {}", e))
+    }
+}
+
+impl Drop for IndexWriter<'_> {
+    /// Roll back the last batch of a scan that did not finish.
+    fn drop(&mut self) {
+        if !self.conn.is_autocommit() {
+            let _ = self.conn.execute_batch("ROLLBACK");
+        }
+    }
+}
+
+/// Bounds `[lower, upper)` matching every path strictly below `root`.
+///
+/// Paths below `root` start with `root` plus a separator, so the upper bound
+/// is the same prefix with the separator bumped to the next character.
+pub fn subtree_bounds(root: &str) -> (String, String) {
+    let trimmed = root.trim_end_matches(MAIN_SEPARATOR);
+    let lower = format!("{}{}", trimmed, MAIN_SEPARATOR);
+    let next = char::from_u32(MAIN_SEPARATOR as u32 + 1).unwrap_or(MAIN_SEPARATOR);
+    let upper = format!("{}{}", trimmed, next);
+    (lower, upper)
+}
+
+/// A root folder stored in the index.
+#[derive(Serialize, Clone)]
+pub struct IndexedRoot {
+    pub path: String,
+    /// Seconds since the Unix epoch.
+    pub indexed_at: u64,
+    pub file_count: u64,
+    pub total_size: u64,
+}
+
+/// Overall state of the file index.
+#[derive(Serialize)]
+pub struct IndexStatus {
+    pub database_path: String,
+    pub database_size: u64,
+    pub entry_count: u64,
+    pub roots: Vec<IndexedRoot>,
+}
+
+/// A row returned by `query_index`.
+#[derive(Serialize)]
+pub struct IndexedFile {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    /// Seconds since the Unix epoch.
+    pub modified: Option<u64>,
+    pub is_dir: bool,
+    pub extension: Option<String>,
+}
+
+/// Report which roots are indexed and how large the index is.
+#[command]
+pub async fn index_status(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+) -> Result<IndexStatus, ExplorerError> {
+    let db_path = index.db_path.clone();
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("index_status", move || {
+            let entry_count: i64 = conn
+                .query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))
+                .map_err(|e| format!("Failed to query index: {}", e))?;
+            let mut stmt = conn
+                .prepare("SELECT path, indexed_at, file_count, total_size FROM roots ORDER BY path")
+                .map_err(|e| format!("Failed to query index: {}", e))?;
+            let roots = stmt
+                .query_map([], |row| {
+                    Ok(IndexedRoot {
+                        path: row.get(0)?,
+                        indexed_at: row.get::<_, i64>(1)? as u64,
+                        file_count: row.get::<_, i64>(2)? as u64,
+                        total_size: row.get::<_, i64>(3)? as u64,
+                    })
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query index: {}", e))?;
+
+            Ok(IndexStatus {
+                database_size: fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0),
+                database_path: db_path.to_string_lossy().to_string(),
+                entry_count: entry_count as u64,
+                roots,
+            })
+        })
+        .await
+}
+
+/// Walk `root` and replace its entries in the index.
+#[command]
+pub async fn rebuild_index(
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    index: State<'_, FileIndex>,
+    root: String,
+    operation_id: Option<String>,
+) -> Result<IndexedRoot, ExplorerError> {
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    let mut conn = index.connect()?;
+    tasks
+        .run_blocking("rebuild_index", move || {
+            index_tree(&mut conn, &handle, &root)
+        })
+        .await
+}
+
+/// Index everything below `root`, replacing its previous entries.
+pub fn index_tree(
+    conn: &mut Connection,
+    operation: &OperationHandle,
+    root: &str,
+) -> Result<IndexedRoot, ExplorerError> {
+    if !Path::new(root).is_dir() {
+        return Err(format!("Not a directory: {}", root).into());
+    }
+
+    let mut writer = IndexWriter::begin(conn, root)?;
+    for entry in WalkDir::new(root).into_iter().flatten() {
+        operation.checkpoint()?;
+        if let Ok(meta) = entry.metadata() {
+            writer.insert(entry.path(), &meta)?;
+        }
+    }
+    let (file_count, total_size) = (writer.file_count, writer.total_size);
+    writer.finish()?;
+
+    Ok(IndexedRoot {
+        path: root.to_string(),
+        indexed_at: to_unix_secs(Ok(SystemTime::now())).unwrap_or(0),
+        file_count,
+        total_size,
+    })
+}
+
+/// Find indexed entries whose name contains `query`, largest first.
+#[command]
+pub async fn query_index(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<IndexedFile>, ExplorerError> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("query_index", move || {
+            let pattern = format!("%{}%", escape_like(&query));
+            let mut stmt = conn
+                .prepare(
+                    "SELECT path, name, size, mtime, is_dir, extension FROM files
+                     WHERE name LIKE ?1 ESCAPE '\\'
+                     ORDER BY size DESC LIMIT ?2",
+                )
+                .map_err(|e| format!("Failed to query index: {}", e))?;
+            Ok(stmt
+                .query_map(params![pattern, limit as i64], row_to_file)
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query index: {}", e))?)
+        })
+        .await
+}
+
+/// Map a `path, name, size, mtime, is_dir, extension` row.
+pub fn row_to_file(row: &rusqlite::Row) -> rusqlite::Result<IndexedFile> {
+    Ok(IndexedFile {
+        path: row.get(0)?,
+        name: row.get(1)?,
+        size: row.get::<_, i64>(2)? as u64,
+        modified: row.get::<_, Option<i64>>(3)?.map(|t| t as u64),
+        is_dir: row.get(4)?,
+        extension: row.get(5)?,
+    })
+}
+
+/// Escape `%`, `_` and `\` for use in a `LIKE ... ESCAPE '\'` pattern.
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
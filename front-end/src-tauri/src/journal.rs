@@ -0,0 +1,509 @@
+//! A journal of copies, moves, renames and deletes, so they can be undone
+//! and redone.
+//!
+//! Operations are kept in a database of their own, newest last, so a scan
+//! holding the file index's write lock cannot keep them from being recorded.
+//! Undoing one marks it as undone; redoing it clears the mark. A new
+//! operation drops everything undone before it, as in an editor.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+use crate::commands::{app_data_dir, to_unix_secs};
+use crate::error::ExplorerError;
+use crate::file_ops::{rename_in_place, transfer_item, PathResult, TransferKind};
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// File name of the journal database inside the app data directory.
+const JOURNAL_FILE_NAME: &str = "journal.sqlite3";
+
+/// Event emitted when a finished operation could not be journalled, and so
+/// cannot be undone.
+pub const JOURNAL_ERROR_EVENT: &str = "journal://error";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS journal (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL,
+        entries TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        undone INTEGER NOT NULL
+    );
+";
+
+/// Operations kept in the journal; the oldest are dropped.
+const MAX_JOURNAL_OPERATIONS: i64 = 500;
+
+/// Default number of operations returned by `get_journal`.
+const DEFAULT_JOURNAL_LIMIT: usize = 50;
+
+/// What a journalled operation did.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalKind {
+    Copy,
+    Move,
+    Rename,
+    /// Moved to the trash.
+    Trash,
+    /// Deleted permanently. Recorded, but cannot be undone.
+    Delete,
+}
+
+/// One item of a journalled operation.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JournalEntry {
+    pub source: String,
+    /// Where the item ended up, for copies, moves and renames.
+    pub target: Option<String>,
+}
+
+/// An operation in the journal.
+#[derive(Serialize)]
+pub struct JournalOperation {
+    pub id: i64,
+    pub kind: JournalKind,
+    pub entries: Vec<JournalEntry>,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    pub undone: bool,
+}
+
+/// Result of `undo_last_operation` or `redo`.
+#[derive(Serialize)]
+pub struct UndoReport {
+    /// The operation, with `undone` as it is now.
+    pub operation: JournalOperation,
+    /// One entry per item, by source path.
+    pub results: Vec<PathResult>,
+}
+
+/// Payload of the `journal://error` event.
+#[derive(Serialize, Clone)]
+pub struct JournalError {
+    pub kind: JournalKind,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+/// Managed state pointing at the journal database.
+pub struct Journal {
+    db_path: PathBuf,
+}
+
+impl Journal {
+    /// Create the journal database under the app data directory if needed.
+    pub fn init(app: &AppHandle) -> Result<Self, String> {
+        let journal = Journal {
+            db_path: app_data_dir(app)?.join(JOURNAL_FILE_NAME),
+        };
+        journal
+            .connect()?
+            .execute_batch(SCHEMA)
+            .map_err(|e| format!("Failed to initialise journal: {}", e))?;
+        Ok(journal)
+    }
+
+    /// Open a new connection to the journal.
+    pub fn connect(&self) -> Result<Connection, String> {
+        let conn = Connection::open(&self.db_path)
+            .map_err(|e| format!("Failed to open journal: {}", e))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(|e| format!("Failed to configure journal: {}", e))?;
+        Ok(conn)
+    }
+}
+
+/// Add a finished operation to the journal.
+///
+/// An operation that succeeded is not reported as failed because it could
+/// not be journalled; `journal://error` is emitted instead, so the UI can
+/// warn that it cannot be undone.
+pub fn record(app: &AppHandle, kind: JournalKind, entries: &[JournalEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    let result = app
+        .state::<Journal>()
+        .connect()
+        .and_then(|conn| insert(&conn, kind, entries));
+    if let Err(message) = result {
+        let _ = app.emit(JOURNAL_ERROR_EVENT, JournalError { kind, message });
+    }
+}
+
+fn insert(conn: &Connection, kind: JournalKind, entries: &[JournalEntry]) -> Result<(), String> {
+    let (Ok(kind), Ok(entries)) = (serde_json::to_string(&kind), serde_json::to_string(entries))
+    else {
+        return Err("Failed to encode journal entries".to_string());
+    };
+    let now = to_unix_secs(Ok(SystemTime::now())).unwrap_or(0) as i64;
+    conn.execute("DELETE FROM journal WHERE undone = 1", [])
+        .and_then(|_| {
+            conn.execute(
+                "INSERT INTO journal (kind, entries, created_at, undone) VALUES (?1, ?2, ?3, 0)",
+                params![kind, entries, now],
+            )
+        })
+        .and_then(|_| {
+            conn.execute(
+                "DELETE FROM journal WHERE id NOT IN
+                 (SELECT id FROM journal ORDER BY id DESC LIMIT ?1)",
+                params![MAX_JOURNAL_OPERATIONS],
+            )
+        })
+        .map(|_| ())
+        .map_err(|e| format!("Failed to record operation in the journal: {}", e))
+}
+
+/// Recent operations, newest first.
+#[command]
+pub async fn get_journal(
+    tasks: State<'_, TaskRegistry>,
+    journal: State<'_, Journal>,
+    limit: Option<usize>,
+) -> Result<Vec<JournalOperation>, ExplorerError> {
+    let limit = limit.unwrap_or(DEFAULT_JOURNAL_LIMIT);
+    let conn = journal.connect()?;
+    tasks
+        .run_blocking("get_journal", move || {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, kind, entries, created_at, undone FROM journal
+                     ORDER BY id DESC LIMIT ?1",
+                )
+                .map_err(|e| format!("Failed to query journal: {}", e))?;
+            let operations = stmt
+                .query_map(params![limit as i64], read_row)
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query journal: {}", e))?;
+            Ok(operations.into_iter().flatten().collect())
+        })
+        .await
+}
+
+/// Undo the latest operation not undone yet: copies are moved to the trash,
+/// moves and renames are moved back, and trashed items are restored.
+///
+/// Permanent deletes cannot be undone and are passed over. Items that can no
+/// longer be put back, e.g. because their old path is taken again, are
+/// reported in the results and stay to be undone by the next call. Moving
+/// items back can be paused or cancelled through `operation_id`, with
+/// progress on `file-ops://progress`.
+#[command]
+pub async fn undo_last_operation(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    journal: State<'_, Journal>,
+    operation_id: Option<String>,
+) -> Result<UndoReport, ExplorerError> {
+    step(
+        app,
+        &tasks,
+        &operations,
+        &journal,
+        operation_id,
+        Direction::Undo,
+    )
+    .await
+}
+
+/// Do the most recently undone operation again.
+#[command]
+pub async fn redo(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    journal: State<'_, Journal>,
+    operation_id: Option<String>,
+) -> Result<UndoReport, ExplorerError> {
+    step(
+        app,
+        &tasks,
+        &operations,
+        &journal,
+        operation_id,
+        Direction::Redo,
+    )
+    .await
+}
+
+async fn step(
+    app: AppHandle,
+    tasks: &TaskRegistry,
+    operations: &OperationRegistry,
+    journal: &Journal,
+    operation_id: Option<String>,
+    direction: Direction,
+) -> Result<UndoReport, ExplorerError> {
+    let conn = journal.connect()?;
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    let label = match direction {
+        Direction::Undo => "undo",
+        Direction::Redo => "redo",
+    };
+    tasks
+        .run_blocking(label, move || {
+            let mut journalled = next_operation(&conn, direction)?
+                .ok_or_else(|| ExplorerError::NotFound(format!("Nothing to {}", label)))?;
+            let results: Vec<PathResult> = journalled
+                .entries
+                .iter()
+                .map(|entry| {
+                    let result = handle
+                        .checkpoint()
+                        .map_err(|e| e.to_string())
+                        .and_then(|()| {
+                            let apply = match direction {
+                                Direction::Undo => undo_entry,
+                                Direction::Redo => redo_entry,
+                            };
+                            apply(&app, &handle, &id, journalled.kind, entry)
+                        });
+                    PathResult::from_result(entry.source.clone(), result)
+                })
+                .collect();
+
+            // Left as it was when nothing could be done, so it can be tried
+            // again once whatever was in the way is gone.
+            let (done, left): (Vec<_>, Vec<_>) = journalled
+                .entries
+                .drain(..)
+                .zip(&results)
+                .partition(|(_, result)| result.success);
+            let done: Vec<JournalEntry> = done.into_iter().map(|(entry, _)| entry).collect();
+            let left: Vec<JournalEntry> = left.into_iter().map(|(entry, _)| entry).collect();
+            if done.is_empty() {
+                journalled.entries = left;
+            } else if left.is_empty() {
+                journalled.entries = done;
+                journalled.undone = direction == Direction::Undo;
+                conn.execute(
+                    "UPDATE journal SET undone = ?1 WHERE id = ?2",
+                    params![journalled.undone, journalled.id],
+                )
+                .map_err(|e| format!("Failed to update journal: {}", e))?;
+            } else {
+                journalled = split(&conn, journalled, done, left, direction)?;
+            }
+            Ok(UndoReport {
+                operation: journalled,
+                results,
+            })
+        })
+        .await
+}
+
+/// Split an operation that was only partly undone or redone in two, and
+/// return the part that was.
+///
+/// The part now undone keeps the operation's row, which is where `redo`
+/// looks next. The part not undone moves to a new row on top, which is where
+/// `undo_last_operation` looks next. Each part can then be undone or redone
+/// on its own, without replaying items already done.
+fn split(
+    conn: &Connection,
+    operation: JournalOperation,
+    done: Vec<JournalEntry>,
+    left: Vec<JournalEntry>,
+    direction: Direction,
+) -> Result<JournalOperation, String> {
+    let (undone, not_undone) = match direction {
+        Direction::Undo => (&done, &left),
+        Direction::Redo => (&left, &done),
+    };
+    let (Ok(kind), Ok(undone), Ok(not_undone)) = (
+        serde_json::to_string(&operation.kind),
+        serde_json::to_string(undone),
+        serde_json::to_string(not_undone),
+    ) else {
+        return Err("Failed to encode journal entries".to_string());
+    };
+    conn.execute(
+        "UPDATE journal SET undone = 1, entries = ?1 WHERE id = ?2",
+        params![undone, operation.id],
+    )
+    .and_then(|_| {
+        conn.execute(
+            "INSERT INTO journal (kind, entries, created_at, undone) VALUES (?1, ?2, ?3, 0)",
+            params![kind, not_undone, operation.created_at as i64],
+        )
+    })
+    .map_err(|e| format!("Failed to update journal: {}", e))?;
+
+    let id = match direction {
+        Direction::Undo => operation.id,
+        Direction::Redo => conn.last_insert_rowid(),
+    };
+    Ok(JournalOperation {
+        id,
+        entries: done,
+        undone: direction == Direction::Undo,
+        ..operation
+    })
+}
+
+/// The operation `undo_last_operation` or `redo` applies to next.
+fn next_operation(
+    conn: &Connection,
+    direction: Direction,
+) -> Result<Option<JournalOperation>, String> {
+    let query = match direction {
+        Direction::Undo => {
+            "SELECT id, kind, entries, created_at, undone FROM journal
+             WHERE undone = 0 ORDER BY id DESC"
+        }
+        // Undo works down from the newest operation, so of the undone ones,
+        // the oldest was undone last.
+        Direction::Redo => {
+            "SELECT id, kind, entries, created_at, undone FROM journal
+             WHERE undone = 1 ORDER BY id"
+        }
+    };
+    let mut stmt = conn
+        .prepare(query)
+        .map_err(|e| format!("Failed to query journal: {}", e))?;
+    let rows = stmt
+        .query_map([], read_row)
+        .map_err(|e| format!("Failed to query journal: {}", e))?;
+    for row in rows {
+        match row.map_err(|e| format!("Failed to query journal: {}", e))? {
+            Some(operation) if operation.kind != JournalKind::Delete => return Ok(Some(operation)),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Decode a row, or `None` if its stored kind or entries no longer parse.
+fn read_row(row: &rusqlite::Row) -> rusqlite::Result<Option<JournalOperation>> {
+    let kind: String = row.get(1)?;
+    let entries: String = row.get(2)?;
+    let (Ok(kind), Ok(entries)) = (serde_json::from_str(&kind), serde_json::from_str(&entries))
+    else {
+        return Ok(None);
+    };
+    Ok(Some(JournalOperation {
+        id: row.get(0)?,
+        kind,
+        entries,
+        created_at: row.get::<_, i64>(3)? as u64,
+        undone: row.get(4)?,
+    }))
+}
+
+fn undo_entry(
+    app: &AppHandle,
+    operation: &Arc<OperationHandle>,
+    operation_id: &str,
+    kind: JournalKind,
+    entry: &JournalEntry,
+) -> Result<(), String> {
+    match (kind, &entry.target) {
+        (JournalKind::Copy, Some(target)) => trash::delete(target)
+            .map_err(|e| format!("Failed to move {} to the trash: {}", target, e)),
+        (JournalKind::Move, Some(target)) => move_to(
+            app,
+            operation,
+            operation_id,
+            target,
+            &entry.source,
+            TransferKind::Move,
+        ),
+        (JournalKind::Rename, Some(target)) => rename_to(target, &entry.source),
+        (JournalKind::Trash, _) => restore_from_trash(Path::new(&entry.source)),
+        _ => Err("This operation cannot be undone".to_string()),
+    }
+}
+
+fn redo_entry(
+    app: &AppHandle,
+    operation: &Arc<OperationHandle>,
+    operation_id: &str,
+    kind: JournalKind,
+    entry: &JournalEntry,
+) -> Result<(), String> {
+    match (kind, &entry.target) {
+        (JournalKind::Copy, Some(target)) => move_to(
+            app,
+            operation,
+            operation_id,
+            &entry.source,
+            target,
+            TransferKind::Copy,
+        ),
+        (JournalKind::Move, Some(target)) => move_to(
+            app,
+            operation,
+            operation_id,
+            &entry.source,
+            target,
+            TransferKind::Move,
+        ),
+        (JournalKind::Rename, Some(target)) => rename_to(&entry.source, target),
+        (JournalKind::Trash, _) => trash::delete(&entry.source)
+            .map_err(|e| format!("Failed to move {} to the trash: {}", entry.source, e)),
+        _ => Err("This operation cannot be redone".to_string()),
+    }
+}
+
+/// Copy or move `from` to `to`.
+fn move_to(
+    app: &AppHandle,
+    operation: &Arc<OperationHandle>,
+    operation_id: &str,
+    from: &str,
+    to: &str,
+    kind: TransferKind,
+) -> Result<(), String> {
+    transfer_item(
+        app,
+        operation.clone(),
+        operation_id.to_string(),
+        Path::new(from),
+        Path::new(to),
+        kind,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Rename `from` to the name of `to`, which is in the same folder.
+fn rename_to(from: &str, to: &str) -> Result<(), String> {
+    let name = Path::new(to)
+        .file_name()
+        .ok_or_else(|| format!("Not a file name: {}", to))?;
+    rename_in_place(Path::new(from), &name.to_string_lossy())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Put the item most recently trashed from `path` back.
+#[cfg(any(windows, all(unix, not(target_os = "macos"))))]
+fn restore_from_trash(path: &Path) -> Result<(), String> {
+    let item = trash::os_limited::list()
+        .map_err(|e| format!("Failed to read the trash: {}", e))?
+        .into_iter()
+        .filter(|item| item.original_path() == path)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| format!("{} is no longer in the trash", path.display()))?;
+    trash::os_limited::restore_all([item])
+        .map_err(|e| format!("Failed to restore {}: {}", path.display(), e))
+}
+
+#[cfg(target_os = "macos")]
+fn restore_from_trash(_path: &Path) -> Result<(), String> {
+    Err("Restoring from the trash is not supported on macOS".to_string())
+}
@@ -0,0 +1,110 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, State};
+use walkdir::WalkDir;
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Event name used to stream the current top-N list while scanning.
+pub const LARGEST_UPDATE_EVENT: &str = "largest://update";
+
+/// Minimum delay between two intermediate updates.
+const UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Default number of files returned when no limit is given.
+const DEFAULT_LIMIT: usize = 100;
+
+/// A file in the largest-files report.
+#[derive(Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LargeFile {
+    // Field order matters: the derived ordering compares by size first.
+    pub size: u64,
+    pub path: String,
+    /// Seconds since the Unix epoch, if the platform reports it.
+    pub modified: Option<u64>,
+}
+
+/// Find the `limit` largest files below `root`, ignoring files under `min_size`.
+///
+/// The current top list is emitted on `largest://update` while walking, and
+/// the final list is returned sorted from largest to smallest.
+#[command]
+pub async fn find_largest_files(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    root: String,
+    limit: Option<usize>,
+    min_size: Option<u64>,
+) -> Result<Vec<LargeFile>, ExplorerError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let min_size = min_size.unwrap_or(0);
+    tasks
+        .run_blocking("largest_files", move || {
+            Ok(collect_largest(&app, &root, limit, min_size)?)
+        })
+        .await
+}
+
+fn collect_largest(
+    app: &AppHandle,
+    root: &str,
+    limit: usize,
+    min_size: u64,
+) -> Result<Vec<LargeFile>, String> {
+    if !Path::new(root).is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Min-heap holding the current top `limit` files; the root is the smallest.
+    let mut heap: BinaryHeap<Reverse<LargeFile>> = BinaryHeap::with_capacity(limit + 1);
+    let mut last_emit = Instant::now();
+
+    for entry in WalkDir::new(root).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let meta = match entry.metadata() {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        let size = meta.len();
+        if size < min_size {
+            continue;
+        }
+        if heap.len() == limit && heap.peek().is_some_and(|smallest| smallest.0.size >= size) {
+            continue;
+        }
+
+        heap.push(Reverse(LargeFile {
+            size,
+            path: entry.path().to_string_lossy().to_string(),
+            modified: to_unix_secs(meta.modified()),
+        }));
+        if heap.len() > limit {
+            heap.pop();
+        }
+
+        if last_emit.elapsed() >= UPDATE_INTERVAL {
+            let _ = app.emit(LARGEST_UPDATE_EVENT, sorted(&heap));
+            last_emit = Instant::now();
+        }
+    }
+
+    Ok(sorted(&heap))
+}
+
+/// Copy the heap into a list ordered from largest to smallest.
+fn sorted(heap: &BinaryHeap<Reverse<LargeFile>>) -> Vec<LargeFile> {
+    let mut files: Vec<LargeFile> = heap.iter().map(|entry| entry.0.clone()).collect();
+    files.sort_by(|a, b| b.cmp(a));
+    files
+}
@@ -1,14 +1,97 @@
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
+// Bring in the commands module.
+mod archive;
+mod autocomplete;
+mod batch_rename;
+mod benchmark;
+mod clipboard;
+mod commands;
+mod compare;
+mod diff;
+mod disk_images;
+mod drives;
+pub mod duplicates;
+mod elevation;
+mod error;
+pub mod exclusions;
+mod export;
+mod favorites;
+mod file_ops;
+mod fuzzy;
+mod hashing;
+mod history;
+mod http_api;
+mod icons;
+mod index;
+mod journal;
+mod largest;
+mod long_paths;
+mod metadata;
+mod native_drag;
+mod open_with;
+pub mod operations;
+mod organize;
+mod partitions;
+mod plugins;
+mod preview;
+mod quota;
+mod remote;
+mod saved_searches;
+pub mod scanner;
+mod scheduler;
+mod search;
+mod session;
+mod settings;
+mod shares;
+mod shred;
+mod similar_images;
+mod snapshots;
+mod streams;
+mod tags;
+mod tasks;
+mod terminal;
+mod thumbnails;
+mod watcher;
+mod windows;
+
+use tauri::Manager;
 
+// The macro defined in `commands.rs` is exported to the crate root,
+// so you can call it directly.
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet])
+        .plugin(tauri_plugin_dialog::init())
+        .manage(tasks::TaskRegistry::default())
+        .manage(file_ops::PendingDeletes::default())
+        .manage(file_ops::PendingConflicts::default())
+        .manage(operations::OperationRegistry::default())
+        .manage(watcher::Watchers::default())
+        .manage(remote::RemoteConnections::default())
+        .manage(thumbnails::queue::ThumbnailQueue::default())
+        .manage(streams::ReadStreams::default())
+        .manage(http_api::ApiServer::default())
+        .manage(windows::WindowStates::default())
+        .setup(|app| {
+            app.manage(settings::SettingsStore::init(app.handle())?);
+            app.manage(index::FileIndex::init(app.handle())?);
+            app.manage(journal::Journal::init(app.handle())?);
+            app.manage(favorites::Favorites::init(app.handle())?);
+            app.manage(thumbnails::protocol::ThumbnailCache::init(app.handle())?);
+            app.manage(terminal::TerminalSettings::init(app.handle())?);
+            app.manage(plugins::Plugins::init(app.handle())?);
+            app.manage(session::SessionStore::init(app.handle())?);
+            drives::spawn_drive_monitor(app.handle().clone());
+            scheduler::spawn_scheduler(app.handle().clone());
+            Ok(())
+        })
+        .register_asynchronous_uri_scheme_protocol(
+            thumbnails::protocol::THUMB_SCHEME,
+            |ctx, request, responder| {
+                thumbnails::protocol::handle_request(ctx.app_handle().clone(), request, responder)
+            },
+        )
+        .on_window_event(windows::on_window_event)
+        .invoke_handler(register_commands!())
         .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .expect("error while running Tauri application");
 }
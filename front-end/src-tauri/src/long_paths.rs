@@ -0,0 +1,63 @@
+//! Windows extended-length (`\\?\`) paths.
+//!
+//! Win32 file APIs reject paths longer than 260 characters unless they carry
+//! the `\\?\` prefix. The standard library adds it on its own for `std::fs`
+//! calls; `to_extended` does the same for the Win32 calls and crates that
+//! take paths directly, and `to_display` strips it again before a path is
+//! reported to the frontend. The app manifest also opts into long paths for
+//! shell APIs on systems where they are enabled. Both functions leave paths
+//! unchanged on other platforms.
+
+use std::path::{Path, PathBuf};
+
+#[cfg(windows)]
+const VERBATIM_PREFIX: &str = r"\\?\";
+
+#[cfg(windows)]
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// `path` as an absolute `\\?\` path, or `\\?\UNC\` for network shares.
+///
+/// Extended paths are passed to the file system as is, so `.` and `..`
+/// components and forward slashes are resolved first.
+#[cfg(windows)]
+pub fn to_extended(path: &Path) -> PathBuf {
+    let raw = path.to_string_lossy();
+    // Already verbatim, or a device path such as `\\.\C:`.
+    if raw.starts_with(VERBATIM_PREFIX) || raw.starts_with(r"\\.\") {
+        return path.to_path_buf();
+    }
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let absolute = absolute.to_string_lossy();
+    match absolute.strip_prefix(r"\\") {
+        Some(share) => PathBuf::from(format!("{}{}", VERBATIM_UNC_PREFIX, share)),
+        None => PathBuf::from(format!("{}{}", VERBATIM_PREFIX, absolute)),
+    }
+}
+
+/// `path` as an absolute `\\?\` path, or `\\?\UNC\` for network shares.
+#[cfg(not(windows))]
+pub fn to_extended(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// `path` without a `\\?\` or `\\?\UNC\` prefix, as users know it.
+#[cfg(windows)]
+pub fn to_display(path: &Path) -> String {
+    let raw = path.to_string_lossy();
+    if let Some(share) = raw.strip_prefix(VERBATIM_UNC_PREFIX) {
+        format!(r"\\{}", share)
+    } else if let Some(local) = raw.strip_prefix(VERBATIM_PREFIX) {
+        local.to_string()
+    } else {
+        raw.to_string()
+    }
+}
+
+/// `path` without a `\\?\` or `\\?\UNC\` prefix, as users know it.
+#[cfg(not(windows))]
+pub fn to_display(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
@@ -0,0 +1,532 @@
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::process::Command;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use exif::{Exif, In, Tag, Value};
+use lofty::prelude::{Accessor, AudioFile, TaggedFileExt};
+use lofty::probe::Probe;
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+use crate::commands::{allocated_size, to_unix_secs};
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Name of the ffprobe executable used to inspect video files.
+const FFPROBE_BIN: &str = "ffprobe";
+
+/// Detailed information about a single file, backing the properties panel.
+#[derive(Serialize)]
+pub struct FileDetails {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    /// Bytes the file occupies on disk: less than `size` for sparse and
+    /// compressed files, rounded up to whole blocks otherwise.
+    pub allocated_size: u64,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    /// Where the symlink points, when `path` is a link.
+    pub symlink_target: Option<String>,
+    /// Seconds since the Unix epoch, if the platform reports it.
+    pub created: Option<u64>,
+    pub modified: Option<u64>,
+    pub accessed: Option<u64>,
+    pub readonly: bool,
+    /// Unix permission bits (e.g. `0o644`); `None` on Windows.
+    pub mode: Option<u32>,
+    /// `mode` as a four-digit octal string, e.g. `"0644"`.
+    pub mode_octal: Option<String>,
+    /// `mode` in `ls -l` notation, e.g. `"rw-r--r--"`.
+    pub mode_symbolic: Option<String>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Owning user name, resolved from `uid` on Unix.
+    pub owner: Option<String>,
+    /// Owning group name, resolved from `gid` on Unix.
+    pub group: Option<String>,
+    pub mime_type: String,
+}
+
+/// Gather detailed metadata for a file or folder.
+#[command]
+pub async fn get_file_info(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<FileDetails, ExplorerError> {
+    tasks
+        .run_blocking("file_info", move || read_file_details(Path::new(&path)))
+        .await
+}
+
+fn read_file_details(path: &Path) -> Result<FileDetails, ExplorerError> {
+    let link_meta =
+        fs::symlink_metadata(path).map_err(|e| ExplorerError::io("Failed to read metadata", e))?;
+    let is_symlink = link_meta.file_type().is_symlink();
+    let symlink_target = if is_symlink {
+        fs::read_link(path)
+            .ok()
+            .map(|target| target.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    // Describe what the link points to when it resolves.
+    let meta = if is_symlink {
+        fs::metadata(path).unwrap_or(link_meta)
+    } else {
+        link_meta
+    };
+
+    let mime_type = if meta.is_dir() {
+        "inode/directory".to_string()
+    } else {
+        detect_type(path)
+            .detected_mime
+            .unwrap_or_else(|| extension_mime(path))
+    };
+
+    let ownership = unix_ownership(&meta);
+    Ok(FileDetails {
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string()),
+        size: if meta.is_dir() { 0 } else { meta.len() },
+        allocated_size: if meta.is_dir() {
+            0
+        } else {
+            allocated_size(path, &meta)
+        },
+        is_dir: meta.is_dir(),
+        is_symlink,
+        symlink_target,
+        created: to_unix_secs(meta.created()),
+        modified: to_unix_secs(meta.modified()),
+        accessed: to_unix_secs(meta.accessed()),
+        readonly: meta.permissions().readonly(),
+        mode: ownership.mode,
+        mode_octal: ownership.mode.map(|mode| format!("{:04o}", mode)),
+        mode_symbolic: ownership.mode.map(symbolic_mode),
+        uid: ownership.uid,
+        gid: ownership.gid,
+        owner: ownership.owner,
+        group: ownership.group,
+        mime_type,
+    })
+}
+
+/// Render permission bits the way `ls -l` does, including setuid, setgid
+/// and sticky bits.
+fn symbolic_mode(mode: u32) -> String {
+    let special = [(0o4000, 's', 'S'), (0o2000, 's', 'S'), (0o1000, 't', 'T')];
+    let mut out = String::with_capacity(9);
+    for (i, (special_bit, set, unset)) in special.into_iter().enumerate() {
+        let bits = mode >> (6 - 3 * i);
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(match (bits & 0o1 != 0, mode & special_bit != 0) {
+            (true, true) => set,
+            (false, true) => unset,
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+/// Unix permission and ownership fields of `FileDetails`.
+#[derive(Default)]
+struct Ownership {
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    owner: Option<String>,
+    group: Option<String>,
+}
+
+#[cfg(unix)]
+fn unix_ownership(meta: &fs::Metadata) -> Ownership {
+    use std::os::unix::fs::MetadataExt;
+
+    Ownership {
+        mode: Some(meta.mode() & 0o7777),
+        uid: Some(meta.uid()),
+        gid: Some(meta.gid()),
+        owner: uzers::get_user_by_uid(meta.uid())
+            .map(|user| user.name().to_string_lossy().to_string()),
+        group: uzers::get_group_by_gid(meta.gid())
+            .map(|group| group.name().to_string_lossy().to_string()),
+    }
+}
+
+#[cfg(not(unix))]
+fn unix_ownership(_meta: &fs::Metadata) -> Ownership {
+    Ownership::default()
+}
+
+/// Photo details read from EXIF tags.
+#[derive(Serialize, Default)]
+pub struct ImageMetadata {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens: Option<String>,
+    pub iso: Option<u32>,
+    /// Exposure time as displayed by cameras, e.g. `1/250`.
+    pub exposure_time: Option<String>,
+    pub f_number: Option<f64>,
+    /// Focal length in millimetres.
+    pub focal_length: Option<f64>,
+    /// Decimal degrees, negative for the southern hemisphere.
+    pub gps_latitude: Option<f64>,
+    /// Decimal degrees, negative for the western hemisphere.
+    pub gps_longitude: Option<f64>,
+    /// Capture date as `YYYY-MM-DDTHH:MM:SS` in camera local time.
+    pub date_taken: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Read EXIF metadata from a photo.
+#[command]
+pub async fn get_image_metadata(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<ImageMetadata, ExplorerError> {
+    tasks
+        .run_blocking("image_metadata", move || {
+            let exif = read_exif(Path::new(&path))?;
+            Ok(image_metadata_from_exif(&exif))
+        })
+        .await
+}
+
+/// Parse the EXIF block of an image file.
+pub fn read_exif(path: &Path) -> Result<Exif, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
+    exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .map_err(|e| format!("Failed to read EXIF data: {}", e))
+}
+
+/// Capture date from EXIF as `YYYY-MM-DDTHH:MM:SS`, preferring the original
+/// capture time over the digitized and modification times.
+pub fn exif_date_taken(exif: &Exif) -> Option<String> {
+    [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+        .iter()
+        .find_map(|tag| match &exif.get_field(*tag, In::PRIMARY)?.value {
+            Value::Ascii(parts) => {
+                let dt = exif::DateTime::from_ascii(parts.first()?).ok()?;
+                Some(format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                    dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+                ))
+            }
+            _ => None,
+        })
+}
+
+fn image_metadata_from_exif(exif: &Exif) -> ImageMetadata {
+    ImageMetadata {
+        camera_make: exif_string(exif, Tag::Make),
+        camera_model: exif_string(exif, Tag::Model),
+        lens: exif_string(exif, Tag::LensModel),
+        iso: exif_uint(exif, Tag::PhotographicSensitivity),
+        exposure_time: exif
+            .get_field(Tag::ExposureTime, In::PRIMARY)
+            .map(|field| field.display_value().to_string()),
+        f_number: exif_rational(exif, Tag::FNumber),
+        focal_length: exif_rational(exif, Tag::FocalLength),
+        gps_latitude: exif_gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S"),
+        gps_longitude: exif_gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W"),
+        date_taken: exif_date_taken(exif),
+        width: exif_uint(exif, Tag::PixelXDimension),
+        height: exif_uint(exif, Tag::PixelYDimension),
+    }
+}
+
+fn exif_string(exif: &Exif, tag: Tag) -> Option<String> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(parts) => {
+            let text = String::from_utf8_lossy(parts.first()?).trim().to_string();
+            Some(text).filter(|text| !text.is_empty())
+        }
+        _ => None,
+    }
+}
+
+fn exif_uint(exif: &Exif, tag: Tag) -> Option<u32> {
+    exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+fn exif_rational(exif: &Exif, tag: Tag) -> Option<f64> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(values) => values.first().map(|r| r.to_f64()),
+        _ => None,
+    }
+}
+
+/// Convert a degrees/minutes/seconds GPS triple into signed decimal degrees.
+fn exif_gps_coordinate(exif: &Exif, tag: Tag, ref_tag: Tag, negative_ref: &str) -> Option<f64> {
+    let degrees = match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(values) if values.len() >= 3 => {
+            values[0].to_f64() + values[1].to_f64() / 60.0 + values[2].to_f64() / 3600.0
+        }
+        _ => return None,
+    };
+    let negative = exif_string(exif, ref_tag).is_some_and(|r| r == negative_ref);
+    Some(if negative { -degrees } else { degrees })
+}
+
+/// Music details read from ID3, FLAC, Vorbis and MP4 tags.
+#[derive(Serialize)]
+pub struct AudioMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub genre: Option<String>,
+    /// Duration in seconds.
+    pub duration: f64,
+    /// Audio bitrate in kbps.
+    pub bitrate: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+    /// Embedded cover art as a data URL.
+    pub cover_art: Option<String>,
+}
+
+/// Read tags and stream properties from an audio file.
+#[command]
+pub async fn get_audio_metadata(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<AudioMetadata, ExplorerError> {
+    tasks
+        .run_blocking("audio_metadata", move || {
+            Ok(read_audio_metadata(Path::new(&path))?)
+        })
+        .await
+}
+
+fn read_audio_metadata(path: &Path) -> Result<AudioMetadata, String> {
+    let tagged = Probe::open(path)
+        .map_err(|e| format!("Failed to open audio file: {}", e))?
+        .read()
+        .map_err(|e| format!("Failed to read audio tags: {}", e))?;
+    let properties = tagged.properties();
+    let tag = tagged.primary_tag().or_else(|| tagged.first_tag());
+
+    let cover_art = tag.and_then(|tag| tag.pictures().first()).map(|picture| {
+        let mime = picture
+            .mime_type()
+            .map(|mime| mime.as_str())
+            .unwrap_or("image/jpeg");
+        format!("data:{};base64,{}", mime, STANDARD.encode(picture.data()))
+    });
+
+    Ok(AudioMetadata {
+        title: tag.and_then(|t| t.title()).map(|v| v.to_string()),
+        artist: tag.and_then(|t| t.artist()).map(|v| v.to_string()),
+        album: tag.and_then(|t| t.album()).map(|v| v.to_string()),
+        year: tag.and_then(|t| t.year()),
+        track: tag.and_then(|t| t.track()),
+        genre: tag.and_then(|t| t.genre()).map(|v| v.to_string()),
+        duration: properties.duration().as_secs_f64(),
+        bitrate: properties.audio_bitrate(),
+        sample_rate: properties.sample_rate(),
+        channels: properties.channels(),
+        cover_art,
+    })
+}
+
+/// Stream details of a video file.
+#[derive(Serialize)]
+pub struct VideoMetadata {
+    /// Duration in seconds.
+    pub duration: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub codec: Option<String>,
+    pub audio_codec: Option<String>,
+    /// Frames per second.
+    pub frame_rate: Option<f64>,
+    /// Overall bitrate in bits per second.
+    pub bitrate: Option<u64>,
+}
+
+/// Subset of `ffprobe -print_format json` output that we read.
+#[derive(Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+/// Read duration, resolution, codecs and bitrate from a video using ffprobe.
+#[command]
+pub async fn get_video_metadata(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<VideoMetadata, ExplorerError> {
+    tasks
+        .run_blocking("video_metadata", move || Ok(read_video_metadata(&path)?))
+        .await
+}
+
+fn read_video_metadata(path: &str) -> Result<VideoMetadata, String> {
+    let output = Command::new(FFPROBE_BIN)
+        .args([
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+        ])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to probe video: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let probe: ProbeOutput = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse ffprobe output: {}", e))?;
+    let stream_of = |kind: &str| {
+        probe
+            .streams
+            .iter()
+            .find(|s| s.codec_type.as_deref() == Some(kind))
+    };
+    let video = stream_of("video");
+    let audio = stream_of("audio");
+    let format = probe.format.as_ref();
+
+    Ok(VideoMetadata {
+        duration: format.and_then(|f| f.duration.as_deref()?.parse().ok()),
+        width: video.and_then(|v| v.width),
+        height: video.and_then(|v| v.height),
+        codec: video.and_then(|v| v.codec_name.clone()),
+        audio_codec: audio.and_then(|a| a.codec_name.clone()),
+        frame_rate: video.and_then(|v| {
+            parse_frame_rate(v.avg_frame_rate.as_deref())
+                .or_else(|| parse_frame_rate(v.r_frame_rate.as_deref()))
+        }),
+        bitrate: format.and_then(|f| f.bit_rate.as_deref()?.parse().ok()),
+    })
+}
+
+/// Parse an ffprobe rational frame rate such as `30000/1001`.
+fn parse_frame_rate(rate: Option<&str>) -> Option<f64> {
+    let (num, den) = rate?.split_once('/')?;
+    let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+    (den > 0.0 && num > 0.0).then(|| num / den)
+}
+
+/// Result of identifying a file by its content.
+#[derive(Serialize)]
+pub struct FileTypeInfo {
+    pub path: String,
+    /// MIME type identified from the file's magic bytes.
+    pub detected_mime: Option<String>,
+    /// Canonical extension for the detected type, e.g. `png`.
+    pub detected_extension: Option<String>,
+    /// MIME type implied by the file name's extension.
+    pub extension_mime: String,
+    /// True when the content does not match the extension.
+    pub mismatch: bool,
+}
+
+/// Identify a file's type from its content rather than its extension.
+#[command]
+pub async fn detect_file_type(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<FileTypeInfo, ExplorerError> {
+    tasks
+        .run_blocking("detect_file_type", move || {
+            if !Path::new(&path).is_file() {
+                return Err(ExplorerError::InvalidInput(format!("Not a file: {}", path)));
+            }
+            Ok(detect_type(Path::new(&path)))
+        })
+        .await
+}
+
+/// Identify every file in a directory, flagging those whose extension lies.
+#[command]
+pub async fn detect_file_types(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<Vec<FileTypeInfo>, ExplorerError> {
+    tasks
+        .run_blocking("detect_file_types", move || {
+            let entries =
+                fs::read_dir(&path).map_err(|e| format!("Failed to read directory: {}", e))?;
+            Ok(entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .map(|path| detect_type(&path))
+                .collect())
+        })
+        .await
+}
+
+/// MIME type guessed from the file name alone.
+fn extension_mime(path: &Path) -> String {
+    mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .essence_str()
+        .to_string()
+}
+
+fn detect_type(path: &Path) -> FileTypeInfo {
+    let detected = infer::get_from_path(path).ok().flatten();
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+
+    // Only flag files whose extension implies a different type; many formats
+    // have several valid extensions (jpg/jpeg, tif/tiff), so compare MIME types.
+    let mismatch = match (&detected, &extension) {
+        (Some(kind), Some(ext)) => {
+            ext != kind.extension()
+                && !mime_guess::from_ext(ext)
+                    .iter()
+                    .any(|mime| mime.essence_str() == kind.mime_type())
+        }
+        _ => false,
+    };
+
+    FileTypeInfo {
+        path: path.to_string_lossy().to_string(),
+        detected_mime: detected.map(|kind| kind.mime_type().to_string()),
+        detected_extension: detected.map(|kind| kind.extension().to_string()),
+        extension_mime: extension_mime(path),
+        mismatch,
+    }
+}
@@ -0,0 +1,105 @@
+//! Dragging files out of the app into other applications.
+//!
+//! The webview's own drag and drop only carries data within the page, so a
+//! native drag session with the files is started instead, which mail
+//! clients, editors and other file managers accept as a file drop.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use drag::{DragItem, DragResult, Image, Options};
+use image::{DynamicImage, ImageOutputFormat};
+use tauri::{command, State, Window};
+
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Size in pixels of the icon shown under the cursor.
+const DRAG_ICON_SIZE: u32 = 64;
+
+/// Start a drag of `paths` out of `window`, as if the user had started
+/// dragging them in a file manager. Call it while the mouse button is down.
+///
+/// The files are offered to be copied. Resolves once the drag ends, with
+/// whether the files were dropped somewhere.
+#[command]
+pub async fn start_native_drag(
+    window: Window,
+    tasks: State<'_, TaskRegistry>,
+    paths: Vec<String>,
+) -> Result<bool, ExplorerError> {
+    if paths.is_empty() {
+        return Err(ExplorerError::InvalidInput("No files given".to_string()));
+    }
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let path = PathBuf::from(path);
+        if std::fs::symlink_metadata(&path).is_err() {
+            return Err(ExplorerError::NotFound(format!(
+                "Path does not exist: {}",
+                path.display()
+            )));
+        }
+        files.push(
+            std::path::absolute(&path)
+                .map_err(|e| ExplorerError::io("Failed to resolve path", e))?,
+        );
+    }
+
+    let icon = drag_icon(&files[0]);
+    let (sender, receiver) = mpsc::channel::<Result<bool, String>>();
+    let target = window.clone();
+    // Drag sessions belong to the UI thread.
+    window
+        .run_on_main_thread(move || {
+            let finished = sender.clone();
+            let on_drop = move |result, _| {
+                let _ = finished.send(Ok(matches!(result, DragResult::Dropped)));
+            };
+            #[cfg(target_os = "linux")]
+            let started = target
+                .gtk_window()
+                .map_err(|e| e.to_string())
+                .and_then(|gtk_window| {
+                    drag::start_drag(
+                        &gtk_window,
+                        DragItem::Files(files),
+                        icon,
+                        on_drop,
+                        Options::default(),
+                    )
+                    .map_err(|e| e.to_string())
+                });
+            #[cfg(not(target_os = "linux"))]
+            let started = drag::start_drag(
+                &target,
+                DragItem::Files(files),
+                icon,
+                on_drop,
+                Options::default(),
+            )
+            .map_err(|e| e.to_string());
+            if let Err(e) = started {
+                let _ = sender.send(Err(format!("Failed to start dragging: {}", e)));
+            }
+        })
+        .map_err(|e| ExplorerError::Failed(format!("Failed to start dragging: {}", e)))?;
+
+    tasks
+        .run_blocking("native_drag", move || {
+            // Without a result the session was dropped unfinished.
+            Ok(receiver.recv().unwrap_or(Ok(false))?)
+        })
+        .await
+}
+
+/// The system icon of `path` as PNG bytes, or a blank square if it has none.
+fn drag_icon(path: &Path) -> Image {
+    let icon = crate::icons::load_icon(path, DRAG_ICON_SIZE)
+        .map(|icon| icon.thumbnail(DRAG_ICON_SIZE, DRAG_ICON_SIZE))
+        .unwrap_or_else(|_| DynamicImage::new_rgba8(DRAG_ICON_SIZE, DRAG_ICON_SIZE));
+    let mut png = Cursor::new(Vec::new());
+    let _ = icon.write_to(&mut png, ImageOutputFormat::Png);
+    Image::Raw(png.into_inner())
+}
@@ -0,0 +1,469 @@
+//! Applications that can open a file, for an "Open with" menu.
+
+use std::path::Path;
+
+use image::DynamicImage;
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::commands::natural_cmp;
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+use crate::thumbnails::png_data_url;
+
+/// Size in pixels of the application icons.
+const APP_ICON_SIZE: u32 = 32;
+
+/// An application that can open a file.
+#[derive(Serialize)]
+pub struct OpenWithApp {
+    /// Pass to `open_with`. The handler name on Windows, the application
+    /// bundle path on macOS and the desktop entry id on Linux.
+    pub id: String,
+    pub name: String,
+    /// PNG data URL, if the application has an icon.
+    pub icon: Option<String>,
+    /// Whether the system opens the file with this application by default.
+    pub is_default: bool,
+}
+
+/// List the applications registered to open `path`, the default first and
+/// the rest by name.
+///
+/// Applications come from the shell's recommended handlers for the file's
+/// extension on Windows, from Launch Services on macOS, and from the desktop
+/// entries declaring the file's MIME type on Linux.
+#[command]
+pub async fn get_open_with_apps(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<Vec<OpenWithApp>, ExplorerError> {
+    tasks
+        .run_blocking("open_with_apps", move || {
+            let mut apps = list_apps(Path::new(&path))?;
+            apps.sort_by(|a, b| {
+                b.is_default
+                    .cmp(&a.is_default)
+                    .then_with(|| natural_cmp(&a.name, &b.name))
+            });
+            Ok(apps)
+        })
+        .await
+}
+
+/// Open `path` with an application returned by `get_open_with_apps`.
+#[command]
+pub async fn open_with(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    app_id: String,
+) -> Result<(), ExplorerError> {
+    if !Path::new(&path).exists() {
+        return Err(ExplorerError::NotFound(format!(
+            "Path does not exist: {}",
+            path
+        )));
+    }
+    tasks
+        .run_blocking("open_with", move || launch(Path::new(&path), &app_id))
+        .await
+}
+
+/// A loaded application icon as a data URL.
+fn icon_url(icon: Result<DynamicImage, String>) -> Option<String> {
+    png_data_url(&icon.ok()?, APP_ICON_SIZE)
+        .ok()
+        .map(|icon| icon.data_url)
+}
+
+fn unknown_app(app_id: &str) -> ExplorerError {
+    ExplorerError::NotFound(format!("No such application: {}", app_id))
+}
+
+/// The file's extension with its dot, as the shell association APIs take it.
+#[cfg(windows)]
+fn association(path: &Path) -> Option<Vec<u16>> {
+    let extension = path.extension().filter(|_| !path.is_dir())?;
+    Some(
+        format!(".{}", extension.to_string_lossy())
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect(),
+    )
+}
+
+/// Handlers registered for the file's extension, with their names.
+#[cfg(windows)]
+fn handlers(
+    path: &Path,
+    filter: windows::Win32::UI::Shell::ASSOC_FILTER,
+) -> Result<Vec<(String, windows::Win32::UI::Shell::IAssocHandler)>, String> {
+    use windows::core::PCWSTR;
+    use windows::Win32::System::Com::{CoInitializeEx, COINIT_APARTMENTTHREADED};
+    use windows::Win32::UI::Shell::SHAssocEnumHandlers;
+
+    let Some(extension) = association(path) else {
+        return Ok(Vec::new());
+    };
+    // Handlers are COM objects. A thread that is already initialised keeps
+    // its apartment.
+    let _ = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+    let handlers = unsafe { SHAssocEnumHandlers(PCWSTR(extension.as_ptr()), filter) }
+        .map_err(|e| format!("Failed to list applications: {}", e))?;
+    let mut found = Vec::new();
+    loop {
+        let mut batch = [None];
+        let mut fetched = 0;
+        if unsafe { handlers.Next(&mut batch, Some(&mut fetched)) }.is_err() || fetched == 0 {
+            break;
+        }
+        if let Some(handler) = batch[0].take() {
+            if let Ok(name) = unsafe { handler.GetName() } {
+                found.push((take_string(name), handler));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Copy a string allocated by the shell and free it.
+#[cfg(windows)]
+fn take_string(text: windows::core::PWSTR) -> String {
+    use windows::Win32::System::Com::CoTaskMemFree;
+
+    let copy = unsafe { text.to_string() }.unwrap_or_default();
+    unsafe { CoTaskMemFree(Some(text.0 as *const _)) };
+    copy
+}
+
+#[cfg(windows)]
+fn list_apps(path: &Path) -> Result<Vec<OpenWithApp>, String> {
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::UI::Shell::{
+        AssocQueryStringW, ASSOCF_INIT_IGNOREUNKNOWN, ASSOCSTR_EXECUTABLE, ASSOC_FILTER_RECOMMENDED,
+    };
+
+    // The default handler's executable, to compare with handler names,
+    // which are executable paths for desktop applications.
+    let default = association(path).and_then(|extension| {
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        unsafe {
+            AssocQueryStringW(
+                ASSOCF_INIT_IGNOREUNKNOWN,
+                ASSOCSTR_EXECUTABLE,
+                PCWSTR(extension.as_ptr()),
+                PCWSTR::null(),
+                PWSTR(buffer.as_mut_ptr()),
+                &mut len,
+            )
+        }
+        .ok()
+        .ok()?;
+        let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..end]))
+    });
+
+    Ok(handlers(path, ASSOC_FILTER_RECOMMENDED)?
+        .into_iter()
+        .map(|(id, handler)| {
+            let name = unsafe { handler.GetUIName() }
+                .map(take_string)
+                .unwrap_or_else(|_| id.clone());
+            let mut icon_path = windows::core::PWSTR::null();
+            let mut index = 0;
+            let icon = unsafe { handler.GetIconLocation(&mut icon_path, &mut index) }
+                .ok()
+                .and_then(|_| {
+                    let icon_path = take_string(icon_path);
+                    icon_url(crate::icons::load_icon(
+                        Path::new(&icon_path),
+                        APP_ICON_SIZE,
+                    ))
+                });
+            OpenWithApp {
+                is_default: default
+                    .as_ref()
+                    .is_some_and(|default| default.eq_ignore_ascii_case(&id)),
+                id,
+                name,
+                icon,
+            }
+        })
+        .collect())
+}
+
+#[cfg(windows)]
+fn launch(path: &Path, app_id: &str) -> Result<(), ExplorerError> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::System::Com::IDataObject;
+    use windows::Win32::UI::Shell::{
+        BHID_DataObject, IShellItem, SHCreateItemFromParsingName, ASSOC_FILTER_NONE,
+    };
+
+    let (_, handler) = handlers(path, ASSOC_FILTER_NONE)?
+        .into_iter()
+        .find(|(id, _)| id == app_id)
+        .ok_or_else(|| unknown_app(app_id))?;
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let failed = |e: windows::core::Error| {
+        ExplorerError::Failed(format!("Failed to open {}: {}", path.display(), e))
+    };
+    unsafe {
+        let item: IShellItem =
+            SHCreateItemFromParsingName(PCWSTR(wide.as_ptr()), None).map_err(failed)?;
+        let data: IDataObject = item.BindToHandler(None, &BHID_DataObject).map_err(failed)?;
+        handler.Invoke(&data).map_err(failed)
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn list_apps(path: &Path) -> Result<Vec<OpenWithApp>, String> {
+    use objc2_app_kit::NSWorkspace;
+    use objc2_foundation::{NSString, NSURL};
+
+    let (apps, default) = unsafe {
+        let url = NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy()));
+        let workspace = NSWorkspace::sharedWorkspace();
+        let default = workspace
+            .URLForApplicationToOpenURL(&url)
+            .and_then(|app| app.path())
+            .map(|app| app.to_string());
+        let apps: Vec<String> = workspace
+            .URLsForApplicationsToOpenURL(&url)
+            .iter()
+            .filter_map(|app| app.path())
+            .map(|app| app.to_string())
+            .collect();
+        (apps, default)
+    };
+    Ok(apps
+        .into_iter()
+        .map(|app| OpenWithApp {
+            name: Path::new(&app)
+                .file_stem()
+                .map_or_else(|| app.clone(), |name| name.to_string_lossy().to_string()),
+            icon: icon_url(crate::icons::load_icon(Path::new(&app), APP_ICON_SIZE)),
+            is_default: default.as_ref() == Some(&app),
+            id: app,
+        })
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+fn launch(path: &Path, app_id: &str) -> Result<(), ExplorerError> {
+    if !list_apps(path)?.iter().any(|app| app.id == app_id) {
+        return Err(unknown_app(app_id));
+    }
+    let status = std::process::Command::new("open")
+        .arg("-a")
+        .arg(app_id)
+        .arg(path)
+        .status()
+        .map_err(|e| ExplorerError::io("Failed to run open", e))?;
+    if !status.success() {
+        return Err(ExplorerError::Failed(format!(
+            "Failed to open {} with {}",
+            path.display(),
+            app_id
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn list_apps(path: &Path) -> Result<Vec<OpenWithApp>, String> {
+    let mime = desktop::mime_type(path);
+    let default = desktop::default_app(&mime);
+    Ok(desktop::entries()
+        .into_iter()
+        .filter(|entry| entry.opens(&mime))
+        .map(|entry| OpenWithApp {
+            icon: entry
+                .icon
+                .as_deref()
+                .and_then(|icon| icon_url(crate::icons::load_named_icon(icon, APP_ICON_SIZE))),
+            is_default: default.as_ref() == Some(&entry.id),
+            id: entry.id,
+            name: entry.name,
+        })
+        .collect())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn launch(path: &Path, app_id: &str) -> Result<(), ExplorerError> {
+    let mime = desktop::mime_type(path);
+    let entry = desktop::entries()
+        .into_iter()
+        .find(|entry| entry.id == app_id && entry.opens(&mime))
+        .ok_or_else(|| unknown_app(app_id))?;
+    // `gio launch` expands the entry's `Exec` line and returns once the
+    // application has started.
+    let status = std::process::Command::new("gio")
+        .arg("launch")
+        .arg(&entry.path)
+        .arg(path)
+        .status()
+        .map_err(|e| ExplorerError::io("Failed to run gio", e))?;
+    if !status.success() {
+        return Err(ExplorerError::Failed(format!(
+            "Failed to open {} with {}",
+            path.display(),
+            entry.name
+        )));
+    }
+    Ok(())
+}
+
+/// Desktop entries and MIME associations, per the freedesktop.org
+/// specifications.
+#[cfg(all(unix, not(target_os = "macos")))]
+mod desktop {
+    use std::collections::HashSet;
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    /// An installed application.
+    pub struct DesktopEntry {
+        /// File name of the entry, such as `org.gnome.gedit.desktop`.
+        pub id: String,
+        pub path: PathBuf,
+        pub name: String,
+        pub icon: Option<String>,
+        mime_types: Vec<String>,
+    }
+
+    impl DesktopEntry {
+        /// Whether the entry declares `mime`, exactly or as `type/*`.
+        pub fn opens(&self, mime: &str) -> bool {
+            let wildcard = mime.split_once('/').map(|(kind, _)| format!("{}/*", kind));
+            self.mime_types
+                .iter()
+                .any(|declared| declared == mime || Some(declared) == wildcard.as_ref())
+        }
+
+        fn parse(id: String, path: PathBuf) -> Option<Self> {
+            let text = fs::read_to_string(&path).ok()?;
+            let mut in_entry = false;
+            let (mut name, mut icon, mut mime_types) = (None, None, Vec::new());
+            let mut is_app = false;
+            for line in text.lines() {
+                let line = line.trim();
+                if line.starts_with('[') {
+                    in_entry = line == "[Desktop Entry]";
+                    continue;
+                }
+                let Some((key, value)) = line.split_once('=').filter(|_| in_entry) else {
+                    continue;
+                };
+                match key.trim() {
+                    "Name" => name = Some(value.trim().to_string()),
+                    "Icon" => icon = Some(value.trim().to_string()),
+                    "MimeType" => {
+                        mime_types = value
+                            .split(';')
+                            .filter(|mime| !mime.is_empty())
+                            .map(str::to_string)
+                            .collect()
+                    }
+                    "Type" => is_app = value.trim() == "Application",
+                    "Hidden" if value.trim() == "true" => return None,
+                    _ => {}
+                }
+            }
+            if !is_app {
+                return None;
+            }
+            Some(DesktopEntry {
+                id,
+                path,
+                name: name?,
+                icon,
+                mime_types,
+            })
+        }
+    }
+
+    /// Installed applications. Entries in the user's data folder hide
+    /// system entries with the same id.
+    pub fn entries() -> Vec<DesktopEntry> {
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+        for dir in application_dirs() {
+            let Ok(files) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let id = file.file_name().to_string_lossy().to_string();
+                if !id.ends_with(".desktop") || !seen.insert(id.clone()) {
+                    continue;
+                }
+                entries.extend(DesktopEntry::parse(id, file.path()));
+            }
+        }
+        entries
+    }
+
+    /// Folders holding desktop entries, most specific first.
+    fn application_dirs() -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        match env::var_os("XDG_DATA_HOME") {
+            Some(data_home) => dirs.push(PathBuf::from(data_home)),
+            None => dirs
+                .extend(env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))),
+        }
+        let data_dirs = env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        dirs.extend(data_dirs.split(':').map(PathBuf::from));
+        dirs.into_iter()
+            .map(|dir| dir.join("applications"))
+            .collect()
+    }
+
+    /// MIME type of the file, detected from its contents by `gio` where
+    /// available and guessed from its name otherwise.
+    pub fn mime_type(path: &Path) -> String {
+        if path.is_dir() {
+            return "inode/directory".to_string();
+        }
+        let detected = Command::new("gio")
+            .args(["info", "--attributes=standard::content-type"])
+            .arg(path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| {
+                // Prints `  standard::content-type: text/plain`.
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix("standard::content-type:"))
+                    .map(|mime| mime.trim().to_string())
+            });
+        detected.unwrap_or_else(|| {
+            mime_guess::from_path(path)
+                .first_or_octet_stream()
+                .essence_str()
+                .to_string()
+        })
+    }
+
+    /// Id of the entry that opens `mime` by default.
+    pub fn default_app(mime: &str) -> Option<String> {
+        let output = Command::new("xdg-mime")
+            .args(["query", "default", mime])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!id.is_empty()).then_some(id)
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+
+/// Description of `ExplorerError::Cancelled`, returned by operations that stop
+/// because of `cancel_operation`.
+pub const CANCELLED: &str = "Operation cancelled";
+
+/// Cancellation and pause controls shared between a running operation and the UI.
+#[derive(Default)]
+pub struct OperationHandle {
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+    lock: Mutex<()>,
+    wake: Condvar,
+}
+
+impl OperationHandle {
+    /// Block while the operation is paused, then report whether it may continue.
+    ///
+    /// Long-running loops call this between units of work.
+    pub fn checkpoint(&self) -> Result<(), ExplorerError> {
+        if self.paused.load(Ordering::Acquire) {
+            let mut guard = self.lock.lock().unwrap();
+            while self.paused.load(Ordering::Acquire) && !self.is_cancelled() {
+                guard = self.wake.wait(guard).unwrap();
+            }
+        }
+        if self.is_cancelled() {
+            Err(ExplorerError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn cancel(&self) {
+        let _guard = self.lock.lock().unwrap();
+        self.cancelled.store(true, Ordering::Release);
+        self.wake.notify_all();
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        let _guard = self.lock.lock().unwrap();
+        self.paused.store(paused, Ordering::Release);
+        self.wake.notify_all();
+    }
+}
+
+/// A registered operation as reported by `list_operations`.
+#[derive(Serialize)]
+pub struct OperationStatus {
+    pub id: String,
+    pub paused: bool,
+    pub cancelled: bool,
+}
+
+/// Managed state mapping operation ids to their controls.
+#[derive(Default)]
+pub struct OperationRegistry {
+    active: Mutex<HashMap<String, Arc<OperationHandle>>>,
+}
+
+impl OperationRegistry {
+    /// Register a new operation. When `id` is `None` a random id is assigned.
+    ///
+    /// The operation stays registered until the returned guard is dropped.
+    pub fn start(&self, id: Option<String>) -> Result<OperationGuard<'_>, String> {
+        let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let mut active = self.active.lock().unwrap();
+        if active.contains_key(&id) {
+            return Err(format!("Operation already running: {}", id));
+        }
+        let handle = Arc::new(OperationHandle::default());
+        active.insert(id.clone(), handle.clone());
+        Ok(OperationGuard {
+            registry: self,
+            id,
+            handle,
+        })
+    }
+
+    /// Whether an operation with this id is registered.
+    pub fn is_running(&self, id: &str) -> bool {
+        self.active.lock().unwrap().contains_key(id)
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<OperationHandle>, String> {
+        self.active
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("No running operation: {}", id))
+    }
+}
+
+/// Keeps an operation registered for as long as it runs.
+pub struct OperationGuard<'a> {
+    registry: &'a OperationRegistry,
+    id: String,
+    handle: Arc<OperationHandle>,
+}
+
+impl OperationGuard<'_> {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Shared handle to pass into the blocking worker.
+    pub fn handle(&self) -> Arc<OperationHandle> {
+        self.handle.clone()
+    }
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        self.registry.active.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Request cancellation of a running scan, copy/move or duplicate search.
+#[command]
+pub fn cancel_operation(
+    operations: State<'_, OperationRegistry>,
+    id: String,
+) -> Result<(), ExplorerError> {
+    operations.get(&id)?.cancel();
+    Ok(())
+}
+
+/// Pause a running operation at its next checkpoint.
+#[command]
+pub fn pause_operation(
+    operations: State<'_, OperationRegistry>,
+    id: String,
+) -> Result<(), ExplorerError> {
+    operations.get(&id)?.set_paused(true);
+    Ok(())
+}
+
+/// Resume a paused operation.
+#[command]
+pub fn resume_operation(
+    operations: State<'_, OperationRegistry>,
+    id: String,
+) -> Result<(), ExplorerError> {
+    operations.get(&id)?.set_paused(false);
+    Ok(())
+}
+
+/// List operations that are currently registered.
+#[command]
+pub fn list_operations(operations: State<'_, OperationRegistry>) -> Vec<OperationStatus> {
+    let active = operations.active.lock().unwrap();
+    let mut list: Vec<OperationStatus> = active
+        .iter()
+        .map(|(id, handle)| OperationStatus {
+            id: id.clone(),
+            paused: handle.is_paused(),
+            cancelled: handle.is_cancelled(),
+        })
+        .collect();
+    list.sort_by(|a, b| a.id.cmp(&b.id));
+    list
+}
@@ -0,0 +1,245 @@
+//! Sorting photos into dated folders, as after emptying a camera card.
+//!
+//! Each photo is dated from its EXIF capture time, or from its modification
+//! time when it has none, and copied or moved into a folder named after
+//! that date, `YYYY/MM` by default.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, State};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::error::ExplorerError;
+use crate::file_ops::{
+    transfer_into, validate_file_name, ConflictPolicy, FileOpReport, TransferKind,
+};
+use crate::journal::{self, JournalKind};
+use crate::metadata::{exif_date_taken, read_exif};
+use crate::operations::OperationRegistry;
+use crate::settings::SettingsStore;
+use crate::tasks::TaskRegistry;
+use crate::thumbnails::is_photo;
+
+/// Event name used to report photos dated while looking through the source.
+/// Copying or moving them is reported on `file-ops://progress`.
+pub const ORGANIZE_PROGRESS_EVENT: &str = "organize://progress";
+
+/// Folder template used when `organize_photos` is called without one.
+const DEFAULT_FOLDER_TEMPLATE: &str = "%Y/%m";
+
+/// Minimum delay between two progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Payload of the `organize://progress` event.
+#[derive(Serialize, Clone, Default)]
+pub struct OrganizeProgress {
+    pub operation_id: String,
+    pub current_file: String,
+    pub photos_found: u64,
+}
+
+/// Where a photo's date came from.
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum DateSource {
+    Exif,
+    /// The photo has no EXIF date, so its modification time was used.
+    Modified,
+}
+
+/// Where one photo goes.
+#[derive(Serialize)]
+pub struct PhotoPlacement {
+    pub source: String,
+    /// Folder the photo goes into.
+    pub folder: String,
+    /// `YYYY-MM-DDTHH:MM:SS`, in the camera's local time for EXIF dates.
+    pub date: String,
+    pub date_source: DateSource,
+}
+
+/// Result of `organize_photos`.
+#[derive(Serialize)]
+pub struct OrganizeReport {
+    pub operation_id: String,
+    /// Photos found, with their folder, in the order they were found.
+    /// Photos already in their folder are left out.
+    pub placements: Vec<PhotoPlacement>,
+    /// Outcome of copying or moving the photos; `None` for a dry run.
+    pub transfer: Option<FileOpReport>,
+}
+
+/// Copy or move the photos below `source` into folders under `destination`
+/// named after the date each was taken.
+///
+/// `template` is a strftime format for the folder, relative to
+/// `destination`, e.g. `%Y/%m` (the default) or `%Y/%Y-%m-%d`. Photos are
+/// dated from EXIF, or from their modification time when it has no date.
+/// `mode` is `copy` (the default) or `move`; `conflict` handles names
+/// already taken, as for `copy_items`. With `dry_run`, the placements are
+/// returned and nothing is touched.
+///
+/// Progress is emitted on `organize://progress` while photos are dated,
+/// then on `file-ops://progress` while they are copied or moved. The
+/// operation can be paused or cancelled through `operation_id`. Files
+/// matching the exclusion rules, and `destination` itself when it is inside
+/// `source`, are left out.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn organize_photos(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    settings: State<'_, SettingsStore>,
+    source: String,
+    destination: String,
+    template: Option<String>,
+    mode: Option<TransferKind>,
+    conflict: Option<ConflictPolicy>,
+    dry_run: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<OrganizeReport, ExplorerError> {
+    let template = template.unwrap_or_else(|| DEFAULT_FOLDER_TEMPLATE.to_string());
+    validate_template(&template).map_err(ExplorerError::InvalidInput)?;
+    if !Path::new(&source).is_dir() {
+        return Err(ExplorerError::NotFound(format!(
+            "Not a directory: {}",
+            source
+        )));
+    }
+    if !Path::new(&destination).is_dir() {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Destination is not a directory: {}",
+            destination
+        )));
+    }
+    let exclusions = settings.exclusions(Path::new(&source))?;
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("organize_photos", move || {
+            let destination = PathBuf::from(&destination);
+            let mut progress = OrganizeProgress {
+                operation_id: id.clone(),
+                ..Default::default()
+            };
+            let mut last_emit = Instant::now();
+            let mut placements = Vec::new();
+            for entry in WalkDir::new(&source)
+                .into_iter()
+                .filter_entry(|entry| {
+                    entry.depth() == 0
+                        || (entry.path() != destination
+                            && entry
+                                .metadata()
+                                .map_or(true, |meta| !exclusions.excludes(entry.path(), &meta)))
+                })
+                .flatten()
+            {
+                handle.checkpoint()?;
+                if !entry.file_type().is_file() || !is_photo(entry.path()) {
+                    continue;
+                }
+                let Some((date, date_source)) = date_taken(&entry) else {
+                    continue;
+                };
+                let folder = destination.join(date.format(&template).to_string());
+                progress.photos_found += 1;
+                progress.current_file = entry.path().to_string_lossy().to_string();
+                if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                    let _ = app.emit(ORGANIZE_PROGRESS_EVENT, &progress);
+                    last_emit = Instant::now();
+                }
+                if entry.path().parent() == Some(folder.as_path()) {
+                    continue;
+                }
+                placements.push(PhotoPlacement {
+                    source: entry.path().to_string_lossy().to_string(),
+                    folder: folder.to_string_lossy().to_string(),
+                    date: date.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                    date_source,
+                });
+            }
+            let _ = app.emit(ORGANIZE_PROGRESS_EVENT, &progress);
+
+            if dry_run.unwrap_or(false) {
+                return Ok(OrganizeReport {
+                    operation_id: id,
+                    placements,
+                    transfer: None,
+                });
+            }
+            let mut items = Vec::with_capacity(placements.len());
+            for placement in &placements {
+                fs::create_dir_all(&placement.folder)
+                    .map_err(|e| format!("Failed to create {}: {}", placement.folder, e))?;
+                items.push((placement.source.clone(), PathBuf::from(&placement.folder)));
+            }
+            let kind = mode.unwrap_or(TransferKind::Copy);
+            let (report, completed) =
+                transfer_into(&app, handle, id.clone(), &items, kind, conflict);
+            let journal_kind = match kind {
+                TransferKind::Copy => JournalKind::Copy,
+                TransferKind::Move => JournalKind::Move,
+            };
+            journal::record(&app, journal_kind, &completed);
+            Ok(OrganizeReport {
+                operation_id: id,
+                placements,
+                transfer: Some(report),
+            })
+        })
+        .await
+}
+
+/// Check that `template` is a strftime format giving a relative folder
+/// path, so photos cannot land outside the destination.
+fn validate_template(template: &str) -> Result<(), String> {
+    // Formatting with an invalid format panics.
+    if StrftimeItems::new(template).any(|item| item == Item::Error) {
+        return Err(format!("Invalid folder template: {}", template));
+    }
+    let sample = NaiveDate::from_ymd_opt(2000, 1, 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .unwrap_or_default()
+        .format(template)
+        .to_string();
+    let mut components = Path::new(&sample).components().peekable();
+    if components.peek().is_none() {
+        return Err("Folder template cannot be empty".to_string());
+    }
+    for component in components {
+        match component {
+            Component::Normal(name) => validate_file_name(&name.to_string_lossy())?,
+            _ => {
+                return Err(format!(
+                    "Folder template must be a relative path: {}",
+                    template
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// When the photo `entry` was taken, from EXIF or else its modification
+/// time.
+fn date_taken(entry: &DirEntry) -> Option<(NaiveDateTime, DateSource)> {
+    let exif_date = read_exif(entry.path())
+        .ok()
+        .and_then(|exif| exif_date_taken(&exif))
+        .and_then(|date| NaiveDateTime::parse_from_str(&date, "%Y-%m-%dT%H:%M:%S").ok());
+    if let Some(date) = exif_date {
+        return Some((date, DateSource::Exif));
+    }
+    let modified = entry.metadata().ok()?.modified().ok()?;
+    Some((
+        DateTime::<Local>::from(modified).naive_local(),
+        DateSource::Modified,
+    ))
+}
@@ -0,0 +1,394 @@
+//! Physical disks and the partitions on them.
+//!
+//! `get_drives` lists mounted volumes; this lists the disks behind them,
+//! including partitions that are not mounted, so the drives view can show
+//! where each volume lives. The layout is read with `lsblk` on Linux,
+//! `diskutil` on macOS and the Storage module's cmdlets on Windows.
+
+use std::process::Command;
+
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::commands::natural_cmp;
+use crate::drives::EncryptionState;
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// A partition, or a volume inside one.
+#[derive(Serialize)]
+pub struct Partition {
+    /// Device path such as `/dev/sda1`, `disk0s2` or
+    /// `\\?\GLOBALROOT\Device\Harddisk0\Partition2`.
+    pub device: String,
+    pub label: Option<String>,
+    pub size: u64,
+    /// Filesystem name such as `ext4`, `apfs` or `NTFS`.
+    pub file_system: Option<String>,
+    /// Partition type, such as `EFI System` or `Apple_APFS`.
+    pub partition_type: Option<String>,
+    /// Where the partition is mounted; empty when it is not.
+    pub mount_points: Vec<String>,
+    /// BitLocker, FileVault or LUKS encryption of the partition; `None` when
+    /// it is not encrypted or the platform cannot tell.
+    pub encryption: Option<EncryptionState>,
+}
+
+/// A physical disk with its partitions.
+#[derive(Serialize)]
+pub struct PhysicalDisk {
+    /// Device path such as `/dev/sda`, `disk0` or `\\.\PHYSICALDRIVE0`.
+    pub device: String,
+    pub model: Option<String>,
+    pub size: u64,
+    pub is_removable: bool,
+    /// Partition table, such as `gpt` or `mbr`; `None` when the disk has
+    /// none.
+    pub partition_table: Option<String>,
+    pub partitions: Vec<Partition>,
+}
+
+/// List the physical disks, their partitions and where those are mounted.
+///
+/// Volumes inside encrypted or LVM partitions on Linux, and APFS volumes in
+/// a container on macOS, are listed after the partition holding them.
+#[command]
+pub async fn get_partitions(
+    tasks: State<'_, TaskRegistry>,
+) -> Result<Vec<PhysicalDisk>, ExplorerError> {
+    tasks
+        .run_blocking("partitions", || {
+            let mut disks = list_disks()?;
+            disks.sort_by(|a, b| natural_cmp(&a.device, &b.device));
+            Ok(disks)
+        })
+        .await
+}
+
+/// Run a tool and return its standard output, or its error output on failure.
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!("{} failed: {}", program, stderr.trim()))
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn list_disks() -> Result<Vec<PhysicalDisk>, String> {
+    use serde_json::Value;
+
+    let output = run(
+        "lsblk",
+        &[
+            "--json",
+            "--bytes",
+            "--output",
+            "NAME,PATH,TYPE,SIZE,FSTYPE,LABEL,PARTLABEL,PARTTYPENAME,MOUNTPOINT,MODEL,RM,PTTYPE",
+        ],
+    )?;
+    let tree: Value = serde_json::from_str(&output)
+        .map_err(|e| format!("Failed to parse lsblk output: {}", e))?;
+
+    let text = |device: &Value, key: &str| {
+        device[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    // Older lsblk versions print every value as a string.
+    let size = |device: &Value| {
+        device["size"]
+            .as_u64()
+            .or_else(|| device["size"].as_str()?.parse().ok())
+            .unwrap_or(0)
+    };
+    let path = |device: &Value| {
+        text(device, "path")
+            .unwrap_or_else(|| format!("/dev/{}", text(device, "name").unwrap_or_default()))
+    };
+
+    fn descendants<'a>(device: &'a Value, found: &mut Vec<&'a Value>) {
+        for child in device["children"].as_array().into_iter().flatten() {
+            found.push(child);
+            descendants(child, found);
+        }
+    }
+
+    let devices = tree["blockdevices"].as_array().cloned().unwrap_or_default();
+    Ok(devices
+        .iter()
+        .filter(|device| device["type"].as_str() == Some("disk"))
+        .map(|disk| {
+            let mut children = Vec::new();
+            descendants(disk, &mut children);
+            PhysicalDisk {
+                device: path(disk),
+                model: text(disk, "model"),
+                size: size(disk),
+                is_removable: disk["rm"].as_bool().unwrap_or(false)
+                    || disk["rm"].as_str() == Some("1"),
+                partition_table: text(disk, "pttype").map(|table| match table.as_str() {
+                    "dos" => "mbr".to_string(),
+                    _ => table,
+                }),
+                partitions: children
+                    .into_iter()
+                    .map(|part| Partition {
+                        device: path(part),
+                        label: text(part, "label").or_else(|| text(part, "partlabel")),
+                        size: size(part),
+                        file_system: text(part, "fstype"),
+                        partition_type: text(part, "parttypename"),
+                        mount_points: text(part, "mountpoint").into_iter().collect(),
+                        // An unlocked LUKS or BitLocker partition has its
+                        // decrypted mapping as a child.
+                        encryption: matches!(
+                            text(part, "fstype").as_deref(),
+                            Some("crypto_LUKS" | "BitLocker")
+                        )
+                        .then(|| {
+                            if part["children"].as_array().is_some_and(|c| !c.is_empty()) {
+                                EncryptionState::Unlocked
+                            } else {
+                                EncryptionState::Locked
+                            }
+                        }),
+                    })
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+fn list_disks() -> Result<Vec<PhysicalDisk>, String> {
+    use serde_json::Value;
+
+    let list = read_plist(&run("diskutil", &["list", "-plist", "physical"])?)?;
+    let containers = read_plist(&run("diskutil", &["list", "-plist", "virtual"])?)?;
+    let info = |device: &str| {
+        run("diskutil", &["info", "-plist", device])
+            .and_then(|output| read_plist(&output))
+            .unwrap_or(Value::Null)
+    };
+    let text = |value: &Value, key: &str| {
+        value[key]
+            .as_str()
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let partition = |value: &Value| {
+        let device = text(value, "DeviceIdentifier").unwrap_or_default();
+        let details = info(&device);
+        let mount_points: Vec<String> = text(value, "MountPoint").into_iter().collect();
+        Partition {
+            label: text(value, "VolumeName"),
+            size: value["Size"].as_u64().unwrap_or(0),
+            file_system: text(&details, "FilesystemType"),
+            partition_type: text(value, "Content"),
+            // Locked volumes cannot be mounted, and encrypted volumes are
+            // mounted as soon as they are unlocked.
+            encryption: is_encrypted(&details).then_some(if mount_points.is_empty() {
+                EncryptionState::Locked
+            } else {
+                EncryptionState::Unlocked
+            }),
+            mount_points,
+            device,
+        }
+    };
+    let all_disks = |plist: &Value| {
+        plist["AllDisksAndPartitions"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+    };
+    let containers = all_disks(&containers);
+
+    Ok(all_disks(&list)
+        .iter()
+        .map(|disk| {
+            let device = text(disk, "DeviceIdentifier").unwrap_or_default();
+            let details = info(&device);
+            let mut partitions = Vec::new();
+            for part in disk["Partitions"].as_array().into_iter().flatten() {
+                partitions.push(partition(part));
+                // The APFS container whose physical store is this partition.
+                let store = text(part, "DeviceIdentifier");
+                let volumes = containers
+                    .iter()
+                    .filter(|container| {
+                        container["APFSPhysicalStores"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .any(|physical| text(physical, "DeviceIdentifier") == store)
+                    })
+                    .flat_map(|container| container["APFSVolumes"].as_array().cloned())
+                    .flatten();
+                partitions.extend(volumes.map(|volume| partition(&volume)));
+            }
+            PhysicalDisk {
+                model: text(&details, "MediaName"),
+                size: disk["Size"].as_u64().unwrap_or(0),
+                is_removable: details["RemovableMediaOrExternalDevice"]
+                    .as_bool()
+                    .unwrap_or(false),
+                partition_table: text(disk, "Content").and_then(|scheme| match scheme.as_str() {
+                    "GUID_partition_scheme" => Some("gpt".to_string()),
+                    "FDisk_partition_scheme" => Some("mbr".to_string()),
+                    "Apple_partition_scheme" => Some("apm".to_string()),
+                    _ => None,
+                }),
+                partitions,
+                device,
+            }
+        })
+        .collect())
+}
+
+/// Whether `diskutil info -plist` output describes an encrypted volume.
+#[cfg(target_os = "macos")]
+pub fn is_encrypted(info: &serde_json::Value) -> bool {
+    info["FileVault"].as_bool() == Some(true) || info["Encryption"].as_bool() == Some(true)
+}
+
+/// Convert an XML property list, as `diskutil -plist` prints, to JSON.
+#[cfg(target_os = "macos")]
+pub fn read_plist(xml: &str) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+
+    fn convert(node: roxmltree::Node) -> Value {
+        let elements = || node.children().filter(|child| child.is_element());
+        match node.tag_name().name() {
+            "dict" => {
+                let mut map = serde_json::Map::new();
+                let mut children = elements();
+                while let (Some(key), Some(value)) = (children.next(), children.next()) {
+                    map.insert(key.text().unwrap_or_default().to_string(), convert(value));
+                }
+                Value::Object(map)
+            }
+            "array" => Value::Array(elements().map(convert).collect()),
+            "integer" => node
+                .text()
+                .and_then(|text| text.trim().parse::<i64>().ok())
+                .map_or(Value::Null, Value::from),
+            "real" => node
+                .text()
+                .and_then(|text| text.trim().parse::<f64>().ok())
+                .map_or(Value::Null, Value::from),
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::String(node.text().unwrap_or_default().to_string()),
+        }
+    }
+
+    let document = roxmltree::Document::parse(xml)
+        .map_err(|e| format!("Failed to parse diskutil output: {}", e))?;
+    Ok(document
+        .root_element()
+        .children()
+        .find(|child| child.is_element())
+        .map_or(serde_json::Value::Null, convert))
+}
+
+#[cfg(windows)]
+fn list_disks() -> Result<Vec<PhysicalDisk>, String> {
+    use crate::drives::bitlocker_state;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct DiskRow {
+        number: u32,
+        model: Option<String>,
+        size: u64,
+        partition_style: Option<String>,
+        bus_type: Option<String>,
+        #[serde(default)]
+        partitions: Vec<PartitionRow>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct PartitionRow {
+        number: u32,
+        size: u64,
+        #[serde(rename = "Type")]
+        partition_type: Option<String>,
+        #[serde(default)]
+        access_paths: Vec<String>,
+        file_system: Option<String>,
+        label: Option<String>,
+    }
+
+    let script = "@(Get-Disk | ForEach-Object { $disk = $_; [pscustomobject]@{ \
+         Number = $disk.Number; Model = $disk.FriendlyName; Size = $disk.Size; \
+         PartitionStyle = \"$($disk.PartitionStyle)\"; BusType = \"$($disk.BusType)\"; \
+         Partitions = @(Get-Partition -DiskNumber $disk.Number -ErrorAction SilentlyContinue | \
+         ForEach-Object { $volume = $_ | Get-Volume -ErrorAction SilentlyContinue; \
+         [pscustomobject]@{ Number = $_.PartitionNumber; Size = $_.Size; Type = \"$($_.Type)\"; \
+         AccessPaths = @($_.AccessPaths | Where-Object { $_ }); FileSystem = $volume.FileSystem; \
+         Label = $volume.FileSystemLabel } }) } }) | ConvertTo-Json -Depth 4";
+    let output = run(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", script],
+    )?;
+    // A single disk is printed as an object rather than an array.
+    let json = output.trim();
+    let rows: Vec<DiskRow> = if json.starts_with('{') {
+        serde_json::from_str(json).map(|row| vec![row])
+    } else {
+        serde_json::from_str(json)
+    }
+    .map_err(|e| format!("Failed to parse disk list: {}", e))?;
+
+    let non_empty = |value: Option<String>| value.filter(|value| !value.is_empty());
+    Ok(rows
+        .into_iter()
+        .map(|disk| PhysicalDisk {
+            device: format!(r"\\.\PHYSICALDRIVE{}", disk.number),
+            model: non_empty(disk.model),
+            size: disk.size,
+            is_removable: matches!(disk.bus_type.as_deref(), Some("USB" | "SD" | "MMC")),
+            partition_table: match disk.partition_style.as_deref() {
+                Some("GPT") => Some("gpt".to_string()),
+                Some("MBR") => Some("mbr".to_string()),
+                _ => None,
+            },
+            partitions: disk
+                .partitions
+                .into_iter()
+                .map(|part| {
+                    let mount_points: Vec<String> = part
+                        .access_paths
+                        .into_iter()
+                        // Volume GUID paths are always present; only drive
+                        // letters and folder mounts are shown.
+                        .filter(|path| !path.starts_with(r"\\?\"))
+                        .collect();
+                    Partition {
+                        device: format!(
+                            r"\\?\GLOBALROOT\Device\Harddisk{}\Partition{}",
+                            disk.number, part.number
+                        ),
+                        label: non_empty(part.label),
+                        size: part.size,
+                        file_system: non_empty(part.file_system),
+                        partition_type: non_empty(part.partition_type),
+                        encryption: mount_points.first().and_then(|root| bitlocker_state(root)),
+                        mount_points,
+                    }
+                })
+                .collect(),
+        })
+        .collect())
+}
@@ -0,0 +1,509 @@
+//! External analyzer plugins.
+//!
+//! A plugin is a folder inside `plugins/` in the app data directory holding
+//! a `plugin.json` manifest and an executable. The explorer runs the
+//! executable once per request, writes one JSON request to its standard
+//! input and reads one JSON response from its standard output:
+//!
+//! - `{"protocol": 1, "method": "metadata", "paths": [...]}` is answered
+//!   with `{"files": [{"path": "...", "metadata": {"key": value}}]}`, extra
+//!   details shown next to the file's own.
+//! - `{"protocol": 1, "method": "cleanup", "root": "..."}` is answered with
+//!   `{"suggestions": [{"path": "...", "reason": "...", "bytes": 123}]}`,
+//!   files or folders below `root` that can likely be deleted.
+//!
+//! Either response may instead be `{"error": "..."}`. Plugins run with the
+//! user's rights, so only install ones you trust.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{command, AppHandle, State};
+
+use crate::commands::app_data_dir;
+use crate::error::ExplorerError;
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// Folder holding the plugins inside the app data directory.
+const PLUGINS_DIR_NAME: &str = "plugins";
+
+/// File name of a plugin's manifest inside its folder.
+const MANIFEST_FILE_NAME: &str = "plugin.json";
+
+/// Version of the request and response format.
+const PROTOCOL_VERSION: u32 = 1;
+
+/// How long a plugin may take to answer unless its manifest says otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a running plugin is checked for completion, timeout or
+/// cancellation.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What a plugin can be asked for.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginCapability {
+    /// Per-file metadata.
+    Metadata,
+    /// Cleanup suggestions for a folder.
+    Cleanup,
+}
+
+/// Contents of `plugin.json`.
+#[derive(Deserialize)]
+struct Manifest {
+    id: String,
+    name: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    description: String,
+    /// Executable, relative to the plugin folder or looked up on `PATH`.
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    capabilities: Vec<PluginCapability>,
+    /// Extensions of the files metadata is asked for; all files when empty.
+    #[serde(default)]
+    extensions: Vec<String>,
+    timeout_secs: Option<u64>,
+}
+
+/// An installed plugin.
+#[derive(Serialize)]
+pub struct PluginInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub capabilities: Vec<PluginCapability>,
+    pub extensions: Vec<String>,
+    pub folder: String,
+    /// Why the manifest could not be read. Such plugins are never run.
+    pub error: Option<String>,
+}
+
+/// Result of `list_plugins`.
+#[derive(Serialize)]
+pub struct PluginList {
+    /// Where plugins are installed, one folder each.
+    pub directory: String,
+    pub plugins: Vec<PluginInfo>,
+}
+
+/// A plugin that failed to answer. The other plugins' results are still
+/// returned.
+#[derive(Serialize)]
+pub struct PluginError {
+    pub plugin_id: String,
+    pub message: String,
+}
+
+/// Metadata a plugin contributed for one file.
+#[derive(Serialize)]
+pub struct PluginMetadata {
+    pub plugin_id: String,
+    pub path: String,
+    pub fields: Map<String, Value>,
+}
+
+/// Result of `get_plugin_metadata`.
+#[derive(Serialize)]
+pub struct PluginMetadataReport {
+    pub entries: Vec<PluginMetadata>,
+    pub errors: Vec<PluginError>,
+}
+
+/// A file or folder a plugin suggests deleting.
+#[derive(Serialize)]
+pub struct CleanupSuggestion {
+    pub plugin_id: String,
+    pub path: String,
+    pub reason: String,
+    /// Space freed by deleting it, if the plugin says.
+    pub bytes: Option<u64>,
+}
+
+/// Result of `get_cleanup_suggestions`.
+#[derive(Serialize)]
+pub struct CleanupReport {
+    pub suggestions: Vec<CleanupSuggestion>,
+    pub errors: Vec<PluginError>,
+}
+
+/// Managed state pointing at the plugins folder. Manifests are read on each
+/// request, so plugins can be added or removed while the app runs.
+pub struct Plugins {
+    dir: PathBuf,
+}
+
+impl Plugins {
+    /// Create the plugins folder in the app data directory if needed.
+    pub fn init(app: &AppHandle) -> Result<Self, String> {
+        let dir = app_data_dir(app)?.join(PLUGINS_DIR_NAME);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create plugins directory: {}", e))?;
+        Ok(Plugins { dir })
+    }
+
+    /// Every plugin folder with its manifest, or why it could not be read.
+    fn load(&self) -> Vec<(PathBuf, Result<Manifest, String>)> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut plugins: Vec<_> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|folder| folder.is_dir())
+            .map(|folder| {
+                let manifest = fs::read(folder.join(MANIFEST_FILE_NAME))
+                    .map_err(|e| format!("Failed to read {}: {}", MANIFEST_FILE_NAME, e))
+                    .and_then(|bytes| {
+                        serde_json::from_slice::<Manifest>(&bytes)
+                            .map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE_NAME, e))
+                    });
+                (folder, manifest)
+            })
+            .collect();
+        plugins.sort_by(|a, b| a.0.cmp(&b.0));
+        plugins
+    }
+
+    /// Plugins with a valid manifest declaring `capability`.
+    fn with_capability(&self, capability: PluginCapability) -> Vec<Plugin> {
+        self.load()
+            .into_iter()
+            .filter_map(|(folder, manifest)| {
+                Some(Plugin {
+                    folder,
+                    manifest: manifest.ok()?,
+                })
+            })
+            .filter(|plugin| plugin.manifest.capabilities.contains(&capability))
+            .collect()
+    }
+}
+
+/// A plugin ready to run.
+struct Plugin {
+    folder: PathBuf,
+    manifest: Manifest,
+}
+
+impl Plugin {
+    /// Whether metadata should be asked for `path`.
+    fn handles(&self, path: &Path) -> bool {
+        let extensions = &self.manifest.extensions;
+        extensions.is_empty()
+            || path.extension().is_some_and(|ext| {
+                let ext = ext.to_string_lossy();
+                extensions
+                    .iter()
+                    .any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(&ext))
+            })
+    }
+
+    /// Run the plugin with `request` and return its response. The plugin is
+    /// killed when it runs past its timeout or the operation is cancelled.
+    fn call(&self, request: &Value, operation: &OperationHandle) -> Result<Value, ExplorerError> {
+        let name = &self.manifest.name;
+        let bundled = self.folder.join(&self.manifest.command);
+        let program = if bundled.is_file() {
+            bundled.into_os_string()
+        } else {
+            self.manifest.command.clone().into()
+        };
+        let mut input = serde_json::to_vec(request)
+            .map_err(|e| format!("Failed to encode request for {}: {}", name, e))?;
+        input.push(b'\n');
+        let mut child = Command::new(program)
+            .args(&self.manifest.args)
+            .current_dir(&self.folder)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", name, e))?;
+
+        // The pipes are written and drained on their own threads, so neither
+        // a chatty plugin nor one that never reads its input can block us
+        // past the timeout on a full pipe.
+        let drain = |pipe: Option<Box<dyn Read + Send>>| {
+            thread::spawn(move || {
+                let mut bytes = Vec::new();
+                if let Some(mut pipe) = pipe {
+                    let _ = pipe.read_to_end(&mut bytes);
+                }
+                bytes
+            })
+        };
+        let stdout = drain(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+        let stderr = drain(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+        if let Some(mut stdin) = child.stdin.take() {
+            // A plugin may exit without reading its input. Once it is done or
+            // killed the write ends, closing stdin.
+            thread::spawn(move || {
+                let _ = stdin.write_all(&input);
+            });
+        }
+
+        let timeout = self
+            .manifest
+            .timeout_secs
+            .map_or(DEFAULT_TIMEOUT, Duration::from_secs);
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .map_err(|e| format!("Failed to wait for {}: {}", name, e))?
+            {
+                break status;
+            }
+            if operation.is_cancelled() || started.elapsed() > timeout {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(if operation.is_cancelled() {
+                    ExplorerError::Cancelled
+                } else {
+                    format!(
+                        "{} did not answer within {} seconds",
+                        name,
+                        timeout.as_secs()
+                    )
+                    .into()
+                });
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+
+        if !status.success() {
+            return Err(format!(
+                "{} failed: {}",
+                name,
+                String::from_utf8_lossy(&stderr).trim()
+            )
+            .into());
+        }
+        let response: Value = serde_json::from_slice(&stdout)
+            .map_err(|e| format!("{} sent an invalid response: {}", name, e))?;
+        if let Some(error) = response.get("error").and_then(Value::as_str) {
+            return Err(format!("{}: {}", name, error).into());
+        }
+        Ok(response)
+    }
+}
+
+/// List the installed plugins, including ones whose manifest is invalid.
+#[command]
+pub async fn list_plugins(
+    tasks: State<'_, TaskRegistry>,
+    plugins: State<'_, Plugins>,
+) -> Result<PluginList, ExplorerError> {
+    let dir = plugins.dir.clone();
+    tasks
+        .run_blocking("list_plugins", move || {
+            let directory = dir.to_string_lossy().to_string();
+            let plugins = Plugins { dir }
+                .load()
+                .into_iter()
+                .map(|(folder, manifest)| {
+                    let folder_name = folder
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let folder = folder.to_string_lossy().to_string();
+                    match manifest {
+                        Ok(manifest) => PluginInfo {
+                            id: manifest.id,
+                            name: manifest.name,
+                            version: manifest.version,
+                            description: manifest.description,
+                            capabilities: manifest.capabilities,
+                            extensions: manifest.extensions,
+                            folder,
+                            error: None,
+                        },
+                        Err(error) => PluginInfo {
+                            id: folder_name.clone(),
+                            name: folder_name,
+                            version: String::new(),
+                            description: String::new(),
+                            capabilities: Vec::new(),
+                            extensions: Vec::new(),
+                            folder,
+                            error: Some(error),
+                        },
+                    }
+                })
+                .collect();
+            Ok(PluginList { directory, plugins })
+        })
+        .await
+}
+
+/// Ask the metadata plugins about `paths`. Each plugin only gets the files
+/// whose extensions its manifest lists.
+#[command]
+pub async fn get_plugin_metadata(
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    plugins: State<'_, Plugins>,
+    paths: Vec<String>,
+    operation_id: Option<String>,
+) -> Result<PluginMetadataReport, ExplorerError> {
+    let plugins = plugins.with_capability(PluginCapability::Metadata);
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    tasks
+        .run_blocking("plugin_metadata", move || {
+            let mut report = PluginMetadataReport {
+                entries: Vec::new(),
+                errors: Vec::new(),
+            };
+            for plugin in plugins {
+                handle.checkpoint()?;
+                let wanted: Vec<&String> = paths
+                    .iter()
+                    .filter(|path| plugin.handles(Path::new(path)))
+                    .collect();
+                if wanted.is_empty() {
+                    continue;
+                }
+                let request = json!({
+                    "protocol": PROTOCOL_VERSION,
+                    "method": "metadata",
+                    "paths": wanted,
+                });
+                match plugin.call(&request, &handle) {
+                    Ok(response) => {
+                        let files = response["files"].as_array().cloned().unwrap_or_default();
+                        report.entries.extend(files.into_iter().filter_map(|file| {
+                            let path = file["path"].as_str()?.to_string();
+                            // Only answers about the files that were asked for.
+                            if !wanted.contains(&&path) {
+                                return None;
+                            }
+                            Some(PluginMetadata {
+                                plugin_id: plugin.manifest.id.clone(),
+                                path,
+                                fields: file["metadata"].as_object()?.clone(),
+                            })
+                        }));
+                    }
+                    Err(ExplorerError::Cancelled) => return Err(ExplorerError::Cancelled),
+                    Err(e) => report.errors.push(PluginError {
+                        plugin_id: plugin.manifest.id.clone(),
+                        message: e.to_string(),
+                    }),
+                }
+            }
+            Ok(report)
+        })
+        .await
+}
+
+/// Ask the cleanup plugins what can be deleted below `root`.
+///
+/// Suggestions outside `root` are dropped. Nothing is deleted; the UI asks
+/// the user and deletes through the usual commands.
+#[command]
+pub async fn get_cleanup_suggestions(
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    plugins: State<'_, Plugins>,
+    root: String,
+    operation_id: Option<String>,
+) -> Result<CleanupReport, ExplorerError> {
+    if !Path::new(&root).is_dir() {
+        return Err(ExplorerError::NotFound(format!(
+            "Not a directory: {}",
+            root
+        )));
+    }
+    let plugins = plugins.with_capability(PluginCapability::Cleanup);
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    tasks
+        .run_blocking("plugin_cleanup", move || {
+            let mut report = CleanupReport {
+                suggestions: Vec::new(),
+                errors: Vec::new(),
+            };
+            let request = json!({
+                "protocol": PROTOCOL_VERSION,
+                "method": "cleanup",
+                "root": root,
+            });
+            for plugin in plugins {
+                handle.checkpoint()?;
+                match plugin.call(&request, &handle) {
+                    Ok(response) => {
+                        let suggestions = response["suggestions"]
+                            .as_array()
+                            .cloned()
+                            .unwrap_or_default();
+                        report
+                            .suggestions
+                            .extend(suggestions.iter().filter_map(|suggestion| {
+                                cleanup_suggestion(&plugin.manifest.id, &root, suggestion)
+                            }));
+                    }
+                    Err(ExplorerError::Cancelled) => return Err(ExplorerError::Cancelled),
+                    Err(e) => report.errors.push(PluginError {
+                        plugin_id: plugin.manifest.id.clone(),
+                        message: e.to_string(),
+                    }),
+                }
+            }
+            Ok(report)
+        })
+        .await
+}
+
+/// Parse one entry of a cleanup response. Suggestions outside `root`,
+/// including ones that only look inside through `..`, are dropped.
+fn cleanup_suggestion(
+    plugin_id: &str,
+    root: &str,
+    suggestion: &Value,
+) -> Option<CleanupSuggestion> {
+    let path = suggestion["path"].as_str()?.to_string();
+    let escapes = Path::new(&path)
+        .components()
+        .any(|component| component == Component::ParentDir);
+    let inside = !escapes && Path::new(&path).starts_with(root) && path != root;
+    inside.then(|| CleanupSuggestion {
+        plugin_id: plugin_id.to_string(),
+        reason: suggestion["reason"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        bytes: suggestion["bytes"].as_u64(),
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleanup_suggestions_stay_below_the_root() {
+        let suggest = |path: &str| cleanup_suggestion("test", "/data", &json!({ "path": path }));
+        assert!(suggest("/data/cache").is_some());
+        assert!(suggest("/data").is_none());
+        assert!(suggest("/database").is_none());
+        assert!(suggest("/data/../../home/user/.ssh").is_none());
+        assert!(suggest("/data/cache/../../etc").is_none());
+    }
+}
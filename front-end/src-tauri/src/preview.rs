@@ -0,0 +1,448 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Default number of bytes returned by `read_text_preview`.
+const DEFAULT_PREVIEW_BYTES: u64 = 64 * 1024;
+
+/// Largest chunk `read_text_preview` returns at once.
+const MAX_PREVIEW_BYTES: u64 = 1024 * 1024;
+
+/// Bytes from the start of the file used to detect its encoding.
+const ENCODING_SAMPLE_SIZE: u64 = 64 * 1024;
+
+/// Default number of bytes returned by `read_hex_preview`.
+const DEFAULT_HEX_BYTES: u64 = 4096;
+
+/// Largest chunk `read_hex_preview` returns at once.
+const MAX_HEX_BYTES: u64 = 64 * 1024;
+
+/// Bytes shown per row of a hex dump.
+const HEX_ROW_WIDTH: usize = 16;
+
+/// Syntax highlighting language ids by file extension, using the names
+/// highlight.js and Prism share.
+const LANGUAGES_BY_EXTENSION: &[(&[&str], &str)] = &[
+    (&["c", "h"], "c"),
+    (&["cc", "cpp", "cxx", "hh", "hpp", "hxx"], "cpp"),
+    (&["cs"], "csharp"),
+    (&["css"], "css"),
+    (&["dart"], "dart"),
+    (&["go"], "go"),
+    (&["htm", "html", "xhtml"], "html"),
+    (&["ini", "cfg", "conf"], "ini"),
+    (&["java"], "java"),
+    (&["js", "cjs", "mjs"], "javascript"),
+    (&["json", "jsonc"], "json"),
+    (&["jsx"], "jsx"),
+    (&["kt", "kts"], "kotlin"),
+    (&["lua"], "lua"),
+    (&["markdown", "md", "mdx"], "markdown"),
+    (&["php"], "php"),
+    (&["pl", "pm"], "perl"),
+    (&["ps1", "psm1"], "powershell"),
+    (&["py", "pyw"], "python"),
+    (&["r"], "r"),
+    (&["rb"], "ruby"),
+    (&["rs"], "rust"),
+    (&["scala"], "scala"),
+    (&["scss", "sass"], "scss"),
+    (&["bash", "sh", "zsh"], "bash"),
+    (&["sql"], "sql"),
+    (&["swift"], "swift"),
+    (&["toml"], "toml"),
+    (&["ts", "cts", "mts"], "typescript"),
+    (&["tsx"], "tsx"),
+    (&["vue"], "vue"),
+    (&["svg", "xml", "xsd", "xsl"], "xml"),
+    (&["yaml", "yml"], "yaml"),
+];
+
+/// Languages of well-known files without an extension.
+const LANGUAGES_BY_FILE_NAME: &[(&str, &str)] = &[
+    ("CMakeLists.txt", "cmake"),
+    ("Dockerfile", "dockerfile"),
+    ("Gemfile", "ruby"),
+    ("Makefile", "makefile"),
+    ("Rakefile", "ruby"),
+];
+
+/// Languages of scripts by the interpreter named in their shebang line.
+const LANGUAGES_BY_INTERPRETER: &[(&str, &str)] = &[
+    ("bash", "bash"),
+    ("deno", "typescript"),
+    ("lua", "lua"),
+    ("node", "javascript"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("python", "python"),
+    ("ruby", "ruby"),
+    ("sh", "bash"),
+    ("zsh", "bash"),
+];
+
+/// One page of a text file.
+#[derive(Serialize)]
+pub struct TextPreview {
+    pub path: String,
+    /// WHATWG name of the detected encoding, e.g. `UTF-8`, `UTF-16LE` or
+    /// `windows-1252` for Latin-1 text.
+    pub encoding: String,
+    /// Set for files that do not look like text; `text` is then empty.
+    pub binary: bool,
+    pub text: String,
+    /// Byte offset the chunk starts at, moved back to a character boundary.
+    pub offset: u64,
+    /// Offset to pass for the next page, or `None` at the end of the file.
+    pub next_offset: Option<u64>,
+    pub total_size: u64,
+}
+
+/// One row of a hex dump.
+#[derive(Serialize)]
+pub struct HexRow {
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+    /// Space-separated two-digit hex values, e.g. `89 50 4e 47`.
+    pub hex: String,
+    /// Printable ASCII characters, with `.` for every other byte.
+    pub ascii: String,
+}
+
+/// A range of a file as hex dump rows.
+#[derive(Serialize)]
+pub struct HexPreview {
+    pub path: String,
+    pub offset: u64,
+    /// Rows of 16 bytes; the last one may be shorter.
+    pub rows: Vec<HexRow>,
+    /// Offset to pass for the next page, or `None` at the end of the file.
+    pub next_offset: Option<u64>,
+    pub total_size: u64,
+}
+
+/// Result of `get_code_preview`.
+#[derive(Serialize)]
+pub struct CodePreview {
+    /// Language id for syntax highlighting, e.g. `rust` or `markdown`;
+    /// `None` for plain text and binary files.
+    pub language: Option<String>,
+    /// Lines in the whole file, not only in `preview`.
+    pub line_count: u64,
+    /// The first page of the file; later pages come from `read_text_preview`.
+    pub preview: TextPreview,
+}
+
+/// Read a page of a text file for the preview pane.
+///
+/// The encoding is detected from the start of the file, so every page of it
+/// is decoded the same way. Pages end on a character boundary; request the
+/// next one from `next_offset`. `max_bytes` defaults to 64 KiB and is capped
+/// at 1 MiB.
+#[command]
+pub async fn read_text_preview(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    offset: Option<u64>,
+    max_bytes: Option<u64>,
+) -> Result<TextPreview, ExplorerError> {
+    let offset = offset.unwrap_or(0);
+    let max_bytes = max_bytes
+        .unwrap_or(DEFAULT_PREVIEW_BYTES)
+        // Room for at least one character in any encoding.
+        .clamp(4, MAX_PREVIEW_BYTES);
+    tasks
+        .run_blocking("text_preview", move || {
+            Ok(read_text_page(path, offset, max_bytes)?)
+        })
+        .await
+}
+
+/// Read `length` bytes from `offset` as hex dump rows for the preview pane.
+///
+/// `length` defaults to 4 KiB and is capped at 64 KiB.
+#[command]
+pub async fn read_hex_preview(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<HexPreview, ExplorerError> {
+    let offset = offset.unwrap_or(0);
+    let length = length.unwrap_or(DEFAULT_HEX_BYTES).min(MAX_HEX_BYTES);
+    tasks
+        .run_blocking("hex_preview", move || {
+            let mut file = File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+            let total_size = file
+                .metadata()
+                .map_err(|e| format!("Failed to read file metadata: {}", e))?
+                .len();
+            let offset = offset.min(total_size);
+            let mut data = Vec::new();
+            file.seek(SeekFrom::Start(offset))
+                .and_then(|_| file.take(length).read_to_end(&mut data))
+                .map_err(|e| format!("Failed to read file: {}", e))?;
+
+            let rows = data
+                .chunks(HEX_ROW_WIDTH)
+                .enumerate()
+                .map(|(i, bytes)| HexRow {
+                    offset: offset + (i * HEX_ROW_WIDTH) as u64,
+                    hex: bytes
+                        .iter()
+                        .map(|b| format!("{:02x}", b))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    ascii: bytes
+                        .iter()
+                        .map(|&b| match b {
+                            0x20..=0x7E => b as char,
+                            _ => '.',
+                        })
+                        .collect(),
+                    bytes: bytes.to_vec(),
+                })
+                .collect();
+            let next = offset + data.len() as u64;
+            Ok(HexPreview {
+                path,
+                offset,
+                rows,
+                next_offset: (next < total_size).then_some(next),
+                total_size,
+            })
+        })
+        .await
+}
+
+/// Read the start of a source or Markdown file with its language and line
+/// count, for a syntax-highlighted preview.
+///
+/// The language comes from the file name, or from the shebang line of
+/// scripts without an extension.
+#[command]
+pub async fn get_code_preview(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<CodePreview, ExplorerError> {
+    tasks
+        .run_blocking("code_preview", move || {
+            let preview = read_text_page(path.clone(), 0, DEFAULT_PREVIEW_BYTES)?;
+            let encoding = match Encoding::for_label(preview.encoding.as_bytes()) {
+                Some(encoding) if !preview.binary => encoding,
+                _ => {
+                    return Ok(CodePreview {
+                        language: None,
+                        line_count: 0,
+                        preview,
+                    })
+                }
+            };
+            Ok(CodePreview {
+                language: detect_language(Path::new(&path), &preview.text).map(str::to_string),
+                line_count: count_lines(&path, encoding)?,
+                preview,
+            })
+        })
+        .await
+}
+
+/// Language of a file by its name, falling back to its shebang line.
+fn detect_language(path: &Path, text: &str) -> Option<&'static str> {
+    let name = path.file_name()?.to_string_lossy();
+    if let Some((_, language)) = LANGUAGES_BY_FILE_NAME.iter().find(|(n, _)| *n == name) {
+        return Some(language);
+    }
+    if let Some(ext) = path.extension() {
+        let ext = ext.to_string_lossy().to_lowercase();
+        return LANGUAGES_BY_EXTENSION
+            .iter()
+            .find(|(exts, _)| exts.contains(&ext.as_str()))
+            .map(|(_, language)| *language);
+    }
+
+    // `#!/usr/bin/python3` or `#!/usr/bin/env -S node --flags`.
+    let shebang = text.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    // `python3.12` is `python`.
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    LANGUAGES_BY_INTERPRETER
+        .iter()
+        .find(|(interpreter, _)| *interpreter == program)
+        .map(|(_, language)| *language)
+}
+
+/// Number of lines in a text file, counting a last line without a newline.
+fn count_lines(path: &str, encoding: &'static Encoding) -> Result<u64, String> {
+    // Byte of each code unit that holds the newline.
+    let (unit, newline_byte) = if encoding == UTF_16LE {
+        (2, 0)
+    } else if encoding == UTF_16BE {
+        (2, 1)
+    } else {
+        (1, 0)
+    };
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let mut position: u64 = 0;
+    let mut lines = 0;
+    let mut ends_with_newline = true;
+    loop {
+        let buf = reader
+            .fill_buf()
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if buf.is_empty() {
+            break;
+        }
+        if unit == 1 {
+            lines += buf.iter().filter(|&&b| b == b'\n').count() as u64;
+            ends_with_newline = buf.last() == Some(&b'\n');
+        } else {
+            for (i, &b) in buf.iter().enumerate() {
+                if (position + i as u64) % unit == newline_byte {
+                    ends_with_newline = b == b'\n';
+                    lines += ends_with_newline as u64;
+                }
+            }
+        }
+        let len = buf.len();
+        position += len as u64;
+        reader.consume(len);
+    }
+    Ok(lines + (position > 0 && !ends_with_newline) as u64)
+}
+
+fn read_text_page(path: String, offset: u64, max_bytes: u64) -> Result<TextPreview, String> {
+    let mut file = File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let total_size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let mut sample = Vec::new();
+    (&mut file)
+        .take(ENCODING_SAMPLE_SIZE)
+        .read_to_end(&mut sample)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let is_complete = sample.len() as u64 == total_size;
+    let Some((encoding, bom_len)) = detect_encoding(&sample, is_complete) else {
+        return Ok(TextPreview {
+            path,
+            encoding: String::new(),
+            binary: true,
+            text: String::new(),
+            offset: 0,
+            next_offset: None,
+            total_size,
+        });
+    };
+
+    // Skip the byte order mark and start on a code unit boundary.
+    let mut start = offset.clamp(bom_len, total_size.max(bom_len));
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        start -= (start - bom_len) % 2;
+    }
+    // Extra bytes to find where the last character ends.
+    let mut chunk = Vec::new();
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| (&mut file).take(max_bytes + 4).read_to_end(&mut chunk))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    // Leading bytes continuing a character from the previous page.
+    let skip = if encoding == UTF_8 {
+        chunk
+            .iter()
+            .take(3)
+            .take_while(|&&b| b & 0xC0 == 0x80)
+            .count()
+    } else {
+        0
+    };
+    let end = char_boundary(encoding, &chunk, skip + max_bytes as usize);
+    let (text, _) = encoding.decode_without_bom_handling(&chunk[skip..end]);
+
+    let next = start + end as u64;
+    Ok(TextPreview {
+        path,
+        encoding: encoding.name().to_string(),
+        binary: false,
+        text: text.into_owned(),
+        offset: start + skip as u64,
+        next_offset: (next < total_size).then_some(next),
+        total_size,
+    })
+}
+
+/// Encoding and byte order mark length of a text file, or `None` if the
+/// sample looks binary.
+fn detect_encoding(sample: &[u8], is_complete: bool) -> Option<(&'static Encoding, u64)> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(sample) {
+        return Some((encoding, bom_len as u64));
+    }
+
+    let nuls = sample.iter().filter(|&&b| b == 0).count();
+    if nuls > 0 {
+        // UTF-16 without a byte order mark: ASCII text has every other byte
+        // zero, on the high side of each code unit.
+        let odd_nuls = sample
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count();
+        let units = sample.len() / 2;
+        if units > 0 && odd_nuls * 10 >= units * 3 && odd_nuls * 10 >= nuls * 9 {
+            return Some((UTF_16LE, 0));
+        }
+        let even_nuls = nuls - odd_nuls;
+        if units > 0 && even_nuls * 10 >= units * 3 && even_nuls * 10 >= nuls * 9 {
+            return Some((UTF_16BE, 0));
+        }
+        return None;
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(sample, is_complete);
+    Some((detector.guess(None, true), 0))
+}
+
+/// Largest end offset up to `limit` that does not split a character.
+fn char_boundary(encoding: &'static Encoding, chunk: &[u8], limit: usize) -> usize {
+    if chunk.len() <= limit {
+        return chunk.len();
+    }
+    if encoding == UTF_8 {
+        // Step back over continuation bytes to the start of the last character.
+        return (limit.saturating_sub(3)..=limit)
+            .rev()
+            .find(|&i| chunk[i] & 0xC0 != 0x80)
+            .unwrap_or(limit);
+    }
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let end = limit - limit % 2;
+        let unit = if encoding == UTF_16LE {
+            u16::from_le_bytes([chunk[end - 2], chunk[end - 1]])
+        } else {
+            u16::from_be_bytes([chunk[end - 2], chunk[end - 1]])
+        };
+        // Keep surrogate pairs together.
+        return if (0xD800..0xDC00).contains(&unit) {
+            end - 2
+        } else {
+            end
+        };
+    }
+    limit
+}
@@ -0,0 +1,269 @@
+//! Disk quotas of the current user.
+//!
+//! On managed and shared machines an administrator may cap how much a user
+//! can store on a volume. The free space reported for the drive is then not
+//! what the user can actually write.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Usage and limits of one quota. Limits are `None` when not set.
+#[derive(Serialize, Default)]
+pub struct QuotaUsage {
+    pub used_bytes: u64,
+    /// Past this the user gets a warning, or a grace period on Unix.
+    pub soft_limit_bytes: Option<u64>,
+    /// Writes fail past this.
+    pub hard_limit_bytes: Option<u64>,
+    /// Files and folders owned, where the filesystem counts them.
+    pub used_files: Option<u64>,
+    pub soft_limit_files: Option<u64>,
+    pub hard_limit_files: Option<u64>,
+}
+
+/// Result of `get_quota`.
+#[derive(Serialize)]
+pub struct QuotaReport {
+    pub path: String,
+    /// Quota of the current user; `None` when quotas are off or the
+    /// filesystem has none.
+    pub user: Option<QuotaUsage>,
+    /// Quota of the current user's primary group. Windows has no group
+    /// quotas.
+    pub group: Option<QuotaUsage>,
+}
+
+/// Read the current user's disk quota on the volume holding `path`.
+///
+/// Uses NTFS quotas on Windows and `quotactl` on macOS and Linux, where
+/// quotas on NFS mounts are asked from the server with the `quota` tool.
+#[command]
+pub async fn get_quota(
+    tasks: State<'_, TaskRegistry>,
+    path: String,
+) -> Result<QuotaReport, ExplorerError> {
+    if !Path::new(&path).exists() {
+        return Err(ExplorerError::NotFound(format!(
+            "Path does not exist: {}",
+            path
+        )));
+    }
+    tasks
+        .run_blocking("quota", move || {
+            let (user, group) = read_quota(Path::new(&path))?;
+            Ok(QuotaReport { path, user, group })
+        })
+        .await
+}
+
+/// Size of the blocks Linux quotas count space in.
+#[cfg(all(unix, not(target_os = "macos")))]
+const QUOTA_BLOCK_SIZE: u64 = 1024;
+
+/// A limit of 0 means no limit.
+#[cfg(unix)]
+fn limit(value: u64) -> Option<u64> {
+    (value > 0).then_some(value)
+}
+
+#[cfg(windows)]
+fn read_quota(path: &Path) -> Result<(Option<QuotaUsage>, Option<QuotaUsage>), String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::Storage::FileSystem::{
+        CLSID_DiskQuotaControl, GetVolumePathNameW, IDiskQuotaControl, DISKQUOTA_STATE_DISABLED,
+        DISKQUOTA_STATE_MASK,
+    };
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+    };
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut volume = [0u16; 261];
+    unsafe { GetVolumePathNameW(PCWSTR(wide.as_ptr()), &mut volume) }
+        .map_err(|e| format!("Failed to find the volume of {}: {}", path.display(), e))?;
+
+    // A thread that is already initialised keeps its apartment.
+    let _ = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+    let user = unsafe {
+        let control: IDiskQuotaControl =
+            CoCreateInstance(&CLSID_DiskQuotaControl, None, CLSCTX_INPROC_SERVER)
+                .map_err(|e| format!("Failed to read quotas: {}", e))?;
+        // Volumes that do not support quotas fail here.
+        if control
+            .Initialize(PCWSTR(volume.as_ptr()), BOOL(0))
+            .is_err()
+        {
+            return Ok((None, None));
+        }
+        let mut state = 0u32;
+        control
+            .GetQuotaState(&mut state)
+            .map_err(|e| format!("Failed to read quotas: {}", e))?;
+        if state & DISKQUOTA_STATE_MASK == DISKQUOTA_STATE_DISABLED {
+            return Ok((None, None));
+        }
+        let account = format!(
+            "{}\\{}",
+            std::env::var("USERDOMAIN").unwrap_or_default(),
+            std::env::var("USERNAME").unwrap_or_default()
+        );
+        let Ok(user) = control.FindUserName(&HSTRING::from(account)) else {
+            return Ok((None, None));
+        };
+        let (mut used, mut threshold, mut limit) = (0i64, 0i64, 0i64);
+        user.GetQuotaUsed(&mut used)
+            .and_then(|()| user.GetQuotaThreshold(&mut threshold))
+            .and_then(|()| user.GetQuotaLimit(&mut limit))
+            .map_err(|e| format!("Failed to read quotas: {}", e))?;
+        // Unset limits are -1.
+        QuotaUsage {
+            used_bytes: used.max(0) as u64,
+            soft_limit_bytes: u64::try_from(threshold).ok(),
+            hard_limit_bytes: u64::try_from(limit).ok(),
+            ..Default::default()
+        }
+    };
+    Ok((Some(user), None))
+}
+
+#[cfg(target_os = "macos")]
+fn read_quota(path: &Path) -> Result<(Option<QuotaUsage>, Option<QuotaUsage>), String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    const USRQUOTA: libc::c_int = 0;
+    const GRPQUOTA: libc::c_int = 1;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| format!("Invalid path: {}", path.display()))?;
+    // Fails when quotas are off for the volume.
+    let get = |kind: libc::c_int, id: libc::c_int| {
+        let mut quota: libc::dqblk = unsafe { std::mem::zeroed() };
+        let status = unsafe {
+            libc::quotactl(
+                path.as_ptr(),
+                libc::QCMD(libc::Q_GETQUOTA, kind),
+                id,
+                &mut quota as *mut libc::dqblk as *mut libc::c_char,
+            )
+        };
+        (status == 0).then(|| QuotaUsage {
+            used_bytes: quota.dqb_curbytes,
+            soft_limit_bytes: limit(quota.dqb_bsoftlimit),
+            hard_limit_bytes: limit(quota.dqb_bhardlimit),
+            used_files: Some(quota.dqb_curinodes as u64),
+            soft_limit_files: limit(quota.dqb_isoftlimit as u64),
+            hard_limit_files: limit(quota.dqb_ihardlimit as u64),
+        })
+    };
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    Ok((
+        get(USRQUOTA, uid as libc::c_int),
+        get(GRPQUOTA, gid as libc::c_int),
+    ))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn read_quota(path: &Path) -> Result<(Option<QuotaUsage>, Option<QuotaUsage>), String> {
+    const USRQUOTA: libc::c_int = 0;
+    const GRPQUOTA: libc::c_int = 1;
+
+    let (device, mount_point, file_system) =
+        mount_of(path).ok_or_else(|| format!("Failed to find the mount of {}", path.display()))?;
+    if file_system.starts_with("nfs") {
+        return Ok((nfs_quota(&device, &mount_point)?, None));
+    }
+
+    let device = std::ffi::CString::new(device).map_err(|e| e.to_string())?;
+    // Fails when quotas are off for the filesystem.
+    let get = |kind: libc::c_int, id: libc::c_int| {
+        // QCMD from <sys/quota.h>.
+        let command = (((libc::Q_GETQUOTA as u32) << 8) | kind as u32) as libc::c_int;
+        let mut quota: libc::dqblk = unsafe { std::mem::zeroed() };
+        let status = unsafe {
+            libc::quotactl(
+                command,
+                device.as_ptr(),
+                id,
+                &mut quota as *mut libc::dqblk as *mut libc::c_char,
+            )
+        };
+        (status == 0).then(|| QuotaUsage {
+            used_bytes: quota.dqb_curspace,
+            soft_limit_bytes: limit(quota.dqb_bsoftlimit * QUOTA_BLOCK_SIZE),
+            hard_limit_bytes: limit(quota.dqb_bhardlimit * QUOTA_BLOCK_SIZE),
+            used_files: Some(quota.dqb_curinodes),
+            soft_limit_files: limit(quota.dqb_isoftlimit),
+            hard_limit_files: limit(quota.dqb_ihardlimit),
+        })
+    };
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    Ok((
+        get(USRQUOTA, uid as libc::c_int),
+        get(GRPQUOTA, gid as libc::c_int),
+    ))
+}
+
+/// Device, mount point and filesystem type of the mount holding `path`,
+/// from `/proc/self/mounts`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount_of(path: &Path) -> Option<(String, String, String)> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let (device, target, file_system) = (fields.next()?, fields.next()?, fields.next()?);
+            // Spaces in mount points are written as `\040`.
+            let target = target.replace("\\040", " ");
+            path.starts_with(&target)
+                .then(|| (device.to_string(), target, file_system.to_string()))
+        })
+        .max_by_key(|(_, target, _)| target.len())
+}
+
+/// The user's quota on an NFS mount, asked from the server's `rquotad` by
+/// the `quota` tool.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn nfs_quota(device: &str, mount_point: &str) -> Result<Option<QuotaUsage>, String> {
+    let output = std::process::Command::new("quota")
+        .args(["--no-wrap", "--raw-grace", "--filesystem-list", mount_point])
+        .output()
+        .map_err(|e| format!("Failed to run quota: {}", e))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // Rows are `server:/export blocks quota limit grace files quota limit
+    // grace`; usage past the soft limit is marked with `*`.
+    let Some(row) = text.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let name = fields.next()?;
+        (name == device || name == mount_point).then(|| {
+            fields
+                .map(|field| field.trim_end_matches('*').parse::<u64>().unwrap_or(0))
+                .collect::<Vec<_>>()
+        })
+    }) else {
+        return Ok(None);
+    };
+    if row.len() < 7 {
+        return Ok(None);
+    }
+    Ok(Some(QuotaUsage {
+        used_bytes: row[0] * QUOTA_BLOCK_SIZE,
+        soft_limit_bytes: limit(row[1] * QUOTA_BLOCK_SIZE),
+        hard_limit_bytes: limit(row[2] * QUOTA_BLOCK_SIZE),
+        used_files: Some(row[4]),
+        soft_limit_files: limit(row[5]),
+        hard_limit_files: limit(row[6]),
+    }))
+}
@@ -0,0 +1,444 @@
+//! Remote filesystems browsed through the same listing and scanning model as
+//! local drives.
+
+pub mod mtp;
+pub mod s3;
+pub mod sftp;
+pub mod webdav;
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, State};
+
+use crate::commands::DirEntryInfo;
+use crate::error::ExplorerError;
+use crate::file_ops::{
+    validate_file_name, FileOpProgress, FileOpReport, PathResult, FILE_OP_PROGRESS_EVENT,
+};
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::scanner::{DirNode, ScanError, ScanProgress, ScanReport, SCAN_PROGRESS_EVENT};
+use crate::tasks::TaskRegistry;
+
+/// Minimum delay between two progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A filesystem reachable over the network.
+///
+/// Paths are `/`-separated and absolute within the remote filesystem.
+pub trait RemoteFs: Send {
+    /// Short description such as `sftp://user@host:22`.
+    fn describe(&self) -> String;
+
+    /// Entries directly inside the folder at `path`.
+    fn list(&mut self, path: &str) -> Result<Vec<DirEntryInfo>, String>;
+
+    /// Metadata of a single file or folder.
+    fn stat(&mut self, path: &str) -> Result<DirEntryInfo, String>;
+
+    /// Stream the contents of a file into `out`.
+    fn download(&mut self, path: &str, out: &mut (dyn Write + Send)) -> Result<(), String>;
+}
+
+/// Build a listing entry for `name` inside the remote folder `parent`.
+pub fn remote_entry(
+    parent: &str,
+    name: &str,
+    size: u64,
+    modified: Option<u64>,
+    is_dir: bool,
+    is_symlink: bool,
+) -> DirEntryInfo {
+    DirEntryInfo {
+        name: name.to_string(),
+        path: join_remote(parent, name),
+        size: if is_dir { 0 } else { size },
+        modified,
+        created: None,
+        is_dir,
+        is_symlink,
+        symlink_target: None,
+        is_broken_link: false,
+        is_cloud_placeholder: false,
+        extension: Path::new(name)
+            .extension()
+            .filter(|_| !is_dir)
+            .map(|ext| ext.to_string_lossy().to_lowercase()),
+    }
+}
+
+/// Join a remote folder and an entry name with a single `/`.
+pub fn join_remote(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent.trim_end_matches('/'), name)
+    }
+}
+
+type SharedFs = Arc<Mutex<Box<dyn RemoteFs>>>;
+
+/// An open remote connection as reported to the frontend.
+#[derive(Serialize, Clone)]
+pub struct RemoteConnection {
+    pub id: String,
+    pub description: String,
+}
+
+/// Managed state holding open remote connections by id.
+#[derive(Default)]
+pub struct RemoteConnections {
+    next_id: AtomicU64,
+    active: Mutex<HashMap<String, (RemoteConnection, SharedFs)>>,
+}
+
+impl RemoteConnections {
+    /// Register a connected filesystem and return its handle.
+    pub fn add(&self, fs: Box<dyn RemoteFs>) -> RemoteConnection {
+        let connection = RemoteConnection {
+            id: format!(
+                "remote-{}",
+                self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+            ),
+            description: fs.describe(),
+        };
+        self.active.lock().unwrap().insert(
+            connection.id.clone(),
+            (connection.clone(), Arc::new(Mutex::new(fs))),
+        );
+        connection
+    }
+
+    fn get(&self, id: &str) -> Result<SharedFs, ExplorerError> {
+        self.active
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|(_, fs)| fs.clone())
+            .ok_or_else(|| ExplorerError::NotFound(format!("No open remote connection: {}", id)))
+    }
+}
+
+/// List open remote connections.
+#[command]
+pub fn list_remote_connections(connections: State<'_, RemoteConnections>) -> Vec<RemoteConnection> {
+    let mut list: Vec<RemoteConnection> = connections
+        .active
+        .lock()
+        .unwrap()
+        .values()
+        .map(|(connection, _)| connection.clone())
+        .collect();
+    list.sort_by(|a, b| a.id.cmp(&b.id));
+    list
+}
+
+/// Close a remote connection.
+#[command]
+pub fn disconnect_remote(
+    connections: State<'_, RemoteConnections>,
+    connection_id: String,
+) -> Result<(), ExplorerError> {
+    connections
+        .active
+        .lock()
+        .unwrap()
+        .remove(&connection_id)
+        .map(|_| ())
+        .ok_or_else(|| {
+            ExplorerError::NotFound(format!("No open remote connection: {}", connection_id))
+        })
+}
+
+/// List a folder on a remote connection.
+#[command]
+pub async fn list_remote(
+    tasks: State<'_, TaskRegistry>,
+    connections: State<'_, RemoteConnections>,
+    connection_id: String,
+    path: String,
+) -> Result<Vec<DirEntryInfo>, ExplorerError> {
+    let remote = connections.get(&connection_id)?;
+    tasks
+        .run_blocking("list_remote", move || {
+            Ok(remote.lock().unwrap().list(&path)?)
+        })
+        .await
+}
+
+/// Scan a remote folder, aggregating sizes per folder like `scan_directory`.
+///
+/// Progress is emitted on `scan://progress`. Symlinks are not followed.
+/// Folders that cannot be listed are left empty and reported in `errors`.
+#[command]
+pub async fn scan_remote(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    connections: State<'_, RemoteConnections>,
+    connection_id: String,
+    path: String,
+    operation_id: Option<String>,
+) -> Result<ScanReport, ExplorerError> {
+    let remote = connections.get(&connection_id)?;
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("scan_remote", move || {
+            let mut remote = remote.lock().unwrap();
+            let mut scan = RemoteScan {
+                app: &app,
+                operation: &handle,
+                progress: ScanProgress {
+                    operation_id: id,
+                    root: path.clone(),
+                    scanned_files: 0,
+                    scanned_dirs: 0,
+                    total_size: 0,
+                    current_path: path.clone(),
+                },
+                last_emit: Instant::now(),
+                errors: Vec::new(),
+            };
+            let tree = scan.visit(remote.as_mut(), &path)?;
+            scan.progress.current_path = path;
+            let _ = app.emit(SCAN_PROGRESS_EVENT, &scan.progress);
+            Ok(ScanReport {
+                tree,
+                errors: scan.errors,
+            })
+        })
+        .await
+}
+
+/// State of a running remote scan.
+struct RemoteScan<'a> {
+    app: &'a AppHandle,
+    operation: &'a OperationHandle,
+    progress: ScanProgress,
+    last_emit: Instant,
+    errors: Vec<ScanError>,
+}
+
+impl RemoteScan<'_> {
+    fn visit(&mut self, remote: &mut dyn RemoteFs, path: &str) -> Result<DirNode, ExplorerError> {
+        self.operation.checkpoint()?;
+        self.progress.scanned_dirs += 1;
+        if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.progress.current_path = path.to_string();
+            let _ = self.app.emit(SCAN_PROGRESS_EVENT, &self.progress);
+            self.last_emit = Instant::now();
+        }
+
+        let mut node = DirNode::new(Path::new(path));
+        node.path = path.to_string();
+        // Unreadable folders are reported as empty rather than failing the scan.
+        let entries = remote.list(path).unwrap_or_else(|error| {
+            self.errors.push(ScanError::new(Path::new(path), error));
+            Vec::new()
+        });
+        for entry in entries {
+            if entry.is_dir && !entry.is_symlink {
+                node.attach(self.visit(remote, &entry.path)?);
+            } else {
+                node.add_file(entry.size, entry.size, true);
+                self.progress.scanned_files += 1;
+                self.progress.total_size += entry.size;
+            }
+        }
+        Ok(node)
+    }
+}
+
+/// Download remote files and folders into the local `destination` folder.
+///
+/// Progress is emitted on `file-ops://progress` like a local copy, and the
+/// download can be paused or cancelled through `operation_id`.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn download_remote(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    connections: State<'_, RemoteConnections>,
+    connection_id: String,
+    paths: Vec<String>,
+    destination: String,
+    operation_id: Option<String>,
+) -> Result<FileOpReport, ExplorerError> {
+    if !Path::new(&destination).is_dir() {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Destination is not a directory: {}",
+            destination
+        )));
+    }
+    let remote = connections.get(&connection_id)?;
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("download_remote", move || {
+            let mut remote = remote.lock().unwrap();
+            let mut download = Download {
+                app: &app,
+                operation: &handle,
+                progress: FileOpProgress {
+                    operation_id: id,
+                    ..Default::default()
+                },
+                last_emit: Instant::now(),
+            };
+            Ok(download.run(remote.as_mut(), &paths, Path::new(&destination)))
+        })
+        .await
+}
+
+/// A file to fetch, resolved before any bytes are transferred.
+struct PlannedFile {
+    remote: String,
+    local: PathBuf,
+    size: u64,
+}
+
+/// State of a running download.
+struct Download<'a> {
+    app: &'a AppHandle,
+    operation: &'a OperationHandle,
+    progress: FileOpProgress,
+    last_emit: Instant,
+}
+
+impl Download<'_> {
+    fn run(
+        &mut self,
+        remote: &mut dyn RemoteFs,
+        paths: &[String],
+        destination: &Path,
+    ) -> FileOpReport {
+        // Resolve every source first so progress has accurate totals.
+        let mut plans = Vec::with_capacity(paths.len());
+        for path in paths {
+            let mut files = Vec::new();
+            let planned = self
+                .plan(remote, path, destination, &mut files)
+                .map(|()| files);
+            self.progress.total_files += planned.as_ref().map_or(0, |f| f.len() as u64);
+            self.progress.total_bytes += planned
+                .as_ref()
+                .map_or(0, |f| f.iter().map(|file| file.size).sum());
+            plans.push(planned);
+        }
+
+        let mut results = Vec::with_capacity(paths.len());
+        for (path, planned) in paths.iter().zip(plans) {
+            let result = planned
+                .and_then(|files| files.iter().try_for_each(|file| self.fetch(remote, file)));
+            results.push(PathResult::from_result(path.clone(), result));
+        }
+        let _ = self.app.emit(FILE_OP_PROGRESS_EVENT, &self.progress);
+
+        FileOpReport {
+            operation_id: self.progress.operation_id.clone(),
+            bytes_copied: self.progress.bytes_copied,
+            files_copied: self.progress.files_copied,
+            cancelled: self.operation.is_cancelled(),
+            results,
+            skipped: Vec::new(),
+            mismatched: Vec::new(),
+        }
+    }
+
+    /// Create local folders for `remote` and collect the files below it.
+    fn plan(
+        &mut self,
+        remote: &mut dyn RemoteFs,
+        path: &str,
+        local_parent: &Path,
+        files: &mut Vec<PlannedFile>,
+    ) -> Result<(), ExplorerError> {
+        self.operation.checkpoint()?;
+        let entry = remote.stat(path)?;
+        // Names come from the server, so never let them escape the destination.
+        validate_file_name(&entry.name).map_err(|e| format!("Cannot download {}: {}", path, e))?;
+        let local = local_parent.join(&entry.name);
+        if fs::symlink_metadata(&local).is_ok() && !(entry.is_dir && local.is_dir()) {
+            return Err(ExplorerError::AlreadyExists(format!(
+                "Target already exists: {}",
+                local.display()
+            )));
+        }
+
+        if entry.is_dir {
+            fs::create_dir_all(&local)
+                .map_err(|e| format!("Failed to create {}: {}", local.display(), e))?;
+            for child in remote.list(path)? {
+                self.plan(remote, &child.path, &local, files)?;
+            }
+        } else {
+            files.push(PlannedFile {
+                remote: path.to_string(),
+                local,
+                size: entry.size,
+            });
+        }
+        Ok(())
+    }
+
+    fn fetch(
+        &mut self,
+        remote: &mut dyn RemoteFs,
+        file: &PlannedFile,
+    ) -> Result<(), ExplorerError> {
+        self.progress.current_file = file.remote.clone();
+        self.progress.file_total_bytes = file.size;
+        self.progress.file_bytes_copied = 0;
+
+        let local = File::create(&file.local)
+            .map_err(|e| format!("Failed to create {}: {}", file.local.display(), e))?;
+        let mut writer = ProgressWriter {
+            file: local,
+            download: self,
+        };
+        let result = remote.download(&file.remote, &mut writer);
+        drop(writer);
+        if let Err(e) = result {
+            let _ = fs::remove_file(&file.local);
+            self.operation.checkpoint()?;
+            return Err(format!("Failed to download {}: {}", file.remote, e).into());
+        }
+        self.progress.files_copied += 1;
+        Ok(())
+    }
+}
+
+/// Local file that reports download progress and honours pause/cancel.
+struct ProgressWriter<'d, 'a> {
+    file: File,
+    download: &'d mut Download<'a>,
+}
+
+impl Write for ProgressWriter<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let download = &mut *self.download;
+        download.operation.checkpoint().map_err(io::Error::other)?;
+        let written = self.file.write(buf)?;
+        download.progress.file_bytes_copied += written as u64;
+        download.progress.bytes_copied += written as u64;
+        if download.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            let _ = download
+                .app
+                .emit(FILE_OP_PROGRESS_EVENT, &download.progress);
+            download.last_emit = Instant::now();
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
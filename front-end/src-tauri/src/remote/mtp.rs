@@ -0,0 +1,328 @@
+#[cfg(not(target_os = "windows"))]
+use std::collections::HashMap;
+#[cfg(not(target_os = "windows"))]
+use std::io::Write;
+
+#[cfg(not(target_os = "windows"))]
+use libmtp_rs::{
+    device::{raw::detect_raw_devices, MtpDevice},
+    object::{filetypes::Filetype, Object},
+    storage::Parent,
+    util::HandlerReturn,
+};
+use serde::Serialize;
+use tauri::{command, State};
+
+#[cfg(not(target_os = "windows"))]
+use super::remote_entry;
+use super::{RemoteConnection, RemoteConnections, RemoteFs};
+#[cfg(not(target_os = "windows"))]
+use crate::commands::DirEntryInfo;
+use crate::drives::DriveInfo;
+#[cfg(not(target_os = "windows"))]
+use crate::drives::DriveType;
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Prefix of the pseudo mount points reported for MTP devices.
+const MTP_SCHEME: &str = "mtp://";
+
+/// An MTP connection together with the storages it exposes.
+#[derive(Serialize)]
+pub struct MtpConnection {
+    pub connection: RemoteConnection,
+    /// One entry per storage (internal memory, SD card). `mount_point` is the
+    /// storage's folder on the connection, e.g. `/Internal shared storage`.
+    pub storages: Vec<DriveInfo>,
+}
+
+/// List phones and media players attached over USB that speak MTP.
+///
+/// Devices are reported as drives with an `mtp://bus:device` mount point and
+/// unknown capacity; pass the mount point to `connect_mtp` to browse them.
+/// Detection does not claim the device, so other apps can still use it.
+#[command]
+pub async fn list_mtp_devices(
+    tasks: State<'_, TaskRegistry>,
+) -> Result<Vec<DriveInfo>, ExplorerError> {
+    tasks
+        .run_blocking("list_mtp_devices", || Ok(detect_devices()?))
+        .await
+}
+
+/// Open an MTP device found by `list_mtp_devices`.
+///
+/// The phone's storages appear as top-level folders of the connection, so they
+/// can be listed with `list_remote`, analyzed with `scan_remote` and copied
+/// off with `download_remote`. Android phones must be unlocked and set to
+/// file transfer mode.
+#[command]
+pub async fn connect_mtp(
+    tasks: State<'_, TaskRegistry>,
+    connections: State<'_, RemoteConnections>,
+    mount_point: String,
+) -> Result<MtpConnection, ExplorerError> {
+    let (fs, storages) = tasks
+        .run_blocking("connect_mtp", move || {
+            let location = mount_point
+                .strip_prefix(MTP_SCHEME)
+                .and_then(|rest| rest.split_once(':'))
+                .and_then(|(bus, dev)| Some((bus.parse().ok()?, dev.parse().ok()?)))
+                .ok_or_else(|| {
+                    ExplorerError::InvalidInput(format!("Not an MTP device: {}", mount_point))
+                })?;
+            open_device(location)
+        })
+        .await?;
+    Ok(MtpConnection {
+        connection: connections.add(fs),
+        storages,
+    })
+}
+
+/// A storage or an object on it, remembered from a listing of its parent.
+#[cfg(not(target_os = "windows"))]
+struct MtpNode {
+    storage: u32,
+    /// Object id; `None` for the root folder of a storage.
+    object: Option<u32>,
+    entry: DirEntryInfo,
+}
+
+/// An opened MTP device.
+///
+/// MTP addresses files by numeric id rather than by path, so ids are
+/// cached as folders are listed and paths are resolved through the cache.
+#[cfg(not(target_os = "windows"))]
+pub struct MtpFs {
+    device: MtpDevice,
+    nodes: HashMap<String, MtpNode>,
+    description: String,
+}
+
+// SAFETY: libmtp device handles are not tied to the thread that opened
+// them. `RemoteConnections` keeps each filesystem behind a mutex, so the
+// handle is never used from two threads at once.
+#[cfg(not(target_os = "windows"))]
+unsafe impl Send for MtpFs {}
+
+#[cfg(not(target_os = "windows"))]
+impl MtpFs {
+    /// Find the node at `path`, listing its parent if it is not cached.
+    /// `None` is the device root.
+    fn lookup(&mut self, path: &str) -> Result<Option<&MtpNode>, String> {
+        let path = format!("/{}", path.trim_matches('/'));
+        if path == "/" {
+            return Ok(None);
+        }
+        if !self.nodes.contains_key(&path) {
+            let parent = path.rsplit_once('/').map_or("/", |(parent, _)| parent);
+            self.list(parent)?;
+        }
+        self.nodes
+            .get(&path)
+            .map(Some)
+            .ok_or_else(|| format!("Not found: {}", path))
+    }
+
+    fn remember(&mut self, storage: u32, object: Option<u32>, entry: DirEntryInfo) {
+        self.nodes.insert(
+            entry.path.clone(),
+            MtpNode {
+                storage,
+                object,
+                entry,
+            },
+        );
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+impl RemoteFs for MtpFs {
+    fn describe(&self) -> String {
+        self.description.clone()
+    }
+
+    fn list(&mut self, path: &str) -> Result<Vec<DirEntryInfo>, String> {
+        let folder = match self.lookup(path)? {
+            None => None,
+            Some(node) if node.entry.is_dir => Some((node.storage, node.object)),
+            Some(_) => return Err(format!("Not a directory: {}", path)),
+        };
+        let path = format!("/{}", path.trim_matches('/'));
+
+        let mut children = Vec::new();
+        let pool = self.device.storage_pool();
+        match folder {
+            None => {
+                for (id, storage) in pool.iter() {
+                    let name = storage_name(id, storage.description());
+                    let entry = remote_entry(&path, &name, 0, None, true, false);
+                    children.push((id, None, entry));
+                }
+            }
+            Some((storage_id, object)) => {
+                let storage = pool
+                    .by_id(storage_id)
+                    .ok_or_else(|| format!("Storage is no longer available: {}", path))?;
+                let parent = object.map_or(Parent::Root, Parent::Folder);
+                for file in storage.files_and_folders(parent) {
+                    let name = file.name();
+                    if name.is_empty() || name.contains('/') {
+                        continue;
+                    }
+                    let is_dir = matches!(file.ftype(), Filetype::Folder);
+                    let modified = Some(file.modification_date().timestamp().max(0) as u64);
+                    let entry = remote_entry(&path, name, file.size(), modified, is_dir, false);
+                    children.push((storage_id, Some(file.id()), entry));
+                }
+            }
+        }
+        drop(pool);
+
+        Ok(children
+            .into_iter()
+            .map(|(storage, object, entry)| {
+                self.remember(storage, object, entry.clone());
+                entry
+            })
+            .collect())
+    }
+
+    fn stat(&mut self, path: &str) -> Result<DirEntryInfo, String> {
+        match self.lookup(path)? {
+            Some(node) => Ok(node.entry.clone()),
+            None => {
+                let mut entry = remote_entry("", &self.description, 0, None, true, false);
+                entry.path = "/".to_string();
+                Ok(entry)
+            }
+        }
+    }
+
+    fn download(&mut self, path: &str, out: &mut (dyn Write + Send)) -> Result<(), String> {
+        let object = match self.lookup(path)? {
+            Some(MtpNode {
+                object: Some(id),
+                entry,
+                ..
+            }) if !entry.is_dir => *id,
+            _ => return Err(format!("Not a file: {}", path)),
+        };
+
+        // libmtp reports a cancelled transfer as success, so keep the
+        // writer's error to return it afterwards.
+        let mut write_error = None;
+        let result = self
+            .device
+            .storage_pool()
+            .get_file_to_handler(object, |data| match out.write_all(data) {
+                Ok(()) => HandlerReturn::Ok(data.len() as u32),
+                Err(e) => {
+                    write_error = Some(e);
+                    HandlerReturn::Cancel
+                }
+            });
+        match write_error {
+            Some(e) => Err(e.to_string()),
+            None => result.map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Folder name for a storage; phones name them e.g. `Internal shared storage`.
+#[cfg(not(target_os = "windows"))]
+fn storage_name(id: u32, description: Option<&str>) -> String {
+    description
+        .map(|name| name.replace('/', "-"))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("Storage {}", id))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn detect_devices() -> Result<Vec<DriveInfo>, String> {
+    // libmtp reports "no devices" as an error.
+    let devices = detect_raw_devices().unwrap_or_default();
+    Ok(devices
+        .iter()
+        .map(|raw| {
+            let entry = raw.device_entry();
+            DriveInfo {
+                mount_point: format!("{}{}:{}", MTP_SCHEME, raw.bus_number(), raw.dev_number()),
+                label: Some(
+                    format!("{} {}", entry.vendor, entry.product)
+                        .trim()
+                        .to_string(),
+                )
+                .filter(|label| !label.is_empty()),
+                drive_type: DriveType::Phone,
+                is_removable: true,
+                file_system: Some("mtp".to_string()),
+                encryption: None,
+                total_bytes: 0,
+                free_bytes: 0,
+                used_bytes: 0,
+            }
+        })
+        .collect())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn open_device(
+    (bus, dev): (u32, u8),
+) -> Result<(Box<dyn RemoteFs>, Vec<DriveInfo>), ExplorerError> {
+    let devices =
+        detect_raw_devices().map_err(|e| format!("Failed to detect MTP devices: {}", e))?;
+    let raw = devices
+        .iter()
+        .find(|raw| raw.bus_number() == bus && raw.dev_number() == dev)
+        .ok_or_else(|| format!("MTP device {}:{} is no longer connected", bus, dev))?;
+    // Uncached, so listings reflect files added on the phone since.
+    let device = raw.open_uncached().ok_or_else(|| {
+        "Failed to open MTP device; unlock the phone and allow file transfer".to_string()
+    })?;
+
+    let name = device
+        .get_friendly_name()
+        .or_else(|_| device.model_name())
+        .unwrap_or_else(|_| format!("MTP device {}:{}", bus, dev));
+    let storages = device
+        .storage_pool()
+        .iter()
+        .map(|(id, storage)| {
+            let total_bytes = storage.maximum_capacity();
+            let free_bytes = storage.free_space_in_bytes();
+            DriveInfo {
+                mount_point: format!("/{}", storage_name(id, storage.description())),
+                label: Some(name.clone()),
+                drive_type: DriveType::Phone,
+                is_removable: true,
+                file_system: Some("mtp".to_string()),
+                encryption: None,
+                total_bytes,
+                free_bytes,
+                used_bytes: total_bytes.saturating_sub(free_bytes),
+            }
+        })
+        .collect();
+
+    let fs = MtpFs {
+        device,
+        nodes: HashMap::new(),
+        description: name,
+    };
+    Ok((Box::new(fs), storages))
+}
+
+// Windows exposes MTP through Windows Portable Devices rather than libmtp.
+#[cfg(target_os = "windows")]
+fn detect_devices() -> Result<Vec<DriveInfo>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(target_os = "windows")]
+fn open_device(_location: (u32, u8)) -> Result<(Box<dyn RemoteFs>, Vec<DriveInfo>), ExplorerError> {
+    Err(ExplorerError::Unsupported(
+        "MTP devices are not supported on Windows yet".to_string(),
+    ))
+}
@@ -0,0 +1,201 @@
+use std::io::Write;
+
+use s3::creds::Credentials;
+use s3::error::S3Error;
+use s3::{Bucket, Region};
+use serde::Deserialize;
+use tauri::{command, State};
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::OffsetDateTime;
+
+use super::{remote_entry, RemoteConnection, RemoteConnections, RemoteFs};
+use crate::commands::DirEntryInfo;
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Region used when `connect_s3` is called without one.
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// Access keys for an S3-compatible service.
+#[derive(Deserialize)]
+pub struct S3Credentials {
+    pub access_key: String,
+    pub secret_key: String,
+    /// Only needed for temporary credentials.
+    pub session_token: Option<String>,
+}
+
+/// A bucket on an S3-compatible service.
+///
+/// Object keys are exposed as `/`-separated paths; key prefixes ending in `/`
+/// are shown as folders and `/` is the root of the bucket.
+pub struct S3Fs {
+    bucket: Box<Bucket>,
+    description: String,
+}
+
+impl S3Fs {
+    /// Fetch the size and modification time of an object, or `None` if no
+    /// object exists under `key`.
+    fn head(&self, key: &str) -> Result<Option<(u64, Option<u64>)>, String> {
+        match self.bucket.head_object(key) {
+            Ok((head, _)) => Ok(Some((
+                head.content_length.unwrap_or(0).max(0) as u64,
+                head.last_modified
+                    .as_deref()
+                    .and_then(|date| OffsetDateTime::parse(date, &Rfc2822).ok())
+                    .map(|date| date.unix_timestamp().max(0) as u64),
+            ))),
+            Err(S3Error::HttpFailWithBody(404, _)) => Ok(None),
+            Err(e) => Err(format!("Failed to read metadata: {}", e)),
+        }
+    }
+}
+
+impl RemoteFs for S3Fs {
+    fn describe(&self) -> String {
+        self.description.clone()
+    }
+
+    fn list(&mut self, path: &str) -> Result<Vec<DirEntryInfo>, String> {
+        let prefix = folder_prefix(path);
+        let pages = self
+            .bucket
+            .list(prefix.clone(), Some("/".to_string()))
+            .map_err(|e| format!("Failed to list objects: {}", e))?;
+
+        let mut entries = Vec::new();
+        for page in pages {
+            for folder in page.common_prefixes.unwrap_or_default() {
+                let name = folder.prefix[prefix.len()..].trim_end_matches('/');
+                if !name.is_empty() {
+                    entries.push(remote_entry(path, name, 0, None, true, false));
+                }
+            }
+            for object in page.contents {
+                // Folder placeholders created by consoles share the prefix's key.
+                let name = &object.key[prefix.len()..];
+                if name.is_empty() {
+                    continue;
+                }
+                let modified = OffsetDateTime::parse(&object.last_modified, &Rfc3339)
+                    .ok()
+                    .map(|date| date.unix_timestamp().max(0) as u64);
+                entries.push(remote_entry(
+                    path,
+                    name,
+                    object.size,
+                    modified,
+                    false,
+                    false,
+                ));
+            }
+        }
+        Ok(entries)
+    }
+
+    fn stat(&mut self, path: &str) -> Result<DirEntryInfo, String> {
+        let key = object_key(path);
+        let name = key.rsplit('/').next().unwrap_or_default();
+        if key.is_empty() {
+            let mut entry = remote_entry("", &self.bucket.name, 0, None, true, false);
+            entry.path = "/".to_string();
+            return Ok(entry);
+        }
+
+        // Folders only exist as key prefixes, so anything that is not an
+        // object is treated as one.
+        let (size, modified, is_dir) = match self.head(key)? {
+            Some((size, modified)) => (size, modified, false),
+            None => (0, None, true),
+        };
+        let mut entry = remote_entry("", name, size, modified, is_dir, false);
+        entry.path = path.to_string();
+        Ok(entry)
+    }
+
+    fn download(&mut self, path: &str, out: &mut (dyn Write + Send)) -> Result<(), String> {
+        self.bucket
+            .get_object_to_writer(object_key(path), out)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Object key for a remote path, without the leading `/`.
+fn object_key(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+/// Key prefix listing the contents of the folder at `path`.
+fn folder_prefix(path: &str) -> String {
+    match object_key(path) {
+        "" => String::new(),
+        key => format!("{}/", key),
+    }
+}
+
+/// Open a bucket on an S3-compatible service such as AWS, MinIO or R2.
+///
+/// `endpoint` is the service URL, e.g. `https://s3.eu-west-1.amazonaws.com`.
+/// Path-style addressing, which most self-hosted services expect, is used
+/// unless `path_style` is `false`.
+#[command]
+pub async fn connect_s3(
+    tasks: State<'_, TaskRegistry>,
+    connections: State<'_, RemoteConnections>,
+    endpoint: String,
+    bucket: String,
+    region: Option<String>,
+    credentials: S3Credentials,
+    path_style: Option<bool>,
+) -> Result<RemoteConnection, ExplorerError> {
+    let fs = tasks
+        .run_blocking("connect_s3", move || {
+            Ok(open_bucket(
+                &endpoint,
+                &bucket,
+                region.as_deref().unwrap_or(DEFAULT_REGION),
+                &credentials,
+                path_style.unwrap_or(true),
+            )?)
+        })
+        .await?;
+    Ok(connections.add(Box::new(fs)))
+}
+
+fn open_bucket(
+    endpoint: &str,
+    name: &str,
+    region: &str,
+    credentials: &S3Credentials,
+    path_style: bool,
+) -> Result<S3Fs, String> {
+    let credentials = Credentials::new(
+        Some(&credentials.access_key),
+        Some(&credentials.secret_key),
+        None,
+        credentials.session_token.as_deref(),
+        None,
+    )
+    .map_err(|e| format!("Invalid credentials: {}", e))?;
+    let region = Region::Custom {
+        region: region.to_string(),
+        endpoint: endpoint.trim_end_matches('/').to_string(),
+    };
+    let mut bucket = Bucket::new(name, region, credentials)
+        .map_err(|e| format!("Failed to open bucket {}: {}", name, e))?;
+    if path_style {
+        bucket = bucket.with_path_style();
+    }
+
+    // Fail early on bad credentials or a missing bucket.
+    bucket
+        .list_page(String::new(), Some("/".to_string()), None, None, Some(1))
+        .map_err(|e| format!("Failed to connect to bucket {}: {}", name, e))?;
+
+    Ok(S3Fs {
+        description: format!("s3://{}/{}", endpoint.trim_end_matches('/'), name),
+        bucket,
+    })
+}
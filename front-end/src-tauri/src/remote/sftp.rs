@@ -0,0 +1,229 @@
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine as _};
+use serde::Deserialize;
+use ssh2::{CheckResult, FileStat, HashType, KnownHostFileKind, Session, Sftp};
+use tauri::{command, AppHandle, Manager, State};
+
+use super::{remote_entry, RemoteConnection, RemoteConnections, RemoteFs};
+use crate::commands::DirEntryInfo;
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Port used when `connect_sftp` is called without one.
+const DEFAULT_SSH_PORT: u16 = 22;
+
+/// Timeout for blocking SSH calls, in milliseconds.
+const SESSION_TIMEOUT_MS: u32 = 30_000;
+
+/// How to authenticate with the SSH server.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SftpAuth {
+    Password {
+        username: String,
+        password: String,
+    },
+    Key {
+        username: String,
+        private_key_path: String,
+        passphrase: Option<String>,
+    },
+    /// Use keys held by the running ssh-agent.
+    Agent {
+        username: String,
+    },
+}
+
+impl SftpAuth {
+    fn username(&self) -> &str {
+        match self {
+            SftpAuth::Password { username, .. }
+            | SftpAuth::Key { username, .. }
+            | SftpAuth::Agent { username } => username,
+        }
+    }
+}
+
+/// An SFTP session.
+pub struct SftpFs {
+    // Kept alive for as long as the SFTP channel is used.
+    _session: Session,
+    sftp: Sftp,
+    description: String,
+}
+
+impl RemoteFs for SftpFs {
+    fn describe(&self) -> String {
+        self.description.clone()
+    }
+
+    fn list(&mut self, path: &str) -> Result<Vec<DirEntryInfo>, String> {
+        let entries = self
+            .sftp
+            .readdir(Path::new(path))
+            .map_err(|e| format!("Failed to read directory: {}", e))?;
+        Ok(entries
+            .iter()
+            .filter_map(|(entry_path, stat)| {
+                let name = entry_path.file_name()?.to_string_lossy().to_string();
+                Some(stat_entry(path, &name, stat))
+            })
+            .collect())
+    }
+
+    fn stat(&mut self, path: &str) -> Result<DirEntryInfo, String> {
+        let stat = self
+            .sftp
+            .lstat(Path::new(path))
+            .map_err(|e| format!("Failed to read metadata: {}", e))?;
+        let remote = PathBuf::from(path);
+        let name = remote
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string());
+        let mut entry = stat_entry("", &name, &stat);
+        entry.path = path.to_string();
+        Ok(entry)
+    }
+
+    fn download(&mut self, path: &str, out: &mut (dyn Write + Send)) -> Result<(), String> {
+        let mut file = self
+            .sftp
+            .open(Path::new(path))
+            .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        io::copy(&mut file, out)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn stat_entry(parent: &str, name: &str, stat: &FileStat) -> DirEntryInfo {
+    remote_entry(
+        parent,
+        name,
+        stat.size.unwrap_or(0),
+        stat.mtime,
+        stat.is_dir(),
+        stat.file_type().is_symlink(),
+    )
+}
+
+/// Connect to an SSH server and open an SFTP session.
+///
+/// The server's host key is checked against `~/.ssh/known_hosts`. A changed
+/// key is always rejected; an unknown one only with `accept_unknown_host`.
+#[command]
+pub async fn connect_sftp(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    connections: State<'_, RemoteConnections>,
+    host: String,
+    port: Option<u16>,
+    auth: SftpAuth,
+    accept_unknown_host: Option<bool>,
+) -> Result<RemoteConnection, ExplorerError> {
+    let port = port.unwrap_or(DEFAULT_SSH_PORT);
+    let known_hosts = app
+        .path()
+        .home_dir()
+        .map(|home| home.join(".ssh").join("known_hosts"))
+        .ok();
+    let fs = tasks
+        .run_blocking("connect_sftp", move || {
+            Ok(open_session(
+                &host,
+                port,
+                &auth,
+                known_hosts.as_deref(),
+                accept_unknown_host.unwrap_or(false),
+            )?)
+        })
+        .await?;
+    Ok(connections.add(Box::new(fs)))
+}
+
+fn open_session(
+    host: &str,
+    port: u16,
+    auth: &SftpAuth,
+    known_hosts: Option<&Path>,
+    accept_unknown_host: bool,
+) -> Result<SftpFs, String> {
+    let tcp = TcpStream::connect((host, port))
+        .map_err(|e| format!("Failed to connect to {}:{}: {}", host, port, e))?;
+    let mut session = Session::new().map_err(|e| format!("Failed to start SSH session: {}", e))?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(SESSION_TIMEOUT_MS);
+    session
+        .handshake()
+        .map_err(|e| format!("SSH handshake failed: {}", e))?;
+
+    verify_host_key(&session, host, port, known_hosts, accept_unknown_host)?;
+
+    let result = match auth {
+        SftpAuth::Password { username, password } => session.userauth_password(username, password),
+        SftpAuth::Key {
+            username,
+            private_key_path,
+            passphrase,
+        } => session.userauth_pubkey_file(
+            username,
+            None,
+            Path::new(private_key_path),
+            passphrase.as_deref(),
+        ),
+        SftpAuth::Agent { username } => session.userauth_agent(username),
+    };
+    result.map_err(|e| format!("Authentication failed: {}", e))?;
+
+    let sftp = session
+        .sftp()
+        .map_err(|e| format!("Failed to start SFTP: {}", e))?;
+    Ok(SftpFs {
+        description: format!("sftp://{}@{}:{}", auth.username(), host, port),
+        _session: session,
+        sftp,
+    })
+}
+
+fn verify_host_key(
+    session: &Session,
+    host: &str,
+    port: u16,
+    known_hosts: Option<&Path>,
+    accept_unknown_host: bool,
+) -> Result<(), String> {
+    let (key, _) = session
+        .host_key()
+        .ok_or_else(|| "Server did not present a host key".to_string())?;
+    let fingerprint = session
+        .host_key_hash(HashType::Sha256)
+        .map(|hash| format!("SHA256:{}", STANDARD_NO_PAD.encode(hash)))
+        .unwrap_or_default();
+
+    let mut known = session
+        .known_hosts()
+        .map_err(|e| format!("Failed to load known hosts: {}", e))?;
+    if let Some(file) = known_hosts.filter(|file| file.exists()) {
+        known
+            .read_file(file, KnownHostFileKind::OpenSSH)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    }
+
+    match known.check_port(host, port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound if accept_unknown_host => Ok(()),
+        CheckResult::NotFound => Err(format!(
+            "Unknown host key for {} ({}); confirm the fingerprint to connect",
+            host, fingerprint
+        )),
+        CheckResult::Mismatch => Err(format!(
+            "Host key for {} does not match known_hosts ({}); refusing to connect",
+            host, fingerprint
+        )),
+        CheckResult::Failure => Err(format!("Failed to verify host key for {}", host)),
+    }
+}
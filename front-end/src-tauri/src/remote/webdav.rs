@@ -0,0 +1,251 @@
+use std::io::Write;
+use std::time::Duration;
+
+use percent_encoding::percent_decode_str;
+use reqwest::blocking::{Client, Response};
+use reqwest::{Method, Url};
+use serde::Deserialize;
+use tauri::{command, State};
+use time::format_description::well_known::Rfc2822;
+use time::OffsetDateTime;
+
+use super::{remote_entry, RemoteConnection, RemoteConnections, RemoteFs};
+use crate::commands::DirEntryInfo;
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Namespace of the WebDAV properties in `PROPFIND` responses.
+const DAV_NAMESPACE: &str = "DAV:";
+
+/// Timeout for establishing a connection to the server.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Properties requested for every entry.
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength/>
+    <d:getlastmodified/>
+  </d:prop>
+</d:propfind>"#;
+
+/// Username and password for the WebDAV server.
+#[derive(Deserialize)]
+pub struct WebDavCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// A folder shared over WebDAV, such as a Nextcloud or ownCloud account.
+///
+/// Remote paths are relative to the URL passed to `connect_webdav`.
+pub struct WebDavFs {
+    client: Client,
+    base: Url,
+    credentials: Option<WebDavCredentials>,
+    description: String,
+}
+
+/// One `<d:response>` of a multistatus reply.
+struct DavEntry {
+    path: String,
+    size: u64,
+    modified: Option<u64>,
+    is_dir: bool,
+}
+
+impl WebDavFs {
+    /// URL of the remote `path` below the base URL.
+    fn url(&self, path: &str) -> Url {
+        let mut url = self.base.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments
+                .pop_if_empty()
+                .extend(path.split('/').filter(|s| !s.is_empty()));
+        }
+        url
+    }
+
+    fn send(&self, method: Method, path: &str, depth: Option<&str>) -> Result<Response, String> {
+        let mut request = self.client.request(method, self.url(path));
+        if let Some(credentials) = &self.credentials {
+            request = request.basic_auth(&credentials.username, Some(&credentials.password));
+        }
+        if let Some(depth) = depth {
+            request = request
+                .header("Depth", depth)
+                .header("Content-Type", "application/xml")
+                .body(PROPFIND_BODY);
+        }
+        request
+            .send()
+            .and_then(Response::error_for_status)
+            .map_err(|e| format!("Request failed: {}", e))
+    }
+
+    /// Run a `PROPFIND` on `path` and parse the entries it returns.
+    fn propfind(&self, path: &str, depth: &str) -> Result<Vec<DavEntry>, String> {
+        let method = Method::from_bytes(b"PROPFIND").map_err(|e| e.to_string())?;
+        let body = self
+            .send(method, path, Some(depth))?
+            .text()
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+        self.parse_multistatus(&body)
+    }
+
+    fn parse_multistatus(&self, body: &str) -> Result<Vec<DavEntry>, String> {
+        let document = roxmltree::Document::parse(body)
+            .map_err(|e| format!("Invalid WebDAV response: {}", e))?;
+        let base_path = decode(self.base.path());
+        let base_path = base_path.trim_end_matches('/');
+
+        let mut entries = Vec::new();
+        for response in document
+            .descendants()
+            .filter(|node| node.has_tag_name((DAV_NAMESPACE, "response")))
+        {
+            let property = |name: &str| {
+                response
+                    .descendants()
+                    .find(|node| node.has_tag_name((DAV_NAMESPACE, name)))
+            };
+            let Some(href) = property("href").and_then(|node| node.text()) else {
+                continue;
+            };
+            // Hrefs may be absolute URLs or server-relative paths.
+            let href = match self.base.join(href.trim()) {
+                Ok(url) => decode(url.path()),
+                Err(_) => continue,
+            };
+            let Some(relative) = href.strip_prefix(base_path) else {
+                continue;
+            };
+
+            entries.push(DavEntry {
+                path: format!("/{}", relative.trim_matches('/')),
+                size: property("getcontentlength")
+                    .and_then(|node| node.text())
+                    .and_then(|text| text.trim().parse().ok())
+                    .unwrap_or(0),
+                modified: property("getlastmodified")
+                    .and_then(|node| node.text())
+                    .and_then(|text| OffsetDateTime::parse(text.trim(), &Rfc2822).ok())
+                    .map(|date| date.unix_timestamp().max(0) as u64),
+                is_dir: property("resourcetype").is_some_and(|node| {
+                    node.children()
+                        .any(|child| child.has_tag_name((DAV_NAMESPACE, "collection")))
+                }),
+            });
+        }
+        Ok(entries)
+    }
+}
+
+impl RemoteFs for WebDavFs {
+    fn describe(&self) -> String {
+        self.description.clone()
+    }
+
+    fn list(&mut self, path: &str) -> Result<Vec<DirEntryInfo>, String> {
+        let folder = normalize(path);
+        Ok(self
+            .propfind(&folder, "1")?
+            .into_iter()
+            // The folder itself is part of the reply.
+            .filter(|entry| entry.path != folder)
+            .filter_map(|entry| {
+                let name = entry.path.rsplit('/').next()?;
+                Some(remote_entry(
+                    &folder,
+                    name,
+                    entry.size,
+                    entry.modified,
+                    entry.is_dir,
+                    false,
+                ))
+            })
+            .collect())
+    }
+
+    fn stat(&mut self, path: &str) -> Result<DirEntryInfo, String> {
+        let path = normalize(path);
+        let entry = self
+            .propfind(&path, "0")?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("Not found: {}", path))?;
+        let name = match path.rsplit('/').next() {
+            Some("") | None => self.base.host_str().unwrap_or("webdav").to_string(),
+            Some(name) => name.to_string(),
+        };
+        let mut info = remote_entry("", &name, entry.size, entry.modified, entry.is_dir, false);
+        info.path = path;
+        Ok(info)
+    }
+
+    fn download(&mut self, path: &str, out: &mut (dyn Write + Send)) -> Result<(), String> {
+        self.send(Method::GET, path, None)?
+            .copy_to(out)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Percent-decode a URL path.
+fn decode(path: &str) -> String {
+    percent_decode_str(path).decode_utf8_lossy().to_string()
+}
+
+/// Remote path with a single leading `/` and no trailing one.
+fn normalize(path: &str) -> String {
+    format!("/{}", path.trim_matches('/'))
+}
+
+/// Connect to a WebDAV server such as Nextcloud or ownCloud.
+///
+/// `url` is the folder to browse, e.g.
+/// `https://cloud.example.com/remote.php/dav/files/alice/`. Listings and
+/// scans go through `list_remote` and `scan_remote`.
+#[command]
+pub async fn connect_webdav(
+    tasks: State<'_, TaskRegistry>,
+    connections: State<'_, RemoteConnections>,
+    url: String,
+    credentials: Option<WebDavCredentials>,
+) -> Result<RemoteConnection, ExplorerError> {
+    let fs = tasks
+        .run_blocking("connect_webdav", move || Ok(open_share(&url, credentials)?))
+        .await?;
+    Ok(connections.add(Box::new(fs)))
+}
+
+fn open_share(url: &str, credentials: Option<WebDavCredentials>) -> Result<WebDavFs, String> {
+    let base = Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if !matches!(base.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme: {}", base.scheme()));
+    }
+    let client = Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut description = base.clone();
+    let _ = description.set_password(None);
+    if let Some(credentials) = &credentials {
+        let _ = description.set_username(&credentials.username);
+    }
+    let fs = WebDavFs {
+        client,
+        base,
+        credentials,
+        description: description.to_string(),
+    };
+
+    // Fail early on bad credentials or a URL that is not a WebDAV folder.
+    let root = fs.propfind("/", "0")?;
+    if !root.first().is_some_and(|entry| entry.is_dir) {
+        return Err(format!("Not a WebDAV folder: {}", url));
+    }
+    Ok(fs)
+}
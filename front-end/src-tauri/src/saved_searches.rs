@@ -0,0 +1,203 @@
+//! Saved searches, or smart folders.
+//!
+//! A saved search keeps a name search with its filters in the index database
+//! and runs it again on demand, so its results follow the file system.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{command, AppHandle, State};
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+use crate::index::FileIndex;
+use crate::search::{search_files, SearchMode, SearchOptions, SearchSummary};
+use crate::settings::SettingsStore;
+use crate::tasks::TaskRegistry;
+
+/// A search stored with `save_search`.
+#[derive(Serialize)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub root: String,
+    pub query: String,
+    pub mode: SearchMode,
+    pub options: SearchOptions,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+}
+
+/// Store a search of `root` under `name`, with the same arguments as
+/// `search_files`, for `run_saved_search`.
+///
+/// Filters are stored as given, so `min_age_days` and `max_age_days` are
+/// measured from each run rather than from when the search was saved.
+#[command]
+pub async fn save_search(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    name: String,
+    root: String,
+    query: String,
+    mode: Option<SearchMode>,
+    options: Option<SearchOptions>,
+) -> Result<SavedSearch, ExplorerError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(ExplorerError::InvalidInput(
+            "Saved search name is empty".to_string(),
+        ));
+    }
+    let options = options.unwrap_or_default();
+    options.filters.clone().validate()?;
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("save_search", move || {
+            let search = SavedSearch {
+                id: uuid::Uuid::new_v4().to_string(),
+                name,
+                root,
+                query,
+                mode: mode.unwrap_or_default(),
+                options,
+                created_at: to_unix_secs(Ok(SystemTime::now())).unwrap_or(0),
+            };
+            let mode = serde_json::to_string(&search.mode)
+                .map_err(|e| format!("Failed to encode search mode: {}", e))?;
+            let options = serde_json::to_string(&search.options)
+                .map_err(|e| format!("Failed to encode search options: {}", e))?;
+            conn.execute(
+                "INSERT INTO saved_searches (id, name, root, query, mode, options, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    search.id,
+                    search.name,
+                    search.root,
+                    search.query,
+                    mode,
+                    options,
+                    search.created_at as i64
+                ],
+            )
+            .map_err(|e| format!("Failed to save search: {}", e))?;
+            Ok(search)
+        })
+        .await
+}
+
+/// List saved searches in the order they were created.
+#[command]
+pub async fn list_saved_searches(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+) -> Result<Vec<SavedSearch>, ExplorerError> {
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("list_saved_searches", move || {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, name, root, query, mode, options, created_at FROM saved_searches
+                     ORDER BY created_at",
+                )
+                .map_err(|e| format!("Failed to query saved searches: {}", e))?;
+            let rows = stmt
+                .query_map([], read_row)
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query saved searches: {}", e))?;
+            Ok(rows.into_iter().flatten().collect())
+        })
+        .await
+}
+
+/// Remove a saved search.
+#[command]
+pub async fn delete_saved_search(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    id: String,
+) -> Result<(), ExplorerError> {
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("delete_saved_search", move || {
+            let deleted = conn
+                .execute("DELETE FROM saved_searches WHERE id = ?1", params![id])
+                .map_err(|e| format!("Failed to delete saved search: {}", e))?;
+            if deleted == 0 {
+                return Err(ExplorerError::NotFound(format!(
+                    "No such saved search: {}",
+                    id
+                )));
+            }
+            Ok(())
+        })
+        .await
+}
+
+/// Run a saved search again, like `search_files` with its stored arguments.
+///
+/// Matches are streamed on `search://results` with the stored root and
+/// query, and the total is returned once the walk completes.
+#[command]
+pub async fn run_saved_search(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    settings: State<'_, SettingsStore>,
+    id: String,
+) -> Result<SearchSummary, ExplorerError> {
+    let conn = index.connect()?;
+    let search = tasks
+        .run_blocking("load_saved_search", move || load(&conn, &id))
+        .await?;
+    if !Path::new(&search.root).is_dir() {
+        return Err(ExplorerError::NotFound(format!(
+            "Saved search folder no longer exists: {}",
+            search.root
+        )));
+    }
+    search_files(
+        app,
+        tasks,
+        settings,
+        search.root,
+        search.query,
+        Some(search.mode),
+        Some(search.options),
+    )
+    .await
+}
+
+fn load(conn: &Connection, id: &str) -> Result<SavedSearch, ExplorerError> {
+    conn.query_row(
+        "SELECT id, name, root, query, mode, options, created_at FROM saved_searches
+         WHERE id = ?1",
+        params![id],
+        read_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to query saved searches: {}", e))?
+    .flatten()
+    .ok_or_else(|| ExplorerError::NotFound(format!("No such saved search: {}", id)))
+}
+
+/// Decode a row, or `None` if its stored mode or options no longer parse.
+fn read_row(row: &rusqlite::Row) -> rusqlite::Result<Option<SavedSearch>> {
+    let mode: String = row.get(4)?;
+    let options: String = row.get(5)?;
+    let (Ok(mode), Ok(options)) = (serde_json::from_str(&mode), serde_json::from_str(&options))
+    else {
+        return Ok(None);
+    };
+    Ok(Some(SavedSearch {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        root: row.get(2)?,
+        query: row.get(3)?,
+        mode,
+        options,
+        created_at: row.get::<_, i64>(6)? as u64,
+    }))
+}
@@ -0,0 +1,809 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use jwalk::{Parallelism, WalkDirGeneric};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Runtime, State};
+
+use crate::commands::{allocated_size, is_cloud_placeholder, to_unix_secs, ListOptions};
+use crate::drives::{containing_drive, is_solid_state, list_drives, DriveType};
+use crate::error::ExplorerError;
+use crate::exclusions::Exclusions;
+use crate::index::{FileIndex, IndexWriter};
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::settings::SettingsStore;
+use crate::tasks::TaskRegistry;
+
+/// Event name used to report scan progress to the frontend.
+pub const SCAN_PROGRESS_EVENT: &str = "scan://progress";
+
+/// Minimum delay between two progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Upper bound on walker threads, on SSDs and when set explicitly.
+pub const MAX_SCAN_THREADS: usize = 16;
+
+/// Walker threads for network shares, where each listing waits on a round
+/// trip rather than the disk.
+const NETWORK_SCAN_THREADS: usize = 8;
+
+/// Walker threads for drives whose kind is unknown.
+const DEFAULT_SCAN_THREADS: usize = 2;
+
+/// A directory in the scanned tree with its aggregated usage.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DirNode {
+    pub name: String,
+    pub path: String,
+    /// Total size in bytes of all files below this directory, counting
+    /// every hard link to a file.
+    pub apparent_size: u64,
+    /// Like `apparent_size`, but counting the data of hard-linked files only
+    /// at the first link reached in the scan.
+    pub disk_usage: u64,
+    /// Bytes the files below this directory occupy on disk, which is less
+    /// than `disk_usage` for sparse and compressed files. Hard-linked files
+    /// are counted once.
+    pub allocated_size: u64,
+    /// Online-only cloud placeholders below this directory, whose contents
+    /// are not stored locally.
+    pub placeholder_count: u64,
+    /// Apparent size of those placeholders.
+    pub placeholder_size: u64,
+    /// Number of files below this directory (recursively).
+    pub file_count: u64,
+    /// Whether the directory was reached through a followed symlink or junction.
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    pub children: Vec<DirNode>,
+}
+
+impl DirNode {
+    pub fn new(path: &Path) -> Self {
+        DirNode {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string_lossy().to_string()),
+            path: path.to_string_lossy().to_string(),
+            apparent_size: 0,
+            disk_usage: 0,
+            allocated_size: 0,
+            placeholder_count: 0,
+            placeholder_size: 0,
+            file_count: 0,
+            is_symlink: false,
+            symlink_target: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Record that this directory is the target of the symlink at its path.
+    fn mark_symlink(&mut self, path: &Path) {
+        self.is_symlink = true;
+        self.symlink_target = fs::read_link(path)
+            .ok()
+            .map(|target| target.to_string_lossy().to_string());
+    }
+
+    /// Fold a finished child directory into this node.
+    pub fn attach(&mut self, child: DirNode) {
+        self.apparent_size += child.apparent_size;
+        self.disk_usage += child.disk_usage;
+        self.allocated_size += child.allocated_size;
+        self.placeholder_count += child.placeholder_count;
+        self.placeholder_size += child.placeholder_size;
+        self.file_count += child.file_count;
+        self.children.push(child);
+    }
+
+    /// Count a file directly in this directory. Further links to a file
+    /// already counted add nothing to its disk usage or allocated size.
+    pub fn add_file(&mut self, size: u64, allocated_size: u64, first_link: bool) {
+        self.apparent_size += size;
+        if first_link {
+            self.disk_usage += size;
+            self.allocated_size += allocated_size;
+        }
+        self.file_count += 1;
+    }
+
+    /// Count a file directly in this directory that is an online-only cloud
+    /// placeholder, on top of `add_file`.
+    pub fn add_placeholder(&mut self, size: u64) {
+        self.placeholder_count += 1;
+        self.placeholder_size += size;
+    }
+}
+
+/// An entry the scan could not read. Whatever it holds is missing from the
+/// totals of the folders above it.
+#[derive(Serialize, Clone)]
+pub struct ScanError {
+    pub path: String,
+    pub reason: String,
+}
+
+impl ScanError {
+    pub fn new(path: &Path, reason: impl fmt::Display) -> Self {
+        ScanError {
+            path: path.to_string_lossy().to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// A failure reported by the walker, described by the underlying I/O
+    /// error where there is one.
+    fn from_walk(error: &jwalk::Error, fallback: &Path) -> Self {
+        let path = error.path().unwrap_or(fallback);
+        match error.io_error() {
+            Some(io_error) => ScanError::new(path, io_error),
+            None => ScanError::new(path, error),
+        }
+    }
+}
+
+/// Result of `scan_directory`.
+#[derive(Serialize)]
+pub struct ScanReport {
+    pub tree: DirNode,
+    /// Folders that could not be listed and entries whose metadata could not
+    /// be read, in the order they were reached.
+    pub errors: Vec<ScanError>,
+}
+
+/// Options accepted by `scan_directory`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Reuse the index for folders whose modification time is unchanged.
+    pub incremental: bool,
+    /// Descend into symlinked folders. Links that point back to one of their
+    /// own ancestors are reported but not followed.
+    pub follow_symlinks: bool,
+    /// Hidden and system entries are left out of the tree and the index
+    /// when filtered.
+    #[serde(flatten)]
+    pub visibility: ListOptions,
+    /// Threads listing folders in parallel during a full scan. Defaults to
+    /// the scan threads setting, or when that is unset to one on spinning
+    /// disks, where parallel reads only add seeks, and to several on SSDs
+    /// and network shares.
+    pub threads: Option<usize>,
+}
+
+/// Payload of the `scan://progress` event.
+#[derive(Serialize, Clone)]
+pub struct ScanProgress {
+    pub operation_id: String,
+    pub root: String,
+    pub scanned_files: u64,
+    pub scanned_dirs: u64,
+    pub total_size: u64,
+    pub current_path: String,
+}
+
+/// Recursively scan a directory, aggregating sizes and file counts per folder.
+///
+/// Each folder reports its apparent size, its disk usage, which counts files
+/// with several hard links once, and the space allocated to its files on
+/// disk. Links are matched by device and inode on Unix and by volume and
+/// file index on Windows. Online-only cloud placeholders are counted per
+/// folder too; scanning reads only their metadata, so it does not download
+/// them.
+///
+/// Progress is emitted on `scan://progress` while walking; the full tree is
+/// returned once the walk completes. The scan can be paused or cancelled
+/// through `operation_id`. Completed scans replace the folder's entries in
+/// the file index.
+///
+/// With `options.incremental` set, folders whose modification time matches the
+/// index are not listed again: their files are taken from the index and only
+/// their subfolders are visited. Changes to a file's size that leave its
+/// folder's mtime untouched are therefore only picked up by a full scan, and
+/// files taken from the index count at their full length towards disk usage
+/// and allocated size.
+///
+/// Entries matching the exclusion rules are left out of the tree and the index.
+/// Incremental scans run on a single thread, as they mostly wait on the index.
+///
+/// Folders that cannot be listed, for lack of permission or otherwise, do not
+/// stop the scan. They appear in the tree without contents and are listed
+/// with the reason in `errors`, so the UI can tell that the totals above them
+/// are incomplete.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn scan_directory(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    index: State<'_, FileIndex>,
+    settings: State<'_, SettingsStore>,
+    path: String,
+    operation_id: Option<String>,
+    options: Option<ScanOptions>,
+) -> Result<ScanReport, ExplorerError> {
+    let mut options = options.unwrap_or_default();
+    options.threads = options.threads.or(settings.get().scan_threads);
+    // Drop trailing separators so stored paths and parents line up.
+    let path = Path::new(&path)
+        .components()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .to_string();
+    let exclusions = settings.exclusions(Path::new(&path))?;
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    let mut conn = index.connect()?;
+    tasks
+        .run_blocking("scan", move || {
+            let report = if options.incremental {
+                let mut writer = IndexWriter::begin_incremental(&mut conn, &path)?;
+                let report = IncrementalScan::new(&app, &handle, &mut writer, id, &path)
+                    .follow_symlinks(options.follow_symlinks)
+                    .visibility(options.visibility)
+                    .exclusions(exclusions)
+                    .run()?;
+                writer.finish()?;
+                report
+            } else {
+                let mut writer = IndexWriter::begin(&mut conn, &path)?;
+                let report = scan_tree(
+                    &handle,
+                    Some(&mut writer),
+                    id,
+                    path,
+                    &options,
+                    exclusions,
+                    &mut |progress| {
+                        let _ = app.emit(SCAN_PROGRESS_EVENT, progress);
+                    },
+                )?;
+                writer.finish()?;
+                report
+            };
+            Ok(report)
+        })
+        .await
+}
+
+/// Walk `path` and build its tree, listing folders on several threads.
+///
+/// Entries are recorded in `writer` when one is given. `on_progress` is
+/// called every `PROGRESS_INTERVAL` and once more when the walk is done.
+pub fn scan_tree(
+    operation: &OperationHandle,
+    mut writer: Option<&mut IndexWriter>,
+    operation_id: String,
+    path: String,
+    options: &ScanOptions,
+    exclusions: Exclusions,
+    on_progress: &mut dyn FnMut(&ScanProgress),
+) -> Result<ScanReport, ExplorerError> {
+    let root = Path::new(&path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path).into());
+    }
+
+    // Stack of open directories, indexed by depth. When an entry at depth `d`
+    // arrives, every directory deeper than `d - 1` is complete.
+    let mut stack: Vec<DirNode> = Vec::new();
+    let mut progress = ScanProgress {
+        operation_id,
+        root: path.clone(),
+        scanned_files: 0,
+        scanned_dirs: 0,
+        total_size: 0,
+        current_path: path.clone(),
+    };
+    let mut last_emit = Instant::now();
+    let mut hard_links = HardLinks::default();
+    let mut errors = Vec::new();
+
+    let threads = options
+        .threads
+        .unwrap_or_else(|| default_scan_threads(root))
+        .clamp(1, MAX_SCAN_THREADS);
+    let parallelism = match threads {
+        1 => Parallelism::Serial,
+        threads => Parallelism::RayonNewPool(threads),
+    };
+    let (visibility, follow_symlinks) = (options.visibility, options.follow_symlinks);
+    let exclusions = Arc::new(exclusions);
+
+    // Entries come back in depth-first order however many threads list
+    // folders. Metadata, hard link identities and allocated sizes are read
+    // on the walker threads as well.
+    let walker = WalkDirGeneric::<((), Option<EntryInfo>)>::new(root)
+        .sort(true)
+        .skip_hidden(false)
+        .follow_links(follow_symlinks)
+        .parallelism(parallelism)
+        .process_read_dir(move |_, _, _, children| {
+            children.retain_mut(|child| {
+                let Ok(child) = child else {
+                    return true;
+                };
+                let path = child.path();
+                let Ok(meta) = child.metadata() else {
+                    return true;
+                };
+                if !(visibility.shows_all() || visibility.shows(&path, &meta))
+                    || exclusions.excludes(&path, &meta)
+                {
+                    return false;
+                }
+                // Report links back to an ancestor without descending.
+                if child.path_is_symlink() && meta.is_dir() && loops_to_ancestor(&path) {
+                    child.read_children_path = None;
+                }
+                child.client_state = Some(EntryInfo::read(&path, meta));
+                true
+            });
+        });
+    for result in walker {
+        operation.checkpoint()?;
+        // Failed listings arrive in place of the folder's children, so the
+        // folder itself is already on the stack.
+        let mut entry = match result {
+            Ok(entry) => entry,
+            Err(error) => {
+                errors.push(ScanError::from_walk(&error, root));
+                continue;
+            }
+        };
+        let depth = entry.depth();
+        while stack.len() > depth {
+            close_top(&mut stack);
+        }
+
+        let path = entry.path();
+        let info = match entry.client_state.take() {
+            Some(info) => Some(info),
+            None => match entry.metadata() {
+                Ok(meta) => Some(EntryInfo::read(&path, meta)),
+                Err(error) => {
+                    errors.push(ScanError::from_walk(&error, &path));
+                    None
+                }
+            },
+        };
+        if let (Some(info), Some(writer)) = (&info, writer.as_deref_mut()) {
+            writer.insert(&path, &info.meta)?;
+        }
+
+        if entry.file_type().is_dir() {
+            let mut node = DirNode::new(&path);
+            if entry.path_is_symlink() {
+                node.mark_symlink(&path);
+            }
+            stack.push(node);
+            progress.scanned_dirs += 1;
+        } else if let Some(parent) = stack.last_mut() {
+            let size = info.as_ref().map_or(0, |info| info.meta.len());
+            match info {
+                Some(info) => {
+                    parent.add_file(size, info.allocated, hard_links.first(info.id));
+                    if info.placeholder {
+                        parent.add_placeholder(size);
+                    }
+                }
+                None => parent.add_file(0, 0, true),
+            }
+            progress.scanned_files += 1;
+            progress.total_size += size;
+        }
+
+        if last_emit.elapsed() >= PROGRESS_INTERVAL {
+            progress.current_path = path.to_string_lossy().to_string();
+            on_progress(&progress);
+            last_emit = Instant::now();
+        }
+    }
+
+    while stack.len() > 1 {
+        close_top(&mut stack);
+    }
+
+    // Final progress event so the UI ends on accurate totals.
+    progress.current_path = path.clone();
+    on_progress(&progress);
+
+    let tree = stack
+        .pop()
+        .ok_or_else(|| format!("Failed to scan directory: {}", path))?;
+    Ok(ScanReport { tree, errors })
+}
+
+/// Identity of a file: device and inode on Unix, volume serial number and
+/// file index on Windows.
+type FileId = (u64, u64);
+
+/// Hard-linked files seen so far in a scan.
+#[derive(Default)]
+struct HardLinks {
+    seen: HashSet<FileId>,
+}
+
+impl HardLinks {
+    /// Whether a file is not a further link to one already counted.
+    fn first(&mut self, id: Option<FileId>) -> bool {
+        id.is_none_or(|id| self.seen.insert(id))
+    }
+}
+
+/// What the walker threads read about each entry.
+#[derive(Debug)]
+struct EntryInfo {
+    meta: Metadata,
+    id: Option<FileId>,
+    allocated: u64,
+    placeholder: bool,
+}
+
+impl EntryInfo {
+    fn read(path: &Path, meta: Metadata) -> Self {
+        let placeholder = is_cloud_placeholder(&meta);
+        EntryInfo {
+            // Opening a placeholder to read its link count can download it.
+            id: if placeholder {
+                None
+            } else {
+                hard_link_id(path, &meta)
+            },
+            allocated: allocated_size(path, &meta),
+            placeholder,
+            meta,
+        }
+    }
+}
+
+/// Identity of the file at `path` if it has more than one hard link.
+/// Symlinks and folders never share data this way.
+#[cfg(unix)]
+fn hard_link_id(_path: &Path, meta: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    (meta.is_file() && meta.nlink() > 1).then(|| (meta.dev(), meta.ino()))
+}
+
+/// Identity of the file at `path` if it has more than one hard link.
+/// Symlinks and folders never share data this way.
+///
+/// The link count is not part of the directory listing on Windows, so the
+/// file is opened to read it.
+#[cfg(windows)]
+fn hard_link_id(path: &Path, meta: &Metadata) -> Option<FileId> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_SHARE_DELETE, FILE_SHARE_READ,
+        FILE_SHARE_WRITE,
+    };
+
+    if !meta.is_file() {
+        return None;
+    }
+    // Querying attributes needs no access rights, so files locked by other
+    // programs open too.
+    let file = fs::OpenOptions::new()
+        .access_mode(0)
+        .share_mode((FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE).0)
+        .open(path)
+        .ok()?;
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle()), &mut info) }.ok()?;
+    (info.nNumberOfLinks > 1).then(|| {
+        let index = (info.nFileIndexHigh as u64) << 32 | info.nFileIndexLow as u64;
+        (info.dwVolumeSerialNumber as u64, index)
+    })
+}
+
+/// Walker threads suited to the drive holding `path`.
+fn default_scan_threads(path: &Path) -> usize {
+    let drives = list_drives();
+    let Some(drive) = containing_drive(&drives, path) else {
+        return DEFAULT_SCAN_THREADS;
+    };
+    if drive.drive_type == DriveType::Network {
+        return NETWORK_SCAN_THREADS;
+    }
+    match is_solid_state(&drive.mount_point) {
+        Some(true) => thread::available_parallelism().map_or(DEFAULT_SCAN_THREADS, |n| n.get()),
+        Some(false) => 1,
+        None => DEFAULT_SCAN_THREADS,
+    }
+}
+
+/// Pop the deepest open directory and attach it to its parent.
+fn close_top(stack: &mut Vec<DirNode>) {
+    if let Some(done) = stack.pop() {
+        if let Some(parent) = stack.last_mut() {
+            parent.attach(done);
+        }
+    }
+}
+
+/// State of a scan that reuses the index for unchanged folders.
+struct IncrementalScan<'a, 'conn, R: Runtime> {
+    app: &'a AppHandle<R>,
+    operation: &'a OperationHandle,
+    writer: &'a mut IndexWriter<'conn>,
+    hard_links: HardLinks,
+    errors: Vec<ScanError>,
+    follow_symlinks: bool,
+    /// Canonical paths of the folders being visited, when links are followed.
+    ancestors: HashSet<PathBuf>,
+    visibility: ListOptions,
+    exclusions: Option<Exclusions>,
+    progress: ScanProgress,
+    last_emit: Instant,
+}
+
+impl<'a, 'conn, R: Runtime> IncrementalScan<'a, 'conn, R> {
+    fn new(
+        app: &'a AppHandle<R>,
+        operation: &'a OperationHandle,
+        writer: &'a mut IndexWriter<'conn>,
+        operation_id: String,
+        root: &str,
+    ) -> Self {
+        IncrementalScan {
+            app,
+            operation,
+            writer,
+            hard_links: HardLinks::default(),
+            errors: Vec::new(),
+            follow_symlinks: false,
+            ancestors: HashSet::new(),
+            visibility: ListOptions::default(),
+            exclusions: None,
+            progress: ScanProgress {
+                operation_id,
+                root: root.to_string(),
+                scanned_files: 0,
+                scanned_dirs: 0,
+                total_size: 0,
+                current_path: root.to_string(),
+            },
+            last_emit: Instant::now(),
+        }
+    }
+
+    fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    fn visibility(mut self, visibility: ListOptions) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    fn exclusions(mut self, exclusions: Exclusions) -> Self {
+        self.exclusions = Some(exclusions);
+        self
+    }
+
+    fn run(mut self) -> Result<ScanReport, ExplorerError> {
+        let root = PathBuf::from(&self.progress.root);
+        let meta = fs::metadata(&root)
+            .ok()
+            .filter(|meta| meta.is_dir())
+            .ok_or_else(|| format!("Not a directory: {}", root.display()))?;
+        let tree = self.visit(&root, &meta)?;
+
+        // Final progress event so the UI ends on accurate totals.
+        self.progress.current_path = self.progress.root.clone();
+        let _ = self.app.emit(SCAN_PROGRESS_EVENT, &self.progress);
+        Ok(ScanReport {
+            tree,
+            errors: self.errors,
+        })
+    }
+
+    fn visit(&mut self, dir: &Path, meta: &Metadata) -> Result<DirNode, ExplorerError> {
+        let canonical = if self.follow_symlinks {
+            fs::canonicalize(dir).ok()
+        } else {
+            None
+        };
+        if let Some(canonical) = &canonical {
+            self.ancestors.insert(canonical.clone());
+        }
+        let node = self.visit_entries(dir, meta);
+        if let Some(canonical) = &canonical {
+            self.ancestors.remove(canonical);
+        }
+        node
+    }
+
+    fn visit_entries(&mut self, dir: &Path, meta: &Metadata) -> Result<DirNode, ExplorerError> {
+        self.operation.checkpoint()?;
+        self.progress.scanned_dirs += 1;
+        self.maybe_emit(dir);
+
+        let mut node = DirNode::new(dir);
+        if self.follow_symlinks && fs::symlink_metadata(dir).is_ok_and(|m| m.is_symlink()) {
+            node.mark_symlink(dir);
+        }
+        let mtime = to_unix_secs(meta.modified());
+        if mtime.is_some() && self.writer.dir_mtime(dir)? == mtime {
+            // Same entries as last time: only subfolders need to be visited.
+            for child in self.writer.children(dir)? {
+                if !self.is_visible(Path::new(&child.path)) {
+                    self.writer.remove(&child.path)?;
+                    continue;
+                }
+                if !child.is_dir {
+                    self.writer.keep(&child);
+                    self.add_file(&mut node, child.size, child.size, None);
+                    continue;
+                }
+                match self.child_metadata(Path::new(&child.path)) {
+                    Some(child_meta) if child_meta.is_dir() => {
+                        node.attach(self.visit(Path::new(&child.path), &child_meta)?)
+                    }
+                    _ => self.writer.remove(&child.path)?,
+                }
+            }
+            return Ok(node);
+        }
+
+        self.writer.insert(dir, meta)?;
+        let entries: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| self.is_visible(path))
+                .collect(),
+            Err(error) => {
+                self.errors.push(ScanError::new(dir, error));
+                Vec::new()
+            }
+        };
+        let present: HashSet<String> = entries
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        for stale in self.writer.children(dir)? {
+            if !present.contains(&stale.path) {
+                self.writer.remove(&stale.path)?;
+            }
+        }
+
+        for path in entries {
+            self.operation.checkpoint()?;
+            let Some(child_meta) = self.child_metadata(&path) else {
+                continue;
+            };
+            if child_meta.is_dir() {
+                node.attach(self.visit(&path, &child_meta)?);
+            } else {
+                self.writer.insert(&path, &child_meta)?;
+                let info = EntryInfo::read(&path, child_meta);
+                self.add_file(&mut node, info.meta.len(), info.allocated, info.id);
+                if info.placeholder {
+                    node.add_placeholder(info.meta.len());
+                }
+            }
+        }
+        Ok(node)
+    }
+
+    /// Whether an entry passes the hidden and system filters and is not
+    /// excluded.
+    fn is_visible(&self, path: &Path) -> bool {
+        if self.visibility.shows_all() && self.exclusions.is_none() {
+            return true;
+        }
+        fs::symlink_metadata(path).is_ok_and(|meta| {
+            (self.visibility.shows_all() || self.visibility.shows(path, &meta))
+                && !self
+                    .exclusions
+                    .as_ref()
+                    .is_some_and(|exclusions| exclusions.excludes(path, &meta))
+        })
+    }
+
+    /// Metadata of an entry, resolving symlinked folders when links are
+    /// followed. Broken links and links to a folder being visited, which
+    /// would loop, are returned as the link itself. Entries that cannot be
+    /// read are recorded as errors.
+    fn child_metadata(&mut self, path: &Path) -> Option<Metadata> {
+        let meta = match fs::symlink_metadata(path) {
+            Ok(meta) => meta,
+            Err(error) => {
+                // Entries deleted since they were listed or indexed are
+                // simply gone.
+                if error.kind() != io::ErrorKind::NotFound {
+                    self.errors.push(ScanError::new(path, error));
+                }
+                return None;
+            }
+        };
+        if !self.follow_symlinks || !meta.is_symlink() {
+            return Some(meta);
+        }
+        match fs::metadata(path) {
+            Ok(target) if !target.is_dir() || !self.loops_back(path) => Some(target),
+            _ => Some(meta),
+        }
+    }
+
+    /// Whether the symlink at `link` resolves to a folder being visited.
+    /// Links that cannot be resolved are not followed either.
+    fn loops_back(&self, link: &Path) -> bool {
+        fs::canonicalize(link).map_or(true, |target| self.ancestors.contains(&target))
+    }
+
+    fn add_file(&mut self, node: &mut DirNode, size: u64, allocated: u64, id: Option<FileId>) {
+        node.add_file(size, allocated, self.hard_links.first(id));
+        self.progress.scanned_files += 1;
+        self.progress.total_size += size;
+    }
+
+    fn maybe_emit(&mut self, current: &Path) {
+        if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.progress.current_path = current.to_string_lossy().to_string();
+            let _ = self.app.emit(SCAN_PROGRESS_EVENT, &self.progress);
+            self.last_emit = Instant::now();
+        }
+    }
+}
+
+/// Whether the symlink at `link` resolves to one of its own ancestors.
+/// Other loops, through several links, are caught by jwalk's own check.
+fn loops_to_ancestor(link: &Path) -> bool {
+    let parent = link.parent().and_then(|p| fs::canonicalize(p).ok());
+    match (parent, fs::canonicalize(link)) {
+        (Some(parent), Ok(target)) => parent.starts_with(target),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[cfg(unix)]
+    #[test]
+    fn mutual_links_are_followed_once() {
+        let dir =
+            std::env::temp_dir().join(format!("ude-scanner-{}", uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::write(dir.join("a").join("ten"), [0u8; 10]).unwrap();
+        fs::write(dir.join("b").join("twenty"), [0u8; 20]).unwrap();
+        std::os::unix::fs::symlink("../b", dir.join("a").join("l")).unwrap();
+        std::os::unix::fs::symlink("../a", dir.join("b").join("l")).unwrap();
+
+        let app = tauri::test::mock_app();
+        let operation = OperationHandle::default();
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(crate::index::SCHEMA).unwrap();
+        let root = dir.to_string_lossy().to_string();
+        let mut writer = IndexWriter::begin_incremental(&mut conn, &root).unwrap();
+        let report =
+            IncrementalScan::new(app.handle(), &operation, &mut writer, "test".into(), &root)
+                .follow_symlinks(true)
+                .run();
+        drop(writer);
+
+        let _ = fs::remove_dir_all(&dir);
+        // a and the b reached through a/l, b and the a reached through b/l,
+        // plus the two 4-byte links leading back to them, which are not followed.
+        assert_eq!(
+            report.unwrap().tree.apparent_size,
+            10 + 20 + 20 + 10 + 4 + 4
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use chrono::{Local, TimeZone};
+use croner::Cron;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+use crate::index::{index_tree, FileIndex, IndexedRoot};
+use crate::operations::OperationRegistry;
+use crate::snapshots::{take_snapshot, SnapshotInfo};
+use crate::tasks::TaskRegistry;
+
+/// Event emitted when a scheduled scan finishes or fails.
+pub const SCHEDULED_SCAN_EVENT: &str = "schedule://scan-complete";
+
+/// Name given to the snapshot recorded after each scheduled scan.
+const SNAPSHOT_NAME: &str = "Scheduled scan";
+
+/// How often the scheduler checks for due scans.
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+
+/// A periodic scan created with `create_schedule`.
+#[derive(Serialize)]
+pub struct ScheduleInfo {
+    pub id: String,
+    pub path: String,
+    pub cron_expr: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    /// Seconds since the Unix epoch of the last completed or failed run.
+    pub last_run: Option<u64>,
+    pub last_error: Option<String>,
+    /// Seconds since the Unix epoch. Runs missed while the app was closed are
+    /// caught up shortly after startup.
+    pub next_run: Option<u64>,
+}
+
+/// Payload of the `schedule://scan-complete` event.
+#[derive(Serialize, Clone)]
+pub struct ScheduledScan {
+    pub schedule_id: String,
+    /// Id under which the scan was registered, for `cancel_operation`.
+    pub operation_id: String,
+    pub path: String,
+    pub root: Option<IndexedRoot>,
+    /// Folder sizes recorded after the scan, for `diff_snapshots`.
+    pub snapshot: Option<SnapshotInfo>,
+    pub error: Option<String>,
+}
+
+/// Scan `path` into the index whenever `cron_expr` matches.
+///
+/// Expressions use the standard five fields (`minute hour day month weekday`),
+/// with an optional leading seconds field, and are evaluated in local time.
+/// Scans only run while the app is open. Each run also stores a snapshot, so
+/// growth between runs can be checked with `diff_snapshots`.
+#[command]
+pub async fn create_schedule(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    path: String,
+    cron_expr: String,
+) -> Result<ScheduleInfo, ExplorerError> {
+    if !Path::new(&path).is_dir() {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Not a directory: {}",
+            path
+        )));
+    }
+    parse_cron(&cron_expr)?;
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("create_schedule", move || {
+            let schedule = ScheduleInfo {
+                id: uuid::Uuid::new_v4().to_string(),
+                created_at: now(),
+                last_run: None,
+                last_error: None,
+                next_run: None,
+                path,
+                cron_expr,
+            };
+            conn.execute(
+                "INSERT INTO schedules (id, path, cron_expr, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    schedule.id,
+                    schedule.path,
+                    schedule.cron_expr,
+                    schedule.created_at as i64
+                ],
+            )
+            .map_err(|e| format!("Failed to save schedule: {}", e))?;
+            Ok(ScheduleInfo {
+                next_run: next_run(&schedule),
+                ..schedule
+            })
+        })
+        .await
+}
+
+/// List scheduled scans with their next run time.
+#[command]
+pub async fn list_schedules(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+) -> Result<Vec<ScheduleInfo>, ExplorerError> {
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("list_schedules", move || Ok(load_schedules(&conn)?))
+        .await
+}
+
+/// Remove a scheduled scan. A run already in progress is not cancelled.
+#[command]
+pub async fn delete_schedule(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    id: String,
+) -> Result<(), ExplorerError> {
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("delete_schedule", move || {
+            let deleted = conn
+                .execute("DELETE FROM schedules WHERE id = ?1", params![id])
+                .map_err(|e| format!("Failed to delete schedule: {}", e))?;
+            if deleted == 0 {
+                return Err(ExplorerError::NotFound(format!("No such schedule: {}", id)));
+            }
+            Ok(())
+        })
+        .await
+}
+
+/// Start the background thread that runs due scans.
+///
+/// Scans run one at a time on this thread, each registered as an operation
+/// so it shows up in `list_operations` and can be cancelled.
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SCHEDULER_TICK);
+        let Ok(mut conn) = app.state::<FileIndex>().connect() else {
+            continue;
+        };
+        let Ok(schedules) = load_schedules(&conn) else {
+            continue;
+        };
+        for schedule in schedules {
+            if schedule.next_run.is_some_and(|next| next <= now()) {
+                run_schedule(&app, &mut conn, &schedule);
+            }
+        }
+    });
+}
+
+fn run_schedule(app: &AppHandle, conn: &mut Connection, schedule: &ScheduleInfo) {
+    let operations = app.state::<OperationRegistry>();
+    let operation_id = format!("schedule-{}", schedule.id);
+    // Still running from an earlier tick, or started by hand.
+    let Ok(operation) = operations.start(Some(operation_id.clone())) else {
+        return;
+    };
+    let handle = operation.handle();
+    let result = index_tree(conn, &handle, &schedule.path).and_then(|root| {
+        let snapshot = take_snapshot(conn, &handle, &schedule.path, Some(SNAPSHOT_NAME.into()))?;
+        Ok((root, snapshot))
+    });
+    drop(operation);
+
+    let error = result.as_ref().err().map(|e| e.to_string());
+    let (root, snapshot) = result.ok().unzip();
+    let _ = conn.execute(
+        "UPDATE schedules SET last_run = ?1, last_error = ?2 WHERE id = ?3",
+        params![now() as i64, error, schedule.id],
+    );
+    let _ = app.emit(
+        SCHEDULED_SCAN_EVENT,
+        ScheduledScan {
+            schedule_id: schedule.id.clone(),
+            operation_id,
+            path: schedule.path.clone(),
+            root,
+            snapshot,
+            error,
+        },
+    );
+}
+
+fn load_schedules(conn: &Connection) -> Result<Vec<ScheduleInfo>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, path, cron_expr, created_at, last_run, last_error FROM schedules
+             ORDER BY created_at",
+        )
+        .map_err(|e| format!("Failed to query schedules: {}", e))?;
+    stmt.query_map([], |row| {
+        Ok(ScheduleInfo {
+            id: row.get(0)?,
+            path: row.get(1)?,
+            cron_expr: row.get(2)?,
+            created_at: row.get::<_, i64>(3)? as u64,
+            last_run: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+            last_error: row.get(5)?,
+            next_run: None,
+        })
+    })
+    .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+    .map(|schedules| {
+        schedules
+            .into_iter()
+            .map(|schedule| ScheduleInfo {
+                next_run: next_run(&schedule),
+                ..schedule
+            })
+            .collect()
+    })
+    .map_err(|e| format!("Failed to query schedules: {}", e))
+}
+
+fn parse_cron(expr: &str) -> Result<Cron, String> {
+    Cron::new(expr)
+        .with_seconds_optional()
+        .parse()
+        .map_err(|e| format!("Invalid cron expression {:?}: {}", expr, e))
+}
+
+/// First match of the schedule after its last run, or after its creation.
+fn next_run(schedule: &ScheduleInfo) -> Option<u64> {
+    let cron = parse_cron(&schedule.cron_expr).ok()?;
+    let after = schedule.last_run.unwrap_or(schedule.created_at);
+    let after = Local.timestamp_opt(after as i64, 0).single()?;
+    let next = cron.find_next_occurrence(&after, false).ok()?;
+    Some(next.timestamp().max(0) as u64)
+}
+
+fn now() -> u64 {
+    to_unix_secs(Ok(SystemTime::now())).unwrap_or(0)
+}
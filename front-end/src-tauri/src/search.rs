@@ -0,0 +1,503 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use globset::{GlobBuilder, GlobMatcher};
+use grep_regex::RegexMatcherBuilder;
+use grep_searcher::sinks::Lossy;
+use grep_searcher::{BinaryDetection, SearcherBuilder};
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, State};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+use crate::exclusions::Exclusions;
+use crate::settings::SettingsStore;
+use crate::tasks::TaskRegistry;
+
+/// Event name used to stream batches of search results.
+pub const SEARCH_RESULTS_EVENT: &str = "search://results";
+
+/// Number of matches collected before a batch is emitted.
+const BATCH_SIZE: usize = 200;
+
+/// Default cap on the number of content matches returned.
+const DEFAULT_MAX_CONTENT_MATCHES: usize = 1000;
+
+/// Longest line snippet returned for a content match, in characters.
+const MAX_SNIPPET_CHARS: usize = 300;
+
+/// Seconds in a day, for the age filters.
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// How the query is interpreted.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    #[default]
+    Substring,
+    Glob,
+    Regex,
+}
+
+/// Extensions counted as documents by `FileKind::Document`.
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "tex", "xls", "xlsx", "ods", "csv", "ppt",
+    "pptx", "odp", "epub", "pages", "numbers", "key",
+];
+
+/// Extensions counted as archives by `FileKind::Archive`.
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    "zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "tbz2", "xz", "txz", "zst", "lz", "lzma", "cab",
+];
+
+/// Options accepted by `search_files`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    pub include_hidden: bool,
+    pub filters: SearchFilters,
+}
+
+/// Broad file categories, by extension.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+}
+
+impl FileKind {
+    fn matches(self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match self {
+            FileKind::Image => mime_type(path) == "image",
+            FileKind::Video => mime_type(path) == "video",
+            FileKind::Audio => mime_type(path) == "audio",
+            FileKind::Document => DOCUMENT_EXTENSIONS.contains(&extension.as_str()),
+            FileKind::Archive => ARCHIVE_EXTENSIONS.contains(&extension.as_str()),
+        }
+    }
+}
+
+/// Top-level MIME type implied by the file name, e.g. `image`.
+fn mime_type(path: &Path) -> String {
+    mime_guess::from_path(path)
+        .first()
+        .map(|mime| mime.type_().to_string())
+        .unwrap_or_default()
+}
+
+/// Conditions a match must meet on top of its name. Unset fields match
+/// everything.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Smallest file size in bytes.
+    pub min_size: Option<u64>,
+    /// Largest file size in bytes.
+    pub max_size: Option<u64>,
+    /// Only entries modified at or after this time, in Unix seconds.
+    pub modified_after: Option<u64>,
+    /// Only entries modified at or before this time, in Unix seconds.
+    pub modified_before: Option<u64>,
+    /// Only entries last modified at least this many days before the search
+    /// runs. Unlike `modified_before`, this stays relative in saved searches.
+    pub min_age_days: Option<u64>,
+    /// Only entries last modified at most this many days before the search
+    /// runs.
+    pub max_age_days: Option<u64>,
+    /// Accepted extensions, without the dot and in any case.
+    pub extensions: Vec<String>,
+    /// Accepted kinds of file.
+    pub kinds: Vec<FileKind>,
+}
+
+impl SearchFilters {
+    /// Check the filters and turn ages into modification times as of now.
+    pub fn validate(mut self) -> Result<Self, ExplorerError> {
+        let now = to_unix_secs(Ok(SystemTime::now())).unwrap_or(0);
+        if let Some(days) = self.min_age_days.take() {
+            let before = now.saturating_sub(days.saturating_mul(SECS_PER_DAY));
+            self.modified_before = Some(self.modified_before.map_or(before, |t| t.min(before)));
+        }
+        if let Some(days) = self.max_age_days.take() {
+            let after = now.saturating_sub(days.saturating_mul(SECS_PER_DAY));
+            self.modified_after = Some(self.modified_after.map_or(after, |t| t.max(after)));
+        }
+        if let (Some(min), Some(max)) = (self.min_size, self.max_size) {
+            if min > max {
+                return Err(ExplorerError::InvalidInput(
+                    "Minimum size is larger than the maximum".to_string(),
+                ));
+            }
+        }
+        if let (Some(after), Some(before)) = (self.modified_after, self.modified_before) {
+            if after > before {
+                return Err(ExplorerError::InvalidInput(
+                    "Modified-after date is later than the modified-before date".to_string(),
+                ));
+            }
+        }
+        for extension in &mut self.extensions {
+            *extension = extension.trim_start_matches('.').to_lowercase();
+        }
+        Ok(self)
+    }
+
+    /// Whether any filter only files can pass is set.
+    fn files_only(&self) -> bool {
+        self.min_size.is_some()
+            || self.max_size.is_some()
+            || !self.extensions.is_empty()
+            || !self.kinds.is_empty()
+    }
+
+    fn matches(&self, entry: &DirEntry) -> bool {
+        let is_dir = entry.file_type().is_dir();
+        if is_dir && self.files_only() {
+            return false;
+        }
+        if !self.extensions.is_empty() {
+            let extension = entry
+                .path()
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase());
+            if !extension.is_some_and(|ext| self.extensions.contains(&ext)) {
+                return false;
+            }
+        }
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|kind| kind.matches(entry.path())) {
+            return false;
+        }
+        let needs_metadata = self.min_size.is_some()
+            || self.max_size.is_some()
+            || self.modified_after.is_some()
+            || self.modified_before.is_some();
+        if !needs_metadata {
+            return true;
+        }
+        let Ok(meta) = entry.metadata() else {
+            return false;
+        };
+        let size = meta.len();
+        let modified = to_unix_secs(meta.modified());
+        self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+            && self
+                .modified_after
+                .is_none_or(|after| modified.is_some_and(|time| time >= after))
+            && self
+                .modified_before
+                .is_none_or(|before| modified.is_some_and(|time| time <= before))
+    }
+}
+
+/// A single file or folder whose name matched the query.
+#[derive(Serialize, Clone)]
+pub struct SearchMatch {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+/// Payload of the `search://results` event.
+#[derive(Serialize, Clone)]
+pub struct SearchBatch {
+    pub root: String,
+    pub query: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Summary returned once the search completes.
+#[derive(Serialize)]
+pub struct SearchSummary {
+    pub total_matches: u64,
+}
+
+/// Compiled form of the query.
+enum Matcher {
+    Substring {
+        needle: String,
+        case_sensitive: bool,
+    },
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn new(query: &str, mode: SearchMode, case_sensitive: bool) -> Result<Self, String> {
+        Ok(match mode {
+            SearchMode::Substring => Matcher::Substring {
+                needle: if case_sensitive {
+                    query.to_string()
+                } else {
+                    query.to_lowercase()
+                },
+                case_sensitive,
+            },
+            SearchMode::Glob => Matcher::Glob(
+                GlobBuilder::new(query)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|e| format!("Invalid glob pattern: {}", e))?
+                    .compile_matcher(),
+            ),
+            SearchMode::Regex => Matcher::Regex(
+                RegexBuilder::new(query)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+                    .map_err(|e| format!("Invalid regular expression: {}", e))?,
+            ),
+        })
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        match self {
+            Matcher::Substring {
+                needle,
+                case_sensitive: true,
+            } => name.contains(needle.as_str()),
+            Matcher::Substring { needle, .. } => name.to_lowercase().contains(needle.as_str()),
+            Matcher::Glob(glob) => glob.is_match(name),
+            Matcher::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Search file and folder names below `root`.
+///
+/// Matches are streamed in batches on `search://results`; the command itself
+/// only returns the total once the walk completes. Entries matching the
+/// exclusion rules are skipped.
+///
+/// `options.filters` narrows the matches by size, modification time,
+/// extension and kind while walking, so only the final matches reach the
+/// frontend. Size, extension and kind filters only match files; the
+/// modification time filters match folders too.
+#[command]
+pub async fn search_files(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    settings: State<'_, SettingsStore>,
+    root: String,
+    query: String,
+    mode: Option<SearchMode>,
+    options: Option<SearchOptions>,
+) -> Result<SearchSummary, ExplorerError> {
+    let mut options = options.unwrap_or_default();
+    options.filters = options.filters.validate()?;
+    let matcher = Matcher::new(&query, mode.unwrap_or_default(), options.case_sensitive)?;
+    let exclusions = settings.exclusions(Path::new(&root))?;
+    tasks
+        .run_blocking("search", move || {
+            Ok(run_search(
+                &app,
+                &root,
+                &query,
+                &matcher,
+                &options,
+                &exclusions,
+            )?)
+        })
+        .await
+}
+
+fn run_search(
+    app: &AppHandle,
+    root: &str,
+    query: &str,
+    matcher: &Matcher,
+    options: &SearchOptions,
+    exclusions: &Exclusions,
+) -> Result<SearchSummary, String> {
+    if !Path::new(root).is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut total_matches = 0;
+    let emit = |matches: Vec<SearchMatch>| {
+        let _ = app.emit(
+            SEARCH_RESULTS_EVENT,
+            SearchBatch {
+                root: root.to_string(),
+                query: query.to_string(),
+                matches,
+            },
+        );
+    };
+
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| {
+            (options.include_hidden || !is_hidden(entry))
+                && entry
+                    .metadata()
+                    .map_or(true, |meta| !exclusions.excludes(entry.path(), &meta))
+        });
+    for entry in walker.flatten() {
+        let name = entry.file_name().to_string_lossy();
+        if !matcher.is_match(&name) || !options.filters.matches(&entry) {
+            continue;
+        }
+
+        let is_dir = entry.file_type().is_dir();
+        batch.push(SearchMatch {
+            name: name.to_string(),
+            path: entry.path().to_string_lossy().to_string(),
+            is_dir,
+            size: if is_dir {
+                0
+            } else {
+                entry.metadata().map(|m| m.len()).unwrap_or(0)
+            },
+        });
+        total_matches += 1;
+
+        if batch.len() >= BATCH_SIZE {
+            emit(std::mem::replace(
+                &mut batch,
+                Vec::with_capacity(BATCH_SIZE),
+            ));
+        }
+    }
+    if !batch.is_empty() {
+        emit(batch);
+    }
+
+    Ok(SearchSummary { total_matches })
+}
+
+/// Whether an entry is a dotfile.
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// Options accepted by `search_content`.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ContentSearchOptions {
+    pub case_sensitive: bool,
+    pub include_hidden: bool,
+    /// Treat the pattern as a literal string instead of a regular expression.
+    pub fixed_string: bool,
+    /// Skip files excluded by `.gitignore` and similar ignore files.
+    pub respect_gitignore: bool,
+    pub max_results: usize,
+}
+
+impl Default for ContentSearchOptions {
+    fn default() -> Self {
+        ContentSearchOptions {
+            case_sensitive: false,
+            include_hidden: false,
+            fixed_string: false,
+            respect_gitignore: true,
+            max_results: DEFAULT_MAX_CONTENT_MATCHES,
+        }
+    }
+}
+
+/// A line inside a file that matched the content pattern.
+#[derive(Serialize, Clone)]
+pub struct ContentMatch {
+    pub path: String,
+    pub line_number: u64,
+    /// The matched line, trimmed and truncated for display.
+    pub snippet: String,
+}
+
+/// Search inside text files below `root` for `pattern`.
+///
+/// Binary files are detected by the presence of NUL bytes and skipped, as
+/// are entries matching the exclusion rules.
+#[command]
+pub async fn search_content(
+    tasks: State<'_, TaskRegistry>,
+    settings: State<'_, SettingsStore>,
+    root: String,
+    pattern: String,
+    options: Option<ContentSearchOptions>,
+) -> Result<Vec<ContentMatch>, ExplorerError> {
+    let options = options.unwrap_or_default();
+    let exclusions = settings.exclusions(Path::new(&root))?;
+    tasks
+        .run_blocking("search_content", move || {
+            Ok(run_content_search(&root, &pattern, &options, exclusions)?)
+        })
+        .await
+}
+
+fn run_content_search(
+    root: &str,
+    pattern: &str,
+    options: &ContentSearchOptions,
+    exclusions: Exclusions,
+) -> Result<Vec<ContentMatch>, String> {
+    if !Path::new(root).exists() {
+        return Err(format!("Path does not exist: {}", root));
+    }
+
+    let matcher = RegexMatcherBuilder::new()
+        .case_insensitive(!options.case_sensitive)
+        .fixed_strings(options.fixed_string)
+        .build(pattern)
+        .map_err(|e| format!("Invalid search pattern: {}", e))?;
+    let mut searcher = SearcherBuilder::new()
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .line_number(true)
+        .build();
+
+    let walker = WalkBuilder::new(root)
+        .hidden(!options.include_hidden)
+        .git_ignore(options.respect_gitignore)
+        .git_global(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .ignore(options.respect_gitignore)
+        .filter_entry(move |entry| {
+            entry.depth() == 0
+                || entry
+                    .metadata()
+                    .map_or(true, |meta| !exclusions.excludes(entry.path(), &meta))
+        })
+        .build();
+
+    let mut matches = Vec::new();
+    for entry in walker.flatten() {
+        if matches.len() >= options.max_results {
+            break;
+        }
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+
+        let path = entry.path().to_string_lossy().to_string();
+        // Unreadable files are skipped rather than failing the whole search.
+        let _ = searcher.search_path(
+            &matcher,
+            entry.path(),
+            Lossy(|line_number, line| {
+                matches.push(ContentMatch {
+                    path: path.clone(),
+                    line_number,
+                    snippet: line.trim().chars().take(MAX_SNIPPET_CHARS).collect(),
+                });
+                Ok(matches.len() < options.max_results)
+            }),
+        );
+    }
+
+    Ok(matches)
+}
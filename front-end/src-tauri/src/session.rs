@@ -0,0 +1,183 @@
+//! The open windows, tabs and running scans, saved as they change and
+//! brought back on the next start.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State, Window};
+
+use crate::commands::{app_data_dir, write_json_atomic};
+use crate::error::ExplorerError;
+use crate::operations::OperationRegistry;
+use crate::windows::{open_window, Tab, WindowState, WindowStates};
+
+/// File name of the session inside the app data directory.
+const SESSION_FILE_NAME: &str = "session.json";
+
+/// Label of the window created at startup.
+const MAIN_WINDOW_LABEL: &str = "main";
+
+/// How long a change waits before the session is written, so a burst of
+/// changes is written once.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// A window as saved in the session.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct SavedWindow {
+    current_path: Option<String>,
+    selection: Vec<String>,
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    /// Folders being scanned when the session was saved.
+    scans: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Session {
+    /// The main window first, then the others.
+    windows: Vec<SavedWindow>,
+}
+
+/// Managed state holding the session saved by the previous run until it is
+/// restored, and writing the current one in the background.
+pub struct SessionStore {
+    saved: Mutex<Option<Session>>,
+    writer: Arc<SessionWriter>,
+}
+
+/// Writes the latest session to disk, off the threads that change it.
+struct SessionWriter {
+    file: PathBuf,
+    /// Session waiting to be written.
+    pending: Mutex<Option<Session>>,
+    changed: Condvar,
+    /// Held while writing, so an older session never lands after a newer
+    /// one.
+    writing: Mutex<()>,
+}
+
+impl SessionWriter {
+    /// Write the pending session, if any.
+    fn flush(&self) {
+        let _writing = self.writing.lock().unwrap();
+        if let Some(session) = self.pending.lock().unwrap().take() {
+            // Failures are ignored; a stale session is not worth
+            // interrupting the user for.
+            let _ = write_json_atomic(&self.file, &session);
+        }
+    }
+}
+
+impl SessionStore {
+    /// Read the session saved by the previous run. It is read before any
+    /// window can save over it; a missing or unreadable file restores
+    /// nothing.
+    pub fn init(app: &AppHandle) -> Result<Self, String> {
+        let file = app_data_dir(app)?.join(SESSION_FILE_NAME);
+        let saved = fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+        let writer = Arc::new(SessionWriter {
+            file,
+            pending: Mutex::new(None),
+            changed: Condvar::new(),
+            writing: Mutex::new(()),
+        });
+        let background = writer.clone();
+        thread::spawn(move || loop {
+            drop(
+                background
+                    .changed
+                    .wait_while(background.pending.lock().unwrap(), |pending| {
+                        pending.is_none()
+                    })
+                    .unwrap(),
+            );
+            thread::sleep(SAVE_DELAY);
+            background.flush();
+        });
+        Ok(SessionStore {
+            saved: Mutex::new(saved),
+            writer,
+        })
+    }
+
+    /// Save `windows` as the session to restore. The session is written in
+    /// the background shortly after, once for a burst of changes.
+    pub fn save(&self, windows: &HashMap<String, WindowState>, operations: &OperationRegistry) {
+        let mut states: Vec<&WindowState> = windows.values().collect();
+        states.sort_by_key(|state| (state.label != MAIN_WINDOW_LABEL, &state.label));
+        let session = Session {
+            windows: states
+                .into_iter()
+                .map(|state| SavedWindow {
+                    current_path: state.current_path.clone(),
+                    selection: state.selection.clone(),
+                    tabs: state.tabs.clone(),
+                    active_tab: state.active_tab,
+                    scans: state
+                        .operations
+                        .iter()
+                        .filter(|operation| operations.is_running(&operation.id))
+                        .filter_map(|operation| operation.scan_path.clone())
+                        .collect(),
+                })
+                .collect(),
+        };
+        *self.writer.pending.lock().unwrap() = Some(session);
+        self.writer.changed.notify_one();
+    }
+
+    /// Write the session saved last right away, for when the app is about to
+    /// exit.
+    pub fn flush(&self) {
+        self.writer.flush();
+    }
+}
+
+/// Bring back the session saved when the app last exited. Call it from the
+/// main window at startup.
+///
+/// The first saved window is restored into the calling window and returned;
+/// the others are opened again and read theirs with `get_window_state`.
+/// Scans that were running are listed in `interrupted_scans` to be started
+/// again. After the first call, so when the main window is reloaded, or
+/// without a saved session, the calling window's current state is returned.
+#[command]
+pub async fn restore_session(
+    app: AppHandle,
+    window: Window,
+    states: State<'_, WindowStates>,
+    session: State<'_, SessionStore>,
+) -> Result<WindowState, ExplorerError> {
+    let label = window.label().to_string();
+    let Some(saved) = session.saved.lock().unwrap().take() else {
+        return Ok(states.get(&label));
+    };
+    let mut saved = saved.windows.into_iter().map(|window| WindowState {
+        current_path: window.current_path,
+        selection: window.selection,
+        active_tab: window.active_tab.min(window.tabs.len().saturating_sub(1)),
+        tabs: window.tabs,
+        interrupted_scans: window.scans,
+        ..Default::default()
+    });
+    let Some(restored) = saved.next() else {
+        return Ok(states.get(&label));
+    };
+    let restored = WindowState { label, ..restored };
+    states.insert(restored.clone());
+    for state in saved {
+        // A window that fails to open is left out rather than keeping the
+        // others from being restored.
+        let _ = open_window(&app, &states, state);
+    }
+    Ok(restored)
+}
@@ -0,0 +1,235 @@
+//! App settings, stored as one JSON file in the app config directory.
+//!
+//! The file records the version of its layout. Files from older versions are
+//! upgraded one step at a time when the app starts, then written back.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+use crate::commands::{app_data_dir, write_json_atomic};
+use crate::error::ExplorerError;
+use crate::exclusions::{ExclusionRules, Exclusions};
+use crate::scanner::MAX_SCAN_THREADS;
+use crate::thumbnails::DEFAULT_THUMBNAIL_QUALITY;
+
+/// File name of the settings inside the app config directory.
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// Version of the settings layout written by this build.
+const SETTINGS_VERSION: u64 = 1;
+
+/// Exclusion rules file in the app data directory from before settings
+/// were kept in one file.
+const LEGACY_EXCLUSIONS_FILE_NAME: &str = "exclusions.json";
+
+/// Event emitted with the new settings whenever they change.
+pub const SETTINGS_CHANGED_EVENT: &str = "settings://changed";
+
+/// A step upgrading the settings layout by one version. It gets the
+/// settings as JSON and the app data directory.
+type Migration = fn(&mut Map<String, Value>, &Path);
+
+/// Step `n` turns version `n` into version `n + 1`.
+const MIGRATIONS: [Migration; SETTINGS_VERSION as usize] = [import_legacy_files];
+
+/// How the UI shows sizes.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeUnits {
+    /// Powers of 1024: KiB, MiB, GiB.
+    #[default]
+    Binary,
+    /// Powers of 1000: kB, MB, GB.
+    Decimal,
+}
+
+/// Everything the user can configure.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// Layout version the settings were written with.
+    pub version: u64,
+    /// JPEG and WebP quality of thumbnails, 1 to 100, where a request does
+    /// not ask for one.
+    pub thumbnail_quality: u8,
+    /// Threads listing folders during a full scan; picked per drive when
+    /// unset.
+    pub scan_threads: Option<usize>,
+    /// Entries left out of scans, searches and the duplicate finder.
+    pub exclusions: ExclusionRules,
+    pub units: SizeUnits,
+    /// Settings this build does not know, written by a newer version. They
+    /// are kept so that going back to that version loses nothing.
+    #[serde(flatten)]
+    unknown: Map<String, Value>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            thumbnail_quality: DEFAULT_THUMBNAIL_QUALITY,
+            scan_threads: None,
+            exclusions: ExclusionRules::default(),
+            units: SizeUnits::default(),
+            unknown: Map::new(),
+        }
+    }
+}
+
+impl Settings {
+    fn validate(&self) -> Result<(), ExplorerError> {
+        if !(1..=100).contains(&self.thumbnail_quality) {
+            return Err(ExplorerError::InvalidInput(
+                "Thumbnail quality must be between 1 and 100".to_string(),
+            ));
+        }
+        if self
+            .scan_threads
+            .is_some_and(|threads| !(1..=MAX_SCAN_THREADS).contains(&threads))
+        {
+            return Err(ExplorerError::InvalidInput(format!(
+                "Scan threads must be between 1 and {}",
+                MAX_SCAN_THREADS
+            )));
+        }
+        self.exclusions.validate()
+    }
+}
+
+/// Managed state holding the settings, kept in sync with the file on disk.
+pub struct SettingsStore {
+    file: PathBuf,
+    settings: Mutex<Settings>,
+}
+
+impl SettingsStore {
+    /// Load the settings from the app config directory, upgrading them from
+    /// an older layout if needed. A missing file starts from the defaults
+    /// and whatever older versions kept in separate files; an unreadable one
+    /// starts from the defaults.
+    pub fn init(app: &AppHandle) -> Result<Self, String> {
+        let dir = app
+            .path()
+            .app_config_dir()
+            .map_err(|e| format!("Failed to resolve app config directory: {}", e))?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create app config directory: {}", e))?;
+        let file = dir.join(SETTINGS_FILE_NAME);
+
+        // No file at all is version 0, from before settings had a file of
+        // their own.
+        let mut stored = match fs::read(&file) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(Value::Object(stored)) => stored,
+                _ => Map::from_iter([("version".to_string(), json!(SETTINGS_VERSION))]),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+            Err(e) => return Err(format!("Failed to read settings: {}", e)),
+        };
+        let version = stored.get("version").and_then(Value::as_u64).unwrap_or(0);
+        let data_dir = app_data_dir(app)?;
+        for migrate in MIGRATIONS.iter().skip(version as usize) {
+            migrate(&mut stored, &data_dir);
+        }
+
+        let mut settings: Settings =
+            serde_json::from_value(Value::Object(stored)).unwrap_or_default();
+        if version < SETTINGS_VERSION {
+            settings.version = SETTINGS_VERSION;
+            write_json_atomic(&file, &settings)?;
+        }
+        if version == 0 {
+            // Imported above, and no longer read.
+            let _ = fs::remove_file(data_dir.join(LEGACY_EXCLUSIONS_FILE_NAME));
+        }
+        Ok(SettingsStore {
+            file,
+            settings: Mutex::new(settings),
+        })
+    }
+
+    /// The current settings.
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Compile the exclusion rules for a walk of `root`.
+    pub fn exclusions(&self, root: &Path) -> Result<Exclusions, String> {
+        Exclusions::new(&self.settings.lock().unwrap().exclusions, root)
+    }
+
+    /// Apply `change`, then validate, save and announce the result on
+    /// `settings://changed`. Nothing changes if any step fails.
+    pub fn update(
+        &self,
+        app: &AppHandle,
+        change: impl FnOnce(&mut Settings) -> Result<(), ExplorerError>,
+    ) -> Result<Settings, ExplorerError> {
+        let mut current = self.settings.lock().unwrap();
+        let mut updated = current.clone();
+        change(&mut updated)?;
+        updated.validate()?;
+        write_json_atomic(&self.file, &updated)?;
+        *current = updated.clone();
+        drop(current);
+        let _ = app.emit(SETTINGS_CHANGED_EVENT, &updated);
+        Ok(updated)
+    }
+}
+
+/// Version 0 to 1: move the exclusion rules, which had a file of their own,
+/// into the settings.
+fn import_legacy_files(stored: &mut Map<String, Value>, data_dir: &Path) {
+    let exclusions = fs::read(data_dir.join(LEGACY_EXCLUSIONS_FILE_NAME))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok());
+    if let Some(exclusions) = exclusions {
+        stored.insert("exclusions".to_string(), exclusions);
+    }
+}
+
+/// Current settings.
+#[command]
+pub fn get_settings(settings: State<'_, SettingsStore>) -> Settings {
+    settings.get()
+}
+
+/// Change some settings, leaving the others as they are, e.g.
+/// `{ "units": "decimal" }`. Each key given replaces that setting whole.
+///
+/// Returns the new settings, which are also emitted on
+/// `settings://changed`.
+#[command]
+pub fn update_settings(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    changes: Map<String, Value>,
+) -> Result<Settings, ExplorerError> {
+    let known = serde_json::to_value(Settings::default()).unwrap_or_default();
+    if let Some(key) = changes
+        .keys()
+        .find(|key| *key == "version" || known.get(key).is_none())
+    {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Unknown setting: {}",
+            key
+        )));
+    }
+    settings.update(&app, |current| {
+        let mut merged = match serde_json::to_value(&*current) {
+            Ok(Value::Object(merged)) => merged,
+            _ => Map::new(),
+        };
+        merged.extend(changes);
+        *current = serde_json::from_value(Value::Object(merged))
+            .map_err(|e| ExplorerError::InvalidInput(format!("Invalid settings: {}", e)))?;
+        Ok(())
+    })
+}
@@ -0,0 +1,377 @@
+use std::collections::HashSet;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// How long to listen for servers announcing themselves on the network.
+#[cfg(target_os = "macos")]
+const DISCOVERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// An SMB server, or a share on one.
+#[derive(Serialize, Clone)]
+pub struct NetworkShare {
+    pub host: String,
+    /// Share name; `None` for a server found during discovery.
+    pub share: Option<String>,
+    /// `smb://host/share` URL to pass to `mount_share`.
+    pub url: String,
+    pub comment: Option<String>,
+}
+
+/// Account used to connect to a share.
+#[derive(Deserialize)]
+pub struct ShareCredentials {
+    pub username: String,
+    pub password: String,
+    /// Windows domain or workgroup, when the server requires one.
+    pub domain: Option<String>,
+}
+
+/// Find SMB servers on the local network, or the shares offered by `host`.
+///
+/// Servers are found through Bonjour/Avahi on macOS and Linux and through the
+/// network browser (`net view`) on Windows. Shares are listed as guest, so
+/// servers that refuse anonymous browsing return an error. Hidden (`$`)
+/// shares are skipped.
+#[command]
+pub async fn discover_network_shares(
+    tasks: State<'_, TaskRegistry>,
+    host: Option<String>,
+) -> Result<Vec<NetworkShare>, ExplorerError> {
+    tasks
+        .run_blocking("discover_network_shares", move || match host {
+            Some(host) => {
+                let host = host.trim_start_matches('\\').trim_start_matches("smb://");
+                let host = host.trim_end_matches(['/', '\\']).to_string();
+                Ok(list_shares(&host)?
+                    .into_iter()
+                    .filter(|(name, _)| !name.ends_with('$'))
+                    .map(|(name, comment)| NetworkShare {
+                        url: format!("smb://{}/{}", host, name),
+                        host: host.clone(),
+                        share: Some(name),
+                        comment: Some(comment).filter(|c| !c.is_empty()),
+                    })
+                    .collect())
+            }
+            None => {
+                let mut seen = HashSet::new();
+                Ok(find_servers()?
+                    .into_iter()
+                    .filter(|host| seen.insert(host.to_lowercase()))
+                    .map(|host| NetworkShare {
+                        url: format!("smb://{}", host),
+                        host,
+                        share: None,
+                        comment: None,
+                    })
+                    .collect())
+            }
+        })
+        .await
+}
+
+/// Connect an SMB share so it shows up in `get_drives`, returning its mount
+/// point.
+///
+/// `url` is `smb://host/share` or `\\host\share`. Windows maps the share to
+/// the next free drive letter, macOS mounts it under `/Volumes` and Linux
+/// mounts it through GVfs (`gio mount`) without needing root.
+#[command]
+pub async fn mount_share(
+    tasks: State<'_, TaskRegistry>,
+    url: String,
+    credentials: Option<ShareCredentials>,
+) -> Result<String, ExplorerError> {
+    tasks
+        .run_blocking("mount_share", move || {
+            let (host, share) = parse_share_url(&url)?;
+            Ok(mount(&host, &share, credentials.as_ref())?)
+        })
+        .await
+}
+
+/// Split a share URL into host and share name.
+fn parse_share_url(url: &str) -> Result<(String, String), String> {
+    let rest = url
+        .strip_prefix("smb://")
+        .or_else(|| url.strip_prefix("\\\\"))
+        .ok_or_else(|| format!("Not an SMB share URL: {}", url))?;
+    let mut parts = rest.split(['/', '\\']).filter(|part| !part.is_empty());
+    // Credentials belong in `credentials`, not the URL.
+    let host = parts
+        .next()
+        .map(|host| host.rsplit('@').next().unwrap_or(host));
+    match (host, parts.next()) {
+        (Some(host), Some(share)) => Ok((host.to_string(), share.to_string())),
+        _ => Err(format!("Share URL must name a host and a share: {}", url)),
+    }
+}
+
+/// Run a tool to completion and return its standard output.
+fn command_output(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Parse a `Share  Type  Comment` table as printed by `net view` and
+/// `smbutil view`, keeping disk shares.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn parse_share_table(output: &str) -> Vec<(String, String)> {
+    let row = regex::Regex::new(r"^(\S.*?)\s+Disk\b\s*(.*)$").unwrap();
+    output
+        .lines()
+        .filter_map(|line| row.captures(line.trim_end()))
+        .map(|caps| (caps[1].to_string(), caps[2].trim().to_string()))
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn find_servers() -> Result<Vec<String>, String> {
+    Ok(command_output("net", &["view"])?
+        .lines()
+        .filter_map(|line| line.strip_prefix("\\\\"))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+fn list_shares(host: &str) -> Result<Vec<(String, String)>, String> {
+    command_output("net", &["view", &format!("\\\\{}", host)])
+        .map(|output| parse_share_table(&output))
+}
+
+#[cfg(target_os = "windows")]
+fn mount(
+    host: &str,
+    share: &str,
+    credentials: Option<&ShareCredentials>,
+) -> Result<String, String> {
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::NetworkManagement::WNet::{
+        WNetUseConnectionW, CONNECT_REDIRECT, CONNECT_TEMPORARY, NETRESOURCEW, RESOURCETYPE_DISK,
+    };
+
+    let wide = |s: &str| -> Vec<u16> { s.encode_utf16().chain(std::iter::once(0)).collect() };
+    let mut remote = wide(&format!("\\\\{}\\{}", host, share));
+    let user = credentials.map(|c| match &c.domain {
+        Some(domain) => wide(&format!("{}\\{}", domain, c.username)),
+        None => wide(&c.username),
+    });
+    let password = credentials.map(|c| wide(&c.password));
+    let as_pcwstr =
+        |s: &Option<Vec<u16>>| s.as_ref().map_or(PCWSTR::null(), |s| PCWSTR(s.as_ptr()));
+
+    let resource = NETRESOURCEW {
+        dwType: RESOURCETYPE_DISK,
+        lpRemoteName: PWSTR(remote.as_mut_ptr()),
+        ..Default::default()
+    };
+    // Receives the drive letter picked by the redirector, e.g. `Z:`.
+    let mut access_name = [0u16; 260];
+    let mut size = access_name.len() as u32;
+    unsafe {
+        WNetUseConnectionW(
+            None,
+            &resource,
+            as_pcwstr(&password),
+            as_pcwstr(&user),
+            CONNECT_REDIRECT | CONNECT_TEMPORARY,
+            PWSTR(access_name.as_mut_ptr()),
+            Some(&mut size),
+            None,
+        )
+    }
+    .to_hresult()
+    .ok()
+    .map_err(|e| format!("Failed to connect \\\\{}\\{}: {}", host, share, e))?;
+
+    let len = access_name.iter().position(|&c| c == 0).unwrap_or(0);
+    Ok(format!(
+        "{}\\",
+        String::from_utf16_lossy(&access_name[..len])
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn find_servers() -> Result<Vec<String>, String> {
+    use std::process::Stdio;
+
+    // `dns-sd` browses until killed, so collect what arrives in a few seconds.
+    let mut child = Command::new("dns-sd")
+        .args(["-B", "_smb._tcp", "local."])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run dns-sd: {}", e))?;
+    std::thread::sleep(DISCOVERY_TIMEOUT);
+    let _ = child.kill();
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run dns-sd: {}", e))?;
+
+    // Rows look like `12:00:00.123  Add  3  4 local.  _smb._tcp.  Name`.
+    let row = regex::Regex::new(r"\sAdd\s+\d+\s+\d+\s+\S+\s+_smb\._tcp\.\s+(.+)$").unwrap();
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| row.captures(line))
+        .map(|caps| format!("{}.local", caps[1].trim()))
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+fn list_shares(host: &str) -> Result<Vec<(String, String)>, String> {
+    command_output("smbutil", &["view", "-g", &format!("//{}", host)])
+        .map(|output| parse_share_table(&output))
+}
+
+#[cfg(target_os = "macos")]
+fn mount(
+    host: &str,
+    share: &str,
+    credentials: Option<&ShareCredentials>,
+) -> Result<String, String> {
+    // Finder's `mount volume` picks a free folder under /Volumes without root.
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut script = format!(
+        "mount volume {}",
+        quote(&format!("smb://{}/{}", host, share))
+    );
+    if let Some(credentials) = credentials {
+        let user = match &credentials.domain {
+            Some(domain) => format!("{};{}", domain, credentials.username),
+            None => credentials.username.clone(),
+        };
+        script.push_str(&format!(
+            " as user name {} with password {}",
+            quote(&user),
+            quote(&credentials.password)
+        ));
+    }
+    command_output("osascript", &["-e", &script])?;
+
+    // `mount` prints `//user@host/share on /Volumes/share (smbfs, ...)`.
+    let wanted = format!("{}/{}", host, share).to_lowercase();
+    command_output("mount", &[])?
+        .lines()
+        .find_map(|line| {
+            let (source, rest) = line.split_once(" on ")?;
+            let source = source.trim_start_matches('/');
+            let source = source.rsplit('@').next().unwrap_or(source).to_lowercase();
+            let (target, _) = rest.rsplit_once(" (")?;
+            (source == wanted).then(|| target.to_string())
+        })
+        .ok_or_else(|| {
+            format!(
+                "Mounted smb://{}/{} but could not find its mount point",
+                host, share
+            )
+        })
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn find_servers() -> Result<Vec<String>, String> {
+    // Resolved rows look like `=;eth0;IPv4;Name;_smb._tcp;local;nas.local;192.168.1.2;445;`.
+    Ok(command_output(
+        "avahi-browse",
+        &["--parsable", "--terminate", "--resolve", "_smb._tcp"],
+    )?
+    .lines()
+    .filter(|line| line.starts_with('='))
+    .filter_map(|line| line.split(';').nth(6))
+    .map(str::to_string)
+    .collect())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn list_shares(host: &str) -> Result<Vec<(String, String)>, String> {
+    // `-g` prints machine-readable `Disk|name|comment` rows.
+    Ok(
+        command_output("smbclient", &["-g", "-N", "-L", &format!("//{}", host)])?
+            .lines()
+            .filter_map(|line| line.strip_prefix("Disk|"))
+            .filter_map(|line| line.split_once('|'))
+            .map(|(name, comment)| (name.to_string(), comment.to_string()))
+            .collect(),
+    )
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount(
+    host: &str,
+    share: &str,
+    credentials: Option<&ShareCredentials>,
+) -> Result<String, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let url = format!("smb://{}/{}", host, share);
+    let mut gio = Command::new("gio");
+    gio.arg("mount");
+    if credentials.is_none() {
+        gio.arg("--anonymous");
+    }
+    let mut child = gio
+        .arg(&url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run gio: {}", e))?;
+
+    // gio prompts for user, domain and password in that order.
+    if let (Some(credentials), Some(mut stdin)) = (credentials, child.stdin.take()) {
+        let _ = writeln!(
+            stdin,
+            "{}\n{}\n{}",
+            credentials.username,
+            credentials.domain.as_deref().unwrap_or(""),
+            credentials.password
+        );
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run gio: {}", e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // Mounting an already mounted share is not an error for the caller.
+    if !output.status.success() && !stderr.contains("already mounted") {
+        return Err(format!("Failed to mount {}: {}", url, stderr.trim()));
+    }
+
+    let mount_point = gvfs_root().join(format!(
+        "smb-share:server={},share={}",
+        host.to_lowercase(),
+        share.to_lowercase()
+    ));
+    if mount_point.is_dir() {
+        Ok(mount_point.to_string_lossy().to_string())
+    } else {
+        Err(format!(
+            "Mounted {} but GVfs did not expose {}",
+            url,
+            mount_point.display()
+        ))
+    }
+}
+
+/// Folder where GVfs exposes the current user's network mounts.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn gvfs_root() -> std::path::PathBuf {
+    std::path::PathBuf::from(format!("/run/user/{}/gvfs", uzers::get_current_uid()))
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, State};
+use walkdir::WalkDir;
+
+use crate::drives::{containing_drive, is_solid_state, list_drives, DriveInfo, DriveType};
+use crate::duplicates::file_identity;
+use crate::error::ExplorerError;
+use crate::file_ops::{measure, PathResult, PendingDeletes};
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// Event name used to report shredding progress.
+pub const SHRED_PROGRESS_EVENT: &str = "shred://progress";
+
+/// Overwrite passes used when `secure_delete` is called without a count.
+const DEFAULT_PASSES: u32 = 3;
+
+/// Upper bound on passes; more than a handful adds nothing on modern disks.
+const MAX_PASSES: u32 = 35;
+
+/// Size of each overwrite write.
+const SHRED_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Minimum delay between two progress events.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Filesystems that write changes to new blocks instead of in place.
+const COPY_ON_WRITE_FILESYSTEMS: &[&str] = &["apfs", "btrfs", "zfs", "bcachefs", "refs"];
+
+/// Payload of the `shred://progress` event.
+#[derive(Serialize, Clone, Default)]
+pub struct ShredProgress {
+    pub operation_id: String,
+    pub current_file: String,
+    /// 1-based pass over `current_file`.
+    pub pass: u32,
+    pub passes: u32,
+    /// Bytes overwritten so far, across all passes and files.
+    pub bytes_written: u64,
+    /// File bytes times passes.
+    pub total_bytes: u64,
+    pub files_done: u64,
+    pub total_files: u64,
+}
+
+/// Why overwriting may leave the old data recoverable.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwriteCaveat {
+    /// SSD wear levelling writes to fresh cells and keeps the old ones.
+    SolidState,
+    /// The filesystem writes new blocks, and snapshots may keep the old ones.
+    CopyOnWrite,
+    /// The server decides where data lands and may keep backups.
+    Network,
+}
+
+/// A volume on which the overwrite is not guaranteed to destroy the data.
+#[derive(Serialize)]
+pub struct ShredWarning {
+    pub mount_point: String,
+    pub caveat: OverwriteCaveat,
+    pub message: String,
+}
+
+/// Result of `secure_delete`.
+#[derive(Serialize)]
+pub struct ShredReport {
+    pub operation_id: String,
+    pub passes: u32,
+    pub files_shredded: u64,
+    pub cancelled: bool,
+    /// One entry per requested path.
+    pub results: Vec<PathResult>,
+    /// Volumes where the overwrite is likely ineffective. Files there were
+    /// still overwritten and deleted.
+    pub warnings: Vec<ShredWarning>,
+    /// Files with other hard links. Overwriting them would destroy the data
+    /// under their other names too, so only the given name was removed and
+    /// the data is left as it was.
+    pub linked: Vec<String>,
+}
+
+/// Overwrite files with random data `passes` times, then delete them.
+///
+/// Folders are shredded recursively; symlinks are removed without touching
+/// their targets, and files with other hard links are only unlinked, as
+/// their data is still in use. Like `delete_permanently`, `confirm_token`
+/// must come from `prepare_delete` for exactly the same paths. Progress is
+/// emitted on `shred://progress` and the operation can be cancelled through
+/// `operation_id`. The report warns about SSDs, copy-on-write filesystems and
+/// network shares, where overwriting in place cannot be guaranteed.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn secure_delete(
+    app: AppHandle,
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    pending: State<'_, PendingDeletes>,
+    paths: Vec<String>,
+    passes: Option<u32>,
+    confirm_token: String,
+    operation_id: Option<String>,
+) -> Result<ShredReport, ExplorerError> {
+    pending.take(&confirm_token, &paths)?;
+    let passes = passes.unwrap_or(DEFAULT_PASSES).clamp(1, MAX_PASSES);
+    let operation = operations.start(operation_id)?;
+    let (id, handle) = (operation.id().to_string(), operation.handle());
+    tasks
+        .run_blocking("secure_delete", move || {
+            let warnings = overwrite_warnings(&paths);
+            let (total_bytes, total_files, _) = measure(&paths);
+            let mut shredder = Shredder {
+                app: &app,
+                operation: &handle,
+                progress: ShredProgress {
+                    operation_id: id.clone(),
+                    passes,
+                    total_bytes: total_bytes * passes as u64,
+                    total_files,
+                    ..Default::default()
+                },
+                last_emit: Instant::now(),
+                buffer: vec![0; SHRED_BUFFER_SIZE],
+                linked: Vec::new(),
+            };
+            let results = paths
+                .iter()
+                .map(|path| {
+                    let result = shredder.shred_path(Path::new(path));
+                    PathResult::from_result(path.clone(), result)
+                })
+                .collect();
+            shredder.emit();
+
+            Ok(ShredReport {
+                operation_id: id,
+                passes,
+                files_shredded: shredder.progress.files_done,
+                cancelled: handle.is_cancelled(),
+                results,
+                warnings,
+                linked: shredder.linked,
+            })
+        })
+        .await
+}
+
+/// State of a running `secure_delete`.
+struct Shredder<'a> {
+    app: &'a AppHandle,
+    operation: &'a OperationHandle,
+    progress: ShredProgress,
+    last_emit: Instant,
+    buffer: Vec<u8>,
+    /// Files that were unlinked without being overwritten.
+    linked: Vec<String>,
+}
+
+impl Shredder<'_> {
+    fn shred_path(&mut self, path: &Path) -> io::Result<()> {
+        let meta = fs::symlink_metadata(path)?;
+        if !meta.is_dir() {
+            return self.shred_file(path, &meta);
+        }
+        // Children come before their folder, so folders are empty when removed.
+        for entry in WalkDir::new(path).contents_first(true) {
+            let entry = entry.map_err(io::Error::from)?;
+            if entry.file_type().is_dir() {
+                fs::remove_dir(entry.path())?;
+            } else {
+                let meta = entry.metadata().map_err(io::Error::from)?;
+                self.shred_file(entry.path(), &meta)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn shred_file(&mut self, path: &Path, meta: &fs::Metadata) -> io::Result<()> {
+        self.checkpoint()?;
+        if meta.file_type().is_symlink() {
+            return fs::remove_file(path);
+        }
+
+        self.progress.current_file = path.to_string_lossy().to_string();
+        let size = meta.len();
+        if file_identity(path)?.links > 1 {
+            fs::remove_file(path)?;
+            self.progress.bytes_written += size * self.progress.passes as u64;
+            self.progress.files_done += 1;
+            self.linked.push(self.progress.current_file.clone());
+            return Ok(());
+        }
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        for pass in 1..=self.progress.passes {
+            self.progress.pass = pass;
+            // Each pass gets fresh random data so no two passes match.
+            let mut stream = random_stream();
+            file.seek(SeekFrom::Start(0))?;
+            let mut remaining = size;
+            while remaining > 0 {
+                self.checkpoint()?;
+                let chunk = remaining.min(self.buffer.len() as u64) as usize;
+                stream.fill(&mut self.buffer[..chunk]);
+                file.write_all(&self.buffer[..chunk])?;
+                remaining -= chunk as u64;
+                self.progress.bytes_written += chunk as u64;
+                if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+                    self.emit();
+                }
+            }
+            // Force each pass to the disk instead of letting the cache merge them.
+            file.sync_all()?;
+        }
+        file.set_len(0)?;
+        file.sync_all()?;
+        drop(file);
+
+        // Rename first so the original name does not linger in the directory.
+        let anonymous = anonymous_name(path);
+        fs::rename(path, &anonymous)?;
+        fs::remove_file(&anonymous)?;
+        self.progress.files_done += 1;
+        Ok(())
+    }
+
+    fn checkpoint(&self) -> io::Result<()> {
+        self.operation.checkpoint().map_err(io::Error::other)
+    }
+
+    fn emit(&mut self) {
+        let _ = self.app.emit(SHRED_PROGRESS_EVENT, &self.progress);
+        self.last_emit = Instant::now();
+    }
+}
+
+/// Keyed BLAKE3 output stream, used as a fast source of random bytes.
+pub fn random_stream() -> blake3::OutputReader {
+    let mut key = [0u8; 32];
+    key[..16].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+    key[16..].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+    blake3::Hasher::new_keyed(&key).finalize_xof()
+}
+
+/// A random sibling path of the same length as the file name, where possible.
+fn anonymous_name(path: &Path) -> PathBuf {
+    let length = path.file_name().map_or(8, |name| name.len()).clamp(8, 32);
+    let name = uuid::Uuid::new_v4().simple().to_string()[..length].to_string();
+    path.with_file_name(name)
+}
+
+/// Warnings for the volumes holding `paths`, one per volume and caveat.
+fn overwrite_warnings(paths: &[String]) -> Vec<ShredWarning> {
+    let drives = list_drives();
+    let mut checked: HashMap<String, Vec<OverwriteCaveat>> = HashMap::new();
+    for path in paths {
+        let Some(drive) = containing_drive(&drives, Path::new(path)) else {
+            continue;
+        };
+        checked
+            .entry(drive.mount_point.clone())
+            .or_insert_with(|| volume_caveats(drive));
+    }
+
+    let mut warnings: Vec<ShredWarning> = checked
+        .into_iter()
+        .flat_map(|(mount_point, caveats)| {
+            caveats.into_iter().map(move |caveat| ShredWarning {
+                mount_point: mount_point.clone(),
+                caveat,
+                message: match caveat {
+                    OverwriteCaveat::SolidState => format!(
+                        "{} is on an SSD; wear levelling may keep copies of the old data",
+                        mount_point
+                    ),
+                    OverwriteCaveat::CopyOnWrite => format!(
+                        "{} uses a copy-on-write filesystem; the old blocks and any \
+                         snapshots are not overwritten",
+                        mount_point
+                    ),
+                    OverwriteCaveat::Network => format!(
+                        "{} is a network share; the server may keep copies of the data",
+                        mount_point
+                    ),
+                },
+            })
+        })
+        .collect();
+    warnings.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    warnings
+}
+
+fn volume_caveats(drive: &DriveInfo) -> Vec<OverwriteCaveat> {
+    let mut caveats = Vec::new();
+    if drive.drive_type == DriveType::Network {
+        caveats.push(OverwriteCaveat::Network);
+        return caveats;
+    }
+    if is_solid_state(&drive.mount_point) == Some(true) {
+        caveats.push(OverwriteCaveat::SolidState);
+    }
+    let file_system = drive.file_system.as_deref().unwrap_or("").to_lowercase();
+    if COPY_ON_WRITE_FILESYSTEMS.contains(&file_system.as_str()) {
+        caveats.push(OverwriteCaveat::CopyOnWrite);
+    }
+    caveats
+}
@@ -0,0 +1,202 @@
+//! Near-duplicate photo detection with perceptual hashes.
+//!
+//! Byte hashes only match exact copies. A perceptual hash is computed from
+//! a small grayscale version of the picture instead, so resized, re-encoded
+//! or lightly edited copies of a photo end up within a few bits of each
+//! other.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use image::GenericImageView;
+use image_hasher::{HashAlg, HasherConfig};
+use serde::Serialize;
+use tauri::{command, State};
+use walkdir::WalkDir;
+
+use crate::error::ExplorerError;
+use crate::exclusions::Exclusions;
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::settings::SettingsStore;
+use crate::tasks::TaskRegistry;
+use crate::thumbnails::{decode_image, is_photo};
+
+/// Bits that may differ between two hashes still considered similar.
+const DEFAULT_THRESHOLD: u32 = 8;
+
+/// Hashes are 64 bits, so a larger threshold would match everything.
+const MAX_THRESHOLD: u32 = 32;
+
+/// Width images are decoded at when the format can render at a chosen size.
+/// The hash itself is computed from a 9x8 downscale.
+const DECODE_WIDTH: u32 = 256;
+
+/// A photo in a group of similar ones.
+#[derive(Serialize)]
+pub struct SimilarImage {
+    pub path: String,
+    /// File size in bytes.
+    pub size: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Hex-encoded 64-bit perceptual hash.
+    pub hash: String,
+    /// Differing hash bits from the first image of the group.
+    pub distance: u32,
+}
+
+/// Photos that look alike, largest image first.
+#[derive(Serialize)]
+pub struct SimilarImageGroup {
+    pub images: Vec<SimilarImage>,
+}
+
+/// Summary returned once the search completes.
+#[derive(Serialize)]
+pub struct SimilarImagesReport {
+    pub groups: Vec<SimilarImageGroup>,
+    pub images_scanned: u64,
+    /// Photos that could not be decoded and were skipped.
+    pub images_failed: u64,
+}
+
+/// Find photos below `root` that look alike even though their bytes differ.
+///
+/// Each photo gets a 64-bit gradient hash (dHash), and photos whose hashes
+/// differ in at most `threshold` bits (default 8, at most 32) are grouped.
+/// Grouping is transitive, so a long chain of small edits can end up in one
+/// group. The search can be paused or cancelled through `operation_id`.
+/// Files matching the exclusion rules are not considered.
+#[command]
+pub async fn find_similar_images(
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    settings: State<'_, SettingsStore>,
+    root: String,
+    threshold: Option<u32>,
+    operation_id: Option<String>,
+) -> Result<SimilarImagesReport, ExplorerError> {
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if threshold > MAX_THRESHOLD {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Threshold must be at most {} bits",
+            MAX_THRESHOLD
+        )));
+    }
+    let exclusions = settings.exclusions(Path::new(&root))?;
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    tasks
+        .run_blocking("similar_images", move || {
+            search(&handle, &root, threshold, &exclusions)
+        })
+        .await
+}
+
+fn search(
+    operation: &OperationHandle,
+    root: &str,
+    threshold: u32,
+    exclusions: &Exclusions,
+) -> Result<SimilarImagesReport, ExplorerError> {
+    if !Path::new(root).is_dir() {
+        return Err(format!("Not a directory: {}", root).into());
+    }
+
+    let hasher = HasherConfig::new()
+        .hash_alg(HashAlg::Gradient)
+        .hash_size(8, 8)
+        .to_hasher();
+    let mut images: Vec<(SimilarImage, u64)> = Vec::new();
+    let mut images_failed = 0;
+    for entry in WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || entry
+                    .metadata()
+                    .map_or(true, |meta| !exclusions.excludes(entry.path(), &meta))
+        })
+        .flatten()
+    {
+        operation.checkpoint()?;
+        if !entry.file_type().is_file() || !is_photo(entry.path()) {
+            continue;
+        }
+        let path = entry.path().to_string_lossy().to_string();
+        let Ok(img) = decode_image(&path, DECODE_WIDTH) else {
+            images_failed += 1;
+            continue;
+        };
+        let hash = hasher.hash_image(&img);
+        let mut bits = [0u8; 8];
+        bits.copy_from_slice(&hash.as_bytes()[..8]);
+        let bits = u64::from_be_bytes(bits);
+        let (width, height) = img.dimensions();
+        images.push((
+            SimilarImage {
+                path,
+                size: entry.metadata().map_or(0, |meta| meta.len()),
+                width,
+                height,
+                hash: format!("{:016x}", bits),
+                distance: 0,
+            },
+            bits,
+        ));
+    }
+    let images_scanned = images.len() as u64;
+
+    // Union every pair within the threshold.
+    let mut parents: Vec<usize> = (0..images.len()).collect();
+    for i in 0..images.len() {
+        operation.checkpoint()?;
+        for j in i + 1..images.len() {
+            if (images[i].1 ^ images[j].1).count_ones() <= threshold {
+                let (a, b) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut by_root: HashMap<usize, Vec<(SimilarImage, u64)>> = HashMap::new();
+    for (i, image) in images.into_iter().enumerate() {
+        let root = find_root(&mut parents, i);
+        by_root.entry(root).or_default().push(image);
+    }
+    let mut groups: Vec<SimilarImageGroup> = by_root
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|mut members| {
+            members.sort_by_key(|(image, _)| {
+                std::cmp::Reverse((image.width as u64 * image.height as u64, image.size))
+            });
+            let first = members[0].1;
+            SimilarImageGroup {
+                images: members
+                    .into_iter()
+                    .map(|(mut image, bits)| {
+                        image.distance = (first ^ bits).count_ones();
+                        image
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.images.len()));
+
+    Ok(SimilarImagesReport {
+        groups,
+        images_scanned,
+        images_failed,
+    })
+}
+
+/// Representative of the set containing `i`, halving paths on the way.
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
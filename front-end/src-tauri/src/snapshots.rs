@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+use crate::index::{subtree_bounds, FileIndex};
+use crate::operations::{OperationHandle, OperationRegistry};
+use crate::tasks::TaskRegistry;
+
+/// Default number of folders returned by `diff_snapshots`.
+const DEFAULT_DIFF_LIMIT: usize = 100;
+
+/// A stored snapshot of folder sizes.
+#[derive(Serialize, Clone)]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub name: String,
+    pub root: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    pub total_size: u64,
+    pub file_count: u64,
+}
+
+/// How one folder changed between two snapshots.
+#[derive(Serialize)]
+pub struct FolderChange {
+    pub path: String,
+    /// `None` if the folder did not exist in the old snapshot.
+    pub old_size: Option<u64>,
+    /// `None` if the folder no longer exists in the new snapshot.
+    pub new_size: Option<u64>,
+    /// New size minus old size; negative when the folder shrank.
+    pub size_change: i64,
+    pub file_count_change: i64,
+}
+
+/// Result of `diff_snapshots`.
+#[derive(Serialize)]
+pub struct SnapshotDiff {
+    pub old: SnapshotInfo,
+    pub new: SnapshotInfo,
+    pub size_change: i64,
+    /// Folders with the largest absolute size change first. Parents appear
+    /// alongside their children, so the UI can drill down.
+    pub folders: Vec<FolderChange>,
+}
+
+/// Record the current size of every folder below `root` under `name`.
+///
+/// Sizes come from the file index, so `root` must have been scanned; rescan
+/// it first for an up-to-date snapshot.
+#[command]
+pub async fn create_snapshot(
+    tasks: State<'_, TaskRegistry>,
+    operations: State<'_, OperationRegistry>,
+    index: State<'_, FileIndex>,
+    root: String,
+    name: Option<String>,
+    operation_id: Option<String>,
+) -> Result<SnapshotInfo, ExplorerError> {
+    let operation = operations.start(operation_id)?;
+    let handle = operation.handle();
+    let mut conn = index.connect()?;
+    tasks
+        .run_blocking("create_snapshot", move || {
+            take_snapshot(&mut conn, &handle, &root, name)
+        })
+        .await
+}
+
+/// List stored snapshots, newest first, optionally only those of `root`.
+#[command]
+pub async fn list_snapshots(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    root: Option<String>,
+) -> Result<Vec<SnapshotInfo>, ExplorerError> {
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("list_snapshots", move || {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, name, root, created_at, total_size, file_count FROM snapshots
+                     WHERE ?1 IS NULL OR root = ?1 ORDER BY created_at DESC",
+                )
+                .map_err(|e| format!("Failed to query snapshots: {}", e))?;
+            Ok(stmt
+                .query_map(params![root], row_to_snapshot)
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query snapshots: {}", e))?)
+        })
+        .await
+}
+
+/// Delete a stored snapshot.
+#[command]
+pub async fn delete_snapshot(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    id: String,
+) -> Result<(), ExplorerError> {
+    let mut conn = index.connect()?;
+    tasks
+        .run_blocking("delete_snapshot", move || {
+            let tx = conn
+                .transaction()
+                .map_err(|e| format!("Failed to start snapshot transaction: {}", e))?;
+            let deleted = tx
+                .execute("DELETE FROM snapshots WHERE id = ?1", params![id])
+                .and_then(|deleted| {
+                    tx.execute(
+                        "DELETE FROM snapshot_folders WHERE snapshot_id = ?1",
+                        params![id],
+                    )?;
+                    Ok(deleted)
+                })
+                .map_err(|e| format!("Failed to delete snapshot: {}", e))?;
+            if deleted == 0 {
+                return Err(ExplorerError::NotFound(format!("No such snapshot: {}", id)));
+            }
+            Ok(tx
+                .commit()
+                .map_err(|e| format!("Failed to delete snapshot: {}", e))?)
+        })
+        .await
+}
+
+/// Report the folders whose size changed the most between two snapshots.
+///
+/// Folders whose size and file count are unchanged are left out.
+#[command]
+pub async fn diff_snapshots(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    old_id: String,
+    new_id: String,
+    limit: Option<usize>,
+) -> Result<SnapshotDiff, ExplorerError> {
+    let limit = limit.unwrap_or(DEFAULT_DIFF_LIMIT);
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("diff_snapshots", move || {
+            let old = load_snapshot(&conn, &old_id)?;
+            let new = load_snapshot(&conn, &new_id)?;
+            let old_folders = load_folders(&conn, &old.id)?;
+            let mut new_folders = load_folders(&conn, &new.id)?;
+
+            let mut folders: Vec<FolderChange> = Vec::new();
+            for (path, (old_size, old_count)) in old_folders {
+                let new = new_folders.remove(&path);
+                folders.push(FolderChange {
+                    size_change: new.map_or(0, |(size, _)| size as i64) - old_size as i64,
+                    file_count_change: new.map_or(0, |(_, count)| count as i64) - old_count as i64,
+                    old_size: Some(old_size),
+                    new_size: new.map(|(size, _)| size),
+                    path,
+                });
+            }
+            for (path, (new_size, new_count)) in new_folders {
+                folders.push(FolderChange {
+                    path,
+                    old_size: None,
+                    new_size: Some(new_size),
+                    size_change: new_size as i64,
+                    file_count_change: new_count as i64,
+                });
+            }
+            folders.retain(|change| {
+                change.size_change != 0
+                    || change.file_count_change != 0
+                    || change.old_size.is_none() != change.new_size.is_none()
+            });
+            folders.sort_by(|a, b| {
+                b.size_change
+                    .unsigned_abs()
+                    .cmp(&a.size_change.unsigned_abs())
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+            folders.truncate(limit);
+
+            Ok(SnapshotDiff {
+                size_change: new.total_size as i64 - old.total_size as i64,
+                old,
+                new,
+                folders,
+            })
+        })
+        .await
+}
+
+/// Store the folder sizes of `root` from the index as a new snapshot.
+pub fn take_snapshot(
+    conn: &mut Connection,
+    operation: &OperationHandle,
+    root: &str,
+    name: Option<String>,
+) -> Result<SnapshotInfo, ExplorerError> {
+    // Stored paths have no trailing separator.
+    let root = Path::new(root)
+        .components()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .to_string();
+    let folders = folder_totals(conn, operation, &root)?;
+    let (total_size, file_count) = folders.get(&root).copied().unwrap_or_default();
+    let created_at = to_unix_secs(Ok(SystemTime::now())).unwrap_or(0);
+    let snapshot = SnapshotInfo {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("{} snapshot", root)),
+        root,
+        created_at,
+        total_size,
+        file_count,
+    };
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start snapshot transaction: {}", e))?;
+    tx.execute(
+        "INSERT INTO snapshots (id, name, root, created_at, total_size, file_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            snapshot.id,
+            snapshot.name,
+            snapshot.root,
+            snapshot.created_at as i64,
+            snapshot.total_size as i64,
+            snapshot.file_count as i64,
+        ],
+    )
+    .map_err(|e| format!("Failed to save snapshot: {}", e))?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO snapshot_folders (snapshot_id, path, size, file_count)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )
+            .map_err(|e| format!("Failed to save snapshot: {}", e))?;
+        for (path, (size, count)) in &folders {
+            operation.checkpoint()?;
+            stmt.execute(params![snapshot.id, path, *size as i64, *count as i64])
+                .map_err(|e| format!("Failed to save snapshot: {}", e))?;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to save snapshot: {}", e))?;
+    Ok(snapshot)
+}
+
+/// Total size and file count of every indexed folder below `root`.
+fn folder_totals(
+    conn: &Connection,
+    operation: &OperationHandle,
+    root: &str,
+) -> Result<BTreeMap<String, (u64, u64)>, ExplorerError> {
+    let (lower, upper) = subtree_bounds(root);
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, size, is_dir FROM files
+             WHERE path = ?1 OR (path >= ?2 AND path < ?3) ORDER BY path",
+        )
+        .map_err(|e| format!("Failed to query index: {}", e))?;
+    let rows = stmt
+        .query_map(params![root, lower, upper], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)? as u64,
+                row.get::<_, bool>(2)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query index: {}", e))?;
+
+    let mut folders: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for row in rows {
+        operation.checkpoint()?;
+        let (path, size, is_dir) = row.map_err(|e| format!("Failed to query index: {}", e))?;
+        if is_dir {
+            folders.insert(path, (0, 0));
+            continue;
+        }
+        // Rows come in path order, so every ancestor is already known.
+        let mut ancestors = Path::new(&path).ancestors().skip(1);
+        while let Some(totals) = ancestors
+            .next()
+            .and_then(|dir| folders.get_mut(dir.to_string_lossy().as_ref()))
+        {
+            totals.0 += size;
+            totals.1 += 1;
+        }
+    }
+    if !folders.contains_key(root) {
+        return Err(format!("No scan found for {}; scan the folder first", root).into());
+    }
+    Ok(folders)
+}
+
+fn load_snapshot(conn: &Connection, id: &str) -> Result<SnapshotInfo, String> {
+    conn.query_row(
+        "SELECT id, name, root, created_at, total_size, file_count FROM snapshots WHERE id = ?1",
+        params![id],
+        row_to_snapshot,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to query snapshots: {}", e))?
+    .ok_or_else(|| format!("No such snapshot: {}", id))
+}
+
+fn load_folders(conn: &Connection, id: &str) -> Result<HashMap<String, (u64, u64)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT path, size, file_count FROM snapshot_folders WHERE snapshot_id = ?1")
+        .map_err(|e| format!("Failed to query snapshots: {}", e))?;
+    stmt.query_map(params![id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            (row.get::<_, i64>(1)? as u64, row.get::<_, i64>(2)? as u64),
+        ))
+    })
+    .and_then(|rows| rows.collect::<Result<HashMap<_, _>, _>>())
+    .map_err(|e| format!("Failed to query snapshots: {}", e))
+}
+
+fn row_to_snapshot(row: &rusqlite::Row) -> rusqlite::Result<SnapshotInfo> {
+    Ok(SnapshotInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        root: row.get(2)?,
+        created_at: row.get::<_, i64>(3)? as u64,
+        total_size: row.get::<_, i64>(4)? as u64,
+        file_count: row.get::<_, i64>(5)? as u64,
+    })
+}
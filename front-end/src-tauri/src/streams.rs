@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::ipc::Response;
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+use crate::tasks::TaskRegistry;
+
+/// Upper bound on streams open at once, in case the frontend leaks handles.
+const MAX_OPEN_STREAMS: usize = 64;
+
+/// Largest chunk `read_chunk` returns at once.
+const MAX_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// A file opened with `open_read_stream`.
+#[derive(Serialize)]
+pub struct ReadStream {
+    pub handle: String,
+    pub path: String,
+    /// Size in bytes when the stream was opened.
+    pub size: u64,
+}
+
+/// Managed state holding files opened for paged reading by handle.
+#[derive(Default)]
+pub struct ReadStreams {
+    next_id: AtomicU64,
+    open: Mutex<HashMap<String, Arc<Mutex<File>>>>,
+}
+
+impl ReadStreams {
+    fn get(&self, handle: &str) -> Result<Arc<Mutex<File>>, ExplorerError> {
+        self.open
+            .lock()
+            .unwrap()
+            .get(handle)
+            .cloned()
+            .ok_or_else(|| ExplorerError::NotFound(format!("No open stream: {}", handle)))
+    }
+}
+
+/// Open a file for reading in chunks with `read_chunk`, without loading it
+/// into memory. Close the handle with `close_stream` when done.
+#[command]
+pub fn open_read_stream(
+    streams: State<'_, ReadStreams>,
+    path: String,
+) -> Result<ReadStream, ExplorerError> {
+    let file = File::open(&path).map_err(|e| ExplorerError::io("Failed to open file", e))?;
+    let meta = file
+        .metadata()
+        .map_err(|e| ExplorerError::io("Failed to read file metadata", e))?;
+    if meta.is_dir() {
+        return Err(ExplorerError::InvalidInput(format!("Not a file: {}", path)));
+    }
+
+    let mut open = streams.open.lock().unwrap();
+    if open.len() >= MAX_OPEN_STREAMS {
+        return Err(ExplorerError::Failed(
+            "Too many open streams; close some first".to_string(),
+        ));
+    }
+    let handle = format!(
+        "stream-{}",
+        streams.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    );
+    open.insert(handle.clone(), Arc::new(Mutex::new(file)));
+    Ok(ReadStream {
+        handle,
+        path,
+        size: meta.len(),
+    })
+}
+
+/// Read up to `len` bytes at `offset` of an open stream, at most 16 MiB.
+///
+/// The bytes are returned as a raw binary response (an `ArrayBuffer` in the
+/// frontend) rather than JSON. A chunk shorter than `len` means the end of
+/// the file was reached.
+#[command]
+pub async fn read_chunk(
+    tasks: State<'_, TaskRegistry>,
+    streams: State<'_, ReadStreams>,
+    handle: String,
+    offset: u64,
+    len: u64,
+) -> Result<Response, ExplorerError> {
+    let file = streams.get(&handle)?;
+    let len = len.min(MAX_CHUNK_SIZE);
+    tasks
+        .run_blocking("read_chunk", move || {
+            let mut file = file.lock().unwrap();
+            let mut chunk = Vec::new();
+            file.seek(SeekFrom::Start(offset))
+                .and_then(|_| (&mut *file).take(len).read_to_end(&mut chunk))
+                .map_err(|e| ExplorerError::io("Failed to read file", e))?;
+            Ok(Response::new(chunk))
+        })
+        .await
+}
+
+/// Close a stream opened with `open_read_stream`.
+#[command]
+pub fn close_stream(streams: State<'_, ReadStreams>, handle: String) -> Result<(), ExplorerError> {
+    streams
+        .open
+        .lock()
+        .unwrap()
+        .remove(&handle)
+        .map(|_| ())
+        .ok_or_else(|| ExplorerError::NotFound(format!("No open stream: {}", handle)))
+}
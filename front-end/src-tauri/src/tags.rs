@@ -0,0 +1,199 @@
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use rusqlite::params;
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+use crate::index::FileIndex;
+use crate::tasks::TaskRegistry;
+
+/// Longest accepted tag, in characters.
+const MAX_TAG_LENGTH: usize = 64;
+
+/// Default number of files returned by `find_by_tag`.
+const DEFAULT_TAG_QUERY_LIMIT: usize = 1000;
+
+/// A tag and how many paths carry it.
+#[derive(Serialize)]
+pub struct TagInfo {
+    pub name: String,
+    pub count: u64,
+}
+
+/// A path returned by `find_by_tag`.
+#[derive(Serialize)]
+pub struct TaggedFile {
+    pub path: String,
+    pub name: String,
+    /// `false` if the file was moved or deleted since it was tagged.
+    pub exists: bool,
+    pub is_dir: bool,
+    pub size: u64,
+    /// Seconds since the Unix epoch.
+    pub modified: Option<u64>,
+    /// Seconds since the Unix epoch.
+    pub tagged_at: u64,
+}
+
+/// Attach `tag` to each of `paths`. Tagging a path twice has no effect.
+///
+/// Tags are matched case-insensitively and kept in the file index database,
+/// so they survive rescans and work for paths on any drive.
+#[command]
+pub async fn add_tag(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    paths: Vec<String>,
+    tag: String,
+) -> Result<(), ExplorerError> {
+    let tag = normalize_tag(&tag)?;
+    let mut conn = index.connect()?;
+    tasks
+        .run_blocking("add_tag", move || {
+            let now = to_unix_secs(Ok(SystemTime::now())).unwrap_or(0) as i64;
+            let tx = conn
+                .transaction()
+                .map_err(|e| format!("Failed to start tag transaction: {}", e))?;
+            for path in &paths {
+                tx.execute(
+                    "INSERT OR IGNORE INTO tags (path, tag, tagged_at) VALUES (?1, ?2, ?3)",
+                    params![path, tag, now],
+                )
+                .map_err(|e| format!("Failed to tag {}: {}", path, e))?;
+            }
+            Ok(tx
+                .commit()
+                .map_err(|e| format!("Failed to save tags: {}", e))?)
+        })
+        .await
+}
+
+/// Detach `tag` from each of `paths`.
+#[command]
+pub async fn remove_tag(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    paths: Vec<String>,
+    tag: String,
+) -> Result<(), ExplorerError> {
+    let tag = normalize_tag(&tag)?;
+    let mut conn = index.connect()?;
+    tasks
+        .run_blocking("remove_tag", move || {
+            let tx = conn
+                .transaction()
+                .map_err(|e| format!("Failed to start tag transaction: {}", e))?;
+            for path in &paths {
+                tx.execute(
+                    "DELETE FROM tags WHERE path = ?1 AND tag = ?2",
+                    params![path, tag],
+                )
+                .map_err(|e| format!("Failed to untag {}: {}", path, e))?;
+            }
+            Ok(tx
+                .commit()
+                .map_err(|e| format!("Failed to save tags: {}", e))?)
+        })
+        .await
+}
+
+/// List tags with their usage counts, most used first.
+///
+/// With `path`, only the tags on that path are returned.
+#[command]
+pub async fn list_tags(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    path: Option<String>,
+) -> Result<Vec<TagInfo>, ExplorerError> {
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("list_tags", move || {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT tag, COUNT(*) FROM tags
+                     WHERE ?1 IS NULL OR path = ?1
+                     GROUP BY tag ORDER BY COUNT(*) DESC, tag",
+                )
+                .map_err(|e| format!("Failed to query tags: {}", e))?;
+            Ok(stmt
+                .query_map(params![path], |row| {
+                    Ok(TagInfo {
+                        name: row.get(0)?,
+                        count: row.get::<_, i64>(1)? as u64,
+                    })
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query tags: {}", e))?)
+        })
+        .await
+}
+
+/// Find paths carrying `tag`, most recently tagged first.
+///
+/// Paths that no longer exist are still returned, with `exists` unset, so
+/// stale tags can be cleaned up.
+#[command]
+pub async fn find_by_tag(
+    tasks: State<'_, TaskRegistry>,
+    index: State<'_, FileIndex>,
+    tag: String,
+    limit: Option<usize>,
+) -> Result<Vec<TaggedFile>, ExplorerError> {
+    let tag = normalize_tag(&tag)?;
+    let limit = limit.unwrap_or(DEFAULT_TAG_QUERY_LIMIT);
+    let conn = index.connect()?;
+    tasks
+        .run_blocking("find_by_tag", move || {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT path, tagged_at FROM tags WHERE tag = ?1
+                     ORDER BY tagged_at DESC, path LIMIT ?2",
+                )
+                .map_err(|e| format!("Failed to query tags: {}", e))?;
+            let rows = stmt
+                .query_map(params![tag, limit as i64], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to query tags: {}", e))?;
+
+            Ok(rows
+                .into_iter()
+                .map(|(path, tagged_at)| {
+                    let meta = fs::metadata(&path).ok();
+                    TaggedFile {
+                        name: Path::new(&path)
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_else(|| path.clone()),
+                        exists: meta.is_some(),
+                        is_dir: meta.as_ref().is_some_and(|m| m.is_dir()),
+                        size: meta.as_ref().filter(|m| !m.is_dir()).map_or(0, |m| m.len()),
+                        modified: meta.and_then(|m| to_unix_secs(m.modified())),
+                        tagged_at: tagged_at as u64,
+                        path,
+                    }
+                })
+                .collect())
+        })
+        .await
+}
+
+fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("Tag must not be empty".to_string());
+    }
+    if tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(format!(
+            "Tag is longer than {} characters: {}",
+            MAX_TAG_LENGTH, tag
+        ));
+    }
+    Ok(tag.to_string())
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::Serialize;
+use tauri::{command, State};
+
+use crate::commands::to_unix_secs;
+use crate::error::ExplorerError;
+
+/// A unit of blocking work currently running on the background pool.
+#[derive(Serialize, Clone)]
+pub struct TaskInfo {
+    pub id: u64,
+    /// Short label describing the work, e.g. `"thumbnail"` or `"scan"`.
+    pub kind: String,
+    /// Seconds since the Unix epoch when the task started.
+    pub started_at: Option<u64>,
+}
+
+/// Managed state tracking in-flight blocking tasks.
+#[derive(Default)]
+pub struct TaskRegistry {
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<u64, TaskInfo>>,
+}
+
+impl TaskRegistry {
+    /// Run `work` on the blocking thread pool so the main thread stays responsive.
+    pub async fn run_blocking<T, F>(&self, kind: &str, work: F) -> Result<T, ExplorerError>
+    where
+        F: FnOnce() -> Result<T, ExplorerError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let _guard = self.register(kind);
+        tauri::async_runtime::spawn_blocking(work)
+            .await
+            .map_err(|e| format!("Background task failed: {}", e))?
+    }
+
+    /// Snapshot of the tasks that are currently running.
+    pub fn running(&self) -> Vec<TaskInfo> {
+        let tasks = self.tasks.lock().unwrap();
+        let mut list: Vec<TaskInfo> = tasks.values().cloned().collect();
+        list.sort_by_key(|task| task.id);
+        list
+    }
+
+    fn register(&self, kind: &str) -> TaskGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let info = TaskInfo {
+            id,
+            kind: kind.to_string(),
+            started_at: to_unix_secs(Ok(SystemTime::now())),
+        };
+        self.tasks.lock().unwrap().insert(id, info);
+        TaskGuard { registry: self, id }
+    }
+}
+
+/// Removes a task from the registry when it finishes or its caller goes away.
+struct TaskGuard<'a> {
+    registry: &'a TaskRegistry,
+    id: u64,
+}
+
+impl Drop for TaskGuard<'_> {
+    fn drop(&mut self) {
+        self.registry.tasks.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// List blocking tasks that are currently in flight.
+#[command]
+pub fn list_running_tasks(tasks: State<'_, TaskRegistry>) -> Vec<TaskInfo> {
+    tasks.running()
+}
@@ -0,0 +1,183 @@
+//! Opening a terminal in a folder.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, State};
+
+use crate::commands::{app_data_dir, write_json_atomic};
+use crate::error::ExplorerError;
+
+/// File name of the terminal choice inside the app data directory.
+const TERMINAL_FILE_NAME: &str = "terminal.json";
+
+/// Placeholder replaced by the folder in `TerminalConfig::args`.
+const PATH_PLACEHOLDER: &str = "{path}";
+
+/// Terminals tried in order on Linux when none is configured, after
+/// `$TERMINAL`.
+#[cfg(all(unix, not(target_os = "macos")))]
+const LINUX_TERMINALS: &[&str] = &[
+    "x-terminal-emulator",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "kitty",
+    "alacritty",
+    "xterm",
+];
+
+/// The terminal `open_terminal` launches.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TerminalConfig {
+    /// Executable to run, by name or path. Without one, the platform's
+    /// default terminal is used.
+    pub program: Option<String>,
+    /// Arguments passed to `program`, with `{path}` replaced by the folder.
+    /// The folder is also the working directory, so most terminals need
+    /// none.
+    pub args: Vec<String>,
+}
+
+/// Managed state holding the terminal choice, kept in sync with the file on
+/// disk.
+pub struct TerminalSettings {
+    file: PathBuf,
+    config: Mutex<TerminalConfig>,
+}
+
+impl TerminalSettings {
+    /// Load the choice from the app data directory, starting with the
+    /// platform default if the file is missing or unreadable.
+    pub fn init(app: &AppHandle) -> Result<Self, String> {
+        let file = app_data_dir(app)?.join(TERMINAL_FILE_NAME);
+        let config = fs::read(&file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Ok(TerminalSettings {
+            file,
+            config: Mutex::new(config),
+        })
+    }
+}
+
+/// Current terminal choice.
+#[command]
+pub fn get_terminal(settings: State<'_, TerminalSettings>) -> TerminalConfig {
+    settings.config.lock().unwrap().clone()
+}
+
+/// Choose the terminal `open_terminal` launches. A config without a program
+/// restores the platform default.
+#[command]
+pub fn set_terminal(
+    settings: State<'_, TerminalSettings>,
+    config: TerminalConfig,
+) -> Result<TerminalConfig, ExplorerError> {
+    if config
+        .program
+        .as_ref()
+        .is_some_and(|program| program.trim().is_empty())
+    {
+        return Err(ExplorerError::InvalidInput(
+            "Terminal program is empty".to_string(),
+        ));
+    }
+    let mut current = settings.config.lock().unwrap();
+    write_json_atomic(&settings.file, &config)?;
+    *current = config.clone();
+    Ok(config)
+}
+
+/// Open a terminal whose working directory is the folder `path`.
+///
+/// Without a configured terminal, this is Windows Terminal, or a console
+/// window running `cmd` where it is not installed, on Windows; Terminal on
+/// macOS; and on Linux `$TERMINAL` or the first installed of the common
+/// terminal emulators.
+#[command]
+pub fn open_terminal(
+    settings: State<'_, TerminalSettings>,
+    path: String,
+) -> Result<(), ExplorerError> {
+    let dir = Path::new(&path);
+    if !dir.is_dir() {
+        return Err(ExplorerError::InvalidInput(format!(
+            "Not a directory: {}",
+            path
+        )));
+    }
+    let config = settings.config.lock().unwrap().clone();
+    match &config.program {
+        Some(program) => {
+            let args = config
+                .args
+                .iter()
+                .map(|arg| arg.replace(PATH_PLACEHOLDER, &path));
+            Command::new(program)
+                .args(args)
+                .current_dir(dir)
+                .spawn()
+                .map_err(|e| ExplorerError::io(&format!("Failed to run {}", program), e))?;
+            Ok(())
+        }
+        None => launch_default(dir),
+    }
+}
+
+#[cfg(windows)]
+fn launch_default(dir: &Path) -> Result<(), ExplorerError> {
+    use std::os::windows::process::CommandExt;
+    use windows::Win32::System::Threading::CREATE_NEW_CONSOLE;
+
+    let terminal = Command::new("wt.exe").arg("-d").arg(dir).spawn();
+    match terminal {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Command::new("cmd.exe")
+                .current_dir(dir)
+                .creation_flags(CREATE_NEW_CONSOLE.0)
+                .spawn()
+                .map_err(|e| ExplorerError::io("Failed to run cmd", e))?;
+            Ok(())
+        }
+        Err(e) => Err(ExplorerError::io("Failed to run Windows Terminal", e)),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn launch_default(dir: &Path) -> Result<(), ExplorerError> {
+    let status = Command::new("open")
+        .args(["-a", "Terminal"])
+        .arg(dir)
+        .status()
+        .map_err(|e| ExplorerError::io("Failed to run open", e))?;
+    if !status.success() {
+        return Err(ExplorerError::Failed("Failed to open Terminal".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn launch_default(dir: &Path) -> Result<(), ExplorerError> {
+    let from_env = std::env::var("TERMINAL").ok().filter(|t| !t.is_empty());
+    let candidates = from_env
+        .iter()
+        .map(String::as_str)
+        .chain(LINUX_TERMINALS.iter().copied());
+    for terminal in candidates {
+        match Command::new(terminal).current_dir(dir).spawn() {
+            Ok(_) => return Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(ExplorerError::io(&format!("Failed to run {}", terminal), e)),
+        }
+    }
+    Err(ExplorerError::NotFound(
+        "No terminal emulator found; choose one with set_terminal".to_string(),
+    ))
+}
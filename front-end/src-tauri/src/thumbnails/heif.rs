@@ -0,0 +1,66 @@
+//! HEIC, HEIF and AVIF decoding through libheif.
+//!
+//! iPhones store photos as HEIC and modern web images are often AVIF; both
+//! are HEIF containers that the `image` crate cannot read.
+
+use std::path::Path;
+
+use image::{DynamicImage, RgbImage};
+use libheif_rs::{ColorSpace, HeifContext, ImageHandle, LibHeif, RgbChroma};
+
+/// Extensions of the HEIF-based formats decoded with libheif.
+const HEIF_EXTENSIONS: &[&str] = &["avif", "heic", "heics", "heif", "heifs", "hif"];
+
+/// Whether `path` has the extension of a HEIF-based format.
+pub fn is_heif(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| HEIF_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Decode the primary image of a HEIF file, or its embedded thumbnail when
+/// that is at least `min_width` pixels wide.
+///
+/// Rotation, mirroring and cropping stored in the file are applied.
+pub fn decode(path: &Path, min_width: u32) -> Result<DynamicImage, String> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| format!("Unsupported path: {}", path.display()))?;
+    let context = HeifContext::read_from_file(path)
+        .map_err(|e| format!("Failed to open HEIF file: {}", e))?;
+    let primary = context
+        .primary_image_handle()
+        .map_err(|e| format!("Failed to read HEIF image: {}", e))?;
+    let handle = embedded_thumbnail(&primary, min_width).unwrap_or(primary);
+
+    let image = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
+        .map_err(|e| format!("Failed to decode HEIF image: {}", e))?;
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or_else(|| "Failed to decode HEIF image: no RGB plane".to_string())?;
+
+    // Rows may be padded beyond three bytes per pixel.
+    let row_len = plane.width as usize * 3;
+    let mut pixels = Vec::with_capacity(row_len * plane.height as usize);
+    for row in plane.data.chunks(plane.stride).take(plane.height as usize) {
+        pixels.extend_from_slice(&row[..row_len]);
+    }
+    RgbImage::from_raw(plane.width, plane.height, pixels)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| "Failed to decode HEIF image: truncated pixel data".to_string())
+}
+
+/// The smallest thumbnail stored in the file that is still wide enough.
+///
+/// Decoding one is much faster than decoding a full-resolution photo.
+fn embedded_thumbnail(primary: &ImageHandle, min_width: u32) -> Option<ImageHandle> {
+    let mut ids = vec![0; primary.number_of_thumbnails()];
+    let count = primary.thumbnail_ids(&mut ids);
+    ids.truncate(count);
+    ids.into_iter()
+        .filter_map(|id| primary.thumbnail(id).ok())
+        .filter(|thumbnail| thumbnail.width() >= min_width)
+        .min_by_key(|thumbnail| thumbnail.width())
+}
@@ -0,0 +1,389 @@
+use std::io::Cursor;
+use std::path::Path;
+use std::process::Command;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use exif::{Exif, In, Tag};
+use image::{
+    imageops::FilterType, io::Reader as ImageReader, DynamicImage, GenericImageView, ImageFormat,
+    ImageOutputFormat,
+};
+use pdfium_render::prelude::{PdfRenderConfig, Pdfium};
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+use crate::error::ExplorerError;
+use crate::metadata::read_exif;
+use crate::settings::{Settings, SettingsStore};
+use crate::tasks::TaskRegistry;
+
+mod heif;
+pub mod protocol;
+pub mod queue;
+mod raw;
+mod svg;
+
+/// Name of the ffmpeg executable used to grab video frames.
+const FFMPEG_BIN: &str = "ffmpeg";
+
+/// Default width in pixels of generated thumbnails.
+const DEFAULT_THUMBNAIL_WIDTH: u32 = 100;
+
+/// Default JPEG and WebP quality.
+pub const DEFAULT_THUMBNAIL_QUALITY: u8 = 90;
+
+/// Largest width or height in pixels that can be requested.
+const MAX_THUMBNAIL_SIZE: u32 = 4096;
+
+/// Encoding of generated thumbnails.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    #[default]
+    Jpeg,
+    Webp,
+    Png,
+}
+
+/// How an image is sized into the requested box.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFit {
+    /// Scale to fit inside the box, keeping the whole image.
+    #[default]
+    Fit,
+    /// Scale to cover the box and crop the overflow around the center.
+    Fill,
+}
+
+/// Size and encoding of a thumbnail.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ThumbnailOptions {
+    pub width: u32,
+    /// Without a height, `fit` keeps the aspect ratio at `width` and `fill`
+    /// crops to a square.
+    pub height: Option<u32>,
+    pub format: ThumbnailFormat,
+    /// 1 to 100; ignored for PNG. Defaults to the thumbnail quality setting.
+    pub quality: Option<u8>,
+    pub fit: ThumbnailFit,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        ThumbnailOptions {
+            width: DEFAULT_THUMBNAIL_WIDTH,
+            height: None,
+            format: ThumbnailFormat::Jpeg,
+            quality: None,
+            fit: ThumbnailFit::Fit,
+        }
+    }
+}
+
+impl ThumbnailFormat {
+    fn mime(self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpeg => "image/jpeg",
+            ThumbnailFormat::Webp => "image/webp",
+            ThumbnailFormat::Png => "image/png",
+        }
+    }
+}
+
+impl ThumbnailOptions {
+    /// Check the size and fill in the quality from `settings` if none was
+    /// asked for.
+    fn validate(self, settings: &Settings) -> Result<Self, String> {
+        let valid = |size: u32| (1..=MAX_THUMBNAIL_SIZE).contains(&size);
+        if !valid(self.width) || !self.height.is_none_or(valid) {
+            return Err(format!(
+                "Thumbnail size must be between 1 and {} pixels",
+                MAX_THUMBNAIL_SIZE
+            ));
+        }
+        Ok(ThumbnailOptions {
+            quality: Some(
+                self.quality
+                    .unwrap_or(settings.thumbnail_quality)
+                    .clamp(1, 100),
+            ),
+            ..self
+        })
+    }
+}
+
+/// Structure returned by the thumbnail command.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThumbnailResponse {
+    pub data_url: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An encoded thumbnail, before it is wrapped in a data URL.
+struct EncodedThumbnail {
+    bytes: Vec<u8>,
+    mime: &'static str,
+    width: u32,
+    height: u32,
+}
+
+impl EncodedThumbnail {
+    fn into_response(self) -> ThumbnailResponse {
+        // Create a data URL with the base64-encoded image.
+        ThumbnailResponse {
+            data_url: format!("data:{};base64,{}", self.mime, STANDARD.encode(&self.bytes)),
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+/// Generate a thumbnail from an image, camera RAW, HEIC/AVIF, SVG or PDF file.
+///
+/// Photos are turned upright according to their EXIF orientation. RAW files
+/// use the JPEG preview the camera embedded in them. `options`
+/// default to a 100 px wide JPEG.
+#[command]
+pub async fn get_thumbnail(
+    tasks: State<'_, TaskRegistry>,
+    settings: State<'_, SettingsStore>,
+    path: String,
+    options: Option<ThumbnailOptions>,
+) -> Result<ThumbnailResponse, ExplorerError> {
+    let options = options.unwrap_or_default().validate(&settings.get())?;
+    tasks
+        .run_blocking("thumbnail", move || {
+            Ok(generate_thumbnail(&path, &options)?)
+        })
+        .await
+}
+
+/// Generate a thumbnail from a frame of a video file.
+///
+/// `timestamp` is the position in seconds to capture; it defaults to one
+/// second in to skip black lead-in frames.
+#[command]
+pub async fn get_video_thumbnail(
+    tasks: State<'_, TaskRegistry>,
+    settings: State<'_, SettingsStore>,
+    path: String,
+    timestamp: Option<f64>,
+    options: Option<ThumbnailOptions>,
+) -> Result<ThumbnailResponse, ExplorerError> {
+    let timestamp = timestamp.unwrap_or(1.0).max(0.0);
+    let options = options.unwrap_or_default().validate(&settings.get())?;
+    tasks
+        .run_blocking("video_thumbnail", move || {
+            Ok(generate_video_thumbnail(&path, timestamp, &options)?)
+        })
+        .await
+}
+
+fn generate_thumbnail(path: &str, options: &ThumbnailOptions) -> Result<ThumbnailResponse, String> {
+    render_thumbnail(path, options).map(EncodedThumbnail::into_response)
+}
+
+fn render_thumbnail(path: &str, options: &ThumbnailOptions) -> Result<EncodedThumbnail, String> {
+    encode_thumbnail(&decode_image(path, options.width)?, options)
+}
+
+/// Encode `img` as a PNG data URL, scaled to fit a square of `size` pixels.
+pub fn png_data_url(img: &DynamicImage, size: u32) -> Result<ThumbnailResponse, String> {
+    let options = ThumbnailOptions {
+        width: size,
+        height: Some(size),
+        format: ThumbnailFormat::Png,
+        ..ThumbnailOptions::default()
+    };
+    encode_thumbnail(img, &options).map(EncodedThumbnail::into_response)
+}
+
+/// Whether `path` looks like a photo that `decode_image` can read, by
+/// extension. PDFs and SVGs are not photos.
+pub fn is_photo(path: &Path) -> bool {
+    raw::is_raw(path) || heif::is_heif(path) || ImageFormat::from_path(path).is_ok()
+}
+
+/// Decode any supported image upright, at a resolution of at least `width`
+/// pixels where the format allows rendering at a chosen size.
+pub fn decode_image(path: &str, width: u32) -> Result<DynamicImage, String> {
+    let is_pdf = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+    if is_pdf {
+        return render_pdf_first_page(path, width);
+    }
+
+    if raw::is_raw(Path::new(path)) {
+        let preview = raw::extract_preview(Path::new(path))?;
+        let img = image::load_from_memory(&preview)
+            .map_err(|e| format!("Failed to decode RAW preview: {}", e))?;
+        // Most RAW formats tag the container; Fujifilm only tags the preview.
+        let exif = read_exif(Path::new(path)).ok().or_else(|| {
+            exif::Reader::new()
+                .read_from_container(&mut Cursor::new(&preview))
+                .ok()
+        });
+        return Ok(apply_orientation(img, exif.as_ref()));
+    }
+
+    if svg::is_svg(Path::new(path)) {
+        return svg::render(Path::new(path), width);
+    }
+
+    if heif::is_heif(Path::new(path)) {
+        return heif::decode(Path::new(path), width);
+    }
+
+    // Open and decode the image.
+    let img: DynamicImage = ImageReader::open(path)
+        .map_err(|e| format!("Failed to open image: {}", e))?
+        .decode()
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+    let exif = read_exif(Path::new(path)).ok();
+
+    Ok(apply_orientation(img, exif.as_ref()))
+}
+
+/// Rotate and flip a decoded image upright according to its EXIF
+/// orientation tag.
+fn apply_orientation(img: DynamicImage, exif: Option<&Exif>) -> DynamicImage {
+    let orientation = exif
+        .and_then(|exif| exif.get_field(Tag::Orientation, In::PRIMARY))
+        .and_then(|field| field.value.get_uint(0));
+    match orientation {
+        Some(2) => img.fliph(),
+        Some(3) => img.rotate180(),
+        Some(4) => img.flipv(),
+        // Transposed: mirrored along the top-left to bottom-right diagonal.
+        Some(5) => img.rotate90().fliph(),
+        Some(6) => img.rotate90(),
+        // Transversed: mirrored along the other diagonal.
+        Some(7) => img.rotate270().fliph(),
+        Some(8) => img.rotate270(),
+        _ => img,
+    }
+}
+
+/// Render the first page of a PDF at the given pixel width.
+fn render_pdf_first_page(path: &str, width: u32) -> Result<DynamicImage, String> {
+    let pdfium = bind_pdfium()?;
+    let document = pdfium
+        .load_pdf_from_file(path, None)
+        .map_err(|e| format!("Failed to open PDF: {}", e))?;
+    let page = document
+        .pages()
+        .first()
+        .map_err(|e| format!("Failed to read first PDF page: {}", e))?;
+    let config = PdfRenderConfig::new().set_target_width(width as i32);
+    let bitmap = page
+        .render_with_config(&config)
+        .map_err(|e| format!("Failed to render PDF page: {}", e))?;
+
+    Ok(bitmap.as_image())
+}
+
+/// Load the pdfium library, preferring a copy bundled next to the executable.
+fn bind_pdfium() -> Result<Pdfium, String> {
+    let bundled = std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            exe.parent()
+                .map(Pdfium::pdfium_platform_library_name_at_path)
+        })
+        .and_then(|library| Pdfium::bind_to_library(library).ok());
+    let bindings = match bundled {
+        Some(bindings) => bindings,
+        None => Pdfium::bind_to_system_library()
+            .map_err(|e| format!("PDF rendering is unavailable: {}", e))?,
+    };
+    Ok(Pdfium::new(bindings))
+}
+
+fn generate_video_thumbnail(
+    path: &str,
+    timestamp: f64,
+    options: &ThumbnailOptions,
+) -> Result<ThumbnailResponse, String> {
+    let frame = extract_video_frame(path, timestamp)?;
+    let img = image::load_from_memory(&frame)
+        .map_err(|e| format!("Failed to decode video frame: {}", e))?;
+
+    encode_thumbnail(&img, options).map(EncodedThumbnail::into_response)
+}
+
+/// Grab a single PNG-encoded frame from a video using ffmpeg.
+fn extract_video_frame(path: &str, timestamp: f64) -> Result<Vec<u8>, String> {
+    // Seeking before `-i` is fast; ffmpeg snaps to the nearest keyframe.
+    let output = Command::new(FFMPEG_BIN)
+        .args(["-v", "error", "-ss"])
+        .arg(format!("{:.3}", timestamp))
+        .arg("-i")
+        .arg(path)
+        .args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "-"])
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(format!(
+            "Failed to extract video frame: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Resize a decoded image and encode it in the requested format.
+fn encode_thumbnail(
+    img: &DynamicImage,
+    options: &ThumbnailOptions,
+) -> Result<EncodedThumbnail, String> {
+    // Resize with high-quality Lanczos3 filter.
+    let thumbnail = match (options.fit, options.height) {
+        (ThumbnailFit::Fit, Some(height)) => {
+            img.resize(options.width, height, FilterType::Lanczos3)
+        }
+        (ThumbnailFit::Fit, None) => {
+            // Keep the aspect ratio at the requested width.
+            let (width, height) = img.dimensions();
+            let aspect_ratio = width as f32 / height as f32;
+            let new_height = ((options.width as f32 / aspect_ratio) as u32).max(1);
+            img.resize_exact(options.width, new_height, FilterType::Lanczos3)
+        }
+        (ThumbnailFit::Fill, height) => img.resize_to_fill(
+            options.width,
+            height.unwrap_or(options.width),
+            FilterType::Lanczos3,
+        ),
+    };
+
+    let quality = options.quality.unwrap_or(DEFAULT_THUMBNAIL_QUALITY);
+    let mut bytes: Vec<u8> = Vec::new();
+    match options.format {
+        ThumbnailFormat::Jpeg => thumbnail
+            .write_to(
+                &mut Cursor::new(&mut bytes),
+                ImageOutputFormat::Jpeg(quality),
+            )
+            .map_err(|e| format!("Failed to encode thumbnail: {}", e))?,
+        ThumbnailFormat::Png => thumbnail
+            .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+            .map_err(|e| format!("Failed to encode thumbnail: {}", e))?,
+        ThumbnailFormat::Webp => {
+            let rgba = thumbnail.to_rgba8();
+            let encoded =
+                webp::Encoder::from_rgba(&rgba, rgba.width(), rgba.height()).encode(quality as f32);
+            bytes.extend_from_slice(&encoded);
+        }
+    }
+
+    Ok(EncodedThumbnail {
+        bytes,
+        mime: options.format.mime(),
+        width: thumbnail.width(),
+        height: thumbnail.height(),
+    })
+}
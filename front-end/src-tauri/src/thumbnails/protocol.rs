@@ -0,0 +1,183 @@
+//! The `thumb://` URI scheme, which serves thumbnails to the webview as plain
+//! image responses instead of base64 data URLs over IPC.
+//!
+//! A request names the file in its percent-encoded path and the thumbnail
+//! options in its query, e.g.
+//! `thumb://localhost/<encodeURIComponent(path)>?width=200&format=webp`.
+//! Windows webviews reach the same handler at `http://thumb.localhost/...`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use percent_encoding::percent_decode_str;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, UriSchemeResponder};
+
+use super::{render_thumbnail, ThumbnailOptions};
+use crate::settings::{Settings, SettingsStore};
+use crate::tasks::TaskRegistry;
+
+/// Name of the URI scheme registered in `main`.
+pub const THUMB_SCHEME: &str = "thumb";
+
+/// Cached thumbnails older than this are removed at startup.
+const MAX_CACHE_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Managed state holding the on-disk cache of rendered thumbnails.
+///
+/// Entries are keyed by path, size, modification time and options, so a
+/// changed file gets a fresh thumbnail.
+#[derive(Clone)]
+pub struct ThumbnailCache {
+    dir: PathBuf,
+}
+
+impl ThumbnailCache {
+    /// Use the `thumbnails` folder of the app cache directory, clearing out
+    /// stale entries in the background.
+    pub fn init(app: &AppHandle) -> Result<Self, String> {
+        let dir = app
+            .path()
+            .app_cache_dir()
+            .map_err(|e| format!("Failed to resolve app cache directory: {}", e))?
+            .join("thumbnails");
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create thumbnail cache: {}", e))?;
+
+        let stale_dir = dir.clone();
+        std::thread::spawn(move || remove_stale_entries(&stale_dir));
+        Ok(ThumbnailCache { dir })
+    }
+
+    /// Thumbnail bytes for `path`, rendering and caching them on a miss.
+    fn get_or_render(&self, path: &str, options: &ThumbnailOptions) -> Result<Vec<u8>, String> {
+        let meta = fs::metadata(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos());
+        let key = format!(
+            "{}\0{}\0{}\0{}\0{:?}\0{}\0{:?}\0{}",
+            path,
+            meta.len(),
+            modified,
+            options.width,
+            options.height,
+            options.format as u8,
+            options.quality,
+            options.fit as u8,
+        );
+        let entry = self
+            .dir
+            .join(blake3::hash(key.as_bytes()).to_hex().as_str());
+
+        if let Ok(bytes) = fs::read(&entry) {
+            return Ok(bytes);
+        }
+        let thumbnail = render_thumbnail(path, options)?;
+        // Concurrent renders of the same entry each write their own file.
+        let tmp = entry.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        if fs::write(&tmp, &thumbnail.bytes).is_ok() && fs::rename(&tmp, &entry).is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        Ok(thumbnail.bytes)
+    }
+}
+
+/// Answer a `thumb://` request on the blocking pool.
+pub fn handle_request(app: AppHandle, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    tauri::async_runtime::spawn(async move {
+        let tasks = app.state::<TaskRegistry>();
+        let cache = app.state::<ThumbnailCache>().inner().clone();
+        let settings = app.state::<SettingsStore>().get();
+        let response = tasks
+            .run_blocking("thumbnail", move || {
+                Ok(respond(&cache, &settings, &request))
+            })
+            .await
+            .unwrap_or_else(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()));
+        responder.respond(response);
+    });
+}
+
+fn respond(
+    cache: &ThumbnailCache,
+    settings: &Settings,
+    request: &Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let uri = request.uri();
+    let path = match percent_decode_str(uri.path().trim_start_matches('/')).decode_utf8() {
+        Ok(path) if !path.is_empty() => path.to_string(),
+        _ => return error_response(StatusCode::BAD_REQUEST, "Missing or invalid file path"),
+    };
+    let options = match parse_options(uri.query()).and_then(|options| options.validate(settings)) {
+        Ok(options) => options,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+    if !Path::new(&path).is_file() {
+        return error_response(StatusCode::NOT_FOUND, &format!("File not found: {}", path));
+    }
+
+    match cache.get_or_render(&path, &options) {
+        Ok(bytes) => Response::builder()
+            .header(header::CONTENT_TYPE, options.format.mime())
+            // The URL stays the same when the file changes; revalidating is a
+            // cache hit on disk.
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(bytes)
+            .unwrap_or_default(),
+        Err(e) => error_response(StatusCode::UNPROCESSABLE_ENTITY, &e),
+    }
+}
+
+/// Read `width`, `height`, `format`, `quality` and `fit` from the query
+/// string, with the same values as `get_thumbnail` accepts.
+fn parse_options(query: Option<&str>) -> Result<ThumbnailOptions, String> {
+    let mut options = ThumbnailOptions::default();
+    for pair in query.unwrap_or("").split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode_str(value).decode_utf8_lossy();
+        let invalid = || format!("Invalid thumbnail parameter: {}", pair);
+        match key {
+            "width" => options.width = value.parse().map_err(|_| invalid())?,
+            "height" => options.height = Some(value.parse().map_err(|_| invalid())?),
+            "quality" => options.quality = Some(value.parse().map_err(|_| invalid())?),
+            "format" => {
+                options.format =
+                    serde_json::from_value(value.as_ref().into()).map_err(|_| invalid())?
+            }
+            "fit" => {
+                options.fit =
+                    serde_json::from_value(value.as_ref().into()).map_err(|_| invalid())?
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(options)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_default()
+}
+
+fn remove_stale_entries(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > MAX_CACHE_AGE);
+        if stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
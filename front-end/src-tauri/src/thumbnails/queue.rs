@@ -0,0 +1,187 @@
+//! Background generation of thumbnails for whole folders.
+//!
+//! The grid asks for the thumbnails of the items on screen and gets each one
+//! as an event once it is ready. Newer requests run first, so the items the
+//! user scrolled to are generated before the ones they scrolled past.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+use super::{generate_thumbnail, ThumbnailOptions, ThumbnailResponse};
+use crate::error::ExplorerError;
+use crate::settings::SettingsStore;
+
+/// Event emitted for each thumbnail generated from the queue.
+pub const THUMBNAIL_READY_EVENT: &str = "thumbnail://ready";
+
+/// Upper bound on worker threads; decoding large photos is memory hungry.
+const MAX_WORKERS: usize = 4;
+
+/// Upper bound on queued requests. The oldest ones are dropped beyond it,
+/// as they belong to items scrolled past long ago.
+const MAX_PENDING: usize = 5000;
+
+/// Payload of the `thumbnail://ready` event.
+#[derive(Serialize, Clone)]
+pub struct ThumbnailReady {
+    pub path: String,
+    pub width: u32,
+    pub thumbnail: Option<ThumbnailResponse>,
+    pub error: Option<String>,
+}
+
+/// A thumbnail to generate: the path and the requested width.
+type Job = (String, u32);
+
+/// Queue order: later batches first, then the order within a batch.
+type Priority = (Reverse<u64>, usize);
+
+#[derive(Default)]
+struct Pending {
+    next_batch: u64,
+    queue: BTreeMap<Priority, Job>,
+    priorities: HashMap<Job, Priority>,
+    in_flight: HashSet<Job>,
+}
+
+impl Pending {
+    fn remove(&mut self, job: &Job) {
+        if let Some(priority) = self.priorities.remove(job) {
+            self.queue.remove(&priority);
+        }
+    }
+}
+
+/// Managed state holding queued thumbnail requests and the worker pool that
+/// serves them.
+#[derive(Default)]
+pub struct ThumbnailQueue {
+    pending: Arc<(Mutex<Pending>, Condvar)>,
+    workers: OnceLock<()>,
+}
+
+impl ThumbnailQueue {
+    /// Start the workers on first use, once an app handle is available.
+    fn ensure_workers(&self, app: &AppHandle) {
+        self.workers.get_or_init(|| {
+            let count = thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+                .clamp(1, MAX_WORKERS);
+            for _ in 0..count {
+                let app = app.clone();
+                let pending = Arc::clone(&self.pending);
+                thread::spawn(move || run_worker(&app, &pending));
+            }
+        });
+    }
+}
+
+fn run_worker(app: &AppHandle, pending: &(Mutex<Pending>, Condvar)) {
+    let (lock, ready) = pending;
+    loop {
+        let job = {
+            let mut state = lock.lock().unwrap();
+            loop {
+                if let Some((_, job)) = state.queue.pop_first() {
+                    state.priorities.remove(&job);
+                    state.in_flight.insert(job.clone());
+                    break job;
+                }
+                state = ready.wait(state).unwrap();
+            }
+        };
+
+        let (path, width) = job.clone();
+        let options = ThumbnailOptions {
+            width,
+            quality: Some(app.state::<SettingsStore>().get().thumbnail_quality),
+            ..ThumbnailOptions::default()
+        };
+        let result = generate_thumbnail(&path, &options);
+        lock.lock().unwrap().in_flight.remove(&job);
+
+        let (thumbnail, error) = match result {
+            Ok(thumbnail) => (Some(thumbnail), None),
+            Err(e) => (None, Some(e)),
+        };
+        let _ = app.emit(
+            THUMBNAIL_READY_EVENT,
+            ThumbnailReady {
+                path,
+                width,
+                thumbnail,
+                error,
+            },
+        );
+    }
+}
+
+/// Queue thumbnails for `paths` at `width` pixels (default 100) and emit each
+/// as a `thumbnail://ready` event when done.
+///
+/// Paths are generated in the order given, ahead of everything requested
+/// earlier; requesting an already queued path again moves it forward. Call
+/// this with the visible items whenever the view scrolls, and
+/// `cancel_thumbnails` for items that left the screen.
+#[command]
+pub fn request_thumbnails(
+    app: AppHandle,
+    queue: State<'_, ThumbnailQueue>,
+    settings: State<'_, SettingsStore>,
+    paths: Vec<String>,
+    width: Option<u32>,
+) -> Result<(), ExplorerError> {
+    let options = ThumbnailOptions {
+        width: width.unwrap_or(ThumbnailOptions::default().width),
+        ..ThumbnailOptions::default()
+    }
+    .validate(&settings.get())?;
+    queue.ensure_workers(&app);
+
+    let (lock, ready) = &*queue.pending;
+    let mut state = lock.lock().unwrap();
+    let batch = state.next_batch;
+    state.next_batch += 1;
+    for (position, path) in paths.into_iter().enumerate() {
+        let job = (path, options.width);
+        if state.in_flight.contains(&job) {
+            continue;
+        }
+        state.remove(&job);
+        let priority = (Reverse(batch), position);
+        state.queue.insert(priority, job.clone());
+        state.priorities.insert(job, priority);
+    }
+    while state.queue.len() > MAX_PENDING {
+        if let Some((_, job)) = state.queue.pop_last() {
+            state.priorities.remove(&job);
+        }
+    }
+    ready.notify_all();
+    Ok(())
+}
+
+/// Drop queued thumbnail requests for `paths`, at any width.
+///
+/// Thumbnails already being generated still finish and are emitted.
+#[command]
+pub fn cancel_thumbnails(queue: State<'_, ThumbnailQueue>, paths: Vec<String>) {
+    let paths: HashSet<String> = paths.into_iter().collect();
+    let (lock, _) = &*queue.pending;
+    let mut state = lock.lock().unwrap();
+    let cancelled: Vec<Job> = state
+        .priorities
+        .keys()
+        .filter(|(path, _)| paths.contains(path))
+        .cloned()
+        .collect();
+    for job in cancelled {
+        state.remove(&job);
+    }
+}
@@ -0,0 +1,275 @@
+//! Embedded JPEG previews of camera RAW files.
+//!
+//! Decoding sensor data is slow and camera specific, but nearly every RAW
+//! format is a TIFF container that also stores ready-made JPEG renditions.
+//! Fujifilm RAF files point to theirs from a fixed header field instead.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Extensions of the RAW formats whose previews can be extracted.
+const RAW_EXTENSIONS: &[&str] = &[
+    "arw", "cr2", "dng", "nef", "nrw", "orf", "pef", "raf", "rw2", "sr2", "srw",
+];
+
+/// Upper bound on IFDs visited, in case of malformed offset chains.
+const MAX_IFDS: usize = 64;
+
+/// Bytes read from the start of a candidate to find its frame type.
+const JPEG_HEADER_PROBE: u64 = 128 * 1024;
+
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+const TAG_EXIF_IFD: u16 = 0x8769;
+/// Panasonic RW2 stores a full-size JPEG directly in this tag.
+const TAG_JPG_FROM_RAW: u16 = 0x002E;
+
+/// Compression values that mark a strip as JPEG data.
+const JPEG_COMPRESSION: &[u32] = &[6, 7];
+
+const RAF_MAGIC: &[u8] = b"FUJIFILMCCD-RAW";
+
+/// Whether `path` has the extension of a supported RAW format.
+pub fn is_raw(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| RAW_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Read the largest embedded JPEG preview of a RAW file.
+pub fn extract_preview(path: &Path) -> Result<Vec<u8>, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open RAW file: {}", e))?;
+    let mut header = [0u8; 92];
+    file.read_exact(&mut header)
+        .map_err(|e| format!("Failed to read RAW file: {}", e))?;
+
+    let candidates = if header.starts_with(RAF_MAGIC) {
+        // Big-endian offset and length of the JPEG at bytes 84 and 88.
+        let offset = u32::from_be_bytes([header[84], header[85], header[86], header[87]]);
+        let length = u32::from_be_bytes([header[88], header[89], header[90], header[91]]);
+        vec![(offset as u64, length as u64)]
+    } else {
+        let big_endian = match &header[..2] {
+            b"II" => false,
+            b"MM" => true,
+            _ => return Err("Unsupported RAW file: not a TIFF container".to_string()),
+        };
+        TiffReader {
+            file: &mut file,
+            big_endian,
+        }
+        .jpeg_candidates(&header)
+    };
+
+    let file_len = file.metadata().map(|m| m.len()).unwrap_or(u64::MAX);
+    let mut best: Option<(u64, u64)> = None;
+    for (offset, length) in candidates {
+        if length == 0 || offset.saturating_add(length) > file_len {
+            continue;
+        }
+        if best.is_some_and(|(_, best_length)| best_length >= length) {
+            continue;
+        }
+        if is_decodable_jpeg(&mut file, offset, length) {
+            best = Some((offset, length));
+        }
+    }
+    let (offset, length) = best.ok_or_else(|| "RAW file has no embedded preview".to_string())?;
+
+    let mut jpeg = vec![0u8; length as usize];
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.read_exact(&mut jpeg))
+        .map_err(|e| format!("Failed to read RAW preview: {}", e))?;
+    Ok(jpeg)
+}
+
+/// Whether the data at `offset` is a baseline or progressive JPEG.
+///
+/// Raw sensor data is often stored as lossless JPEG, which also starts with
+/// an SOI marker but cannot be shown as a preview.
+fn is_decodable_jpeg(file: &mut File, offset: u64, length: u64) -> bool {
+    let mut data = Vec::new();
+    if file.seek(SeekFrom::Start(offset)).is_err()
+        || file
+            .take(length.min(JPEG_HEADER_PROBE))
+            .read_to_end(&mut data)
+            .is_err()
+    {
+        return false;
+    }
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return false;
+    }
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return false;
+        }
+        let marker = data[pos + 1];
+        match marker {
+            // Padding before a marker.
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            0xC0..=0xC2 => return true,
+            // Other frame types: lossless, hierarchical or arithmetic coded.
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return false,
+            _ => {}
+        }
+        let segment = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        pos += 2 + segment;
+    }
+    false
+}
+
+/// Walks the IFDs of a TIFF-based RAW file.
+struct TiffReader<'f> {
+    file: &'f mut File,
+    big_endian: bool,
+}
+
+/// The entries of one IFD that can point at a JPEG.
+#[derive(Default)]
+struct IfdJpegTags {
+    compression: Option<u32>,
+    strip_offsets: Vec<u32>,
+    strip_byte_counts: Vec<u32>,
+    jpeg_offset: Option<u32>,
+    jpeg_length: Option<u32>,
+}
+
+impl TiffReader<'_> {
+    /// Offsets and lengths of every JPEG referenced from the IFD tree.
+    fn jpeg_candidates(&mut self, header: &[u8]) -> Vec<(u64, u64)> {
+        let mut candidates = Vec::new();
+        let mut pending = vec![self.u32(&header[4..8]) as u64];
+        let mut visited = HashSet::new();
+        while let Some(offset) = pending.pop() {
+            if offset == 0 || visited.len() >= MAX_IFDS || !visited.insert(offset) {
+                continue;
+            }
+            let Some((tags, children, next)) = self.read_ifd(offset, &mut candidates) else {
+                continue;
+            };
+            pending.push(next);
+            pending.extend(children);
+
+            if let (Some(start), Some(length)) = (tags.jpeg_offset, tags.jpeg_length) {
+                candidates.push((start as u64, length as u64));
+            }
+            let is_jpeg = tags
+                .compression
+                .is_some_and(|c| JPEG_COMPRESSION.contains(&c));
+            if is_jpeg && tags.strip_offsets.len() == 1 && tags.strip_byte_counts.len() == 1 {
+                candidates.push((
+                    tags.strip_offsets[0] as u64,
+                    tags.strip_byte_counts[0] as u64,
+                ));
+            }
+        }
+        candidates
+    }
+
+    /// Parse the IFD at `offset`, returning its JPEG tags, the offsets of its
+    /// child IFDs and the offset of the next IFD in the chain.
+    fn read_ifd(
+        &mut self,
+        offset: u64,
+        candidates: &mut Vec<(u64, u64)>,
+    ) -> Option<(IfdJpegTags, Vec<u64>, u64)> {
+        let count = self.read_at(offset, 2)?;
+        let count = self.u16(&count) as u64;
+        let entries = self.read_at(offset + 2, count * 12 + 4)?;
+        let mut tags = IfdJpegTags::default();
+        let mut children = Vec::new();
+        for entry in entries.chunks_exact(12) {
+            let tag = self.u16(&entry[0..2]);
+            let kind = self.u16(&entry[2..4]);
+            let count = self.u32(&entry[4..8]);
+            match tag {
+                TAG_COMPRESSION => {
+                    tags.compression = self.values(entry, kind, count)?.first().copied()
+                }
+                TAG_STRIP_OFFSETS => tags.strip_offsets = self.values(entry, kind, count)?,
+                TAG_STRIP_BYTE_COUNTS => {
+                    tags.strip_byte_counts = self.values(entry, kind, count)?
+                }
+                TAG_JPEG_OFFSET => {
+                    tags.jpeg_offset = self.values(entry, kind, count)?.first().copied()
+                }
+                TAG_JPEG_LENGTH => {
+                    tags.jpeg_length = self.values(entry, kind, count)?.first().copied()
+                }
+                TAG_SUB_IFDS | TAG_EXIF_IFD => children.extend(
+                    self.values(entry, kind, count)?
+                        .into_iter()
+                        .map(|child| child as u64),
+                ),
+                // The payload itself is the JPEG.
+                TAG_JPG_FROM_RAW if count > 4 => {
+                    candidates.push((self.u32(&entry[8..12]) as u64, count as u64))
+                }
+                _ => {}
+            }
+        }
+        let next = self.u32(&entries[entries.len() - 4..]) as u64;
+        Some((tags, children, next))
+    }
+
+    /// Integer values of an entry of type SHORT, LONG or IFD.
+    fn values(&mut self, entry: &[u8], kind: u16, count: u32) -> Option<Vec<u32>> {
+        let size = match kind {
+            3 => 2,
+            4 | 13 => 4,
+            _ => return Some(Vec::new()),
+        };
+        // Offset arrays beyond this are not previews.
+        let count = count.min(1024) as u64;
+        let data = if count * size <= 4 {
+            entry[8..12].to_vec()
+        } else {
+            self.read_at(self.u32(&entry[8..12]) as u64, count * size)?
+        };
+        Some(
+            data.chunks_exact(size as usize)
+                .take(count as usize)
+                .map(|bytes| match size {
+                    2 => self.u16(bytes) as u32,
+                    _ => self.u32(bytes),
+                })
+                .collect(),
+        )
+    }
+
+    fn read_at(&mut self, offset: u64, length: u64) -> Option<Vec<u8>> {
+        let mut data = vec![0u8; length as usize];
+        self.file.seek(SeekFrom::Start(offset)).ok()?;
+        self.file.read_exact(&mut data).ok()?;
+        Some(data)
+    }
+
+    fn u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! SVG rasterization through resvg.
+
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use image::{DynamicImage, RgbImage};
+use resvg::tiny_skia::{Color, Pixmap, Transform};
+use resvg::usvg::{fontdb, Options, Tree};
+
+/// Extensions of plain and gzip-compressed SVG files.
+const SVG_EXTENSIONS: &[&str] = &["svg", "svgz"];
+
+/// Upper bound on the height of a rendered SVG, for extremely tall drawings.
+const MAX_RENDER_HEIGHT: u32 = 4096;
+
+/// System fonts for text in SVGs, loaded once on first use.
+static FONTS: OnceLock<Arc<fontdb::Database>> = OnceLock::new();
+
+/// Whether `path` has the extension of an SVG file.
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| SVG_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Render an SVG file at `width` pixels, keeping its aspect ratio.
+///
+/// Drawings are rendered on white, as most assume a light page and JPEG
+/// thumbnails have no transparency.
+pub fn render(path: &Path, width: u32) -> Result<DynamicImage, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to open SVG: {}", e))?;
+    let fontdb = FONTS
+        .get_or_init(|| {
+            let mut db = fontdb::Database::new();
+            db.load_system_fonts();
+            Arc::new(db)
+        })
+        .clone();
+    let options = Options {
+        // Relative image references resolve next to the file.
+        resources_dir: path.parent().map(Path::to_path_buf),
+        fontdb,
+        ..Options::default()
+    };
+    let tree =
+        Tree::from_data(&data, &options).map_err(|e| format!("Failed to parse SVG: {}", e))?;
+
+    let size = tree.size();
+    let scale = width as f32 / size.width();
+    let height = ((size.height() * scale).round() as u32).clamp(1, MAX_RENDER_HEIGHT);
+    let mut pixmap = Pixmap::new(width.max(1), height)
+        .ok_or_else(|| "Failed to render SVG: invalid size".to_string())?;
+    pixmap.fill(Color::WHITE);
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    // Fully opaque after the white fill, so premultiplied alpha changes nothing.
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| [pixel.red(), pixel.green(), pixel.blue()])
+        .collect();
+    RgbImage::from_raw(pixmap.width(), pixmap.height(), pixels)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| "Failed to render SVG: invalid size".to_string())
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, State};
+
+use crate::error::ExplorerError;
+use crate::long_paths::{to_display, to_extended};
+
+/// Event name used to report filesystem changes under watched paths.
+pub const FS_CHANGE_EVENT: &str = "fs://change";
+
+/// Kind of change reported to the frontend.
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+/// Payload of the `fs://change` event.
+#[derive(Serialize, Clone)]
+pub struct FsChange {
+    /// The path passed to `watch_path` that this change belongs to.
+    pub watched_path: String,
+    pub kind: ChangeKind,
+    /// Affected paths; for renames this is `[from, to]` when both are known.
+    pub paths: Vec<String>,
+}
+
+/// Managed state holding one watcher per watched path.
+#[derive(Default)]
+pub struct Watchers {
+    active: Mutex<HashMap<String, RecommendedWatcher>>,
+}
+
+/// Start watching a folder and emit `fs://change` events when its contents change.
+///
+/// Only direct children are watched unless `recursive` is set. Watching a path
+/// that is already watched is a no-op.
+#[command]
+pub fn watch_path(
+    app: AppHandle,
+    watchers: State<'_, Watchers>,
+    path: String,
+    recursive: Option<bool>,
+) -> Result<(), ExplorerError> {
+    let mut active = watchers.active.lock().unwrap();
+    if active.contains_key(&path) {
+        return Ok(());
+    }
+
+    let watched_path = path.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+        if let Ok(event) = result {
+            if let Some(change) = to_change(&watched_path, event) {
+                let _ = app.emit(FS_CHANGE_EVENT, change);
+            }
+        }
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    let mode = if recursive.unwrap_or(false) {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    // The Windows watcher opens the folder with a plain Win32 call.
+    watcher
+        .watch(&to_extended(Path::new(&path)), mode)
+        .map_err(|e| format!("Failed to watch path: {}", e))?;
+
+    active.insert(path, watcher);
+    Ok(())
+}
+
+/// Stop watching a folder previously passed to `watch_path`.
+#[command]
+pub fn unwatch_path(watchers: State<'_, Watchers>, path: String) -> Result<(), ExplorerError> {
+    // Dropping the watcher stops it.
+    watchers
+        .active
+        .lock()
+        .unwrap()
+        .remove(&path)
+        .map(|_| ())
+        .ok_or_else(|| ExplorerError::NotFound(format!("Path is not being watched: {}", path)))
+}
+
+/// Translate a notify event into the simplified change reported to the UI.
+fn to_change(watched_path: &str, event: Event) -> Option<FsChange> {
+    let kind = match event.kind {
+        EventKind::Create(_) => ChangeKind::Created,
+        EventKind::Modify(ModifyKind::Name(_)) => ChangeKind::Renamed,
+        EventKind::Modify(_) => ChangeKind::Modified,
+        EventKind::Remove(_) => ChangeKind::Deleted,
+        // Access and unclassified events don't change what the UI shows.
+        _ => return None,
+    };
+
+    Some(FsChange {
+        watched_path: watched_path.to_string(),
+        kind,
+        paths: event.paths.iter().map(|p| to_display(p)).collect(),
+    })
+}
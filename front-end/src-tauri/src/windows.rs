@@ -0,0 +1,270 @@
+//! Several explorer windows side by side, each with its own state.
+//!
+//! State is keyed by window label. The window created at startup is `main`;
+//! further ones are `explorer-<id>`. Operations attached to a window are
+//! cancelled when it closes, so closing one window never stops the scans of
+//! another. Every change is saved as the session restored on the next start.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    command, AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder, Window, WindowEvent,
+};
+
+use crate::error::ExplorerError;
+use crate::operations::{cancel_operation, OperationRegistry};
+use crate::session::SessionStore;
+
+/// Prefix of the labels of windows opened with `new_window`.
+const WINDOW_LABEL_PREFIX: &str = "explorer-";
+
+/// Size in logical pixels of windows opened with `new_window`.
+const NEW_WINDOW_SIZE: (f64, f64) = (1200.0, 800.0);
+
+/// A tab open in a window.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Tab {
+    pub path: String,
+    /// Vertical scroll offset of the listing in pixels.
+    #[serde(default)]
+    pub scroll_top: f64,
+}
+
+/// An operation attached to a window.
+#[derive(Serialize, Clone)]
+pub struct AttachedOperation {
+    pub id: String,
+    /// Folder being scanned, for operations that are scans.
+    pub scan_path: Option<String>,
+}
+
+/// What one window shows.
+#[derive(Serialize, Clone, Default)]
+pub struct WindowState {
+    pub label: String,
+    /// Folder the window is showing.
+    pub current_path: Option<String>,
+    /// Paths selected in the window.
+    pub selection: Vec<String>,
+    pub tabs: Vec<Tab>,
+    /// Index into `tabs` of the tab in front.
+    pub active_tab: usize,
+    /// Operations attached to the window that are still running.
+    pub operations: Vec<AttachedOperation>,
+    /// Folders that were being scanned when the app last exited, so the
+    /// window can offer to scan them again. Only set by `restore_session`.
+    pub interrupted_scans: Vec<String>,
+}
+
+/// Managed state holding the state of every open window.
+#[derive(Default)]
+pub struct WindowStates {
+    windows: Mutex<HashMap<String, WindowState>>,
+}
+
+impl WindowStates {
+    /// Change the state of `window`, creating it if needed, and save the
+    /// session. The session is written in the background, so this never
+    /// waits on the disk.
+    fn update<T>(&self, window: &Window, change: impl FnOnce(&mut WindowState) -> T) -> T {
+        let mut windows = self.windows.lock().unwrap();
+        let label = window.label();
+        let state = windows
+            .entry(label.to_string())
+            .or_insert_with(|| WindowState {
+                label: label.to_string(),
+                ..Default::default()
+            });
+        let result = change(state);
+        window
+            .state::<SessionStore>()
+            .save(&windows, &window.state());
+        result
+    }
+
+    /// State of the window labelled `label`.
+    pub fn get(&self, label: &str) -> WindowState {
+        self.windows
+            .lock()
+            .unwrap()
+            .get(label)
+            .cloned()
+            .unwrap_or_else(|| WindowState {
+                label: label.to_string(),
+                ..Default::default()
+            })
+    }
+
+    /// Set the state a window opened by the app starts with.
+    pub fn insert(&self, state: WindowState) {
+        self.windows
+            .lock()
+            .unwrap()
+            .insert(state.label.clone(), state);
+    }
+}
+
+/// Forget a window's state once it is closed and cancel the operations
+/// attached to it.
+///
+/// The last window to close stays in the saved session, since closing it
+/// quits the app.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if !matches!(event, WindowEvent::Destroyed) {
+        return;
+    }
+    let states = window.state::<WindowStates>();
+    let session = window.state::<SessionStore>();
+    let operations = window.state::<OperationRegistry>();
+    let mut windows = states.windows.lock().unwrap();
+    if windows.keys().all(|label| label == window.label()) {
+        // Saved before the operations are cancelled, so the scans they were
+        // running are restored as interrupted.
+        session.save(&windows, &operations);
+    }
+    let removed = windows.remove(window.label());
+    let quitting = windows.is_empty();
+    if !quitting {
+        session.save(&windows, &operations);
+    }
+    drop(windows);
+    if quitting {
+        // The background writer may not get to run before the app exits.
+        session.flush();
+    }
+    for operation in removed.map(|state| state.operations).unwrap_or_default() {
+        // Operations that already finished are gone from the registry.
+        let _ = cancel_operation(window.state(), operation.id);
+    }
+}
+
+/// Open another explorer window, showing `path` if given, and return its
+/// label. The new window reads the path with `get_window_state`.
+#[command]
+pub async fn new_window(
+    app: AppHandle,
+    states: State<'_, WindowStates>,
+    path: Option<String>,
+) -> Result<String, ExplorerError> {
+    if let Some(path) = &path {
+        if !Path::new(path).is_dir() {
+            return Err(ExplorerError::NotFound(format!(
+                "Not a directory: {}",
+                path
+            )));
+        }
+    }
+    open_window(
+        &app,
+        &states,
+        WindowState {
+            current_path: path,
+            ..Default::default()
+        },
+    )
+}
+
+/// Open a window starting with `state`, under a new label, and return the
+/// label.
+pub fn open_window(
+    app: &AppHandle,
+    states: &WindowStates,
+    state: WindowState,
+) -> Result<String, ExplorerError> {
+    let label = format!("{}{}", WINDOW_LABEL_PREFIX, uuid::Uuid::new_v4().simple());
+    let title = app.package_info().name.clone();
+    let title = match &state.current_path {
+        Some(path) => format!("{} - {}", path, title),
+        None => title,
+    };
+    states.insert(WindowState {
+        label: label.clone(),
+        ..state
+    });
+
+    let (width, height) = NEW_WINDOW_SIZE;
+    let built = WebviewWindowBuilder::new(app, &label, WebviewUrl::default())
+        .title(title)
+        .inner_size(width, height)
+        .build();
+    if let Err(e) = built {
+        states.windows.lock().unwrap().remove(&label);
+        return Err(ExplorerError::Failed(format!(
+            "Failed to open window: {}",
+            e
+        )));
+    }
+    Ok(label)
+}
+
+/// State of the calling window. Operations that have not started yet or
+/// have finished are left out.
+#[command]
+pub fn get_window_state(
+    window: Window,
+    states: State<'_, WindowStates>,
+    operations: State<'_, OperationRegistry>,
+) -> WindowState {
+    let mut state = states.update(&window, |state| state.clone());
+    state
+        .operations
+        .retain(|operation| operations.is_running(&operation.id));
+    state
+}
+
+/// Record the folder the calling window shows.
+#[command]
+pub fn set_window_path(window: Window, states: State<'_, WindowStates>, path: Option<String>) {
+    states.update(&window, |state| state.current_path = path);
+}
+
+/// Record the paths selected in the calling window.
+#[command]
+pub fn set_window_selection(window: Window, states: State<'_, WindowStates>, paths: Vec<String>) {
+    states.update(&window, |state| state.selection = paths);
+}
+
+/// Record the tabs open in the calling window and their scroll positions.
+/// Call it again when they change, debounced while scrolling.
+#[command]
+pub fn set_window_tabs(
+    window: Window,
+    states: State<'_, WindowStates>,
+    tabs: Vec<Tab>,
+    active_tab: usize,
+) -> Result<(), ExplorerError> {
+    if active_tab >= tabs.len().max(1) {
+        return Err(ExplorerError::InvalidInput(format!(
+            "No tab at index {}",
+            active_tab
+        )));
+    }
+    states.update(&window, |state| {
+        state.tabs = tabs;
+        state.active_tab = active_tab;
+    });
+    Ok(())
+}
+
+/// Attach the operation `id` to the calling window, so it is listed in the
+/// window's state and cancelled when the window closes. Attach it before
+/// starting the operation with that id, so a window closed right away cannot
+/// miss it.
+///
+/// For scans, `scan_path` is the folder being scanned; scans still running
+/// when the app exits are offered again by `restore_session`.
+#[command]
+pub fn attach_operation(
+    window: Window,
+    states: State<'_, WindowStates>,
+    id: String,
+    scan_path: Option<String>,
+) {
+    states.update(&window, |state| {
+        state.operations.retain(|operation| operation.id != id);
+        state.operations.push(AttachedOperation { id, scan_path });
+    });
+}
@@ -53,4 +53,15 @@ export interface Filters {
   modifiedBefore: Moment | null;    // using Moment for date objects
   lowQualityVideos: boolean;        // a boolean flag
   previewImage: boolean;        // a boolean flag
-}
\ No newline at end of file
+}
+export interface DriveInfo {
+  mount_point: string;
+  label?: string;
+  drive_type: "fixed" | "removable" | "network" | "cd_rom" | "ram_disk" | "phone" | "unknown";
+  is_removable: boolean;
+  file_system?: string;
+  encryption?: "unlocked" | "locked";
+  total_bytes: number;
+  free_bytes: number;
+  used_bytes: number;
+}
@@ -18,8 +18,9 @@ import {
 } from 'antd';
 import { FolderOpenOutlined } from '@ant-design/icons';
 import { motion } from 'framer-motion';
-import { FileInfo, Filters, ScanProgress } from '../interfaces';
+import { DriveInfo, FileInfo, Filters, ScanProgress } from '../interfaces';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import axios from 'axios';
 import { open } from '@tauri-apps/plugin-dialog';
 import HistorySelect from '../components/HistorySelect';
@@ -39,7 +40,7 @@ const Dashboard: React.FC<DashboardProps> = ({
   messageApi,
 }) => {
 
-  const [drives, setDrives] = useState<string[]>([]);
+  const [drives, setDrives] = useState<DriveInfo[]>([]);
   const [selectedPath, setSelectedPath] = useState("");
   const [progress, setProgress] = useState<ScanProgress>();
   const [mode, setMode] = useState<"scan" | "search">("scan");
@@ -72,7 +73,7 @@ const Dashboard: React.FC<DashboardProps> = ({
   useEffect(() => {
     const getDrives = async () => {
       try {
-        const driveList = await invoke<string[]>('get_drives');
+        const driveList = await invoke<DriveInfo[]>('get_drives');
         setDrives(driveList);
       } catch (error) {
         messageApi.error('Failed to get drives');
@@ -83,6 +84,16 @@ const Dashboard: React.FC<DashboardProps> = ({
     getDrives();
   }, []);
 
+  // Keep the drive list in sync as drives are mounted or unmounted
+  useEffect(() => {
+    const unlisten = listen<{ drives: DriveInfo[] }>('drives://changes', (event) => {
+      setDrives(event.payload.drives);
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
   const handleSelectFolder = async () => {
     try {
       const selected = await open({ multiple: false, directory: true });
@@ -273,7 +284,7 @@ const Dashboard: React.FC<DashboardProps> = ({
               style={{ flex: 1 }}
               placeholder="Select Drive"
               onChange={setSelectedPath}
-              options={drives.map(drive => ({ label: drive, value: drive }))}
+              options={drives.map(drive => ({ label: drive.label ? `${drive.label} (${drive.mount_point})` : drive.mount_point, value: drive.mount_point }))}
             />
             <Button
               icon={<FolderOpenOutlined />}